@@ -673,6 +673,8 @@ impl MultiAppConfig {
             enabled: true, // 自动启用
             created_at: Some(timestamp),
             updated_at: Some(timestamp),
+            layer: false,
+            layer_order: 0,
         };
 
         // 插入到对应的应用配置中
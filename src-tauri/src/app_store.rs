@@ -5,6 +5,12 @@ use tauri_plugin_store::StoreExt;
 
 use crate::error::AppError;
 
+/// Store 文件名
+const STORE_FILE: &str = "app_paths.json";
+
+/// 应用标识（与 tauri.conf.json 中的 identifier 一致），决定 Store 文件所在目录
+const APP_IDENTIFIER: &str = "com.ccswitchs.desktop";
+
 /// Store 中的键名
 const STORE_KEY_APP_CONFIG_DIR: &str = "app_config_dir_override";
 
@@ -15,7 +21,7 @@ fn override_cache() -> &'static RwLock<Option<PathBuf>> {
     APP_CONFIG_DIR_OVERRIDE.get_or_init(|| RwLock::new(None))
 }
 
-fn update_cached_override(value: Option<PathBuf>) {
+pub(crate) fn update_cached_override(value: Option<PathBuf>) {
     if let Ok(mut guard) = override_cache().write() {
         *guard = value;
     }
@@ -27,7 +33,7 @@ pub fn get_app_config_dir_override() -> Option<PathBuf> {
 }
 
 fn read_override_from_store(app: &tauri::AppHandle) -> Option<PathBuf> {
-    let store = match app.store_builder("app_paths.json").build() {
+    let store = match app.store_builder(STORE_FILE).build() {
         Ok(store) => store,
         Err(e) => {
             log::warn!("无法创建 Store: {e}");
@@ -35,7 +41,11 @@ fn read_override_from_store(app: &tauri::AppHandle) -> Option<PathBuf> {
         }
     };
 
-    match store.get(STORE_KEY_APP_CONFIG_DIR) {
+    parse_override(store.get(STORE_KEY_APP_CONFIG_DIR).as_ref())
+}
+
+fn parse_override(value: Option<&Value>) -> Option<PathBuf> {
+    match value {
         Some(Value::String(path_str)) => {
             let path_str = path_str.trim();
             if path_str.is_empty() {
@@ -63,6 +73,21 @@ fn read_override_from_store(app: &tauri::AppHandle) -> Option<PathBuf> {
     }
 }
 
+/// 不经 AppHandle 直接读取 Store 文件中的 app_config_dir 覆盖值（供命令行使用）
+///
+/// Store 文件位于应用数据目录（`<data_dir>/<identifier>/app_paths.json`）
+pub(crate) fn read_override_from_store_file() -> Option<PathBuf> {
+    let path = dirs::data_dir()?.join(APP_IDENTIFIER).join(STORE_FILE);
+    let content = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<Value>(&content) {
+        Ok(store) => parse_override(store.get(STORE_KEY_APP_CONFIG_DIR)),
+        Err(e) => {
+            log::warn!("解析 Store 文件失败: {path:?}, 错误: {e}");
+            None
+        }
+    }
+}
+
 /// 从 Store 刷新 app_config_dir 覆盖值并更新缓存
 pub fn refresh_app_config_dir_override(app: &tauri::AppHandle) -> Option<PathBuf> {
     let value = read_override_from_store(app);
@@ -76,7 +101,7 @@ pub fn set_app_config_dir_to_store(
     path: Option<&str>,
 ) -> Result<(), AppError> {
     let store = app
-        .store_builder("app_paths.json")
+        .store_builder(STORE_FILE)
         .build()
         .map_err(|e| AppError::Message(format!("创建 Store 失败: {e}")))?;
 
@@ -0,0 +1,4 @@
+//! 无界面命令行工具：与桌面端共享数据库与服务层
+fn main() {
+    std::process::exit(cc_switch_lib::run_cli());
+}
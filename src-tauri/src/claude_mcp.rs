@@ -402,36 +402,7 @@ pub fn set_mcp_servers_map(
     }
     let mut out: Map<String, Value> = Map::new();
     for (id, spec) in servers.iter() {
-        let mut obj = if let Some(map) = spec.as_object() {
-            map.clone()
-        } else {
-            return Err(AppError::McpValidation(format!(
-                "MCP 服务器 '{id}' 不是对象"
-            )));
-        };
-
-        if let Some(server_val) = obj.remove("server") {
-            let server_obj = server_val.as_object().cloned().ok_or_else(|| {
-                AppError::McpValidation(format!("MCP 服务器 '{id}' server 字段不是对象"))
-            })?;
-            obj = server_obj;
-        }
-
-        obj.remove("enabled");
-        obj.remove("source");
-        obj.remove("id");
-        obj.remove("name");
-        obj.remove("description");
-        obj.remove("tags");
-        obj.remove("homepage");
-        obj.remove("docs");
-
-        // Windows 平台自动包装 npx/npm 等命令为 cmd /c 格式（WSL 路径除外）
-        if !is_wsl_target {
-            wrap_command_for_windows(&mut obj);
-        }
-
-        out.insert(id.clone(), Value::Object(obj));
+        out.insert(id.clone(), to_live_server(id, spec, !is_wsl_target)?);
     }
 
     {
@@ -445,6 +416,45 @@ pub fn set_mcp_servers_map(
     Ok(())
 }
 
+/// 把统一结构的 MCP 服务器规范转换为写入 Claude 配置的格式：移除 UI 辅助字段（enabled/source 等），
+/// `wrap_windows` 为 true 时在 Windows 上包装 npx/npm 等命令
+pub(crate) fn to_live_server(
+    id: &str,
+    spec: &Value,
+    wrap_windows: bool,
+) -> Result<Value, AppError> {
+    let mut obj = if let Some(map) = spec.as_object() {
+        map.clone()
+    } else {
+        return Err(AppError::McpValidation(format!(
+            "MCP 服务器 '{id}' 不是对象"
+        )));
+    };
+
+    if let Some(server_val) = obj.remove("server") {
+        let server_obj = server_val.as_object().cloned().ok_or_else(|| {
+            AppError::McpValidation(format!("MCP 服务器 '{id}' server 字段不是对象"))
+        })?;
+        obj = server_obj;
+    }
+
+    obj.remove("enabled");
+    obj.remove("source");
+    obj.remove("id");
+    obj.remove("name");
+    obj.remove("description");
+    obj.remove("tags");
+    obj.remove("homepage");
+    obj.remove("docs");
+
+    // Windows 平台自动包装 npx/npm 等命令为 cmd /c 格式（WSL 路径除外）
+    if wrap_windows {
+        wrap_command_for_windows(&mut obj);
+    }
+
+    Ok(Value::Object(obj))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,512 @@
+//! 无界面命令行入口（`cc-switch-cli`）
+//!
+//! 与 GUI 共享同一个 SQLite 数据库与服务层（`ProviderService` / `McpService` /
+//! `PromptService` / `SkillService`），所有 Live 配置写入都走 GUI 相同的写入函数，
+//! 便于在远程开发机和 CI 中切换供应商；`proxy serve` 可在无界面服务器上常驻运行本地代理。
+
+mod proxy;
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::{McpService, PromptService, ProviderService, SkillService};
+use crate::store::AppState;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "cc-switch-cli",
+    version,
+    about = "Headless CC Switch: manage providers, MCP servers, prompts and skills"
+)]
+struct Cli {
+    /// 以 JSON 格式输出结果
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// 覆盖 CC Switch 配置目录（默认沿用 GUI 中设置的目录，未设置时为 ~/.cc-switch）
+    #[arg(long, global = true, value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 供应商管理
+    #[command(subcommand)]
+    Provider(ProviderCommand),
+    /// MCP 服务器管理
+    #[command(subcommand)]
+    Mcp(McpCommand),
+    /// 提示词管理
+    #[command(subcommand)]
+    Prompt(PromptCommand),
+    /// Skills 管理
+    #[command(subcommand)]
+    Skill(SkillCommand),
+    /// 本地代理
+    #[command(subcommand)]
+    Proxy(ProxyCommand),
+}
+
+#[derive(Debug, Args)]
+struct AppArg {
+    /// 目标应用：claude / codex / gemini / opencode
+    #[arg(long, short, default_value = "claude", value_parser = parse_app)]
+    app: AppType,
+}
+
+#[derive(Debug, Args)]
+struct RequiredAppArg {
+    /// 目标应用：claude / codex / gemini / opencode
+    #[arg(long, short, value_parser = parse_app)]
+    app: AppType,
+}
+
+/// 开关参数：`--enable` / `--disable`，都不传时翻转当前状态
+#[derive(Debug, Args)]
+struct ToggleArg {
+    #[arg(long, conflicts_with = "disable")]
+    enable: bool,
+    #[arg(long)]
+    disable: bool,
+}
+
+impl ToggleArg {
+    fn resolve(&self, current: bool) -> bool {
+        if self.enable {
+            true
+        } else if self.disable {
+            false
+        } else {
+            !current
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum ProviderCommand {
+    /// 列出供应商
+    List(AppArg),
+    /// 显示当前供应商 ID
+    Current(AppArg),
+    /// 切换到指定供应商（写入 Live 配置并同步 MCP）
+    Switch {
+        id: String,
+        #[command(flatten)]
+        app: AppArg,
+    },
+    /// 从 JSON 文件添加供应商（`-` 表示标准输入）
+    Add {
+        #[arg(long, value_name = "FILE")]
+        file: PathBuf,
+        #[command(flatten)]
+        app: AppArg,
+    },
+    /// 删除供应商
+    Delete {
+        id: String,
+        #[command(flatten)]
+        app: AppArg,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum McpCommand {
+    /// 列出 MCP 服务器
+    List,
+    /// 启用/禁用某个 MCP 服务器在指定应用中的同步
+    Toggle {
+        id: String,
+        #[command(flatten)]
+        app: RequiredAppArg,
+        #[command(flatten)]
+        toggle: ToggleArg,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PromptCommand {
+    /// 列出提示词
+    List(AppArg),
+    /// 启用提示词（写入 CLAUDE.md / AGENTS.md / GEMINI.md）
+    Enable {
+        id: String,
+        #[command(flatten)]
+        app: AppArg,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SkillCommand {
+    /// 列出已安装的 Skills
+    List,
+    /// 启用/禁用某个 Skill 在指定应用中的同步
+    Toggle {
+        id: String,
+        #[command(flatten)]
+        app: RequiredAppArg,
+        #[command(flatten)]
+        toggle: ToggleArg,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProxyCommand {
+    /// 前台运行本地代理，收到 SIGINT / SIGTERM 时恢复 Live 配置后退出
+    Serve {
+        /// 仅启动代理，不接管各应用的 Live 配置
+        #[arg(long)]
+        no_takeover: bool,
+        /// 输出调试日志
+        #[arg(long, short)]
+        verbose: bool,
+    },
+}
+
+fn parse_app(s: &str) -> Result<AppType, String> {
+    s.parse::<AppType>().map_err(|e| e.to_string())
+}
+
+/// 命令行入口，返回进程退出码
+pub fn run_cli() -> i32 {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() { 2 } else { 0 };
+        }
+    };
+
+    // 未指定 --config-dir 时沿用 GUI 在 Store 中保存的配置目录，与 GUI 使用同一数据库
+    let config_dir = cli
+        .config_dir
+        .clone()
+        .or_else(crate::app_store::read_override_from_store_file);
+    crate::app_store::update_cached_override(config_dir);
+
+    let result = Database::init()
+        .map(|db| AppState::new(Arc::new(db)))
+        .and_then(|state| execute(&state, &cli.command, cli.json));
+
+    match result {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{output}");
+            }
+            0
+        }
+        Err(e) => {
+            if cli.json {
+                eprintln!("{}", serde_json::json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("error: {e}");
+            }
+            1
+        }
+    }
+}
+
+fn execute(state: &AppState, command: &Command, json: bool) -> Result<String, AppError> {
+    match command {
+        Command::Provider(cmd) => execute_provider(state, cmd, json),
+        Command::Mcp(cmd) => execute_mcp(state, cmd, json),
+        Command::Prompt(cmd) => execute_prompt(state, cmd, json),
+        Command::Skill(cmd) => execute_skill(state, cmd, json),
+        Command::Proxy(ProxyCommand::Serve {
+            no_takeover,
+            verbose,
+        }) => proxy::serve(state, !no_takeover, *verbose, json),
+    }
+}
+
+fn execute_provider(
+    state: &AppState,
+    cmd: &ProviderCommand,
+    json: bool,
+) -> Result<String, AppError> {
+    match cmd {
+        ProviderCommand::List(AppArg { app }) => {
+            let providers = ProviderService::list(state, app.clone())?;
+            let current = ProviderService::current(state, app.clone())?;
+            if json {
+                return to_json(&serde_json::json!({
+                    "app": app.as_str(),
+                    "current": current,
+                    "providers": providers.values().collect::<Vec<_>>(),
+                }));
+            }
+            Ok(providers
+                .values()
+                .map(|p| {
+                    let marker = if p.id == current { "*" } else { " " };
+                    format!("{marker} {}\t{}", p.id, p.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        ProviderCommand::Current(AppArg { app }) => {
+            let current = ProviderService::current(state, app.clone())?;
+            if json {
+                return to_json(&serde_json::json!({ "app": app.as_str(), "current": current }));
+            }
+            Ok(current)
+        }
+        ProviderCommand::Switch { id, app } => {
+            // CLI 进程内没有运行中的代理，接管期间直接切换会覆盖接管配置
+            state.proxy_service.ensure_live_not_taken_over(&app.app)?;
+            ProviderService::switch(state, app.app.clone(), id)?;
+            done(json, "switch", &app.app, id)
+        }
+        ProviderCommand::Add { file, app } => {
+            let provider = read_provider_file(file)?;
+            let id = provider.id.clone();
+            ProviderService::add(state, app.app.clone(), provider)?;
+            done(json, "add", &app.app, &id)
+        }
+        ProviderCommand::Delete { id, app } => {
+            ProviderService::delete(state, app.app.clone(), id)?;
+            done(json, "delete", &app.app, id)
+        }
+    }
+}
+
+fn execute_mcp(state: &AppState, cmd: &McpCommand, json: bool) -> Result<String, AppError> {
+    match cmd {
+        McpCommand::List => {
+            let servers = McpService::get_all_servers(state)?;
+            if json {
+                return to_json(&servers.values().collect::<Vec<_>>());
+            }
+            Ok(servers
+                .values()
+                .map(|s| {
+                    let apps = s
+                        .apps
+                        .enabled_apps()
+                        .iter()
+                        .map(|a| a.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!("{}\t{}\t[{apps}]", s.id, s.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        McpCommand::Toggle { id, app, toggle } => {
+            let servers = McpService::get_all_servers(state)?;
+            let server = servers
+                .get(id)
+                .ok_or_else(|| AppError::Message(format!("MCP 服务器 {id} 不存在")))?;
+            let enabled = toggle.resolve(server.apps.is_enabled_for(&app.app));
+            McpService::toggle_app(state, id, app.app.clone(), enabled)?;
+            toggled(json, &app.app, id, enabled)
+        }
+    }
+}
+
+fn execute_prompt(state: &AppState, cmd: &PromptCommand, json: bool) -> Result<String, AppError> {
+    match cmd {
+        PromptCommand::List(AppArg { app }) => {
+            let prompts = PromptService::get_prompts(state, app.clone())?;
+            if json {
+                return to_json(&prompts.values().collect::<Vec<_>>());
+            }
+            Ok(prompts
+                .values()
+                .map(|p| {
+                    let marker = if p.enabled { "*" } else { " " };
+                    format!("{marker} {}\t{}", p.id, p.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        PromptCommand::Enable { id, app } => {
+            PromptService::enable_prompt(state, app.app.clone(), id)?;
+            done(json, "enable", &app.app, id)
+        }
+    }
+}
+
+fn execute_skill(state: &AppState, cmd: &SkillCommand, json: bool) -> Result<String, AppError> {
+    match cmd {
+        SkillCommand::List => {
+            let skills = SkillService::get_all_installed(&state.db)
+                .map_err(|e| AppError::Message(e.to_string()))?;
+            if json {
+                return to_json(&skills);
+            }
+            Ok(skills
+                .iter()
+                .map(|s| {
+                    let apps = s
+                        .apps
+                        .enabled_apps()
+                        .iter()
+                        .map(|a| a.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!("{}\t{}\t[{apps}]", s.id, s.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        SkillCommand::Toggle { id, app, toggle } => {
+            let skill = state
+                .db
+                .get_installed_skill(id)?
+                .ok_or_else(|| AppError::Message(format!("Skill {id} 不存在")))?;
+            let enabled = toggle.resolve(skill.apps.is_enabled_for(&app.app));
+            SkillService::toggle_app(&state.db, id, &app.app, enabled)
+                .map_err(|e| AppError::Message(e.to_string()))?;
+            toggled(json, &app.app, id, enabled)
+        }
+    }
+}
+
+fn read_provider_file(file: &PathBuf) -> Result<Provider, AppError> {
+    let content = if file.as_os_str() == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| AppError::IoContext {
+                context: "读取标准输入失败".to_string(),
+                source: e,
+            })?;
+        buf
+    } else {
+        std::fs::read_to_string(file).map_err(|e| AppError::io(file, e))?
+    };
+    serde_json::from_str(&content).map_err(|e| AppError::json(file, e))
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string_pretty(value).map_err(|e| AppError::JsonSerialize { source: e })
+}
+
+fn done(json: bool, action: &str, app: &AppType, id: &str) -> Result<String, AppError> {
+    if json {
+        return to_json(&serde_json::json!({
+            "ok": true,
+            "action": action,
+            "app": app.as_str(),
+            "id": id,
+        }));
+    }
+    Ok(format!("{action}: {id} ({})", app.as_str()))
+}
+
+fn toggled(json: bool, app: &AppType, id: &str, enabled: bool) -> Result<String, AppError> {
+    if json {
+        return to_json(&serde_json::json!({
+            "ok": true,
+            "app": app.as_str(),
+            "id": id,
+            "enabled": enabled,
+        }));
+    }
+    let state = if enabled { "enabled" } else { "disabled" };
+    Ok(format!("{id}: {state} for {}", app.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("cc-switch-cli").chain(args.iter().copied()))
+            .expect("parse args")
+    }
+
+    #[test]
+    fn proxy_serve_takes_over_by_default() {
+        let cli = parse(&["proxy", "serve"]);
+        assert!(matches!(
+            cli.command,
+            Command::Proxy(ProxyCommand::Serve {
+                no_takeover: false,
+                verbose: false
+            })
+        ));
+
+        let cli = parse(&["proxy", "serve", "--no-takeover", "-v"]);
+        assert!(matches!(
+            cli.command,
+            Command::Proxy(ProxyCommand::Serve {
+                no_takeover: true,
+                verbose: true
+            })
+        ));
+    }
+
+    #[test]
+    fn provider_commands_default_to_claude() {
+        let cli = parse(&["provider", "switch", "p1"]);
+        match cli.command {
+            Command::Provider(ProviderCommand::Switch { id, app }) => {
+                assert_eq!(id, "p1");
+                assert_eq!(app.app, AppType::Claude);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn global_json_flag_after_subcommand() {
+        let cli = parse(&["provider", "list", "--app", "codex", "--json"]);
+        assert!(cli.json);
+        assert!(matches!(
+            cli.command,
+            Command::Provider(ProviderCommand::List(AppArg {
+                app: AppType::Codex
+            }))
+        ));
+    }
+
+    #[test]
+    fn toggle_requires_app_and_rejects_conflicting_flags() {
+        let base = ["cc-switch-cli", "mcp", "toggle", "fetch"];
+        assert!(Cli::try_parse_from(base).is_err());
+        assert!(Cli::try_parse_from(base.iter().copied().chain([
+            "--app",
+            "gemini",
+            "--enable",
+            "--disable"
+        ]))
+        .is_err());
+        assert!(Cli::try_parse_from(base.iter().copied().chain(["--app", "unknown"])).is_err());
+    }
+
+    #[test]
+    fn toggle_flips_when_no_flag_given() {
+        let flip = ToggleArg {
+            enable: false,
+            disable: false,
+        };
+        assert!(flip.resolve(false));
+        assert!(!flip.resolve(true));
+        let on = ToggleArg {
+            enable: true,
+            disable: false,
+        };
+        assert!(on.resolve(true));
+    }
+
+    #[test]
+    fn mcp_list_outputs_json_array() {
+        let db = Arc::new(Database::memory().expect("memory db"));
+        let state = AppState::new(db);
+        let output = execute(&state, &Command::Mcp(McpCommand::List), true).expect("execute");
+        let value: serde_json::Value = serde_json::from_str(&output).expect("valid json");
+        assert_eq!(value, serde_json::json!([]));
+    }
+}
@@ -0,0 +1,177 @@
+//! `proxy serve`：无界面常驻运行本地代理
+//!
+//! 以 `app_handle: None` 启动 `ProxyServer`，配置全部从数据库读取；
+//! 收到 SIGINT / SIGTERM 后通过 `stop_with_restore` 恢复 Live 配置再退出。
+
+use crate::error::AppError;
+use crate::store::AppState;
+
+/// 守护进程输出到标准错误的简易日志实现（GUI 使用 tauri-plugin-log，此处无 AppHandle 可用）
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "{} [{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+fn init_logger(verbose: bool) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(if verbose {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Info
+        });
+    }
+}
+
+/// 前台运行代理直到收到退出信号
+pub(super) fn serve(
+    state: &AppState,
+    takeover: bool,
+    verbose: bool,
+    json: bool,
+) -> Result<String, AppError> {
+    init_logger(verbose);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| AppError::IoContext {
+            context: "创建 tokio 运行时失败".to_string(),
+            source: e,
+        })?;
+
+    runtime.block_on(serve_async(state, takeover, json))
+}
+
+async fn serve_async(state: &AppState, takeover: bool, json: bool) -> Result<String, AppError> {
+    init_http_client(state);
+    recover_leftover_takeover(state).await;
+
+    let service = &state.proxy_service;
+    let info = if takeover {
+        let info = service
+            .start_with_takeover()
+            .await
+            .map_err(AppError::Message)?;
+        mark_taken_over_apps(state).await;
+        info
+    } else {
+        service.start().await.map_err(AppError::Message)?
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&info).map_err(|e| AppError::JsonSerialize { source: e })?
+        );
+    } else {
+        println!(
+            "Proxy listening on {}://{}:{} (takeover: {})",
+            if info.tls { "https" } else { "http" },
+            info.address,
+            info.port,
+            if takeover { "on" } else { "off" }
+        );
+    }
+
+    wait_for_shutdown_signal().await;
+    log::info!("收到退出信号，正在停止代理并恢复 Live 配置...");
+
+    if takeover {
+        service
+            .stop_with_restore()
+            .await
+            .map_err(AppError::Message)?;
+    } else {
+        service.stop().await.map_err(AppError::Message)?;
+    }
+
+    Ok(String::new())
+}
+
+/// 与桌面端启动流程一致：使用数据库中保存的全局出站代理初始化 HTTP 客户端
+fn init_http_client(state: &AppState) {
+    let proxy_url = state.db.get_global_proxy_url().ok().flatten();
+    if let Err(e) = crate::proxy::http_client::init(proxy_url.as_deref()) {
+        log::error!("[GlobalProxy] Failed to initialize with saved config: {e}");
+        if let Err(fallback_err) = crate::proxy::http_client::init(None) {
+            log::error!("[GlobalProxy] Failed to initialize direct connection: {fallback_err}");
+        }
+    }
+}
+
+/// 上次进程被强制结束时可能残留接管状态，先恢复再重新接管，避免把占位符当作原始配置备份
+async fn recover_leftover_takeover(state: &AppState) {
+    let has_backups = state.db.has_any_live_backup().await.unwrap_or(false);
+    let live_taken_over = state.proxy_service.detect_takeover_in_live_configs();
+
+    if has_backups || live_taken_over {
+        log::warn!("检测到接管残留，正在恢复 Live 配置...");
+        if let Err(e) = state.proxy_service.recover_from_crash().await {
+            log::error!("恢复 Live 配置失败: {e}");
+        }
+    }
+}
+
+/// `start_with_takeover` 只写入 Live 配置；同步标记各应用的 `proxy_config.enabled`，
+/// 使故障转移切换与 `stop_with_restore` 的状态清理对这些应用生效。
+async fn mark_taken_over_apps(state: &AppState) {
+    for app_type in ["claude", "codex", "gemini"] {
+        let has_backup = matches!(state.db.get_live_backup(app_type).await, Ok(Some(_)));
+        if !has_backup {
+            continue;
+        }
+        match state.db.get_proxy_config_for_app(app_type).await {
+            Ok(mut config) if !config.enabled => {
+                config.enabled = true;
+                if let Err(e) = state.db.update_proxy_config_for_app(config).await {
+                    log::warn!("设置 {app_type} enabled 状态失败: {e}");
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("读取 {app_type} 代理配置失败: {e}"),
+        }
+    }
+}
+
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(e) => {
+                log::warn!("注册 SIGTERM 处理失败，仅监听 Ctrl+C: {e}");
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
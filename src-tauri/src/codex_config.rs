@@ -13,10 +13,10 @@ use std::path::Path;
 /// 获取 Codex 配置目录路径
 pub fn get_codex_config_dir() -> PathBuf {
     if let Some(custom) = crate::settings::get_codex_override_dir() {
-        return custom;
+        return crate::live_watch::redirect(custom);
     }
 
-    get_home_dir().join(".codex")
+    crate::live_watch::redirect(get_home_dir().join(".codex"))
 }
 
 /// 获取 Codex auth.json 路径
@@ -4,6 +4,7 @@
 
 use crate::database::FailoverQueueItem;
 use crate::provider::Provider;
+use crate::proxy::types::{HedgeConfig, LoadBalanceStrategy};
 use crate::store::AppState;
 use std::str::FromStr;
 use tauri::Emitter;
@@ -169,3 +170,81 @@ pub async fn set_auto_failover_enabled(
 
     Ok(())
 }
+
+/// 获取指定应用故障转移队列的负载均衡策略
+#[tauri::command]
+pub async fn get_load_balance_strategy(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<LoadBalanceStrategy, String> {
+    state
+        .db
+        .get_load_balance_strategy(&app_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置指定应用故障转移队列的负载均衡策略
+#[tauri::command]
+pub async fn set_load_balance_strategy(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    strategy: LoadBalanceStrategy,
+) -> Result<(), String> {
+    log::info!(
+        "[Failover] Setting load_balance_strategy: app_type='{app_type}', strategy={}",
+        strategy.as_str()
+    );
+    state
+        .db
+        .set_load_balance_strategy(&app_type, strategy)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取指定应用的对冲请求配置
+#[tauri::command]
+pub async fn get_hedge_config(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<HedgeConfig, String> {
+    state
+        .db
+        .get_hedge_config(&app_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置指定应用的对冲请求配置
+///
+/// 等待时间需小于流式首字超时，否则首字超时会先于对冲触发
+#[tauri::command]
+pub async fn set_hedge_config(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    config: HedgeConfig,
+) -> Result<(), String> {
+    if config.enabled {
+        if config.delay_ms == 0 {
+            return Err("对冲等待时间必须大于 0".to_string());
+        }
+        let app_config = state
+            .db
+            .get_proxy_config_for_app(&app_type)
+            .await
+            .map_err(|e| e.to_string())?;
+        let first_byte_timeout_ms = app_config.streaming_first_byte_timeout as u64 * 1000;
+        if first_byte_timeout_ms > 0 && config.delay_ms as u64 >= first_byte_timeout_ms {
+            return Err(format!(
+                "对冲等待时间需小于流式首字超时（{}ms）",
+                first_byte_timeout_ms
+            ));
+        }
+    }
+
+    state
+        .db
+        .set_hedge_config(&app_type, config)
+        .await
+        .map_err(|e| e.to_string())
+}
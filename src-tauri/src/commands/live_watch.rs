@@ -0,0 +1,37 @@
+//! live 配置外部修改命令
+//!
+//! 监视任务检测到的外部修改通过 `live-config-changed` 事件通知前端，由用户选择吸收、
+//! 重新应用或忽略
+
+use tauri::State;
+
+use crate::live_watch::LiveConfigChange;
+use crate::services::LiveWatchService;
+use crate::store::AppState;
+
+/// 获取尚未处理的外部修改
+#[tauri::command]
+pub async fn get_live_config_changes() -> Result<Vec<LiveConfigChange>, String> {
+    Ok(LiveWatchService::get_changes())
+}
+
+/// 把外部修改吸收到数据库
+#[tauri::command]
+pub async fn absorb_live_config_change(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<(), String> {
+    LiveWatchService::absorb(&state, &path).map_err(|e| e.to_string())
+}
+
+/// 按数据库重新写入 live 配置，覆盖外部修改
+#[tauri::command]
+pub async fn reapply_live_config(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    LiveWatchService::reapply(&state, &path).map_err(|e| e.to_string())
+}
+
+/// 忽略外部修改
+#[tauri::command]
+pub async fn dismiss_live_config_change(path: String) -> Result<(), String> {
+    LiveWatchService::dismiss(&path).map_err(|e| e.to_string())
+}
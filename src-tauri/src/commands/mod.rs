@@ -6,8 +6,10 @@ mod env;
 mod failover;
 mod global_proxy;
 mod import_export;
+mod live_watch;
 mod mcp;
 mod misc;
+mod model_route;
 mod plugin;
 mod prompt;
 mod provider;
@@ -17,6 +19,8 @@ mod settings;
 pub mod skill;
 mod stream_check;
 mod usage;
+mod virtual_key;
+mod workspace;
 
 pub use config::*;
 pub use deeplink::*;
@@ -24,8 +28,10 @@ pub use env::*;
 pub use failover::*;
 pub use global_proxy::*;
 pub use import_export::*;
+pub use live_watch::*;
 pub use mcp::*;
 pub use misc::*;
+pub use model_route::*;
 pub use plugin::*;
 pub use prompt::*;
 pub use provider::*;
@@ -35,3 +41,5 @@ pub use settings::*;
 pub use skill::*;
 pub use stream_check::*;
 pub use usage::*;
+pub use virtual_key::*;
+pub use workspace::*;
@@ -0,0 +1,56 @@
+//! 模型路由命令
+//!
+//! 管理代理模式下的跨供应商模型路由（model_routes 表）
+
+use crate::provider::ModelMatchType;
+use crate::proxy::types::ModelRoute;
+use crate::store::AppState;
+
+/// 获取指定应用的模型路由
+#[tauri::command]
+pub async fn get_model_routes(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<Vec<ModelRoute>, String> {
+    state
+        .db
+        .get_model_routes(&app_type)
+        .map_err(|e| e.to_string())
+}
+
+/// 新增或更新模型路由（id 为空时自动生成）
+#[tauri::command]
+pub async fn save_model_route(
+    state: tauri::State<'_, AppState>,
+    mut route: ModelRoute,
+) -> Result<ModelRoute, String> {
+    if route.pattern.trim().is_empty() {
+        return Err("模型匹配模式不能为空".to_string());
+    }
+    if route.match_type == ModelMatchType::Regex {
+        regex::Regex::new(&route.pattern).map_err(|e| format!("无效的正则表达式: {e}"))?;
+    }
+    if route.id.trim().is_empty() {
+        route.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    state
+        .db
+        .save_model_route(&route)
+        .map_err(|e| e.to_string())?;
+
+    Ok(route)
+}
+
+/// 删除模型路由
+#[tauri::command]
+pub async fn delete_model_route(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    id: String,
+) -> Result<(), String> {
+    state
+        .db
+        .delete_model_route(&app_type, &id)
+        .map_err(|e| e.to_string())
+}
@@ -1,10 +1,12 @@
 use indexmap::IndexMap;
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use tauri::State;
 
 use crate::app_config::AppType;
 use crate::prompt::Prompt;
+use crate::prompt_revision::{DiffLine, PromptRevision, PromptRevisionSource};
 use crate::services::PromptService;
 use crate::store::AppState;
 
@@ -25,7 +27,8 @@ pub async fn upsert_prompt(
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    PromptService::upsert_prompt(&state, app_type, &id, prompt).map_err(|e| e.to_string())
+    PromptService::upsert_prompt(&state, app_type, &id, prompt, PromptRevisionSource::Ui)
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -62,3 +65,66 @@ pub async fn get_current_prompt_file_content(app: String) -> Result<Option<Strin
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
     PromptService::get_current_file_content(app_type).map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+pub async fn get_prompt_variables(
+    app: String,
+    state: State<'_, AppState>,
+) -> Result<BTreeMap<String, String>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    PromptService::get_variables(&state, app_type).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_prompt_variables(
+    app: String,
+    variables: BTreeMap<String, String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    PromptService::set_variables(&state, app_type, variables).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_prompt_revisions(
+    app: String,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<PromptRevision>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    PromptService::get_revisions(&state, app_type, &id).map_err(|e| e.to_string())
+}
+
+/// 按行对比两个版本（未指定 `to` 时与当前内容对比）
+#[tauri::command]
+pub async fn diff_prompt_revisions(
+    app: String,
+    id: String,
+    from: i64,
+    to: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DiffLine>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    PromptService::diff_revisions(&state, app_type, &id, from, to).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rollback_prompt(
+    app: String,
+    id: String,
+    revision_id: i64,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    PromptService::rollback_prompt(&state, app_type, &id, revision_id).map_err(|e| e.to_string())
+}
+
+/// 已删除的提示词（通过 `rollback_prompt` 回滚到其版本即可恢复）
+#[tauri::command]
+pub async fn get_deleted_prompts(
+    app: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<PromptRevision>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    PromptService::get_deleted_prompts(&state, app_type).map_err(|e| e.to_string())
+}
@@ -234,6 +234,133 @@ pub async fn set_pricing_model_source(
         .map_err(|e| e.to_string())
 }
 
+/// 获取响应缓存配置
+#[tauri::command]
+pub async fn get_response_cache_config(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<ResponseCacheConfig, String> {
+    state
+        .db
+        .get_response_cache_config(&app_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置响应缓存配置（关闭时同时清空该应用的缓存）
+#[tauri::command]
+pub async fn set_response_cache_config(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    config: ResponseCacheConfig,
+) -> Result<(), String> {
+    let db = &state.db;
+    db.set_response_cache_config(&app_type, config)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !config.enabled {
+        db.clear_response_cache(Some(&app_type))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 清空响应缓存（不指定 app_type 时清空全部），返回删除的条目数
+#[tauri::command]
+pub async fn clear_response_cache(
+    state: tauri::State<'_, AppState>,
+    app_type: Option<String>,
+) -> Result<usize, String> {
+    state
+        .db
+        .clear_response_cache(app_type.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 获取请求/响应体抓取配置
+#[tauri::command]
+pub async fn get_body_capture_config(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<BodyCaptureConfig, String> {
+    state
+        .db
+        .get_body_capture_config(&app_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置请求/响应体抓取配置
+#[tauri::command]
+pub async fn set_body_capture_config(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    config: BodyCaptureConfig,
+) -> Result<(), String> {
+    state
+        .db
+        .set_body_capture_config(&app_type, config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 清空抓取的请求/响应体（不指定 app_type 时清空全部），返回删除的条数
+#[tauri::command]
+pub async fn clear_request_body_captures(
+    state: tauri::State<'_, AppState>,
+    app_type: Option<String>,
+) -> Result<usize, String> {
+    state
+        .db
+        .clear_request_body_captures(app_type.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 获取代理入站访问控制配置
+#[tauri::command]
+pub async fn get_proxy_auth_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<ProxyAuthConfig, String> {
+    state.db.get_proxy_auth_config().map_err(|e| e.to_string())
+}
+
+/// 设置代理入站访问控制配置（代理运行时立即生效）
+#[tauri::command]
+pub async fn set_proxy_auth_config(
+    state: tauri::State<'_, AppState>,
+    config: ProxyAuthConfig,
+) -> Result<(), String> {
+    state.proxy_service.update_auth_config(config).await
+}
+
+/// 获取代理 TLS 配置
+#[tauri::command]
+pub async fn get_proxy_tls_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<ProxyTlsConfig, String> {
+    state.db.get_proxy_tls_config().map_err(|e| e.to_string())
+}
+
+/// 设置代理 TLS 配置（下次启动代理时生效）
+#[tauri::command]
+pub async fn set_proxy_tls_config(
+    state: tauri::State<'_, AppState>,
+    config: ProxyTlsConfig,
+) -> Result<(), String> {
+    state
+        .db
+        .set_proxy_tls_config(&config)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取代理实际使用的 TLS 证书路径（供客户端信任自签名证书）
+#[tauri::command]
+pub async fn get_proxy_tls_cert_path(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let config = state.db.get_proxy_tls_config().map_err(|e| e.to_string())?;
+    let (cert_path, _) = crate::proxy::tls::resolve_paths(&config);
+    Ok(cert_path.to_string_lossy().to_string())
+}
+
 /// 检查代理服务器是否正在运行
 #[tauri::command]
 pub async fn is_proxy_running(state: tauri::State<'_, AppState>) -> Result<bool, String> {
@@ -269,9 +396,15 @@ pub async fn get_provider_health(
     app_type: String,
 ) -> Result<ProviderHealth, String> {
     let db = &state.db;
-    db.get_provider_health(&provider_id, &app_type)
+    let mut health = db
+        .get_provider_health(&provider_id, &app_type)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    health.rate_limit = state
+        .proxy_service
+        .get_provider_rate_limit(&provider_id, &app_type)
+        .await;
+    Ok(health)
 }
 
 /// 重置熔断器
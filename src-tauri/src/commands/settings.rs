@@ -81,6 +81,28 @@ pub async fn set_rectifier_config(
     Ok(true)
 }
 
+/// 获取链路追踪配置
+#[tauri::command]
+pub async fn get_telemetry_config(
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<crate::proxy::types::TelemetryConfig, String> {
+    state.db.get_telemetry_config().map_err(|e| e.to_string())
+}
+
+/// 设置链路追踪配置（立即生效，无需重启代理）
+#[tauri::command]
+pub async fn set_telemetry_config(
+    state: tauri::State<'_, crate::AppState>,
+    config: crate::proxy::types::TelemetryConfig,
+) -> Result<bool, String> {
+    state
+        .db
+        .set_telemetry_config(&config)
+        .map_err(|e| e.to_string())?;
+    crate::proxy::otlp::configure(config);
+    Ok(true)
+}
+
 /// 获取日志配置
 #[tauri::command]
 pub async fn get_log_config(
@@ -2,9 +2,7 @@
 
 use crate::app_config::AppType;
 use crate::error::AppError;
-use crate::services::stream_check::{
-    HealthStatus, StreamCheckConfig, StreamCheckResult, StreamCheckService,
-};
+use crate::services::stream_check::{StreamCheckConfig, StreamCheckResult, StreamCheckService};
 use crate::store::AppState;
 use std::collections::HashSet;
 use tauri::State;
@@ -69,16 +67,7 @@ pub async fn stream_check_all_providers(
 
         let result = StreamCheckService::check_with_retry(&app_type, &provider, &config)
             .await
-            .unwrap_or_else(|e| StreamCheckResult {
-                status: HealthStatus::Failed,
-                success: false,
-                message: e.to_string(),
-                response_time_ms: None,
-                http_status: None,
-                model_used: String::new(),
-                tested_at: chrono::Utc::now().timestamp(),
-                retry_count: 0,
-            });
+            .unwrap_or_else(|e| StreamCheckResult::failed(e.to_string()));
 
         let _ = state
             .db
@@ -1,6 +1,8 @@
 //! 使用统计相关命令
 
 use crate::error::AppError;
+use crate::proxy::body_capture::replay_capture;
+use crate::proxy::types::ReplayResult;
 use crate::services::usage_stats::*;
 use crate::store::AppState;
 use tauri::State;
@@ -31,6 +33,12 @@ pub fn get_provider_stats(state: State<'_, AppState>) -> Result<Vec<ProviderStat
     state.db.get_provider_stats()
 }
 
+/// 获取虚拟 Key 统计
+#[tauri::command]
+pub fn get_virtual_key_stats(state: State<'_, AppState>) -> Result<Vec<VirtualKeyStats>, AppError> {
+    state.db.get_virtual_key_stats()
+}
+
 /// 获取模型统计
 #[tauri::command]
 pub fn get_model_stats(state: State<'_, AppState>) -> Result<Vec<ModelStats>, AppError> {
@@ -57,6 +65,25 @@ pub fn get_request_detail(
     state.db.get_request_detail(&request_id)
 }
 
+/// 将抓取的请求重放到指定供应商
+#[tauri::command]
+pub async fn replay_captured_request(
+    state: State<'_, AppState>,
+    request_id: String,
+    provider_id: String,
+) -> Result<ReplayResult, AppError> {
+    let capture = state
+        .db
+        .get_request_body_capture(&request_id, chrono::Utc::now().timestamp())?
+        .ok_or_else(|| AppError::Message(format!("未找到请求 {request_id} 的抓取记录")))?;
+    let provider = state
+        .db
+        .get_provider_by_id(&provider_id, &capture.app_type)?
+        .ok_or_else(|| AppError::Message(format!("供应商不存在: {provider_id}")))?;
+
+    replay_capture(state.db.clone(), &capture, &provider).await
+}
+
 /// 获取模型定价列表
 #[tauri::command]
 pub fn get_model_pricing(state: State<'_, AppState>) -> Result<Vec<ModelPricingInfo>, AppError> {
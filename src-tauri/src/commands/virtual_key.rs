@@ -0,0 +1,86 @@
+//! 虚拟 Key 命令
+//!
+//! 管理代理的多用户虚拟 Key（proxy_virtual_keys 表）
+
+use crate::proxy::types::{CreatedVirtualKey, VirtualKey};
+use crate::proxy::virtual_key;
+use crate::store::AppState;
+
+/// 校验并规范化虚拟 Key 的可编辑字段
+fn normalize(key: &mut VirtualKey) -> Result<(), String> {
+    key.name = key.name.trim().to_string();
+    if key.name.is_empty() {
+        return Err("虚拟 Key 名称不能为空".to_string());
+    }
+
+    for limit in [&mut key.limit_daily_usd, &mut key.limit_monthly_usd] {
+        *limit = limit
+            .take()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        if let Some(value) = limit.as_deref() {
+            match value.parse::<f64>() {
+                Ok(v) if v >= 0.0 => {}
+                _ => return Err(format!("无效的消费限额: {value}")),
+            }
+        }
+    }
+
+    key.allowed_apps.retain(|app| !app.trim().is_empty());
+    key.allowed_models.retain(|model| !model.trim().is_empty());
+    Ok(())
+}
+
+/// 获取全部虚拟 Key
+#[tauri::command]
+pub async fn get_virtual_keys(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<VirtualKey>, String> {
+    state.db.get_virtual_keys().map_err(|e| e.to_string())
+}
+
+/// 新建虚拟 Key（明文 Key 只在此处返回一次）
+#[tauri::command]
+pub async fn create_virtual_key(
+    state: tauri::State<'_, AppState>,
+    mut key: VirtualKey,
+) -> Result<CreatedVirtualKey, String> {
+    normalize(&mut key)?;
+
+    let secret = virtual_key::generate_secret();
+    key.id = uuid::Uuid::new_v4().to_string();
+    key.key_prefix = virtual_key::display_prefix(&secret);
+    key.created_at = chrono::Utc::now().timestamp();
+
+    state
+        .db
+        .insert_virtual_key(&key, &virtual_key::hash_secret(&secret))
+        .map_err(|e| e.to_string())?;
+
+    Ok(CreatedVirtualKey { key, secret })
+}
+
+/// 更新虚拟 Key 的名称、权限与限额
+#[tauri::command]
+pub async fn update_virtual_key(
+    state: tauri::State<'_, AppState>,
+    mut key: VirtualKey,
+) -> Result<VirtualKey, String> {
+    normalize(&mut key)?;
+
+    state
+        .db
+        .update_virtual_key(&key)
+        .map_err(|e| e.to_string())?;
+
+    Ok(key)
+}
+
+/// 删除虚拟 Key
+#[tauri::command]
+pub async fn delete_virtual_key(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    state.db.delete_virtual_key(&id).map_err(|e| e.to_string())
+}
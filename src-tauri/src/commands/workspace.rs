@@ -0,0 +1,82 @@
+//! 工作区命令
+//!
+//! 管理登记的项目目录，以及提示词/MCP 在各工作区中的启用状态
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use tauri::State;
+
+use crate::app_config::AppType;
+use crate::services::WorkspaceService;
+use crate::store::AppState;
+use crate::workspace::Workspace;
+
+/// 获取全部工作区（含各工作区中启用的提示词/MCP）
+#[tauri::command]
+pub async fn get_workspaces(state: State<'_, AppState>) -> Result<Vec<Workspace>, String> {
+    WorkspaceService::get_workspaces(&state).map_err(|e| e.to_string())
+}
+
+/// 登记项目目录（名称为空时使用目录名）
+#[tauri::command]
+pub async fn add_workspace(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+) -> Result<Workspace, String> {
+    WorkspaceService::add_workspace(&state, &name, &path).map_err(|e| e.to_string())
+}
+
+/// 移除工作区，并清理写入项目文件的内容
+#[tauri::command]
+pub async fn remove_workspace(state: State<'_, AppState>, id: String) -> Result<bool, String> {
+    WorkspaceService::remove_workspace(&state, &id).map_err(|e| e.to_string())
+}
+
+/// 在工作区中启用/停用提示词
+#[tauri::command]
+pub async fn set_workspace_prompt_enabled(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    app: String,
+    prompt_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    WorkspaceService::set_prompt_enabled(&state, &workspace_id, app_type, &prompt_id, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// 在工作区中启用/停用 MCP 服务器
+#[tauri::command]
+pub async fn set_workspace_mcp_enabled(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    server_id: String,
+    app: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    WorkspaceService::set_mcp_enabled(&state, &workspace_id, &server_id, app_type, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取工作区的提示词模板变量
+#[tauri::command]
+pub async fn get_workspace_variables(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<BTreeMap<String, String>, String> {
+    WorkspaceService::get_variables(&state, &workspace_id).map_err(|e| e.to_string())
+}
+
+/// 更新工作区的提示词模板变量
+#[tauri::command]
+pub async fn set_workspace_variables(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    variables: BTreeMap<String, String>,
+) -> Result<(), String> {
+    WorkspaceService::set_variables(&state, &workspace_id, variables).map_err(|e| e.to_string())
+}
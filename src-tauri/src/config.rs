@@ -35,15 +35,15 @@ pub fn get_home_dir() -> PathBuf {
 /// 获取 Claude Code 配置目录路径
 pub fn get_claude_config_dir() -> PathBuf {
     if let Some(custom) = crate::settings::get_claude_override_dir() {
-        return custom;
+        return crate::live_watch::redirect(custom);
     }
 
-    get_home_dir().join(".claude")
+    crate::live_watch::redirect(get_home_dir().join(".claude"))
 }
 
 /// 默认 Claude MCP 配置文件路径 (~/.claude.json)
 pub fn get_default_claude_mcp_path() -> PathBuf {
-    get_home_dir().join(".claude.json")
+    crate::live_watch::redirect(get_home_dir().join(".claude.json"))
 }
 
 fn derive_mcp_path_from_override(dir: &Path) -> Option<PathBuf> {
@@ -63,7 +63,7 @@ fn derive_mcp_path_from_override(dir: &Path) -> Option<PathBuf> {
 pub fn get_claude_mcp_path() -> PathBuf {
     if let Some(custom_dir) = crate::settings::get_claude_override_dir() {
         if let Some(path) = derive_mcp_path_from_override(&custom_dir) {
-            return path;
+            return crate::live_watch::redirect(path);
         }
     }
     get_default_claude_mcp_path()
@@ -216,6 +216,9 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), AppError> {
         }
     }
 
+    // 替换前登记，监视任务不会把这次写入当作外部修改
+    crate::live_watch::record_write(path, data);
+
     #[cfg(windows)]
     {
         // Windows 上 rename 目标存在会失败，先移除再重命名（尽量接近原子性）
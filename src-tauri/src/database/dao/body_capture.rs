@@ -0,0 +1,160 @@
+//! 请求/响应体抓取 DAO
+//!
+//! 管理抓取配置（proxy_config 表）与抓取记录（proxy_request_bodies 表）
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::types::{BodyCaptureConfig, RequestBodyCapture};
+use rusqlite::OptionalExtension;
+
+impl Database {
+    /// 获取请求/响应体抓取配置
+    pub async fn get_body_capture_config(
+        &self,
+        app_type: &str,
+    ) -> Result<BodyCaptureConfig, AppError> {
+        let result = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT body_capture_enabled, body_capture_max_bytes, body_capture_retention_hours
+                 FROM proxy_config WHERE app_type = ?1",
+                [app_type],
+                |row| {
+                    Ok(BodyCaptureConfig {
+                        enabled: row.get::<_, i64>(0)? != 0,
+                        max_body_bytes: row.get::<_, i64>(1)?.max(0) as u32,
+                        retention_hours: row.get::<_, i64>(2)?.max(0) as u32,
+                    })
+                },
+            )
+        };
+
+        match result {
+            Ok(config) => Ok(config),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(BodyCaptureConfig::default())
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 设置请求/响应体抓取配置
+    pub async fn set_body_capture_config(
+        &self,
+        app_type: &str,
+        config: BodyCaptureConfig,
+    ) -> Result<(), AppError> {
+        // 确保行存在
+        self.ensure_proxy_config_row_exists(app_type)?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_config SET
+                body_capture_enabled = ?2,
+                body_capture_max_bytes = ?3,
+                body_capture_retention_hours = ?4,
+                updated_at = datetime('now')
+             WHERE app_type = ?1",
+            rusqlite::params![
+                app_type,
+                config.enabled as i64,
+                config.max_body_bytes as i64,
+                config.retention_hours as i64
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 保存抓取记录，并清理已过期的记录
+    pub fn save_request_body_capture(
+        &self,
+        capture: &RequestBodyCapture,
+        retention_hours: u32,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "INSERT OR REPLACE INTO proxy_request_bodies
+                (request_id, app_type, endpoint, request_headers, request_body, request_truncated,
+                 response_status, response_headers, response_body, response_truncated,
+                 created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                capture.request_id,
+                capture.app_type,
+                capture.endpoint,
+                capture.request_headers,
+                capture.request_body,
+                capture.request_truncated as i64,
+                capture.response_status as i64,
+                capture.response_headers,
+                capture.response_body,
+                capture.response_truncated as i64,
+                capture.created_at,
+                capture.created_at + retention_hours as i64 * 3600,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "DELETE FROM proxy_request_bodies WHERE expires_at <= ?1",
+            [capture.created_at],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 获取未过期的抓取记录
+    pub fn get_request_body_capture(
+        &self,
+        request_id: &str,
+        now: i64,
+    ) -> Result<Option<RequestBodyCapture>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.query_row(
+            "SELECT request_id, app_type, endpoint, request_headers, request_body,
+                    request_truncated, response_status, response_headers, response_body,
+                    response_truncated, created_at
+             FROM proxy_request_bodies
+             WHERE request_id = ?1 AND expires_at > ?2",
+            rusqlite::params![request_id, now],
+            |row| {
+                Ok(RequestBodyCapture {
+                    request_id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    endpoint: row.get(2)?,
+                    request_headers: row.get(3)?,
+                    request_body: row.get(4)?,
+                    request_truncated: row.get::<_, i64>(5)? != 0,
+                    response_status: row.get::<_, i64>(6)? as u16,
+                    response_headers: row.get(7)?,
+                    response_body: row.get(8)?,
+                    response_truncated: row.get::<_, i64>(9)? != 0,
+                    created_at: row.get(10)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 清空抓取记录（指定 app_type 时只清空该应用），返回删除的条数
+    pub fn clear_request_body_captures(&self, app_type: Option<&str>) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        let deleted = match app_type {
+            Some(app_type) => conn.execute(
+                "DELETE FROM proxy_request_bodies WHERE app_type = ?1",
+                [app_type],
+            ),
+            None => conn.execute("DELETE FROM proxy_request_bodies", []),
+        }
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(deleted)
+    }
+}
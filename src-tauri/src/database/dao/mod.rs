@@ -2,15 +2,21 @@
 //!
 //! Database access operations for each domain
 
+pub mod body_capture;
 pub mod failover;
 pub mod mcp;
+pub mod model_routes;
+pub mod prompt_revisions;
 pub mod prompts;
 pub mod providers;
 pub mod proxy;
+pub mod response_cache;
 pub mod settings;
 pub mod skills;
 pub mod stream_check;
 pub mod universal_providers;
+pub mod virtual_keys;
+pub mod workspaces;
 
 // 所有 DAO 方法都通过 Database impl 提供，无需单独导出
 // 导出 FailoverQueueItem 供外部使用
@@ -0,0 +1,109 @@
+//! 模型路由 DAO
+//!
+//! 管理代理模式下的跨供应商模型路由（model_routes 表）
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::provider::ModelMatchType;
+use crate::proxy::types::ModelRoute;
+
+fn match_type_to_str(match_type: ModelMatchType) -> &'static str {
+    match match_type {
+        ModelMatchType::Glob => "glob",
+        ModelMatchType::Regex => "regex",
+    }
+}
+
+fn match_type_from_str(value: &str) -> ModelMatchType {
+    match value {
+        "regex" => ModelMatchType::Regex,
+        _ => ModelMatchType::Glob,
+    }
+}
+
+impl Database {
+    /// 获取某个应用的模型路由（按 sort_index 排序）
+    pub fn get_model_routes(&self, app_type: &str) -> Result<Vec<ModelRoute>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_type, name, pattern, match_type, provider_ids, enabled, sort_index
+                 FROM model_routes
+                 WHERE app_type = ?1
+                 ORDER BY sort_index ASC, created_at ASC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let routes = stmt
+            .query_map([app_type], |row| {
+                let match_type: String = row.get(4)?;
+                let provider_ids: String = row.get(5)?;
+                Ok(ModelRoute {
+                    id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    name: row.get(2)?,
+                    pattern: row.get(3)?,
+                    match_type: match_type_from_str(&match_type),
+                    provider_ids: serde_json::from_str(&provider_ids).unwrap_or_default(),
+                    enabled: row.get(6)?,
+                    sort_index: row.get(7)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(routes)
+    }
+
+    /// 新增或更新模型路由
+    pub fn save_model_route(&self, route: &ModelRoute) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        let provider_ids = serde_json::to_string(&route.provider_ids)
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let now = chrono::Utc::now().timestamp();
+
+        conn.execute(
+            "INSERT INTO model_routes
+                (id, app_type, name, pattern, match_type, provider_ids, enabled, sort_index, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+             ON CONFLICT(id) DO UPDATE SET
+                app_type = excluded.app_type,
+                name = excluded.name,
+                pattern = excluded.pattern,
+                match_type = excluded.match_type,
+                provider_ids = excluded.provider_ids,
+                enabled = excluded.enabled,
+                sort_index = excluded.sort_index,
+                updated_at = excluded.updated_at",
+            rusqlite::params![
+                route.id,
+                route.app_type,
+                route.name,
+                route.pattern,
+                match_type_to_str(route.match_type),
+                provider_ids,
+                route.enabled,
+                route.sort_index,
+                now,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 删除模型路由
+    pub fn delete_model_route(&self, app_type: &str, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "DELETE FROM model_routes WHERE id = ?1 AND app_type = ?2",
+            rusqlite::params![id, app_type],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
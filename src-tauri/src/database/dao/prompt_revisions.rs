@@ -0,0 +1,180 @@
+//! 提示词版本历史 DAO
+//!
+//! 记录提示词内容的每次变化（prompt_revisions 表），见 [`crate::prompt_revision`]
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::prompt_revision::{PromptRevision, PromptRevisionSource, MAX_REVISIONS_PER_PROMPT};
+use rusqlite::{params, OptionalExtension};
+
+fn row_to_revision(row: &rusqlite::Row<'_>) -> rusqlite::Result<PromptRevision> {
+    let source: String = row.get(3)?;
+    Ok(PromptRevision {
+        id: row.get(0)?,
+        prompt_id: row.get(1)?,
+        content: row.get(2)?,
+        source: PromptRevisionSource::parse(&source).unwrap_or(PromptRevisionSource::Sync),
+        created_at: row.get(4)?,
+        prompt_name: row.get(5)?,
+    })
+}
+
+impl Database {
+    /// 获取提示词的全部版本（最新的在前）
+    pub fn get_prompt_revisions(
+        &self,
+        app_type: &str,
+        prompt_id: &str,
+    ) -> Result<Vec<PromptRevision>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, prompt_id, content, source, created_at, prompt_name FROM prompt_revisions
+                 WHERE app_type = ?1 AND prompt_id = ?2 ORDER BY id DESC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let revisions = stmt
+            .query_map(params![app_type, prompt_id], row_to_revision)
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(revisions)
+    }
+
+    /// 按 ID 获取提示词版本
+    pub fn get_prompt_revision(
+        &self,
+        app_type: &str,
+        revision_id: i64,
+    ) -> Result<Option<PromptRevision>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.query_row(
+            "SELECT id, prompt_id, content, source, created_at, prompt_name FROM prompt_revisions
+             WHERE app_type = ?1 AND id = ?2",
+            params![app_type, revision_id],
+            row_to_revision,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 记录提示词内容的变化（与最新版本相同时不记录）
+    ///
+    /// 提示词还没有任何版本时，`baseline`（保存前的内容及其时间）先作为 Sync 版本补录，
+    /// 避免启用版本历史前的内容在第一次修改时丢失
+    pub fn record_prompt_revision(
+        &self,
+        app_type: &str,
+        prompt_id: &str,
+        content: &str,
+        source: PromptRevisionSource,
+        baseline: Option<(&str, i64)>,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let latest: Option<String> = conn
+            .query_row(
+                "SELECT content FROM prompt_revisions
+                 WHERE app_type = ?1 AND prompt_id = ?2 ORDER BY id DESC LIMIT 1",
+                params![app_type, prompt_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if latest.as_deref() == Some(content) {
+            return Ok(());
+        }
+
+        let insert = |content: &str, source: PromptRevisionSource, created_at: i64| {
+            conn.execute(
+                "INSERT INTO prompt_revisions (app_type, prompt_id, content, source, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![app_type, prompt_id, content, source.as_str(), created_at],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))
+        };
+
+        if latest.is_none() {
+            if let Some((previous, updated_at)) = baseline.filter(|(c, _)| *c != content) {
+                insert(previous, PromptRevisionSource::Sync, updated_at)?;
+            }
+        }
+        insert(content, source, chrono::Utc::now().timestamp())?;
+
+        conn.execute(
+            "DELETE FROM prompt_revisions
+             WHERE app_type = ?1 AND prompt_id = ?2 AND id NOT IN (
+                SELECT id FROM prompt_revisions
+                WHERE app_type = ?1 AND prompt_id = ?2 ORDER BY id DESC LIMIT ?3
+             )",
+            params![app_type, prompt_id, MAX_REVISIONS_PER_PROMPT as i64],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 记录提示词被删除时的内容与名称（总是记录，作为已删除提示词的恢复入口）
+    pub fn record_prompt_deletion(
+        &self,
+        app_type: &str,
+        prompt_id: &str,
+        name: &str,
+        content: &str,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "INSERT INTO prompt_revisions
+                (app_type, prompt_id, content, source, created_at, prompt_name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                app_type,
+                prompt_id,
+                content,
+                PromptRevisionSource::Delete.as_str(),
+                chrono::Utc::now().timestamp(),
+                name
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 已删除（且尚未恢复）的提示词：每个提示词最近一次删除时记录的版本（最新的在前）
+    pub fn get_deleted_prompt_revisions(
+        &self,
+        app_type: &str,
+    ) -> Result<Vec<PromptRevision>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT r.id, r.prompt_id, r.content, r.source, r.created_at, r.prompt_name
+                 FROM prompt_revisions r
+                 WHERE r.app_type = ?1 AND r.source = ?2
+                   AND r.id = (SELECT MAX(id) FROM prompt_revisions
+                               WHERE app_type = r.app_type AND prompt_id = r.prompt_id)
+                   AND NOT EXISTS (SELECT 1 FROM prompts p
+                                   WHERE p.app_type = r.app_type AND p.id = r.prompt_id)
+                 ORDER BY r.id DESC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let revisions = stmt
+            .query_map(
+                params![app_type, PromptRevisionSource::Delete.as_str()],
+                row_to_revision,
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(revisions)
+    }
+}
@@ -14,7 +14,8 @@ impl Database {
         let conn = lock_conn!(self.conn);
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, content, description, enabled, created_at, updated_at
+                "SELECT id, name, content, description, enabled, created_at, updated_at,
+                    is_layer, layer_order
              FROM prompts WHERE app_type = ?1
              ORDER BY created_at ASC, id ASC",
             )
@@ -29,6 +30,8 @@ impl Database {
                 let enabled: bool = row.get(4)?;
                 let created_at: Option<i64> = row.get(5)?;
                 let updated_at: Option<i64> = row.get(6)?;
+                let layer: bool = row.get(7)?;
+                let layer_order: i32 = row.get(8)?;
 
                 Ok((
                     id.clone(),
@@ -40,6 +43,8 @@ impl Database {
                         enabled,
                         created_at,
                         updated_at,
+                        layer,
+                        layer_order,
                     },
                 ))
             })
@@ -58,8 +63,9 @@ impl Database {
         let conn = lock_conn!(self.conn);
         conn.execute(
             "INSERT OR REPLACE INTO prompts (
-                id, app_type, name, content, description, enabled, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                id, app_type, name, content, description, enabled, created_at, updated_at,
+                is_layer, layer_order
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
                 prompt.id,
                 app_type,
@@ -69,6 +75,8 @@ impl Database {
                 prompt.enabled,
                 prompt.created_at,
                 prompt.updated_at,
+                prompt.layer,
+                prompt.layer_order,
             ],
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
@@ -187,6 +187,104 @@ impl Database {
         Ok(())
     }
 
+    /// 获取负载均衡策略
+    pub async fn get_load_balance_strategy(
+        &self,
+        app_type: &str,
+    ) -> Result<LoadBalanceStrategy, AppError> {
+        let result: Result<String, _> = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT load_balance_strategy FROM proxy_config WHERE app_type = ?1",
+                [app_type],
+                |row| row.get(0),
+            )
+        };
+
+        match result {
+            Ok(value) => Ok(value.parse().unwrap_or_else(|e| {
+                log::warn!("[{app_type}] {e}，回退为按队列顺序");
+                LoadBalanceStrategy::Priority
+            })),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(LoadBalanceStrategy::Priority)
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 设置负载均衡策略
+    pub async fn set_load_balance_strategy(
+        &self,
+        app_type: &str,
+        strategy: LoadBalanceStrategy,
+    ) -> Result<(), AppError> {
+        // 确保行存在
+        self.ensure_proxy_config_row_exists(app_type)?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_config SET
+                load_balance_strategy = ?2,
+                updated_at = datetime('now')
+             WHERE app_type = ?1",
+            rusqlite::params![app_type, strategy.as_str()],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 获取对冲请求配置
+    pub async fn get_hedge_config(&self, app_type: &str) -> Result<HedgeConfig, AppError> {
+        let result = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT hedge_enabled, hedge_delay_ms FROM proxy_config WHERE app_type = ?1",
+                [app_type],
+                |row| {
+                    Ok(HedgeConfig {
+                        enabled: row.get::<_, i64>(0)? != 0,
+                        delay_ms: row.get::<_, i64>(1)?.max(0) as u32,
+                    })
+                },
+            )
+        };
+
+        match result {
+            Ok(config) => Ok(config),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(HedgeConfig::default())
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 设置对冲请求配置
+    pub async fn set_hedge_config(
+        &self,
+        app_type: &str,
+        config: HedgeConfig,
+    ) -> Result<(), AppError> {
+        // 确保行存在
+        self.ensure_proxy_config_row_exists(app_type)?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_config SET
+                hedge_enabled = ?2,
+                hedge_delay_ms = ?3,
+                updated_at = datetime('now')
+             WHERE app_type = ?1",
+            rusqlite::params![app_type, config.enabled as i64, config.delay_ms as i64],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// 获取应用级代理配置
     pub async fn get_proxy_config_for_app(
         &self,
@@ -292,7 +390,7 @@ impl Database {
     /// 确保指定 app_type 的 proxy_config 行存在（同步版本，用于 set_* 函数）
     ///
     /// 使用与 schema.rs seed 相同的 per-app 默认值
-    fn ensure_proxy_config_row_exists(&self, app_type: &str) -> Result<(), AppError> {
+    pub(super) fn ensure_proxy_config_row_exists(&self, app_type: &str) -> Result<(), AppError> {
         let conn = self
             .conn
             .lock()
@@ -334,7 +432,7 @@ impl Database {
     /// 初始化 proxy_config 表的三行数据
     ///
     /// 使用与 schema.rs seed 相同的 per-app 默认值
-    async fn init_proxy_config_rows(&self) -> Result<(), AppError> {
+    pub(super) async fn init_proxy_config_rows(&self) -> Result<(), AppError> {
         let conn = lock_conn!(self.conn);
 
         // 使用与 schema.rs seed 相同的 per-app 默认值
@@ -497,6 +595,7 @@ impl Database {
                         last_failure_at: row.get(5)?,
                         last_error: row.get(6)?,
                         updated_at: row.get(7)?,
+                        rate_limit: None,
                     })
                 },
             )
@@ -514,6 +613,7 @@ impl Database {
                 last_failure_at: None,
                 last_error: None,
                 updated_at: chrono::Utc::now().to_rfc3339(),
+                rate_limit: None,
             }),
             Err(e) => Err(AppError::Database(e.to_string())),
         }
@@ -646,6 +746,37 @@ impl Database {
         Ok(())
     }
 
+    /// 获取各供应商近期成功请求的平均首字延迟（毫秒）
+    ///
+    /// 非流式请求没有 first_token_ms，使用 latency_ms 代替
+    pub fn get_provider_avg_first_token_ms(
+        &self,
+        app_type: &str,
+        since: i64,
+    ) -> Result<std::collections::HashMap<String, f64>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT provider_id, AVG(COALESCE(first_token_ms, latency_ms))
+                 FROM proxy_request_logs
+                 WHERE app_type = ?1 AND created_at >= ?2
+                   AND status_code >= 200 AND status_code < 300
+                 GROUP BY provider_id",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(rusqlite::params![app_type, since], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<_, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(rows)
+    }
+
     // ==================== Circuit Breaker Config (Legacy Compatibility) ====================
 
     /// 获取熔断器配置（兼容旧接口，从 claude 行读取）
@@ -913,4 +1044,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_load_balance_strategy_round_trip() -> Result<(), AppError> {
+        use crate::proxy::types::LoadBalanceStrategy;
+
+        let db = Database::memory()?;
+
+        let default = db.get_load_balance_strategy("codex").await?;
+        assert_eq!(default, LoadBalanceStrategy::Priority);
+
+        db.set_load_balance_strategy("codex", LoadBalanceStrategy::LeastLatency)
+            .await?;
+        assert_eq!(
+            db.get_load_balance_strategy("codex").await?,
+            LoadBalanceStrategy::LeastLatency
+        );
+        assert_eq!(
+            db.get_load_balance_strategy("claude").await?,
+            LoadBalanceStrategy::Priority
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,178 @@
+//! 响应缓存 DAO
+//!
+//! 管理代理的响应缓存配置（proxy_config 表）与缓存条目（proxy_response_cache 表）
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::response_cache::CachedResponse;
+use crate::proxy::types::ResponseCacheConfig;
+use rusqlite::OptionalExtension;
+
+impl Database {
+    /// 获取响应缓存配置
+    pub async fn get_response_cache_config(
+        &self,
+        app_type: &str,
+    ) -> Result<ResponseCacheConfig, AppError> {
+        let result = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT response_cache_enabled, response_cache_ttl_seconds, response_cache_max_entries
+                 FROM proxy_config WHERE app_type = ?1",
+                [app_type],
+                |row| {
+                    Ok(ResponseCacheConfig {
+                        enabled: row.get::<_, i64>(0)? != 0,
+                        ttl_seconds: row.get::<_, i64>(1)?.max(0) as u32,
+                        max_entries: row.get::<_, i64>(2)?.max(0) as u32,
+                    })
+                },
+            )
+        };
+
+        match result {
+            Ok(config) => Ok(config),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(ResponseCacheConfig::default())
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 设置响应缓存配置
+    pub async fn set_response_cache_config(
+        &self,
+        app_type: &str,
+        config: ResponseCacheConfig,
+    ) -> Result<(), AppError> {
+        // 确保行存在
+        self.ensure_proxy_config_row_exists(app_type)?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_config SET
+                response_cache_enabled = ?2,
+                response_cache_ttl_seconds = ?3,
+                response_cache_max_entries = ?4,
+                updated_at = datetime('now')
+             WHERE app_type = ?1",
+            rusqlite::params![
+                app_type,
+                config.enabled as i64,
+                config.ttl_seconds as i64,
+                config.max_entries as i64
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 查找未过期的缓存响应，命中时累加命中次数
+    pub fn get_cached_response(
+        &self,
+        cache_key: &str,
+        now: i64,
+    ) -> Result<Option<CachedResponse>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let cached = conn
+            .query_row(
+                "SELECT cache_key, app_type, endpoint, provider_id, model, status_code,
+                        content_type, response_body
+                 FROM proxy_response_cache
+                 WHERE cache_key = ?1 AND expires_at > ?2",
+                rusqlite::params![cache_key, now],
+                |row| {
+                    Ok(CachedResponse {
+                        cache_key: row.get(0)?,
+                        app_type: row.get(1)?,
+                        endpoint: row.get(2)?,
+                        provider_id: row.get(3)?,
+                        model: row.get(4)?,
+                        status_code: row.get::<_, i64>(5)? as u16,
+                        content_type: row.get(6)?,
+                        body: row.get(7)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if cached.is_some() {
+            conn.execute(
+                "UPDATE proxy_response_cache SET hit_count = hit_count + 1, last_hit_at = ?2
+                 WHERE cache_key = ?1",
+                rusqlite::params![cache_key, now],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        Ok(cached)
+    }
+
+    /// 写入缓存响应，并清理过期条目与超出条数上限的最早条目
+    pub fn save_cached_response(
+        &self,
+        cached: &CachedResponse,
+        config: &ResponseCacheConfig,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "INSERT OR REPLACE INTO proxy_response_cache
+                (cache_key, app_type, endpoint, provider_id, model, status_code,
+                 content_type, response_body, hit_count, created_at, expires_at, last_hit_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0, ?9, ?10, NULL)",
+            rusqlite::params![
+                cached.cache_key,
+                cached.app_type,
+                cached.endpoint,
+                cached.provider_id,
+                cached.model,
+                cached.status_code as i64,
+                cached.content_type,
+                cached.body,
+                now,
+                now + config.ttl_seconds as i64,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "DELETE FROM proxy_response_cache WHERE expires_at <= ?1",
+            [now],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "DELETE FROM proxy_response_cache
+             WHERE app_type = ?1 AND cache_key NOT IN (
+                SELECT cache_key FROM proxy_response_cache
+                WHERE app_type = ?1
+                ORDER BY created_at DESC, rowid DESC
+                LIMIT ?2
+             )",
+            rusqlite::params![cached.app_type, config.max_entries as i64],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 清空响应缓存（指定 app_type 时只清空该应用），返回删除的条目数
+    pub fn clear_response_cache(&self, app_type: Option<&str>) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        let deleted = match app_type {
+            Some(app_type) => conn.execute(
+                "DELETE FROM proxy_response_cache WHERE app_type = ?1",
+                [app_type],
+            ),
+            None => conn.execute("DELETE FROM proxy_response_cache", []),
+        }
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(deleted)
+    }
+}
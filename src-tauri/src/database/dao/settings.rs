@@ -204,4 +204,99 @@ impl Database {
             .map_err(|e| AppError::Database(format!("序列化日志配置失败: {e}")))?;
         self.set_setting("log_config", &json)
     }
+
+    // --- 代理访问控制 ---
+
+    /// 获取代理访问控制配置
+    pub fn get_proxy_auth_config(&self) -> Result<crate::proxy::types::ProxyAuthConfig, AppError> {
+        match self.get_setting("proxy_auth_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析代理访问控制配置失败: {e}"))),
+            None => Ok(crate::proxy::types::ProxyAuthConfig::default()),
+        }
+    }
+
+    /// 更新代理访问控制配置
+    pub fn set_proxy_auth_config(
+        &self,
+        config: &crate::proxy::types::ProxyAuthConfig,
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Database(format!("序列化代理访问控制配置失败: {e}")))?;
+        self.set_setting("proxy_auth_config", &json)
+    }
+
+    // --- 代理 TLS 配置 ---
+
+    /// 获取代理监听的 TLS 配置
+    pub fn get_proxy_tls_config(&self) -> Result<crate::proxy::types::ProxyTlsConfig, AppError> {
+        match self.get_setting("proxy_tls_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析代理 TLS 配置失败: {e}"))),
+            None => Ok(crate::proxy::types::ProxyTlsConfig::default()),
+        }
+    }
+
+    /// 更新代理监听的 TLS 配置
+    pub fn set_proxy_tls_config(
+        &self,
+        config: &crate::proxy::types::ProxyTlsConfig,
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Database(format!("序列化代理 TLS 配置失败: {e}")))?;
+        self.set_setting("proxy_tls_config", &json)
+    }
+
+    // --- 链路追踪配置 ---
+
+    /// 获取链路追踪配置
+    pub fn get_telemetry_config(&self) -> Result<crate::proxy::types::TelemetryConfig, AppError> {
+        match self.get_setting("telemetry_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析链路追踪配置失败: {e}"))),
+            None => Ok(crate::proxy::types::TelemetryConfig::default()),
+        }
+    }
+
+    /// 更新链路追踪配置
+    pub fn set_telemetry_config(
+        &self,
+        config: &crate::proxy::types::TelemetryConfig,
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Database(format!("序列化链路追踪配置失败: {e}")))?;
+        self.set_setting("telemetry_config", &json)
+    }
+
+    // --- 提示词模板变量 ---
+
+    /// 获取提示词模板变量（`scope` 为应用 ID，或 `workspace:{id}` 表示工作区变量）
+    pub fn get_prompt_variables(
+        &self,
+        scope: &str,
+    ) -> Result<std::collections::BTreeMap<String, String>, AppError> {
+        match self.get_setting(&format!("prompt_variables_{scope}"))? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析提示词模板变量失败: {e}"))),
+            None => Ok(Default::default()),
+        }
+    }
+
+    /// 更新提示词模板变量（为空时删除）
+    pub fn set_prompt_variables(
+        &self,
+        scope: &str,
+        variables: &std::collections::BTreeMap<String, String>,
+    ) -> Result<(), AppError> {
+        let key = format!("prompt_variables_{scope}");
+        if variables.is_empty() {
+            let conn = lock_conn!(self.conn);
+            conn.execute("DELETE FROM settings WHERE key = ?1", params![key])
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            return Ok(());
+        }
+        let json = serde_json::to_string(variables)
+            .map_err(|e| AppError::Database(format!("序列化提示词模板变量失败: {e}")))?;
+        self.set_setting(&key, &json)
+    }
 }
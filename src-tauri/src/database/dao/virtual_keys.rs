@@ -0,0 +1,149 @@
+//! 虚拟 Key DAO
+//!
+//! 管理代理的多用户虚拟 Key（proxy_virtual_keys 表），Key 本身只以 SHA-256 摘要保存
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::types::VirtualKey;
+use rusqlite::OptionalExtension;
+
+const SELECT_COLUMNS: &str = "id, name, key_prefix, allowed_apps, allowed_models,
+    limit_daily_usd, limit_monthly_usd, enabled, created_at";
+
+fn row_to_virtual_key(row: &rusqlite::Row<'_>) -> rusqlite::Result<VirtualKey> {
+    let allowed_apps: String = row.get(3)?;
+    let allowed_models: String = row.get(4)?;
+    Ok(VirtualKey {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        key_prefix: row.get(2)?,
+        allowed_apps: serde_json::from_str(&allowed_apps).unwrap_or_default(),
+        allowed_models: serde_json::from_str(&allowed_models).unwrap_or_default(),
+        limit_daily_usd: row.get(5)?,
+        limit_monthly_usd: row.get(6)?,
+        enabled: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+fn to_json(values: &[String]) -> Result<String, AppError> {
+    serde_json::to_string(values).map_err(|e| AppError::Database(e.to_string()))
+}
+
+impl Database {
+    /// 获取全部虚拟 Key（按创建时间排序）
+    pub fn get_virtual_keys(&self) -> Result<Vec<VirtualKey>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {SELECT_COLUMNS} FROM proxy_virtual_keys ORDER BY created_at ASC"
+            ))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let keys = stmt
+            .query_map([], row_to_virtual_key)
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(keys)
+    }
+
+    /// 按 ID 获取虚拟 Key
+    pub fn get_virtual_key(&self, id: &str) -> Result<Option<VirtualKey>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.query_row(
+            &format!("SELECT {SELECT_COLUMNS} FROM proxy_virtual_keys WHERE id = ?1"),
+            [id],
+            row_to_virtual_key,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 按 Key 摘要查找虚拟 Key（入站认证使用）
+    pub fn find_virtual_key_by_hash(&self, key_hash: &str) -> Result<Option<VirtualKey>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.query_row(
+            &format!("SELECT {SELECT_COLUMNS} FROM proxy_virtual_keys WHERE key_hash = ?1"),
+            [key_hash],
+            row_to_virtual_key,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 新增虚拟 Key
+    pub fn insert_virtual_key(&self, key: &VirtualKey, key_hash: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "INSERT INTO proxy_virtual_keys
+                (id, name, key_hash, key_prefix, allowed_apps, allowed_models,
+                 limit_daily_usd, limit_monthly_usd, enabled, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
+            rusqlite::params![
+                key.id,
+                key.name,
+                key_hash,
+                key.key_prefix,
+                to_json(&key.allowed_apps)?,
+                to_json(&key.allowed_models)?,
+                key.limit_daily_usd,
+                key.limit_monthly_usd,
+                key.enabled,
+                key.created_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 更新虚拟 Key 的名称、权限与限额（Key 本身不可修改）
+    pub fn update_virtual_key(&self, key: &VirtualKey) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let updated = conn
+            .execute(
+                "UPDATE proxy_virtual_keys SET
+                    name = ?2,
+                    allowed_apps = ?3,
+                    allowed_models = ?4,
+                    limit_daily_usd = ?5,
+                    limit_monthly_usd = ?6,
+                    enabled = ?7,
+                    updated_at = ?8
+                 WHERE id = ?1",
+                rusqlite::params![
+                    key.id,
+                    key.name,
+                    to_json(&key.allowed_apps)?,
+                    to_json(&key.allowed_models)?,
+                    key.limit_daily_usd,
+                    key.limit_monthly_usd,
+                    key.enabled,
+                    chrono::Utc::now().timestamp(),
+                ],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if updated == 0 {
+            return Err(AppError::Database(format!("虚拟 Key 不存在: {}", key.id)));
+        }
+        Ok(())
+    }
+
+    /// 删除虚拟 Key（已有的请求日志保留归属 ID）
+    pub fn delete_virtual_key(&self, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute("DELETE FROM proxy_virtual_keys WHERE id = ?1", [id])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! 工作区 DAO
+//!
+//! 管理登记的项目目录（workspaces 表）及提示词/MCP 在各工作区中的启用记录
+//! （workspace_assignments 表，存在即启用）
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::workspace::{Workspace, WorkspaceAssignment, WorkspaceItemKind};
+
+impl Database {
+    /// 获取全部工作区及其启用记录（按创建时间排序）
+    pub fn get_workspaces(&self) -> Result<Vec<Workspace>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare("SELECT id, name, path, created_at FROM workspaces ORDER BY created_at ASC")
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut workspaces = stmt
+            .query_map([], |row| {
+                Ok(Workspace {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    path: row.get(2)?,
+                    created_at: row.get(3)?,
+                    assignments: Vec::new(),
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT workspace_id, kind, app_type, item_id FROM workspace_assignments
+                 ORDER BY workspace_id, kind, app_type, item_id",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        for row in rows {
+            let (workspace_id, kind, app_type, item_id) =
+                row.map_err(|e| AppError::Database(e.to_string()))?;
+            let Some(kind) = WorkspaceItemKind::parse(&kind) else {
+                continue;
+            };
+            if let Some(workspace) = workspaces.iter_mut().find(|w| w.id == workspace_id) {
+                workspace.assignments.push(WorkspaceAssignment {
+                    kind,
+                    app_type,
+                    item_id,
+                });
+            }
+        }
+
+        Ok(workspaces)
+    }
+
+    /// 新增工作区
+    pub fn insert_workspace(&self, workspace: &Workspace) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "INSERT INTO workspaces (id, name, path, created_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                workspace.id,
+                workspace.name,
+                workspace.path,
+                workspace.created_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 删除工作区及其全部启用记录
+    pub fn delete_workspace(&self, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "DELETE FROM workspace_assignments WHERE workspace_id = ?1",
+            [id],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute("DELETE FROM workspaces WHERE id = ?1", [id])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 在工作区中启用或停用一个条目
+    pub fn set_workspace_assignment(
+        &self,
+        workspace_id: &str,
+        assignment: &WorkspaceAssignment,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let sql = if enabled {
+            "INSERT OR IGNORE INTO workspace_assignments (workspace_id, kind, app_type, item_id)
+             VALUES (?1, ?2, ?3, ?4)"
+        } else {
+            "DELETE FROM workspace_assignments
+             WHERE workspace_id = ?1 AND kind = ?2 AND app_type = ?3 AND item_id = ?4"
+        };
+        conn.execute(
+            sql,
+            rusqlite::params![
+                workspace_id,
+                assignment.kind.as_str(),
+                assignment.app_type,
+                assignment.item_id,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
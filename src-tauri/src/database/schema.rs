@@ -68,6 +68,7 @@ impl Database {
         conn.execute("CREATE TABLE IF NOT EXISTS prompts (
             id TEXT NOT NULL, app_type TEXT NOT NULL, name TEXT NOT NULL, content TEXT NOT NULL,
             description TEXT, enabled BOOLEAN NOT NULL DEFAULT 1, created_at INTEGER, updated_at INTEGER,
+            is_layer BOOLEAN NOT NULL DEFAULT 0, layer_order INTEGER NOT NULL DEFAULT 0,
             PRIMARY KEY (id, app_type)
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
@@ -122,6 +123,15 @@ impl Database {
             circuit_min_requests INTEGER NOT NULL DEFAULT 10,
             default_cost_multiplier TEXT NOT NULL DEFAULT '1',
             pricing_model_source TEXT NOT NULL DEFAULT 'response',
+            load_balance_strategy TEXT NOT NULL DEFAULT 'priority',
+            response_cache_enabled INTEGER NOT NULL DEFAULT 0,
+            response_cache_ttl_seconds INTEGER NOT NULL DEFAULT 3600,
+            response_cache_max_entries INTEGER NOT NULL DEFAULT 500,
+            body_capture_enabled INTEGER NOT NULL DEFAULT 0,
+            body_capture_max_bytes INTEGER NOT NULL DEFAULT 262144,
+            body_capture_retention_hours INTEGER NOT NULL DEFAULT 24,
+            hedge_enabled INTEGER NOT NULL DEFAULT 0,
+            hedge_delay_ms INTEGER NOT NULL DEFAULT 3000,
             created_at TEXT NOT NULL DEFAULT (datetime('now')), updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
@@ -180,7 +190,9 @@ impl Database {
             total_cost_usd TEXT NOT NULL DEFAULT '0', latency_ms INTEGER NOT NULL, first_token_ms INTEGER,
             duration_ms INTEGER, status_code INTEGER NOT NULL, error_message TEXT, session_id TEXT,
             provider_type TEXT, is_streaming INTEGER NOT NULL DEFAULT 0,
-            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL
+            cost_multiplier TEXT NOT NULL DEFAULT '1.0', masked_key TEXT,
+            cache_hit INTEGER NOT NULL DEFAULT 0, virtual_key_id TEXT, hedge_role TEXT,
+            created_at INTEGER NOT NULL
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
         conn.execute("CREATE INDEX IF NOT EXISTS idx_request_logs_provider ON proxy_request_logs(provider_id, app_type)", [])
@@ -202,6 +214,11 @@ impl Database {
             [],
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
+        // 旧库在迁移补齐 virtual_key_id 列之前索引会创建失败，由 v9 -> v10 迁移负责补建
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_request_logs_virtual_key ON proxy_request_logs(virtual_key_id, created_at)",
+            [],
+        );
 
         // 11. Model Pricing 表
         conn.execute(
@@ -232,7 +249,69 @@ impl Database {
 
         // 注意：circuit_breaker_config 已合并到 proxy_config 表中
 
-        // 16. Proxy Live Backup 表 (Live 配置备份)
+        // 13. Model Routes 表（跨供应商模型路由，provider_ids 为按优先级排序的 JSON 数组）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_routes (
+            id TEXT PRIMARY KEY, app_type TEXT NOT NULL, name TEXT NOT NULL DEFAULT '',
+            pattern TEXT NOT NULL, match_type TEXT NOT NULL DEFAULT 'glob',
+            provider_ids TEXT NOT NULL DEFAULT '[]', enabled INTEGER NOT NULL DEFAULT 1,
+            sort_index INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_model_routes_app ON model_routes(app_type, sort_index)",
+            [],
+        );
+
+        // 14. Proxy Response Cache 表（相同非流式请求的响应缓存，cache_key 为规范化请求体的哈希）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_response_cache (
+            cache_key TEXT PRIMARY KEY, app_type TEXT NOT NULL, endpoint TEXT NOT NULL,
+            provider_id TEXT NOT NULL, model TEXT NOT NULL, status_code INTEGER NOT NULL,
+            content_type TEXT, response_body BLOB NOT NULL, hit_count INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL, expires_at INTEGER NOT NULL, last_hit_at INTEGER
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_response_cache_app ON proxy_response_cache(app_type, created_at)",
+            [],
+        );
+
+        // 15. Proxy Request Bodies 表（调试用的请求/响应体抓取，request_id 对应 proxy_request_logs）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_request_bodies (
+            request_id TEXT PRIMARY KEY, app_type TEXT NOT NULL, endpoint TEXT NOT NULL,
+            request_headers TEXT NOT NULL DEFAULT '{}', request_body TEXT NOT NULL DEFAULT '',
+            request_truncated INTEGER NOT NULL DEFAULT 0, response_status INTEGER NOT NULL DEFAULT 0,
+            response_headers TEXT NOT NULL DEFAULT '{}', response_body TEXT NOT NULL DEFAULT '',
+            response_truncated INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL, expires_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_request_bodies_expires ON proxy_request_bodies(expires_at)",
+            [],
+        );
+
+        // 16. Proxy Virtual Keys 表（多用户虚拟 Key，只保存 Key 的 SHA-256 摘要）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_virtual_keys (
+            id TEXT PRIMARY KEY, name TEXT NOT NULL, key_hash TEXT NOT NULL UNIQUE,
+            key_prefix TEXT NOT NULL, allowed_apps TEXT NOT NULL DEFAULT '[]',
+            allowed_models TEXT NOT NULL DEFAULT '[]', limit_daily_usd TEXT, limit_monthly_usd TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 17. Proxy Live Backup 表 (Live 配置备份)
         conn.execute(
             "CREATE TABLE IF NOT EXISTS proxy_live_backup (
             app_type TEXT PRIMARY KEY, original_config TEXT NOT NULL, backed_up_at TEXT NOT NULL
@@ -241,6 +320,41 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 18. Workspaces 表（登记的项目目录）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS workspaces (
+            id TEXT PRIMARY KEY, name TEXT NOT NULL, path TEXT NOT NULL UNIQUE,
+            created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 19. Workspace Assignments 表（提示词/MCP 在哪些工作区中启用，存在即启用）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS workspace_assignments (
+            workspace_id TEXT NOT NULL, kind TEXT NOT NULL, app_type TEXT NOT NULL,
+            item_id TEXT NOT NULL, PRIMARY KEY (workspace_id, kind, app_type, item_id)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 20. Prompt Revisions 表（提示词内容的历史版本，删除提示词时保留并记录名称以便恢复）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS prompt_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL, prompt_id TEXT NOT NULL,
+            content TEXT NOT NULL, source TEXT NOT NULL, created_at INTEGER NOT NULL,
+            prompt_name TEXT
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_prompt_revisions_prompt ON prompt_revisions(app_type, prompt_id, id)",
+            [],
+        );
+
         // 尝试添加 live_takeover_active 列到 proxy_config 表
         let _ = conn.execute(
             "ALTER TABLE proxy_config ADD COLUMN live_takeover_active INTEGER NOT NULL DEFAULT 0",
@@ -360,6 +474,46 @@ impl Database {
                         Self::migrate_v4_to_v5(conn)?;
                         Self::set_user_version(conn, 5)?;
                     }
+                    5 => {
+                        log::info!("迁移数据库从 v5 到 v6（负载均衡策略）");
+                        Self::migrate_v5_to_v6(conn)?;
+                        Self::set_user_version(conn, 6)?;
+                    }
+                    6 => {
+                        log::info!("迁移数据库从 v6 到 v7（API Key 池）");
+                        Self::migrate_v6_to_v7(conn)?;
+                        Self::set_user_version(conn, 7)?;
+                    }
+                    7 => {
+                        log::info!("迁移数据库从 v7 到 v8（响应缓存）");
+                        Self::migrate_v7_to_v8(conn)?;
+                        Self::set_user_version(conn, 8)?;
+                    }
+                    8 => {
+                        log::info!("迁移数据库从 v8 到 v9（请求/响应体抓取）");
+                        Self::migrate_v8_to_v9(conn)?;
+                        Self::set_user_version(conn, 9)?;
+                    }
+                    9 => {
+                        log::info!("迁移数据库从 v9 到 v10（虚拟 Key）");
+                        Self::migrate_v9_to_v10(conn)?;
+                        Self::set_user_version(conn, 10)?;
+                    }
+                    10 => {
+                        log::info!("迁移数据库从 v10 到 v11（对冲请求）");
+                        Self::migrate_v10_to_v11(conn)?;
+                        Self::set_user_version(conn, 11)?;
+                    }
+                    11 => {
+                        log::info!("迁移数据库从 v11 到 v12（提示词分层）");
+                        Self::migrate_v11_to_v12(conn)?;
+                        Self::set_user_version(conn, 12)?;
+                    }
+                    12 => {
+                        log::info!("迁移数据库从 v12 到 v13（恢复已删除的提示词）");
+                        Self::migrate_v12_to_v13(conn)?;
+                        Self::set_user_version(conn, 13)?;
+                    }
                     _ => {
                         return Err(AppError::Database(format!(
                             "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
@@ -914,6 +1068,158 @@ impl Database {
         Ok(())
     }
 
+    /// v5 -> v6 迁移：新增故障转移队列的负载均衡策略
+    fn migrate_v5_to_v6(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "load_balance_strategy",
+                "TEXT NOT NULL DEFAULT 'priority'",
+            )?;
+        }
+
+        log::info!("v5 -> v6 迁移完成：已添加负载均衡策略字段");
+        Ok(())
+    }
+
+    /// v6 -> v7 迁移：请求日志记录实际使用的 API Key（脱敏）
+    fn migrate_v6_to_v7(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "masked_key", "TEXT")?;
+        }
+
+        log::info!("v6 -> v7 迁移完成：已添加请求日志的 API Key 字段");
+        Ok(())
+    }
+
+    /// v7 -> v8 迁移：响应缓存配置与请求日志的缓存命中标记
+    fn migrate_v7_to_v8(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "response_cache_enabled",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "response_cache_ttl_seconds",
+                "INTEGER NOT NULL DEFAULT 3600",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "response_cache_max_entries",
+                "INTEGER NOT NULL DEFAULT 500",
+            )?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "cache_hit",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        log::info!("v7 -> v8 迁移完成：已添加响应缓存配置与缓存命中字段");
+        Ok(())
+    }
+
+    /// v8 -> v9 迁移：请求/响应体抓取配置
+    fn migrate_v8_to_v9(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "body_capture_enabled",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "body_capture_max_bytes",
+                "INTEGER NOT NULL DEFAULT 262144",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "body_capture_retention_hours",
+                "INTEGER NOT NULL DEFAULT 24",
+            )?;
+        }
+
+        log::info!("v8 -> v9 迁移完成：已添加请求/响应体抓取配置");
+        Ok(())
+    }
+
+    /// v9 -> v10 迁移：请求日志归属的虚拟 Key
+    fn migrate_v9_to_v10(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "virtual_key_id", "TEXT")?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_request_logs_virtual_key ON proxy_request_logs(virtual_key_id, created_at)",
+                [],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        log::info!("v9 -> v10 迁移完成：已添加请求日志的虚拟 Key 字段");
+        Ok(())
+    }
+
+    /// v10 -> v11 迁移：对冲请求配置与请求日志的对冲标记
+    fn migrate_v10_to_v11(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "hedge_enabled",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "hedge_delay_ms",
+                "INTEGER NOT NULL DEFAULT 3000",
+            )?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "hedge_role", "TEXT")?;
+        }
+
+        log::info!("v10 -> v11 迁移完成：已添加对冲请求配置与对冲标记字段");
+        Ok(())
+    }
+
+    /// v11 -> v12 迁移：可组合的提示词分层
+    fn migrate_v11_to_v12(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "prompts")? {
+            Self::add_column_if_missing(conn, "prompts", "is_layer", "BOOLEAN NOT NULL DEFAULT 0")?;
+            Self::add_column_if_missing(
+                conn,
+                "prompts",
+                "layer_order",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        log::info!("v11 -> v12 迁移完成：已添加提示词分层字段");
+        Ok(())
+    }
+
+    /// v12 -> v13 迁移：删除提示词时记录的提示词名称
+    fn migrate_v12_to_v13(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "prompt_revisions")? {
+            Self::add_column_if_missing(conn, "prompt_revisions", "prompt_name", "TEXT")?;
+        }
+
+        log::info!("v12 -> v13 迁移完成：已添加提示词版本的名称字段");
+        Ok(())
+    }
+
     /// 插入默认模型定价数据
     /// 格式: (model_id, display_name, input, output, cache_read, cache_creation)
     /// 注意: model_id 使用短横线格式（如 claude-haiku-4-5），与 API 返回的模型名称标准化后一致
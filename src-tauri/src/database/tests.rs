@@ -327,6 +327,407 @@ fn schema_migration_v4_adds_pricing_model_columns() {
     );
 }
 
+#[test]
+fn schema_migration_v5_adds_load_balance_strategy_column() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch("CREATE TABLE proxy_config (app_type TEXT PRIMARY KEY);")
+        .expect("seed v5 schema");
+
+    Database::set_user_version(&conn, 5).expect("set user_version=5");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let strategy = get_column_info(&conn, "proxy_config", "load_balance_strategy");
+    assert_eq!(strategy.r#type, "TEXT");
+    assert_eq!(strategy.notnull, 1);
+    assert_eq!(
+        normalize_default(&strategy.default).as_deref(),
+        Some("priority")
+    );
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn schema_migration_v6_adds_request_log_masked_key_column() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch("CREATE TABLE proxy_request_logs (request_id TEXT PRIMARY KEY);")
+        .expect("seed v6 schema");
+
+    Database::set_user_version(&conn, 6).expect("set user_version=6");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let masked_key = get_column_info(&conn, "proxy_request_logs", "masked_key");
+    assert_eq!(masked_key.r#type, "TEXT");
+    assert_eq!(masked_key.notnull, 0);
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn schema_migration_v7_adds_response_cache_columns() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        "CREATE TABLE proxy_config (app_type TEXT PRIMARY KEY);
+         CREATE TABLE proxy_request_logs (request_id TEXT PRIMARY KEY);",
+    )
+    .expect("seed v7 schema");
+
+    Database::set_user_version(&conn, 7).expect("set user_version=7");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let enabled = get_column_info(&conn, "proxy_config", "response_cache_enabled");
+    assert_eq!(enabled.r#type, "INTEGER");
+    assert_eq!(enabled.notnull, 1);
+    assert_eq!(normalize_default(&enabled.default).as_deref(), Some("0"));
+    let ttl = get_column_info(&conn, "proxy_config", "response_cache_ttl_seconds");
+    assert_eq!(normalize_default(&ttl.default).as_deref(), Some("3600"));
+    let max_entries = get_column_info(&conn, "proxy_config", "response_cache_max_entries");
+    assert_eq!(
+        normalize_default(&max_entries.default).as_deref(),
+        Some("500")
+    );
+
+    let cache_hit = get_column_info(&conn, "proxy_request_logs", "cache_hit");
+    assert_eq!(cache_hit.r#type, "INTEGER");
+    assert_eq!(cache_hit.notnull, 1);
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn response_cache_expires_and_evicts_oldest_entries() {
+    use crate::proxy::response_cache::CachedResponse;
+    use crate::proxy::types::ResponseCacheConfig;
+
+    let db = Database::memory().expect("create memory db");
+    let config = ResponseCacheConfig {
+        enabled: true,
+        ttl_seconds: 60,
+        max_entries: 2,
+    };
+    let entry = |key: &str, app_type: &str| CachedResponse {
+        cache_key: key.to_string(),
+        app_type: app_type.to_string(),
+        endpoint: "/v1/messages".to_string(),
+        provider_id: "p1".to_string(),
+        model: "claude-sonnet-4-5".to_string(),
+        status_code: 200,
+        content_type: Some("application/json".to_string()),
+        body: br#"{"id":"msg_1"}"#.to_vec(),
+    };
+
+    db.save_cached_response(&entry("a", "claude"), &config, 1000)
+        .expect("save a");
+    db.save_cached_response(&entry("b", "claude"), &config, 1001)
+        .expect("save b");
+    db.save_cached_response(&entry("x", "codex"), &config, 1001)
+        .expect("save x");
+
+    let hit = db
+        .get_cached_response("a", 1010)
+        .expect("get a")
+        .expect("a cached");
+    assert_eq!(hit.body, br#"{"id":"msg_1"}"#.to_vec());
+    assert_eq!(hit.content_type.as_deref(), Some("application/json"));
+
+    // 超出条数上限时只淘汰同一应用中最早写入的条目
+    db.save_cached_response(&entry("c", "claude"), &config, 1002)
+        .expect("save c");
+    assert!(db.get_cached_response("a", 1010).expect("get a").is_none());
+    assert!(db.get_cached_response("b", 1010).expect("get b").is_some());
+    assert!(db.get_cached_response("x", 1010).expect("get x").is_some());
+
+    // 过期后不再命中
+    assert!(db.get_cached_response("b", 1061).expect("get b").is_none());
+
+    assert_eq!(db.clear_response_cache(Some("claude")).expect("clear"), 2);
+    assert!(db.get_cached_response("x", 1010).expect("get x").is_some());
+}
+
+#[test]
+fn schema_migration_v8_adds_body_capture_columns() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch("CREATE TABLE proxy_config (app_type TEXT PRIMARY KEY);")
+        .expect("seed v8 schema");
+
+    Database::set_user_version(&conn, 8).expect("set user_version=8");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let enabled = get_column_info(&conn, "proxy_config", "body_capture_enabled");
+    assert_eq!(enabled.r#type, "INTEGER");
+    assert_eq!(enabled.notnull, 1);
+    assert_eq!(normalize_default(&enabled.default).as_deref(), Some("0"));
+    let max_bytes = get_column_info(&conn, "proxy_config", "body_capture_max_bytes");
+    assert_eq!(
+        normalize_default(&max_bytes.default).as_deref(),
+        Some("262144")
+    );
+    let retention = get_column_info(&conn, "proxy_config", "body_capture_retention_hours");
+    assert_eq!(normalize_default(&retention.default).as_deref(), Some("24"));
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn schema_migration_v9_adds_virtual_key_attribution() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        "CREATE TABLE proxy_request_logs (request_id TEXT PRIMARY KEY, created_at INTEGER NOT NULL);",
+    )
+    .expect("seed v9 schema");
+
+    Database::set_user_version(&conn, 9).expect("set user_version=9");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let column = get_column_info(&conn, "proxy_request_logs", "virtual_key_id");
+    assert_eq!(column.r#type, "TEXT");
+    assert_eq!(column.notnull, 0);
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn schema_migration_v10_adds_hedge_columns() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        "CREATE TABLE proxy_config (app_type TEXT PRIMARY KEY);
+         INSERT INTO proxy_config (app_type) VALUES ('claude');
+         CREATE TABLE proxy_request_logs (request_id TEXT PRIMARY KEY, created_at INTEGER NOT NULL);",
+    )
+    .expect("seed v10 schema");
+
+    Database::set_user_version(&conn, 10).expect("set user_version=10");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let (enabled, delay_ms): (i64, i64) = conn
+        .query_row(
+            "SELECT hedge_enabled, hedge_delay_ms FROM proxy_config WHERE app_type = 'claude'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .expect("read hedge config");
+    assert_eq!((enabled, delay_ms), (0, 3000));
+    assert_eq!(
+        get_column_info(&conn, "proxy_request_logs", "hedge_role").r#type,
+        "TEXT"
+    );
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn schema_migration_v11_adds_prompt_layer_columns() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        "CREATE TABLE prompts (
+            id TEXT NOT NULL, app_type TEXT NOT NULL, name TEXT NOT NULL, content TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1, PRIMARY KEY (id, app_type)
+         );
+         INSERT INTO prompts (id, app_type, name, content) VALUES ('base', 'claude', 'Base', 'x');",
+    )
+    .expect("seed v11 schema");
+
+    Database::set_user_version(&conn, 11).expect("set user_version=11");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let (is_layer, layer_order): (bool, i64) = conn
+        .query_row(
+            "SELECT is_layer, layer_order FROM prompts WHERE id = 'base'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .expect("read layer columns");
+    assert_eq!((is_layer, layer_order), (false, 0));
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn schema_migration_v12_adds_prompt_revision_name() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        "CREATE TABLE prompt_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL, prompt_id TEXT NOT NULL,
+            content TEXT NOT NULL, source TEXT NOT NULL, created_at INTEGER NOT NULL
+         );
+         INSERT INTO prompt_revisions (app_type, prompt_id, content, source, created_at)
+         VALUES ('claude', 'base', 'x', 'ui', 1000);",
+    )
+    .expect("seed v12 schema");
+
+    Database::set_user_version(&conn, 12).expect("set user_version=12");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let name: Option<String> = conn
+        .query_row(
+            "SELECT prompt_name FROM prompt_revisions WHERE prompt_id = 'base'",
+            [],
+            |row| row.get(0),
+        )
+        .expect("read prompt_name");
+    assert!(name.is_none());
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn request_body_captures_expire_after_retention() {
+    use crate::proxy::types::RequestBodyCapture;
+
+    let db = Database::memory().expect("create memory db");
+    let capture = |request_id: &str, created_at: i64| RequestBodyCapture {
+        request_id: request_id.to_string(),
+        app_type: "claude".to_string(),
+        endpoint: "/v1/messages".to_string(),
+        request_headers: r#"{"x-api-key":"[REDACTED]"}"#.to_string(),
+        request_body: r#"{"model":"claude-sonnet-4-5"}"#.to_string(),
+        request_truncated: false,
+        response_status: 200,
+        response_headers: "{}".to_string(),
+        response_body: "event: message_stop\n\n".to_string(),
+        response_truncated: true,
+        created_at,
+    };
+
+    db.save_request_body_capture(&capture("a", 1000), 1)
+        .expect("save a");
+    let saved = db
+        .get_request_body_capture("a", 1000)
+        .expect("get a")
+        .expect("a captured");
+    assert_eq!(saved.response_status, 200);
+    assert!(saved.response_truncated);
+    assert_eq!(saved.request_headers, r#"{"x-api-key":"[REDACTED]"}"#);
+
+    // 超过保留时长后不再返回，并在下次写入时被清理
+    assert!(db
+        .get_request_body_capture("a", 4600)
+        .expect("get a")
+        .is_none());
+    db.save_request_body_capture(&capture("b", 4600), 1)
+        .expect("save b");
+    assert_eq!(db.clear_request_body_captures(None).expect("clear"), 1);
+}
+
+#[test]
+fn prompt_revisions_record_changes_with_baseline() {
+    use crate::prompt_revision::PromptRevisionSource;
+
+    let db = Database::memory().expect("create memory db");
+    db.record_prompt_revision(
+        "claude",
+        "base",
+        "v2",
+        PromptRevisionSource::Ui,
+        Some(("v1", 1000)),
+    )
+    .expect("record v2");
+    // 与最新版本相同的内容不重复记录，已有版本后不再补录原内容
+    db.record_prompt_revision("claude", "base", "v2", PromptRevisionSource::Backfill, None)
+        .expect("record v2 again");
+    db.record_prompt_revision(
+        "claude",
+        "base",
+        "v3",
+        PromptRevisionSource::Backfill,
+        Some(("v2", 2000)),
+    )
+    .expect("record v3");
+
+    let revisions = db
+        .get_prompt_revisions("claude", "base")
+        .expect("list revisions");
+    let summary: Vec<(&str, PromptRevisionSource)> = revisions
+        .iter()
+        .map(|r| (r.content.as_str(), r.source))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("v3", PromptRevisionSource::Backfill),
+            ("v2", PromptRevisionSource::Ui),
+            ("v1", PromptRevisionSource::Sync),
+        ]
+    );
+    assert_eq!(revisions[2].created_at, 1000);
+    assert!(db
+        .get_prompt_revisions("codex", "base")
+        .expect("list codex revisions")
+        .is_empty());
+}
+
+#[test]
+fn deleted_prompts_keep_revisions_until_restored() {
+    use crate::prompt::Prompt;
+    use crate::prompt_revision::PromptRevisionSource;
+
+    let db = Database::memory().expect("create memory db");
+    let prompt = Prompt {
+        id: "base".to_string(),
+        name: "Base".to_string(),
+        content: "v2".to_string(),
+        description: None,
+        enabled: false,
+        created_at: Some(1000),
+        updated_at: Some(2000),
+        layer: false,
+        layer_order: 0,
+    };
+    db.save_prompt("claude", &prompt).expect("save prompt");
+    db.record_prompt_revision("claude", "base", "v2", PromptRevisionSource::Ui, None)
+        .expect("record v2");
+    assert!(db
+        .get_deleted_prompt_revisions("claude")
+        .expect("list deleted")
+        .is_empty());
+
+    db.record_prompt_deletion("claude", "base", "Base", "v2")
+        .expect("record deletion");
+    db.delete_prompt("claude", "base").expect("delete prompt");
+
+    let deleted = db
+        .get_deleted_prompt_revisions("claude")
+        .expect("list deleted");
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].source, PromptRevisionSource::Delete);
+    assert_eq!(deleted[0].prompt_name.as_deref(), Some("Base"));
+    assert_eq!(
+        db.get_prompt_revisions("claude", "base")
+            .expect("list revisions")
+            .len(),
+        2
+    );
+
+    // 恢复后不再列为已删除
+    db.save_prompt("claude", &prompt).expect("restore prompt");
+    assert!(db
+        .get_deleted_prompt_revisions("claude")
+        .expect("list deleted")
+        .is_empty());
+}
+
 #[test]
 fn schema_create_tables_repairs_legacy_proxy_config_singleton_to_per_app() {
     let conn = Connection::open_in_memory().expect("open memory db");
@@ -6,6 +6,7 @@ use super::utils::decode_base64_param;
 use super::DeepLinkImportRequest;
 use crate::error::AppError;
 use crate::prompt::Prompt;
+use crate::prompt_revision::PromptRevisionSource;
 use crate::services::PromptService;
 use crate::store::AppState;
 use crate::AppType;
@@ -69,10 +70,18 @@ pub fn import_prompt_from_deeplink(
         enabled: false, // Always start as disabled, will be enabled later if needed
         created_at: Some(timestamp),
         updated_at: Some(timestamp),
+        layer: false,
+        layer_order: 0,
     };
 
     // Save using PromptService
-    PromptService::upsert_prompt(state, app_type.clone(), &id, prompt)?;
+    PromptService::upsert_prompt(
+        state,
+        app_type.clone(),
+        &id,
+        prompt,
+        PromptRevisionSource::Deeplink,
+    )?;
 
     // If enabled flag is set, enable this prompt (which will disable others)
     if should_enable {
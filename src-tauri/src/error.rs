@@ -56,6 +56,8 @@ pub enum AppError {
     AllProvidersCircuitOpen,
     #[error("未配置供应商")]
     NoProvidersConfigured,
+    #[error("所有供应商均已超出消费限额")]
+    AllProvidersOverBudget,
 }
 
 impl AppError {
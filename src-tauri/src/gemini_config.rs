@@ -8,10 +8,10 @@ use std::path::PathBuf;
 /// 获取 Gemini 配置目录路径（支持设置覆盖）
 pub fn get_gemini_dir() -> PathBuf {
     if let Some(custom) = crate::settings::get_gemini_override_dir() {
-        return custom;
+        return crate::live_watch::redirect(custom);
     }
 
-    get_home_dir().join(".gemini")
+    crate::live_watch::redirect(get_home_dir().join(".gemini"))
 }
 
 /// 获取 Gemini .env 文件路径
@@ -3,6 +3,7 @@ mod app_store;
 mod auto_launch;
 mod claude_mcp;
 mod claude_plugin;
+mod cli;
 mod codex_config;
 mod commands;
 mod config;
@@ -12,11 +13,15 @@ mod error;
 mod gemini_config;
 mod gemini_mcp;
 mod init_status;
+mod live_watch;
 mod mcp;
 mod opencode_config;
 mod panic_hook;
 mod prompt;
 mod prompt_files;
+mod prompt_layers;
+mod prompt_revision;
+mod prompt_template;
 mod provider;
 mod provider_defaults;
 mod proxy;
@@ -27,8 +32,10 @@ mod store;
 mod tray;
 mod usage_script;
 mod vscode_sync;
+mod workspace;
 
 pub use app_config::{AppType, McpApps, McpServer, MultiAppConfig};
+pub use cli::run_cli;
 pub use codex_config::{get_codex_auth_path, get_codex_config_path, write_codex_live_atomic};
 pub use commands::open_provider_terminal;
 pub use commands::*;
@@ -747,6 +754,9 @@ pub fn run() {
                 restore_proxy_state_on_startup(&state).await;
             });
 
+            // 监视 live 配置文件的外部修改
+            crate::live_watch::start(app.handle().clone());
+
             // 静默启动：根据设置决定是否显示主窗口
             let settings = crate::settings::get_settings();
             if let Some(window) = app.get_webview_window("main") {
@@ -798,6 +808,8 @@ pub fn run() {
             commands::save_settings,
             commands::get_rectifier_config,
             commands::set_rectifier_config,
+            commands::get_telemetry_config,
+            commands::set_telemetry_config,
             commands::get_log_config,
             commands::set_log_config,
             commands::restart_app,
@@ -837,6 +849,12 @@ pub fn run() {
             commands::enable_prompt,
             commands::import_prompt_from_file,
             commands::get_current_prompt_file_content,
+            commands::get_prompt_variables,
+            commands::set_prompt_variables,
+            commands::get_prompt_revisions,
+            commands::diff_prompt_revisions,
+            commands::rollback_prompt,
+            commands::get_deleted_prompts,
             // ours: endpoint speed test + custom endpoint management
             commands::test_api_endpoints,
             commands::get_custom_endpoints,
@@ -904,6 +922,17 @@ pub fn run() {
             commands::set_default_cost_multiplier,
             commands::get_pricing_model_source,
             commands::set_pricing_model_source,
+            commands::get_response_cache_config,
+            commands::set_response_cache_config,
+            commands::clear_response_cache,
+            commands::get_body_capture_config,
+            commands::set_body_capture_config,
+            commands::clear_request_body_captures,
+            commands::get_proxy_auth_config,
+            commands::set_proxy_auth_config,
+            commands::get_proxy_tls_config,
+            commands::set_proxy_tls_config,
+            commands::get_proxy_tls_cert_path,
             commands::is_proxy_running,
             commands::is_live_takeover_active,
             commands::switch_proxy_provider,
@@ -920,13 +949,41 @@ pub fn run() {
             commands::remove_from_failover_queue,
             commands::get_auto_failover_enabled,
             commands::set_auto_failover_enabled,
+            commands::get_load_balance_strategy,
+            commands::set_load_balance_strategy,
+            commands::get_hedge_config,
+            commands::set_hedge_config,
+            // Model routes
+            commands::get_model_routes,
+            commands::save_model_route,
+            commands::delete_model_route,
+            // Virtual keys
+            commands::get_virtual_keys,
+            commands::create_virtual_key,
+            commands::update_virtual_key,
+            commands::delete_virtual_key,
+            // Workspaces
+            commands::get_workspaces,
+            commands::add_workspace,
+            commands::remove_workspace,
+            commands::set_workspace_prompt_enabled,
+            commands::set_workspace_mcp_enabled,
+            commands::get_workspace_variables,
+            commands::set_workspace_variables,
+            // Live config changes
+            commands::get_live_config_changes,
+            commands::absorb_live_config_change,
+            commands::reapply_live_config,
+            commands::dismiss_live_config_change,
             // Usage statistics
             commands::get_usage_summary,
             commands::get_usage_trends,
             commands::get_provider_stats,
+            commands::get_virtual_key_stats,
             commands::get_model_stats,
             commands::get_request_logs,
             commands::get_request_detail,
+            commands::replay_captured_request,
             commands::get_model_pricing,
             commands::update_model_pricing,
             commands::delete_model_pricing,
@@ -0,0 +1,659 @@
+//! Live 配置文件监视
+//!
+//! 监视各应用的 live 文件（供应商配置、提示词文件、MCP 配置）所在的目录，文件变化后与按数据库
+//! 计算出的期望内容比较，不一致即为外部修改，通过 `live-config-changed` 事件通知前端，
+//! 由用户选择吸收到数据库、重新应用或忽略。
+//!
+//! - 期望内容由切换供应商时使用的同一组写入函数生成（见 [`crate::services::LiveWatchService`]）：
+//!   把当前文件复制到临时目录，在 [`RenderScope`] 内经 [`redirect`] 把 live 路径重定向过去后执行写入。
+//!   写入函数分布在各应用的配置模块中，按当前文件内容读取、合并再写回，路径均来自各应用的配置目录
+//!   函数；在这几个目录函数处重定向，写入函数不用各自增加路径参数。重定向只作用于当前线程，
+//!   其他线程同时进行的真实写入不受影响
+//! - 启动时检查一次，应用未运行期间的修改同样会被发现
+//! - cc-switch 自己的写入经 [`crate::config::atomic_write`] 直接登记为期望内容
+//! - 文件事件后等待 [`SETTLE_DELAY`] 再检查，内容在两次检查之间保持不变才通知
+//!   （避免读到其他工具写了一半的文件），同一内容只通知一次
+//! - cc-switch 写入后 [`CONFLICT_WINDOW`] 内又被外部覆盖时标记为冲突，即有其他工具在争用该文件
+//! - `~/.claude.json` 只比较 `mcpServers`，其余字段由 Claude Code 自己频繁改写；
+//!   JSON 文件按格式化后的内容比较
+//! - 文件被删除不视为外部修改
+//!
+//! 监视的是文件所在目录而不是文件本身：原子替换（rename）后原文件的监视会失效。
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::prompt_revision::{diff_lines, DiffLine};
+use crate::services::LiveWatchService;
+use crate::store::AppState;
+
+/// 文件事件后等待写入结束的时间（期间的事件合并为一次检查）
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// cc-switch 写入后多久内被外部覆盖视为冲突
+const CONFLICT_WINDOW: Duration = Duration::from_secs(30);
+
+static WATCHER: LazyLock<Watcher> = LazyLock::new(|| Watcher {
+    files: Mutex::new(HashMap::new()),
+    started: AtomicBool::new(false),
+});
+
+thread_local! {
+    /// 计算期望内容期间，live 配置目录被重定向到的临时目录
+    static RENDER_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// live 配置的读写路径（由各应用的配置目录函数调用）
+///
+/// 当前线程正在计算期望内容时，路径被映射到临时目录下，写入函数不会改动真实文件
+pub fn redirect(path: PathBuf) -> PathBuf {
+    RENDER_ROOT.with(|root| match root.borrow().as_ref() {
+        Some(root) => root.join(
+            path.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect::<PathBuf>(),
+        ),
+        None => path,
+    })
+}
+
+/// 当前线程是否正在计算期望内容（此时写入函数应跳过 live 文件以外的副作用）
+pub fn is_rendering() -> bool {
+    RENDER_ROOT.with(|root| root.borrow().is_some())
+}
+
+/// 在 `root` 下计算期望内容，离开作用域时恢复真实路径
+pub struct RenderScope(());
+
+impl RenderScope {
+    pub fn enter(root: &Path) -> Self {
+        RENDER_ROOT.with(|r| *r.borrow_mut() = Some(root.to_path_buf()));
+        Self(())
+    }
+}
+
+impl Drop for RenderScope {
+    fn drop(&mut self) {
+        RENDER_ROOT.with(|r| *r.borrow_mut() = None);
+    }
+}
+
+/// live 文件中由 cc-switch 管理的内容类型（决定吸收修改时写回哪里）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LiveFileKind {
+    /// 当前供应商配置
+    Provider,
+    /// 已启用的提示词
+    Prompt,
+    /// MCP 服务器
+    Mcp,
+}
+
+/// 参与比较的文件内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveSection {
+    /// 整个文件
+    Whole,
+    /// JSON 文件中的一个顶层字段
+    JsonKey(&'static str),
+}
+
+impl LiveSection {
+    /// 参与比较的内容（JSON 按格式化后的内容比较）
+    pub fn extract(&self, raw: &str) -> String {
+        match self {
+            LiveSection::Whole => serde_json::from_str::<Value>(raw)
+                .ok()
+                .filter(Value::is_object)
+                .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                .unwrap_or_else(|| raw.to_string()),
+            LiveSection::JsonKey(key) => serde_json::from_str::<Value>(raw)
+                .ok()
+                .and_then(|root| root.get(*key).cloned())
+                .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 受监视的 live 文件
+#[derive(Debug, Clone)]
+pub struct ManagedFile {
+    pub app: AppType,
+    pub kinds: &'static [LiveFileKind],
+    pub section: LiveSection,
+    pub path: PathBuf,
+}
+
+/// 各应用受监视的 live 文件（路径遵循当前的配置目录覆盖设置）
+pub fn managed_files() -> Vec<ManagedFile> {
+    use LiveFileKind::{Mcp, Prompt, Provider};
+
+    let file = |app: AppType, kinds: &'static [LiveFileKind], path: PathBuf| ManagedFile {
+        app,
+        kinds,
+        section: LiveSection::Whole,
+        path,
+    };
+    let mut files = vec![
+        file(
+            AppType::Claude,
+            &[Provider],
+            crate::config::get_claude_settings_path(),
+        ),
+        ManagedFile {
+            section: LiveSection::JsonKey("mcpServers"),
+            ..file(
+                AppType::Claude,
+                &[Mcp],
+                crate::config::get_claude_mcp_path(),
+            )
+        },
+        file(
+            AppType::Codex,
+            &[Provider],
+            crate::codex_config::get_codex_auth_path(),
+        ),
+        file(
+            AppType::Codex,
+            &[Provider, Mcp],
+            crate::codex_config::get_codex_config_path(),
+        ),
+        file(
+            AppType::Gemini,
+            &[Provider],
+            crate::gemini_config::get_gemini_env_path(),
+        ),
+        file(
+            AppType::Gemini,
+            &[Provider, Mcp],
+            crate::gemini_config::get_gemini_settings_path(),
+        ),
+        file(
+            AppType::OpenCode,
+            &[Provider, Mcp],
+            crate::opencode_config::get_opencode_config_path(),
+        ),
+    ];
+    for app in AppType::all() {
+        if let Ok(path) = crate::prompt_files::prompt_file_path(&app) {
+            files.push(file(app, &[Prompt], path));
+        }
+    }
+    files
+}
+
+/// 一次外部修改
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveConfigChange {
+    pub app_type: String,
+    pub kinds: Vec<LiveFileKind>,
+    pub path: String,
+    /// 受管内容 → 当前文件内容
+    pub diff: Vec<DiffLine>,
+    /// cc-switch 写入后不久即被覆盖（有其他工具在争用该文件）
+    pub conflict: bool,
+    pub detected_at: i64,
+}
+
+struct Watcher {
+    files: Mutex<HashMap<PathBuf, FileState>>,
+    started: AtomicBool,
+}
+
+struct FileState {
+    section: LiveSection,
+    /// 期望内容（最近一次按数据库计算或由 cc-switch 写入的内容）
+    managed: Option<String>,
+    /// cc-switch 最近一次写入的时间
+    written_at: Option<Instant>,
+    /// 上次检查时的内容
+    seen: Option<String>,
+    /// 已通知过的外部修改内容
+    reported: Option<String>,
+    /// 尚未处理的外部修改
+    pending: Option<LiveConfigChange>,
+}
+
+impl FileState {
+    fn new(section: LiveSection) -> Self {
+        Self {
+            section,
+            managed: None,
+            written_at: None,
+            seen: None,
+            reported: None,
+            pending: None,
+        }
+    }
+
+    /// 登记 cc-switch 写入的内容
+    fn record_write(&mut self, content: String, now: Instant) {
+        self.managed = Some(content.clone());
+        self.seen = Some(content);
+        self.written_at = Some(now);
+        self.reported = None;
+        self.pending = None;
+    }
+
+    /// 记录本次检查到的内容，返回是否需要重新计算期望内容
+    fn observe(&mut self, content: &str) -> bool {
+        if self.seen.as_deref() != Some(content) {
+            // 等内容稳定后再比较
+            self.seen = Some(content.to_string());
+            return false;
+        }
+        if self.managed.as_deref() == Some(content) {
+            self.reported = None;
+            self.pending = None;
+            return false;
+        }
+        self.reported.as_deref() != Some(content)
+    }
+
+    /// 与重新计算的期望内容比较，出现新的外部修改时返回
+    ///
+    /// `expected` 为 None 表示无法计算（如代理接管中），接受当前内容
+    fn resolve(
+        &mut self,
+        file: &ManagedFile,
+        content: String,
+        expected: Option<String>,
+        now: Instant,
+    ) -> Option<LiveConfigChange> {
+        // 计算期间文件又被改写，下次检查再比较
+        if self.seen.as_deref() != Some(content.as_str()) {
+            return None;
+        }
+        let expected = expected.unwrap_or_else(|| content.clone());
+        if expected == content {
+            self.managed = Some(expected);
+            self.reported = None;
+            self.pending = None;
+            return None;
+        }
+
+        let change = LiveConfigChange {
+            app_type: file.app.as_str().to_string(),
+            kinds: file.kinds.to_vec(),
+            path: file.path.display().to_string(),
+            diff: diff_lines(&expected, &content),
+            conflict: self
+                .written_at
+                .is_some_and(|at| now.duration_since(at) < CONFLICT_WINDOW),
+            detected_at: chrono::Utc::now().timestamp(),
+        };
+        self.managed = Some(expected);
+        self.reported = Some(content);
+        self.pending = Some(change.clone());
+        Some(change)
+    }
+}
+
+fn lock_files() -> MutexGuard<'static, HashMap<PathBuf, FileState>> {
+    match WATCHER.files.lock() {
+        Ok(files) => files,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// 登记 cc-switch 对文件的写入（由 [`crate::config::atomic_write`] 在替换文件前调用）
+///
+/// 只记录已在监视的文件
+pub fn record_write(path: &Path, data: &[u8]) {
+    let mut files = lock_files();
+    if let Some(state) = files.get_mut(path) {
+        let content = state.section.extract(&String::from_utf8_lossy(data));
+        state.record_write(content, Instant::now());
+    }
+}
+
+/// 启动后台监视任务（幂等）
+pub fn start(app: AppHandle) {
+    if WATCHER.started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    // 受监视的文件及其所在目录（目录事件用于发现新建的配置目录）
+    let interesting: Arc<Mutex<HashSet<PathBuf>>> = Default::default();
+    let filter = interesting.clone();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        let interesting = filter.lock().unwrap_or_else(|e| e.into_inner());
+        if event.paths.iter().any(|path| interesting.contains(path)) {
+            let _ = tx.send(());
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::error!("创建 live 配置文件监视器失败: {e}");
+            return;
+        }
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let mut watched_dirs = HashSet::new();
+        // 启动时检查一次
+        let mut recheck = true;
+        loop {
+            if !recheck && rx.recv().await.is_none() {
+                break;
+            }
+            tokio::time::sleep(SETTLE_DELAY).await;
+            while rx.try_recv().is_ok() {}
+
+            watch_managed_dirs(&mut watcher, &mut watched_dirs, &interesting);
+            let handle = app.clone();
+            let (changes, unsettled) =
+                tauri::async_runtime::spawn_blocking(move || poll(&handle.state::<AppState>()))
+                    .await
+                    .unwrap_or_default();
+            for change in changes {
+                log::info!(
+                    "检测到 live 配置被外部修改: {}{}",
+                    change.path,
+                    if change.conflict { "（冲突）" } else { "" }
+                );
+                if let Err(e) = app.emit("live-config-changed", &change) {
+                    log::error!("发射 live-config-changed 事件失败: {e}");
+                }
+            }
+            // 有文件内容尚未稳定时稍后再检查一次
+            recheck = unsettled;
+        }
+    });
+}
+
+/// 监视受管文件所在的目录（路径随配置目录覆盖设置变化，每次检查前更新）
+fn watch_managed_dirs(
+    watcher: &mut RecommendedWatcher,
+    watched_dirs: &mut HashSet<PathBuf>,
+    interesting: &Mutex<HashSet<PathBuf>>,
+) {
+    let files = managed_files();
+    let mut paths = HashSet::new();
+    for file in &files {
+        paths.insert(file.path.clone());
+        let Some(dir) = file.path.parent() else {
+            continue;
+        };
+        paths.insert(dir.to_path_buf());
+        if watched_dirs.contains(dir) || !dir.is_dir() {
+            continue;
+        }
+        match watcher.watch(dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                watched_dirs.insert(dir.to_path_buf());
+            }
+            Err(e) => log::warn!("监视目录 {} 失败: {e}", dir.display()),
+        }
+    }
+    *interesting.lock().unwrap_or_else(|e| e.into_inner()) = paths;
+}
+
+/// 检查一遍所有受监视的文件，返回新的外部修改及是否有文件内容尚未稳定
+fn poll(state: &AppState) -> (Vec<LiveConfigChange>, bool) {
+    // 在锁外读取：与 cc-switch 的写入交错时，本次读到的旧内容会在下次检查时被覆盖
+    let mut changed: Vec<(ManagedFile, String)> = Vec::new();
+    let mut unsettled = false;
+    for file in managed_files() {
+        let Ok(raw) = std::fs::read_to_string(&file.path) else {
+            continue;
+        };
+        let content = file.section.extract(&raw);
+
+        let mut files = lock_files();
+        let file_state = files
+            .entry(file.path.clone())
+            .or_insert_with(|| FileState::new(file.section));
+        unsettled |= file_state.seen.as_deref() != Some(content.as_str());
+        if file_state.observe(&content) {
+            changed.push((file, content));
+        }
+    }
+    if changed.is_empty() {
+        return (Vec::new(), unsettled);
+    }
+
+    // 按应用计算期望内容（执行写入函数，不持有锁）
+    let mut expected: HashMap<PathBuf, Option<String>> = HashMap::new();
+    for app in AppType::all() {
+        let app_files: Vec<&ManagedFile> = changed
+            .iter()
+            .map(|(file, _)| file)
+            .filter(|file| file.app == app)
+            .collect();
+        if app_files.is_empty() {
+            continue;
+        }
+        match LiveWatchService::expected_contents(state, &app, &app_files) {
+            Ok(contents) => expected.extend(contents),
+            Err(e) => log::warn!("计算 {} 的 live 配置期望内容失败: {e}", app.as_str()),
+        }
+    }
+
+    let now = Instant::now();
+    let mut files = lock_files();
+    let changes = changed
+        .into_iter()
+        .filter_map(|(file, content)| {
+            // 计算失败时不重复尝试，等文件内容再次变化
+            let expected = match expected.remove(&file.path) {
+                Some(expected) => expected,
+                None => {
+                    if let Some(file_state) = files.get_mut(&file.path) {
+                        file_state.reported = Some(content);
+                    }
+                    return None;
+                }
+            };
+            files
+                .get_mut(&file.path)?
+                .resolve(&file, content, expected, now)
+        })
+        .collect();
+    (changes, unsettled)
+}
+
+/// 尚未处理的外部修改
+pub fn pending_changes() -> Vec<LiveConfigChange> {
+    let mut changes: Vec<_> = lock_files()
+        .values()
+        .filter_map(|state| state.pending.clone())
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn change_not_found(path: &Path) -> AppError {
+    AppError::localized(
+        "live_change_not_found",
+        format!("没有待处理的外部修改: {}", path.display()),
+        format!("No pending external change: {}", path.display()),
+    )
+}
+
+/// 查找尚未处理的外部修改
+pub fn find_pending(path: &str) -> Result<LiveConfigChange, AppError> {
+    let path = Path::new(path);
+    lock_files()
+        .get(path)
+        .and_then(|state| state.pending.clone())
+        .ok_or_else(|| change_not_found(path))
+}
+
+/// 接受文件的当前内容作为期望内容（已吸收到数据库、重新应用或忽略）
+pub fn accept_current(path: &str) -> Result<(), AppError> {
+    let path = Path::new(path);
+    let raw = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    if let Some(state) = lock_files().get_mut(path) {
+        let content = state.section.extract(&raw);
+        state.managed = Some(content.clone());
+        state.seen = Some(content);
+        state.reported = None;
+        state.pending = None;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claude_settings(path: &str) -> ManagedFile {
+        ManagedFile {
+            app: AppType::Claude,
+            kinds: &[LiveFileKind::Provider],
+            section: LiveSection::Whole,
+            path: PathBuf::from(path),
+        }
+    }
+
+    /// 模拟一次检查：需要时按 `expected` 比较
+    fn check(
+        state: &mut FileState,
+        file: &ManagedFile,
+        content: &str,
+        expected: &str,
+        now: Instant,
+    ) -> Option<LiveConfigChange> {
+        if !state.observe(content) {
+            return None;
+        }
+        state.resolve(file, content.to_string(), Some(expected.to_string()), now)
+    }
+
+    #[test]
+    fn reports_stable_external_changes_once() {
+        let file = claude_settings("/tmp/settings.json");
+        let start = Instant::now();
+        let mut state = FileState::new(LiveSection::Whole);
+
+        // 首次检查只记录内容；与期望内容一致时不通知
+        assert!(check(&mut state, &file, "a\nb", "a\nb", start).is_none());
+        assert!(check(&mut state, &file, "a\nb", "a\nb", start).is_none());
+        assert_eq!(state.managed.as_deref(), Some("a\nb"));
+
+        // 内容变化后需要再检查一次确认稳定
+        assert!(check(&mut state, &file, "a\nc", "a\nb", start).is_none());
+        let change = check(&mut state, &file, "a\nc", "a\nb", start).unwrap();
+        assert!(!change.conflict);
+        assert_eq!(change.diff.len(), 3);
+        assert!(check(&mut state, &file, "a\nc", "a\nb", start).is_none());
+        assert!(state.pending.is_some());
+
+        // 改回期望内容后不再有待处理的修改
+        check(&mut state, &file, "a\nb", "a\nb", start);
+        assert!(check(&mut state, &file, "a\nb", "a\nb", start).is_none());
+        assert!(state.pending.is_none());
+    }
+
+    #[test]
+    fn reports_changes_made_before_watching() {
+        let file = claude_settings("/tmp/settings.json");
+        let start = Instant::now();
+        let mut state = FileState::new(LiveSection::Whole);
+
+        // 应用未运行期间被修改：第一次确认稳定后即与数据库的期望内容比较
+        assert!(check(&mut state, &file, "edited", "from-db", start).is_none());
+        let change = check(&mut state, &file, "edited", "from-db", start).unwrap();
+        assert!(!change.conflict);
+        assert_eq!(state.managed.as_deref(), Some("from-db"));
+
+        // 无法计算期望内容时接受当前内容
+        let mut state = FileState::new(LiveSection::Whole);
+        assert!(!state.observe("taken-over"));
+        assert!(state.observe("taken-over"));
+        assert!(state
+            .resolve(&file, "taken-over".to_string(), None, start)
+            .is_none());
+        assert!(!state.observe("taken-over"));
+    }
+
+    #[test]
+    fn flags_overwrite_shortly_after_own_write_as_conflict() {
+        let file = claude_settings("/tmp/settings.json");
+        let start = Instant::now();
+        let mut state = FileState::new(LiveSection::Whole);
+
+        state.record_write("ours".to_string(), start);
+        assert!(check(&mut state, &file, "ours", "ours", start).is_none());
+
+        check(&mut state, &file, "theirs", "ours", start);
+        let change = check(
+            &mut state,
+            &file,
+            "theirs",
+            "ours",
+            start + Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(change.conflict);
+
+        state.record_write("ours".to_string(), start);
+        check(&mut state, &file, "later", "ours", start);
+        let change = check(
+            &mut state,
+            &file,
+            "later",
+            "ours",
+            start + CONFLICT_WINDOW * 2,
+        )
+        .unwrap();
+        assert!(!change.conflict);
+    }
+
+    #[test]
+    fn redirects_live_paths_while_rendering() {
+        let real = PathBuf::from("/home/me/.claude/settings.json");
+        assert_eq!(redirect(real.clone()), real);
+        assert!(!is_rendering());
+        {
+            let _scope = RenderScope::enter(Path::new("/tmp/render"));
+            assert!(is_rendering());
+            assert_eq!(
+                redirect(real.clone()),
+                PathBuf::from("/tmp/render/home/me/.claude/settings.json")
+            );
+        }
+        assert_eq!(redirect(real.clone()), real);
+        assert!(!is_rendering());
+    }
+
+    #[test]
+    fn compares_only_the_configured_json_key() {
+        let section = LiveSection::JsonKey("mcpServers");
+        let a = section.extract(r#"{"mcpServers": {"x": {"command": "npx"}}, "numStartups": 1}"#);
+        let b = section.extract(r#"{"numStartups": 2, "mcpServers": {"x": {"command": "npx"}}}"#);
+        assert_eq!(a, b);
+        assert_eq!(section.extract(r#"{"numStartups": 2}"#), "");
+        assert_eq!(section.extract("not json"), "");
+
+        // 整个 JSON 文件按格式化后的内容比较，其他文件按原文比较
+        assert_eq!(
+            LiveSection::Whole.extract(r#"{"env":{"A":"1"}}"#),
+            LiveSection::Whole.extract("{\n  \"env\": { \"A\": \"1\" }\n}\n")
+        );
+        assert_eq!(
+            LiveSection::Whole.extract("model = \"x\"\n"),
+            "model = \"x\"\n"
+        );
+    }
+}
@@ -39,13 +39,14 @@ use std::path::PathBuf;
 /// 可通过 settings.opencode_config_dir 覆盖
 pub fn get_opencode_dir() -> PathBuf {
     if let Some(override_dir) = get_opencode_override_dir() {
-        return override_dir;
+        return crate::live_watch::redirect(override_dir);
     }
 
     // 所有平台统一使用 ~/.config/opencode
-    dirs::home_dir()
+    let dir = dirs::home_dir()
         .map(|h| h.join(".config").join("opencode"))
-        .unwrap_or_else(|| PathBuf::from(".config").join("opencode"))
+        .unwrap_or_else(|| PathBuf::from(".config").join("opencode"));
+    crate::live_watch::redirect(dir)
 }
 
 /// 获取 OpenCode 配置文件路径
@@ -13,4 +13,10 @@ pub struct Prompt {
     pub created_at: Option<i64>,
     #[serde(rename = "updatedAt", skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<i64>,
+    /// 可组合的分层提示词：可与其他提示词同时启用，按 `layer_order` 拼接写入
+    #[serde(default)]
+    pub layer: bool,
+    /// 分层顺序（越小越靠前，相同时按 ID 排序）
+    #[serde(rename = "layerOrder", default)]
+    pub layer_order: i32,
 }
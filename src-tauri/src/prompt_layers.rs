@@ -0,0 +1,249 @@
+//! 可组合的提示词分层
+//!
+//! 普通提示词互斥：启用一个即停用其他普通提示词。分层提示词（`layer = true`）可以与其他
+//! 提示词同时启用，写入时按确定的顺序拼接（已启用的普通提示词在前，分层按
+//! `layer_order`、ID 排序）。
+//!
+//! 存在已启用的分层，或文件中已有受管区块时，提示词文件只改写受管区块：
+//!
+//! ```text
+//! 手写内容（保留）
+//! <!-- cc-switch:prompts:begin -->
+//! <!-- cc-switch:layer team-base -->
+//! ...
+//! <!-- cc-switch:prompts:end -->
+//! 手写内容（保留）
+//! ```
+//!
+//! 首次写入区块时，文件中旧版本整体写入的提示词被替换，其余手写内容保留在区块之前。
+//! 每个分层以 `<!-- cc-switch:layer {id} -->` 开头，回填时按 ID 拆回各提示词。
+
+use indexmap::IndexMap;
+
+use crate::prompt::Prompt;
+
+pub const BEGIN_MARKER: &str = "<!-- cc-switch:prompts:begin -->";
+pub const END_MARKER: &str = "<!-- cc-switch:prompts:end -->";
+const LAYER_MARKER_PREFIX: &str = "<!-- cc-switch:layer ";
+const MARKER_SUFFIX: &str = " -->";
+
+/// 参与写入的提示词（按写入顺序）
+pub fn active_prompts(prompts: &IndexMap<String, Prompt>) -> Vec<&Prompt> {
+    let mut active: Vec<&Prompt> = prompts.values().filter(|p| p.enabled).collect();
+    sort_for_writing(&mut active);
+    active
+}
+
+/// 按写入顺序排序：普通提示词在前，分层按 `layer_order`、ID 排序
+pub fn sort_for_writing(prompts: &mut [&Prompt]) {
+    prompts.sort_by(|a, b| {
+        a.layer
+            .cmp(&b.layer)
+            .then(a.layer_order.cmp(&b.layer_order))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// 是否以受管区块方式写入
+pub fn uses_managed_block(prompts: &IndexMap<String, Prompt>, live: &str) -> bool {
+    prompts.values().any(|p| p.enabled && p.layer) || find_block(live).is_some()
+}
+
+/// 渲染受管区块（不含结尾换行）
+pub fn render_block(active: &[&Prompt]) -> String {
+    let layers: Vec<String> = active
+        .iter()
+        .map(|p| {
+            format!(
+                "{LAYER_MARKER_PREFIX}{}{MARKER_SUFFIX}\n{}",
+                p.id,
+                p.content.trim()
+            )
+        })
+        .collect();
+
+    if layers.is_empty() {
+        format!("{BEGIN_MARKER}\n{END_MARKER}")
+    } else {
+        format!("{BEGIN_MARKER}\n{}\n{END_MARKER}", layers.join("\n\n"))
+    }
+}
+
+/// 用受管区块替换文件中的已有区块，区块外的内容保持不变
+///
+/// 文件中还没有受管区块时：原内容与 `legacy` 之一相同（旧版本整体写入的提示词，
+/// 已由数据库保存）则直接替换；否则视为手写内容保留，区块追加到末尾
+pub fn merge_block(live: &str, block: &str, legacy: &[&str]) -> String {
+    if let Some((start, end)) = find_block(live) {
+        return format!("{}{block}{}", &live[..start], &live[end..]);
+    }
+    let trimmed = live.trim();
+    if trimmed.is_empty() || legacy.iter().any(|content| content.trim() == trimmed) {
+        format!(
+            "{block}
+"
+        )
+    } else {
+        format!("{}\n\n{block}\n", live.trim_end())
+    }
+}
+
+/// 用受管区块替换已有区块；没有区块时追加到原内容末尾（用于项目中由用户维护的文件）
+pub fn append_block(live: &str, block: &str) -> String {
+    merge_block(live, block, &[])
+}
+
+/// 移除受管区块及其后的换行，区块外的内容保持不变
+pub fn remove_block(live: &str) -> String {
+    match find_block(live) {
+        Some((start, end)) => {
+            let after = &live[end..];
+            let after = after.strip_prefix('\n').unwrap_or(after);
+            let before = live[..start].trim_end_matches('\n');
+            match (before.is_empty(), after.is_empty()) {
+                (true, _) => after.to_string(),
+                (false, true) => format!("{before}\n"),
+                (false, false) => format!("{before}\n\n{}", after.trim_start_matches('\n')),
+            }
+        }
+        None => live.to_string(),
+    }
+}
+
+/// 解析受管区块中各分层的内容（ID, 内容），没有受管区块时返回 None
+pub fn parse_block(live: &str) -> Option<Vec<(String, String)>> {
+    let (start, end) = find_block(live)?;
+    let body = &live[start + BEGIN_MARKER.len()..end - END_MARKER.len()];
+
+    let mut layers: Vec<(String, String)> = Vec::new();
+    for line in body.lines() {
+        let id = line
+            .trim()
+            .strip_prefix(LAYER_MARKER_PREFIX)
+            .and_then(|rest| rest.strip_suffix(MARKER_SUFFIX));
+        match (id, layers.last_mut()) {
+            (Some(id), _) => layers.push((id.trim().to_string(), String::new())),
+            (None, Some((_, content))) => {
+                content.push_str(line);
+                content.push('\n');
+            }
+            // 第一个分层标记之前的内容不属于任何提示词
+            (None, None) => {}
+        }
+    }
+
+    Some(
+        layers
+            .into_iter()
+            .map(|(id, content)| (id, content.trim().to_string()))
+            .collect(),
+    )
+}
+
+/// 受管区块的字节范围（从开始标记到结束标记末尾）
+fn find_block(live: &str) -> Option<(usize, usize)> {
+    let start = live.find(BEGIN_MARKER)?;
+    let end = live[start..].find(END_MARKER)? + start + END_MARKER.len();
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str, content: &str, enabled: bool, layer: bool, layer_order: i32) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            name: id.to_string(),
+            content: content.to_string(),
+            description: None,
+            enabled,
+            created_at: None,
+            updated_at: None,
+            layer,
+            layer_order,
+        }
+    }
+
+    fn prompts(items: Vec<Prompt>) -> IndexMap<String, Prompt> {
+        items.into_iter().map(|p| (p.id.clone(), p)).collect()
+    }
+
+    #[test]
+    fn orders_base_prompt_before_layers() {
+        let prompts = prompts(vec![
+            prompt("personal", "me", true, true, 20),
+            prompt("project-b", "b", true, true, 10),
+            prompt("project-a", "a", true, true, 10),
+            prompt("base", "team", true, false, 99),
+            prompt("off", "x", false, true, 0),
+        ]);
+        let ids: Vec<&str> = active_prompts(&prompts)
+            .iter()
+            .map(|p| p.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["base", "project-a", "project-b", "personal"]);
+        assert!(uses_managed_block(&prompts, ""));
+    }
+
+    #[test]
+    fn preserves_text_outside_managed_block() {
+        let base = prompt("base", "# Team\nrules\n", true, false, 0);
+        let personal = prompt("personal", "be brief", true, true, 1);
+        let block = render_block(&[&base, &personal]);
+
+        // 旧版本整体写入的提示词被区块替换，手写内容保留
+        let first = merge_block("legacy content\n", &block, &["legacy content"]);
+        assert!(first.starts_with(BEGIN_MARKER));
+        assert!(!first.contains("legacy content"));
+        assert_eq!(
+            merge_block("hand-written", &block, &["legacy content"]),
+            format!("hand-written\n\n{block}\n")
+        );
+
+        let edited = format!("my notes\n\n{first}\ntrailing notes\n");
+        let updated = render_block(&[&base]);
+        let merged = merge_block(&edited, &updated, &[]);
+        assert_eq!(merged, format!("my notes\n\n{updated}\n\ntrailing notes\n"));
+        assert!(!merged.contains("be brief"));
+    }
+
+    #[test]
+    fn appends_and_removes_block_in_project_files() {
+        let base = prompt("base", "team rules", true, false, 0);
+        let block = render_block(&[&base]);
+
+        let live = "# Project\n\nBuild with cargo.\n";
+        let appended = append_block(live, &block);
+        assert_eq!(
+            appended,
+            format!("# Project\n\nBuild with cargo.\n\n{block}\n")
+        );
+        assert_eq!(append_block(&appended, &block), appended);
+        assert_eq!(remove_block(&appended), live);
+
+        assert_eq!(append_block("", &block), format!("{block}\n"));
+        assert_eq!(remove_block(&format!("{block}\n")), "");
+        assert_eq!(remove_block(live), live);
+    }
+
+    #[test]
+    fn parses_layers_back_from_block() {
+        let base = prompt("base", "# Team\n\nrules", true, false, 0);
+        let personal = prompt("personal", "be brief", true, true, 1);
+        let live = format!(
+            "notes\n{}\n",
+            render_block(&[&base, &personal]).replace("be brief", "be very brief")
+        );
+
+        assert_eq!(
+            parse_block(&live),
+            Some(vec![
+                ("base".to_string(), "# Team\n\nrules".to_string()),
+                ("personal".to_string(), "be very brief".to_string()),
+            ])
+        );
+        assert_eq!(parse_block("no markers"), None);
+        assert_eq!(parse_block(&render_block(&[])), Some(vec![]));
+    }
+}
@@ -0,0 +1,160 @@
+//! 提示词版本历史
+//!
+//! 提示词内容每次变化都在 prompt_revisions 表中记录一个版本（内容未变化时不记录），
+//! 可按版本对比差异并回滚。删除提示词时保留全部版本并记录一个 Delete 版本，
+//! 回滚到其中任一版本即可恢复已删除的提示词。
+
+use serde::{Deserialize, Serialize};
+
+/// 每条提示词最多保留的版本数（超出时删除最旧的版本）
+pub const MAX_REVISIONS_PER_PROMPT: usize = 100;
+
+/// 内容变更的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptRevisionSource {
+    /// 界面编辑
+    Ui,
+    /// 从 live 提示词文件回填（含自动备份）
+    Backfill,
+    /// 深链接导入
+    Deeplink,
+    /// 从提示词文件导入（手动导入或首次启动自动导入）
+    Import,
+    /// 同步已有内容：启用版本历史前就存在的提示词，首次变更时补录的原内容
+    Sync,
+    /// 回滚到历史版本
+    Rollback,
+    /// 删除提示词时的最后内容（带有提示词名称，用于恢复）
+    Delete,
+}
+
+impl PromptRevisionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptRevisionSource::Ui => "ui",
+            PromptRevisionSource::Backfill => "backfill",
+            PromptRevisionSource::Deeplink => "deeplink",
+            PromptRevisionSource::Import => "import",
+            PromptRevisionSource::Sync => "sync",
+            PromptRevisionSource::Rollback => "rollback",
+            PromptRevisionSource::Delete => "delete",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ui" => Some(PromptRevisionSource::Ui),
+            "backfill" => Some(PromptRevisionSource::Backfill),
+            "deeplink" => Some(PromptRevisionSource::Deeplink),
+            "import" => Some(PromptRevisionSource::Import),
+            "sync" => Some(PromptRevisionSource::Sync),
+            "rollback" => Some(PromptRevisionSource::Rollback),
+            "delete" => Some(PromptRevisionSource::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// 提示词的一个历史版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRevision {
+    pub id: i64,
+    pub prompt_id: String,
+    pub content: String,
+    pub source: PromptRevisionSource,
+    pub created_at: i64,
+    /// 提示词名称（只有 Delete 版本记录）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_name: Option<String>,
+}
+
+/// 差异行的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// 按行对比的一行结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// 按行对比两段内容（最长公共子序列），删除行排在对应的新增行之前
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j]：old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let line = |op, text: &str| DiffLine {
+        op,
+        text: text.to_string(),
+    };
+    let mut result = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            result.push(line(DiffOp::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            result.push(line(DiffOp::Delete, old[i]));
+            i += 1;
+        } else {
+            result.push(line(DiffOp::Insert, new[j]));
+            j += 1;
+        }
+    }
+    result.extend(old[i..].iter().map(|&text| line(DiffOp::Delete, text)));
+    result.extend(new[j..].iter().map(|&text| line(DiffOp::Insert, text)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(diff: &[DiffLine]) -> Vec<(DiffOp, &str)> {
+        diff.iter().map(|l| (l.op, l.text.as_str())).collect()
+    }
+
+    #[test]
+    fn diffs_changed_lines() {
+        let diff = diff_lines(
+            "# Rules\nbe brief\nuse tabs\n",
+            "# Rules\nbe concise\nuse tabs\nrun tests",
+        );
+        assert_eq!(
+            ops(&diff),
+            vec![
+                (DiffOp::Equal, "# Rules"),
+                (DiffOp::Delete, "be brief"),
+                (DiffOp::Insert, "be concise"),
+                (DiffOp::Equal, "use tabs"),
+                (DiffOp::Insert, "run tests"),
+            ]
+        );
+
+        assert!(diff_lines("same", "same")
+            .iter()
+            .all(|l| l.op == DiffOp::Equal));
+        assert_eq!(ops(&diff_lines("", "new")), vec![(DiffOp::Insert, "new")]);
+    }
+}
@@ -0,0 +1,359 @@
+//! 提示词模板
+//!
+//! 提示词内容在写入文件前按目标应用渲染，同一份提示词可以为 `CLAUDE.md`、`AGENTS.md`、
+//! `GEMINI.md` 渲染出不同内容：
+//!
+//! ```text
+//! {{name}}                       变量（应用变量、工作区变量及内置的 app 等）
+//! {{> prompt-id}}                引入同一应用下的另一条提示词（同样按模板渲染）
+//! {{#app claude codex}}...{{else}}...{{/app}}
+//!                                按目标应用选择内容，else 可省略
+//! \{{                            输出字面量 {{
+//! ```
+//!
+//! 单独占一行的 `{{#app}}` / `{{else}}` / `{{/app}}` 连同换行一起移除，不会留下空行。
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::prompt::Prompt;
+
+/// 内置变量：目标应用 ID
+pub const VAR_APP: &str = "app";
+/// 内置变量：工作区名称（仅写入工作区时可用）
+pub const VAR_WORKSPACE_NAME: &str = "workspace_name";
+/// 内置变量：工作区目录（仅写入工作区时可用）
+pub const VAR_WORKSPACE_PATH: &str = "workspace_path";
+
+/// 渲染上下文
+pub struct TemplateContext<'a> {
+    pub app: &'a AppType,
+    /// 可用变量（已合并应用变量、工作区变量与内置变量）
+    pub variables: &'a BTreeMap<String, String>,
+    /// 可被引入的提示词（同一应用）
+    pub prompts: &'a IndexMap<String, Prompt>,
+}
+
+/// 内容是否使用了模板语法
+///
+/// 模板提示词写入文件的是渲染结果，不能再从文件回填
+pub fn is_template(content: &str) -> bool {
+    content.contains("{{")
+}
+
+/// 渲染提示词内容
+pub fn render(prompt: &Prompt, ctx: &TemplateContext<'_>) -> Result<String, AppError> {
+    if !is_template(&prompt.content) {
+        return Ok(prompt.content.clone());
+    }
+    let mut stack = vec![prompt.id.clone()];
+    render_content(&prompt.content, ctx, &mut stack)
+}
+
+fn render_content(
+    content: &str,
+    ctx: &TemplateContext<'_>,
+    stack: &mut Vec<String>,
+) -> Result<String, AppError> {
+    let nodes = parse(content)?;
+    let mut out = String::new();
+    render_nodes(&nodes, ctx, stack, &mut out)?;
+    Ok(out)
+}
+
+fn render_nodes(
+    nodes: &[Node<'_>],
+    ctx: &TemplateContext<'_>,
+    stack: &mut Vec<String>,
+    out: &mut String,
+) -> Result<(), AppError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => match ctx.variables.get(*name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    return Err(AppError::localized(
+                        "prompt_template_unknown_variable",
+                        format!("提示词模板引用了未定义的变量: {name}"),
+                        format!("Prompt template references undefined variable: {name}"),
+                    ))
+                }
+            },
+            Node::Include(id) => {
+                if stack.iter().any(|s| s == id) {
+                    return Err(AppError::localized(
+                        "prompt_template_include_cycle",
+                        format!("提示词循环引入: {} -> {id}", stack.join(" -> ")),
+                        format!("Prompt include cycle: {} -> {id}", stack.join(" -> ")),
+                    ));
+                }
+                let Some(included) = ctx.prompts.get(*id) else {
+                    return Err(AppError::localized(
+                        "prompt_template_include_missing",
+                        format!("提示词模板引入的提示词不存在: {id}"),
+                        format!("Prompt template includes a missing prompt: {id}"),
+                    ));
+                };
+                stack.push(id.to_string());
+                let rendered = render_content(&included.content, ctx, stack)?;
+                stack.pop();
+                out.push_str(rendered.trim());
+            }
+            Node::App {
+                apps,
+                then,
+                otherwise,
+            } => {
+                let branch = if apps.contains(ctx.app) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, ctx, stack, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+enum Node<'a> {
+    Text(String),
+    Var(&'a str),
+    Include(&'a str),
+    App {
+        apps: Vec<AppType>,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+}
+
+/// 尚未闭合的 `{{#app}}` 块
+struct Block<'a> {
+    apps: Vec<AppType>,
+    then: Vec<Node<'a>>,
+    otherwise: Option<Vec<Node<'a>>>,
+}
+
+impl<'a> Block<'a> {
+    fn nodes(&mut self) -> &mut Vec<Node<'a>> {
+        match &mut self.otherwise {
+            Some(otherwise) => otherwise,
+            None => &mut self.then,
+        }
+    }
+}
+
+fn syntax_error(zh: impl Into<String>, en: impl Into<String>) -> AppError {
+    AppError::localized(
+        "prompt_template_syntax",
+        format!("提示词模板语法错误：{}", zh.into()),
+        format!("Prompt template syntax error: {}", en.into()),
+    )
+}
+
+fn parse(source: &str) -> Result<Vec<Node<'_>>, AppError> {
+    let mut root: Vec<Node<'_>> = Vec::new();
+    let mut blocks: Vec<Block<'_>> = Vec::new();
+    let mut text = String::new();
+    let mut rest = source;
+    // `text` 是否从行首开始（用于判断块标签是否单独占一行）
+    let mut text_at_line_start = true;
+
+    while let Some(open) = rest.find("{{") {
+        if rest[..open].ends_with('\\') {
+            text.push_str(&rest[..open - 1]);
+            text.push_str("{{");
+            rest = &rest[open + 2..];
+            continue;
+        }
+        text.push_str(&rest[..open]);
+
+        let after_open = &rest[open + 2..];
+        let Some(close) = after_open.find("}}") else {
+            return Err(syntax_error("缺少 }} 闭合标签", "missing closing }}"));
+        };
+        let tag = after_open[..close].trim();
+        rest = &after_open[close + 2..];
+
+        let is_block_tag = tag.starts_with('#') || tag.starts_with('/') || tag == "else";
+        if is_block_tag {
+            // 单独占一行的块标签：去掉行首空白与行尾换行
+            let line_start = text.rfind('\n').map(|p| p + 1);
+            let prefix_blank = match line_start {
+                Some(start) => text[start..].trim().is_empty(),
+                None => text_at_line_start && text.trim().is_empty(),
+            };
+            let line_end = rest.find('\n');
+            let suffix_blank = rest[..line_end.unwrap_or(rest.len())].trim().is_empty();
+            if prefix_blank && suffix_blank {
+                text.truncate(line_start.unwrap_or(0));
+                rest = line_end.map_or("", |end| &rest[end + 1..]);
+                text_at_line_start = true;
+            } else {
+                text_at_line_start = false;
+            }
+        } else {
+            text_at_line_start = false;
+        }
+
+        let current = match blocks.last_mut() {
+            Some(block) => block.nodes(),
+            None => &mut root,
+        };
+        if !text.is_empty() {
+            current.push(Node::Text(std::mem::take(&mut text)));
+        }
+
+        if let Some(id) = tag.strip_prefix('>') {
+            let id = id.trim();
+            if id.is_empty() {
+                return Err(syntax_error(
+                    "引入缺少提示词 ID",
+                    "include without prompt id",
+                ));
+            }
+            current.push(Node::Include(id));
+        } else if let Some(args) = tag.strip_prefix("#app") {
+            let apps = args
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|s| !s.is_empty())
+                .map(AppType::from_str)
+                .collect::<Result<Vec<_>, _>>()?;
+            if apps.is_empty() {
+                return Err(syntax_error(
+                    "{{#app}} 至少需要一个应用",
+                    "{{#app}} needs at least one app",
+                ));
+            }
+            blocks.push(Block {
+                apps,
+                then: Vec::new(),
+                otherwise: None,
+            });
+        } else if tag == "else" {
+            match blocks.last_mut() {
+                Some(block) if block.otherwise.is_none() => block.otherwise = Some(Vec::new()),
+                _ => {
+                    return Err(syntax_error(
+                        "{{else}} 不在 {{#app}} 块中",
+                        "{{else}} outside of an {{#app}} block",
+                    ))
+                }
+            }
+        } else if tag == "/app" {
+            let Some(block) = blocks.pop() else {
+                return Err(syntax_error("多余的 {{/app}}", "unexpected {{/app}}"));
+            };
+            let node = Node::App {
+                apps: block.apps,
+                then: block.then,
+                otherwise: block.otherwise.unwrap_or_default(),
+            };
+            match blocks.last_mut() {
+                Some(parent) => parent.nodes().push(node),
+                None => root.push(node),
+            }
+        } else if !tag.is_empty()
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        {
+            current.push(Node::Var(tag));
+        } else {
+            return Err(syntax_error(
+                format!("无法识别的标签 {{{{{tag}}}}}"),
+                format!("unrecognized tag {{{{{tag}}}}}"),
+            ));
+        }
+    }
+
+    if !blocks.is_empty() {
+        return Err(syntax_error(
+            "{{#app}} 缺少 {{/app}}",
+            "{{#app}} without {{/app}}",
+        ));
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        root.push(Node::Text(text));
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str, content: &str) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            name: id.to_string(),
+            content: content.to_string(),
+            description: None,
+            enabled: false,
+            created_at: None,
+            updated_at: None,
+            layer: false,
+            layer_order: 0,
+        }
+    }
+
+    fn render_for(app: AppType, prompts: &[Prompt], id: &str) -> Result<String, AppError> {
+        let prompts: IndexMap<String, Prompt> =
+            prompts.iter().map(|p| (p.id.clone(), p.clone())).collect();
+        let variables = BTreeMap::from([
+            (VAR_APP.to_string(), app.as_str().to_string()),
+            ("team".to_string(), "Platform".to_string()),
+        ]);
+        let ctx = TemplateContext {
+            app: &app,
+            variables: &variables,
+            prompts: &prompts,
+        };
+        render(&prompts[id], &ctx)
+    }
+
+    #[test]
+    fn renders_variables_includes_and_app_blocks() {
+        let prompts = [
+            prompt(
+                "main",
+                "# {{team}} rules for {{ app }}\n\n{{> shared}}\n\n{{#app claude}}\nUse CLAUDE.md.\n{{else}}\nUse AGENTS.md.\n{{/app}}\nBraces: \\{{team}}\n",
+            ),
+            prompt("shared", "Be concise.\n"),
+        ];
+
+        assert_eq!(
+            render_for(AppType::Claude, &prompts, "main").unwrap(),
+            "# Platform rules for claude\n\nBe concise.\n\nUse CLAUDE.md.\nBraces: {{team}}\n"
+        );
+        assert_eq!(
+            render_for(AppType::Codex, &prompts, "main").unwrap(),
+            "# Platform rules for codex\n\nBe concise.\n\nUse AGENTS.md.\nBraces: {{team}}\n"
+        );
+    }
+
+    #[test]
+    fn reports_template_errors() {
+        let error_key = |content: &str| match render_for(
+            AppType::Claude,
+            &[prompt("main", content), prompt("loop", "{{> main}}")],
+            "main",
+        ) {
+            Err(AppError::Localized { key, .. }) => key,
+            other => panic!("expected localized error, got {other:?}"),
+        };
+
+        assert_eq!(error_key("{{missing}}"), "prompt_template_unknown_variable");
+        assert_eq!(error_key("{{> nope}}"), "prompt_template_include_missing");
+        assert_eq!(error_key("{{> loop}}"), "prompt_template_include_cycle");
+        assert_eq!(error_key("{{#app claude}}x"), "prompt_template_syntax");
+        assert_eq!(error_key("{{team"), "prompt_template_syntax");
+        assert_eq!(error_key("{{#app vscode}}x{{/app}}"), "unsupported_app");
+    }
+}
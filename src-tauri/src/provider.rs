@@ -168,6 +168,58 @@ pub struct ProviderTestConfig {
     pub max_retries: Option<u32>,
 }
 
+/// 模型路由规则的匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelMatchType {
+    /// 通配符（`*` 任意字符序列，`?` 单个字符），不区分大小写
+    #[default]
+    Glob,
+    /// 正则表达式（按原样匹配，可用 `(?i)` 忽略大小写）
+    Regex,
+}
+
+/// API Key 池的轮换方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRotation {
+    /// 按顺序轮流使用
+    #[default]
+    RoundRobin,
+    /// 优先使用最久未被限流的 Key
+    LeastRecentlyRateLimited,
+}
+
+/// 模型路由规则
+///
+/// 按顺序匹配请求模型名与条件，命中的第一条规则决定实际发送给上游的模型
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ModelRouteRule {
+    /// 请求模型名的匹配模式
+    pub pattern: String,
+    /// 匹配方式（默认 glob）
+    #[serde(rename = "matchType", default)]
+    pub match_type: ModelMatchType,
+    /// 仅在 thinking / reasoning 开启（true）或关闭（false）时生效
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<bool>,
+    /// 工具数量下限（含）
+    #[serde(rename = "minTools", skip_serializing_if = "Option::is_none")]
+    pub min_tools: Option<u32>,
+    /// 工具数量上限（含）
+    #[serde(rename = "maxTools", skip_serializing_if = "Option::is_none")]
+    pub max_tools: Option<u32>,
+    /// 估算 prompt tokens 下限（含）
+    #[serde(rename = "minPromptTokens", skip_serializing_if = "Option::is_none")]
+    pub min_prompt_tokens: Option<u64>,
+    /// 估算 prompt tokens 上限（含）
+    #[serde(rename = "maxPromptTokens", skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<u64>,
+    /// 命中后使用的模型
+    #[serde(rename = "targetModel")]
+    pub target_model: String,
+}
+
 /// 供应商单独的代理配置
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProviderProxyConfig {
@@ -230,11 +282,25 @@ pub struct ProviderMeta {
     /// 供应商单独的代理配置
     #[serde(rename = "proxyConfig", skip_serializing_if = "Option::is_none")]
     pub proxy_config: Option<ProviderProxyConfig>,
-    /// Claude API 格式（仅 Claude 供应商使用）
-    /// - "anthropic": 原生 Anthropic Messages API，直接透传
+    /// 上游 API 格式（Claude / Codex 供应商使用）
+    /// - "anthropic": 原生 Anthropic Messages API，直接透传（Claude 默认）
     /// - "openai_chat": OpenAI Chat Completions 格式，需要转换
+    ///   （Claude: Anthropic ↔ Chat；Codex: Responses ↔ Chat）
+    /// - "gemini": Gemini generateContent 格式，需要转换（仅 Claude）
     #[serde(rename = "apiFormat", skip_serializing_if = "Option::is_none")]
     pub api_format: Option<String>,
+    /// 负载均衡权重（weighted 策略使用，未设置时为 1，0 表示仅作为备用）
+    #[serde(rename = "loadBalanceWeight", skip_serializing_if = "Option::is_none")]
+    pub load_balance_weight: Option<u32>,
+    /// 模型路由规则（代理模式下按顺序匹配，优先于 env 中的模型映射）
+    #[serde(rename = "modelRoutes", default, skip_serializing_if = "Vec::is_empty")]
+    pub model_routes: Vec<ModelRouteRule>,
+    /// 额外的 API Key（代理模式下与配置中的 Key 组成 Key 池轮换使用）
+    #[serde(rename = "apiKeys", default, skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<String>,
+    /// Key 池轮换方式（未设置时为轮询）
+    #[serde(rename = "keyRotation", skip_serializing_if = "Option::is_none")]
+    pub key_rotation: Option<KeyRotation>,
 }
 
 impl ProviderManager {
@@ -0,0 +1,339 @@
+//! 代理监听入站访问控制
+//!
+//! 监听 0.0.0.0 等非回环地址时，局域网内的其他机器也能使用本机配置的 API Key。
+//! 启用访问控制后：
+//!
+//! - 客户端需在 `x-api-key` / `Authorization: Bearer` / `x-goog-api-key` 中携带代理访问令牌
+//! - 配置了 IP 白名单时，只接受白名单内的客户端（支持单个 IP 与 CIDR）
+//! - 回环地址默认放行：接管模式写入 Live 配置的是占位 Token，本机客户端无需改动
+//! - 也可以携带虚拟 Key（见 [`super::virtual_key`]），按 Key 限制应用、模型与消费
+//!
+//! 未通过校验的请求以 `ProxyError::AuthError` 拒绝，响应体按客户端的 API 格式构造。
+//! CORS 允许的来源单独配置，为空时允许任意来源（与旧版本一致）。
+
+use super::virtual_key::{self, VIRTUAL_KEY_HEADER};
+use super::{body_capture::app_type_for_path, server::ProxyState, session::ClientFormat};
+use super::{
+    types::{ProxyAuthConfig, VirtualKey},
+    ProxyError,
+};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// 不需要认证的路径（健康检查）
+const PUBLIC_PATHS: &[&str] = &["/health"];
+
+/// 入站访问控制中间件
+pub async fn auth_middleware(
+    State(state): State<ProxyState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    // 虚拟 Key ID 只能由本中间件写入
+    request.headers_mut().remove(VIRTUAL_KEY_HEADER);
+
+    let path = request.uri().path().to_string();
+    if PUBLIC_PATHS.contains(&path.as_str()) {
+        return next.run(request).await;
+    }
+
+    let config = read_config(&state.auth);
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+    let app_type = app_type_for_path(&path);
+
+    let result = resolve_virtual_key(&state, request.headers()).and_then(|virtual_key| {
+        check_request(&config, peer, request.headers(), virtual_key.is_some())?;
+        Ok(virtual_key)
+    });
+    let virtual_key = match result {
+        Ok(virtual_key) => virtual_key,
+        Err(reason) => {
+            log::warn!(
+                "拒绝未授权的代理请求: path={path}, client={}, reason={reason}",
+                peer.map(|ip| ip.to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            );
+            let format = match app_type {
+                Some("claude") => ClientFormat::Claude,
+                Some("gemini") => ClientFormat::Gemini,
+                _ => ClientFormat::OpenAI,
+            };
+            return ProxyError::AuthError(reason.to_string()).into_client_response(format);
+        }
+    };
+
+    if let Some(key) = virtual_key {
+        if let Some(app_type) = app_type.filter(|app| !virtual_key::app_allowed(&key, app)) {
+            return ProxyError::Forbidden(format!("虚拟 Key「{}」不允许访问 {app_type}", key.name))
+                .into_response();
+        }
+        if let Ok(value) = HeaderValue::from_str(&key.id) {
+            request.headers_mut().insert(VIRTUAL_KEY_HEADER, value);
+        }
+    }
+
+    next.run(request).await
+}
+
+/// 识别客户端携带的虚拟 Key
+///
+/// 令牌不是虚拟 Key 格式时返回 `Ok(None)`，交给共享令牌校验；
+/// 是虚拟 Key 格式但无效或已停用时直接拒绝，避免被当作匿名请求放行
+fn resolve_virtual_key(
+    state: &ProxyState,
+    headers: &HeaderMap,
+) -> Result<Option<VirtualKey>, &'static str> {
+    let Some(token) = client_token(headers).filter(|t| virtual_key::is_virtual_key(t)) else {
+        return Ok(None);
+    };
+
+    match state
+        .db
+        .find_virtual_key_by_hash(&virtual_key::hash_secret(token))
+    {
+        Ok(Some(key)) if key.enabled => Ok(Some(key)),
+        Ok(Some(_)) => Err("虚拟 Key 已停用"),
+        Ok(None) => Err("虚拟 Key 无效"),
+        Err(e) => {
+            log::error!("查询虚拟 Key 失败: {e}");
+            Err("虚拟 Key 校验失败")
+        }
+    }
+}
+
+/// 按配置构造 CORS 层（来源列表在每次请求时读取，修改后无需重启）
+pub fn cors_layer(auth: Arc<RwLock<ProxyAuthConfig>>) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            origin_allowed(&read_config(&auth).cors_origins, origin)
+        }))
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
+
+fn read_config(auth: &RwLock<ProxyAuthConfig>) -> ProxyAuthConfig {
+    match auth.read() {
+        Ok(config) => config.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// 校验请求来源与访问令牌，返回拒绝原因
+///
+/// `virtual_key` 表示客户端携带了有效的虚拟 Key，此时无需再匹配共享令牌
+fn check_request(
+    config: &ProxyAuthConfig,
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    virtual_key: bool,
+) -> Result<(), &'static str> {
+    if !config.enabled {
+        return Ok(());
+    }
+    if config.allow_loopback && peer.is_some_and(|ip| ip.is_loopback()) {
+        return Ok(());
+    }
+
+    if !config.ip_allowlist.is_empty() {
+        let allowed = peer.is_some_and(|ip| {
+            config
+                .ip_allowlist
+                .iter()
+                .any(|entry| ip_matches(entry.trim(), ip))
+        });
+        if !allowed {
+            return Err("客户端 IP 不在白名单内");
+        }
+    }
+
+    let expected = config.access_token.trim();
+    if expected.is_empty() || virtual_key {
+        return Ok(());
+    }
+    match client_token(headers) {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        Some(_) => Err("代理访问令牌无效"),
+        None => Err("缺少代理访问令牌"),
+    }
+}
+
+/// 从请求头读取客户端携带的令牌
+fn client_token(headers: &HeaderMap) -> Option<&str> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+
+    header("x-api-key")
+        .or_else(|| {
+            header("authorization").map(|v| {
+                v.strip_prefix("Bearer ")
+                    .or_else(|| v.strip_prefix("bearer "))
+                    .unwrap_or(v)
+                    .trim()
+            })
+        })
+        .or_else(|| header("x-goog-api-key"))
+}
+
+/// 白名单条目：单个 IP 或 CIDR（如 192.168.1.0/24、fd00::/8）
+fn ip_matches(entry: &str, ip: IpAddr) -> bool {
+    let (addr, prefix) = match entry.split_once('/') {
+        Some((addr, prefix)) => match prefix.parse::<u8>() {
+            Ok(prefix) => (addr, Some(prefix)),
+            Err(_) => return false,
+        },
+        None => (entry, None),
+    };
+    let Ok(network) = addr.parse::<IpAddr>() else {
+        return false;
+    };
+
+    match (network.to_canonical(), ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let prefix = prefix.unwrap_or(32).min(32) as u32;
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let prefix = prefix.unwrap_or(128).min(128) as u32;
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+fn origin_allowed(allowed: &[String], origin: &HeaderValue) -> bool {
+    if allowed.is_empty() {
+        return true;
+    }
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    allowed.iter().any(|entry| {
+        let entry = entry.trim().trim_end_matches('/');
+        entry == "*" || entry.eq_ignore_ascii_case(origin)
+    })
+}
+
+/// 比较耗时与内容无关，避免通过响应时间猜测令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProxyAuthConfig {
+        ProxyAuthConfig {
+            enabled: true,
+            access_token: "team-secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn accepts_token_from_any_supported_header() {
+        let lan: Option<IpAddr> = Some("192.168.1.20".parse().unwrap());
+        let config = config();
+
+        for (name, value) in [
+            ("x-api-key", "team-secret"),
+            ("authorization", "Bearer team-secret"),
+            ("x-goog-api-key", "team-secret"),
+        ] {
+            assert_eq!(
+                check_request(&config, lan, &headers(name, value), false),
+                Ok(())
+            );
+        }
+        assert_eq!(
+            check_request(&config, lan, &headers("x-api-key", "PROXY_MANAGED"), false),
+            Err("代理访问令牌无效")
+        );
+        assert_eq!(
+            check_request(&config, lan, &HeaderMap::new(), false),
+            Err("缺少代理访问令牌")
+        );
+        // 有效的虚拟 Key 无需匹配共享令牌，但仍受 IP 白名单限制
+        let vk = headers("x-api-key", "sk-ccs-0123");
+        assert_eq!(check_request(&config, lan, &vk, true), Ok(()));
+        let mut config = config;
+        config.ip_allowlist = vec!["10.0.0.0/8".to_string()];
+        assert!(check_request(&config, lan, &vk, true).is_err());
+    }
+
+    #[test]
+    fn loopback_is_trusted_unless_disabled() {
+        let local: Option<IpAddr> =
+            Some("::ffff:127.0.0.1".parse::<IpAddr>().unwrap().to_canonical());
+        let mut config = config();
+        assert_eq!(
+            check_request(&config, local, &HeaderMap::new(), false),
+            Ok(())
+        );
+
+        config.allow_loopback = false;
+        assert!(check_request(&config, local, &HeaderMap::new(), false).is_err());
+
+        config.enabled = false;
+        assert_eq!(
+            check_request(&config, None, &HeaderMap::new(), false),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn enforces_ip_allowlist_with_cidr() {
+        let mut config = config();
+        config.access_token.clear();
+        config.ip_allowlist = vec!["192.168.1.0/24".to_string(), "fd00::/8".to_string()];
+
+        let check =
+            |ip: &str| check_request(&config, Some(ip.parse().unwrap()), &HeaderMap::new(), false);
+        assert_eq!(check("192.168.1.77"), Ok(()));
+        assert_eq!(check("fd12::1"), Ok(()));
+        assert_eq!(check("192.168.2.1"), Err("客户端 IP 不在白名单内"));
+        assert!(check_request(&config, None, &HeaderMap::new(), false).is_err());
+
+        assert!(ip_matches("10.0.0.5", "10.0.0.5".parse().unwrap()));
+        assert!(ip_matches("0.0.0.0/0", "8.8.8.8".parse().unwrap()));
+        assert!(!ip_matches("not-an-ip", "8.8.8.8".parse().unwrap()));
+    }
+
+    #[test]
+    fn matches_configured_cors_origins() {
+        let origin = HeaderValue::from_static("http://localhost:5173");
+        assert!(origin_allowed(&[], &origin));
+        assert!(origin_allowed(
+            &["http://localhost:5173/".to_string()],
+            &origin
+        ));
+        assert!(!origin_allowed(
+            &["https://example.com".to_string()],
+            &origin
+        ));
+    }
+}
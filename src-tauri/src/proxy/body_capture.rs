@@ -0,0 +1,541 @@
+//! 请求/响应体抓取
+//!
+//! 调试用的抓取模式（按应用启用，默认关闭）：中间件为每个代理请求分配请求 ID，
+//! 启用抓取时把请求体与响应体（流式响应为拼接后的完整 SSE 文本）写入 proxy_request_bodies 表。
+//!
+//! - 请求 ID 通过 [`REQUEST_ID_HEADER`] 传给处理器并回写到响应头，请求日志使用同一个 ID
+//! - 认证类请求头、JSON 中的密钥字段以及形似 API Key 的字符串在写入前脱敏
+//! - 请求体与响应体分别按配置的字节数截断，记录按保留时长自动过期
+
+use super::{
+    failover_switch::FailoverSwitchManager,
+    forwarder::RequestForwarder,
+    provider_router::ProviderRouter,
+    server::ProxyState,
+    types::{ProxyStatus, RectifierConfig, ReplayResult, RequestBodyCapture},
+    ProxyError,
+};
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures::StreamExt;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+use tokio::sync::RwLock;
+
+/// 请求 ID 头（中间件写入请求与响应，不转发给上游）
+pub const REQUEST_ID_HEADER: &str = "x-cc-switch-request-id";
+
+/// 读取请求体的上限（与路由的 DefaultBodyLimit 一致）
+const MAX_REQUEST_BODY_BYTES: usize = 200 * 1024 * 1024;
+
+/// 重放请求的超时（秒）
+const REPLAY_TIMEOUT_SECS: u64 = 300;
+
+/// 脱敏后的占位符
+const REDACTED: &str = "[REDACTED]";
+
+/// 需要脱敏的请求/响应头
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "api-key",
+    "cookie",
+    "set-cookie",
+];
+
+/// 需要脱敏的 JSON 字段（忽略大小写、下划线与连字符）
+const SENSITIVE_FIELDS: &[&str] = &[
+    "apikey",
+    "authorization",
+    "accesstoken",
+    "refreshtoken",
+    "idtoken",
+    "clientsecret",
+    "password",
+    "secret",
+];
+
+/// 形似 API Key / Bearer Token 的字符串
+static KEY_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i:bearer\s+)[A-Za-z0-9._~+/=-]{8,}|\bsk-[A-Za-z0-9_-]{8,}|\bAIza[0-9A-Za-z_-]{20,}",
+    )
+    .expect("valid key pattern")
+});
+
+/// 文本中形如 `"api_key": "..."` 的字段（用于截断后无法解析的 JSON 与 SSE 文本）
+static FIELD_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)("(?:api[_-]?key|authorization|access[_-]?token|refresh[_-]?token|id[_-]?token|client[_-]?secret|password|secret)"\s*:\s*)"(?:[^"\\]|\\.)*""#,
+    )
+    .expect("valid field pattern")
+});
+
+/// 携带 API Key 的查询参数（Gemini 客户端使用 `?key=`）
+const QUERY_KEY_NAMES: &[&str] = &["key", "api_key", "apikey", "api-key"];
+
+/// 查询参数中的 API Key
+static QUERY_KEY_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)([?&](?:key|api[_-]?key)=)[^&#]*").expect("valid query key pattern")
+});
+
+/// 根据请求路径识别应用类型（与 server.rs 中的路由一一对应）
+pub fn app_type_for_path(path: &str) -> Option<&'static str> {
+    if path.ends_with("/v1/messages") {
+        Some("claude")
+    } else if path.ends_with("/chat/completions") || path.ends_with("/responses") {
+        Some("codex")
+    } else if path.contains("/v1beta/") {
+        Some("gemini")
+    } else {
+        None
+    }
+}
+
+/// 抓取记录对应的转发端点（重放时使用，与各处理器传给 forwarder 的端点一致）
+pub fn forward_endpoint(app_type: &str, endpoint: &str) -> Option<String> {
+    let path = endpoint.split('?').next().unwrap_or(endpoint);
+    match app_type {
+        "claude" => Some("/v1/messages".to_string()),
+        "codex" if path.ends_with("/chat/completions") => Some("/chat/completions".to_string()),
+        "codex" if path.ends_with("/responses") => Some("/responses".to_string()),
+        // 认证由 forwarder 按供应商配置添加，去掉抓取时已脱敏的 key 参数
+        "gemini" => {
+            let query: Vec<&str> = endpoint
+                .split_once('?')
+                .map(|(_, query)| query)
+                .unwrap_or_default()
+                .split('&')
+                .filter(|param| {
+                    let name = param.split('=').next().unwrap_or_default();
+                    !param.is_empty() && !QUERY_KEY_NAMES.contains(&name.to_lowercase().as_str())
+                })
+                .collect();
+            if query.is_empty() {
+                Some(path.to_string())
+            } else {
+                Some(format!("{path}?{}", query.join("&")))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// 代理请求中间件：分配请求 ID，启用抓取时记录请求与响应
+pub async fn capture_middleware(
+    State(state): State<ProxyState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let request_id_value = HeaderValue::from_str(&request_id).ok();
+    if let Some(value) = &request_id_value {
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, value.clone());
+    }
+
+    let capture = match app_type_for_path(request.uri().path()) {
+        Some(app_type) => match state.db.get_body_capture_config(app_type).await {
+            Ok(config) if config.enabled => Some((app_type, config)),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("[{app_type}] 读取请求体抓取配置失败: {e}");
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut response = match capture {
+        Some((app_type, config)) => {
+            let endpoint = request
+                .uri()
+                .path_and_query()
+                .map(|pq| redact_query(pq.as_str()))
+                .unwrap_or_else(|| request.uri().path().to_string());
+            let (parts, body) = request.into_parts();
+            let bytes = match axum::body::to_bytes(body, MAX_REQUEST_BODY_BYTES).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    return ProxyError::InvalidRequest(format!("读取请求体失败: {e}"))
+                        .into_response()
+                }
+            };
+
+            let max_bytes = config.max_body_bytes as usize;
+            let (request_body, request_truncated) = redact_body(&bytes, max_bytes);
+            let recorder = CaptureRecorder {
+                db: state.db.clone(),
+                retention_hours: config.retention_hours,
+                max_bytes,
+                capture: RequestBodyCapture {
+                    request_id: request_id.clone(),
+                    app_type: app_type.to_string(),
+                    endpoint,
+                    request_headers: redact_headers(&parts.headers),
+                    request_body,
+                    request_truncated,
+                    response_status: 0,
+                    response_headers: "{}".to_string(),
+                    response_body: String::new(),
+                    response_truncated: false,
+                    created_at: chrono::Utc::now().timestamp(),
+                },
+                response_bytes: Vec::new(),
+            };
+
+            let response = next
+                .run(Request::from_parts(parts, Body::from(bytes)))
+                .await;
+            record_response(response, recorder)
+        }
+        None => next.run(request).await,
+    };
+
+    if let Some(value) = request_id_value {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// 包装响应体：边转发边记录，响应体结束（或客户端断开）时写入抓取记录
+fn record_response(response: Response, mut recorder: CaptureRecorder) -> Response {
+    let (parts, body) = response.into_parts();
+    recorder.capture.response_status = parts.status.as_u16();
+    recorder.capture.response_headers = redact_headers(&parts.headers);
+
+    let stream = body.into_data_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            recorder.push(bytes);
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 单个请求的抓取记录（Drop 时写入数据库）
+struct CaptureRecorder {
+    db: Arc<Database>,
+    retention_hours: u32,
+    max_bytes: usize,
+    capture: RequestBodyCapture,
+    response_bytes: Vec<u8>,
+}
+
+impl CaptureRecorder {
+    fn push(&mut self, bytes: &Bytes) {
+        let remaining = self.max_bytes.saturating_sub(self.response_bytes.len());
+        if bytes.len() > remaining {
+            self.capture.response_truncated = true;
+        }
+        self.response_bytes
+            .extend_from_slice(&bytes[..bytes.len().min(remaining)]);
+    }
+}
+
+impl Drop for CaptureRecorder {
+    fn drop(&mut self) {
+        let db = self.db.clone();
+        let retention_hours = self.retention_hours;
+        let max_bytes = self.max_bytes;
+        let response_bytes = std::mem::take(&mut self.response_bytes);
+        let mut capture = std::mem::take(&mut self.capture);
+        let mut save = move || {
+            let (body, truncated) = redact_body(&response_bytes, max_bytes);
+            capture.response_body = body;
+            capture.response_truncated |= truncated;
+
+            if let Err(e) = db.save_request_body_capture(&capture, retention_hours) {
+                log::warn!("[{}] 保存请求体抓取记录失败: {e}", capture.app_type);
+            }
+        };
+
+        // 响应体通常在运行时的工作线程上被释放，脱敏与写库放到阻塞线程池执行
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(save);
+            }
+            Err(_) => save(),
+        }
+    }
+}
+
+/// 将抓取的请求重放到指定供应商（不经过故障转移与熔断器，不记录请求日志）
+///
+/// 已脱敏的请求头不会发送，认证信息按目标供应商的配置重新添加
+pub async fn replay_capture(
+    db: Arc<Database>,
+    capture: &RequestBodyCapture,
+    provider: &Provider,
+) -> Result<ReplayResult, AppError> {
+    if capture.request_truncated {
+        return Err(AppError::localized(
+            "body_capture.replay_truncated",
+            "抓取的请求体已被截断，无法重放。请调大抓取上限后重新抓取。",
+            "The captured request body was truncated and cannot be replayed. Increase the capture limit and capture again.",
+        ));
+    }
+    let app_type = AppType::from_str(&capture.app_type)?;
+    let endpoint = forward_endpoint(&capture.app_type, &capture.endpoint).ok_or_else(|| {
+        AppError::Message(format!("无法识别抓取请求的端点: {}", capture.endpoint))
+    })?;
+    let body: Value = serde_json::from_str(&capture.request_body)
+        .map_err(|e| AppError::Message(format!("抓取的请求体不是有效的 JSON: {e}")))?;
+    let headers = replay_headers(&capture.request_headers);
+    let max_bytes = db
+        .get_body_capture_config(&capture.app_type)
+        .await?
+        .max_body_bytes as usize;
+
+    let forwarder = RequestForwarder::new(
+        Arc::new(ProviderRouter::new(db.clone())),
+        REPLAY_TIMEOUT_SECS,
+        Arc::new(RwLock::new(ProxyStatus::default())),
+        Arc::new(RwLock::new(HashMap::new())),
+        Arc::new(FailoverSwitchManager::new(db)),
+        None,
+        provider.id.clone(),
+        0,
+        0,
+        RectifierConfig::default(),
+    );
+
+    let start = Instant::now();
+    let (status_code, bytes) = match forwarder
+        .replay(&app_type, provider, &endpoint, &body, &headers)
+        .await
+    {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| AppError::Message(format!("读取上游响应失败: {e}")))?;
+            (status, bytes.to_vec())
+        }
+        Err(ProxyError::UpstreamError { status, body, .. }) => {
+            (status, body.unwrap_or_default().into_bytes())
+        }
+        Err(e) => return Err(AppError::Message(format!("重放请求失败: {e}"))),
+    };
+    let (response_body, truncated) = redact_body(&bytes, max_bytes);
+
+    Ok(ReplayResult {
+        provider_id: provider.id.clone(),
+        provider_name: provider.name.clone(),
+        status_code,
+        latency_ms: start.elapsed().as_millis() as u64,
+        response_body,
+        truncated,
+    })
+}
+
+/// 从抓取的请求头恢复可重放的请求头（跳过已脱敏的值）
+fn replay_headers(captured: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let Ok(Value::Object(map)) = serde_json::from_str::<Value>(captured) else {
+        return headers;
+    };
+    for (name, value) in map {
+        let Some(value) = value.as_str().filter(|v| !v.contains(REDACTED)) else {
+            continue;
+        };
+        if let (Ok(name), Ok(value)) = (
+            axum::http::HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
+/// 请求/响应头转为 JSON 对象文本，认证类头替换为占位符
+pub fn redact_headers(headers: &HeaderMap) -> String {
+    let mut map = Map::new();
+    for name in headers.keys() {
+        let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+            REDACTED.to_string()
+        } else {
+            headers
+                .get_all(name)
+                .iter()
+                .map(|v| redact_text(&String::from_utf8_lossy(v.as_bytes())))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        map.insert(name.as_str().to_string(), Value::String(value));
+    }
+    Value::Object(map).to_string()
+}
+
+/// 脱敏并截断请求/响应体：可解析的 JSON 按字段脱敏，其余文本按正则脱敏
+///
+/// 返回 (脱敏后的文本, 是否截断)
+pub fn redact_body(bytes: &[u8], max_bytes: usize) -> (String, bool) {
+    let text = match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => redact_text(&String::from_utf8_lossy(bytes)),
+    };
+    truncate_utf8(text, max_bytes)
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive_field(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        Value::String(text) if KEY_PATTERN.is_match(text) => {
+            *text = KEY_PATTERN.replace_all(text, REDACTED).into_owned();
+        }
+        _ => {}
+    }
+}
+
+fn is_sensitive_field(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect();
+    SENSITIVE_FIELDS.contains(&normalized.as_str())
+}
+
+/// 脱敏 URL 查询参数中的 API Key
+fn redact_query(path_and_query: &str) -> String {
+    QUERY_KEY_PATTERN
+        .replace_all(path_and_query, format!("${{1}}{REDACTED}"))
+        .into_owned()
+}
+
+fn redact_text(text: &str) -> String {
+    let text = FIELD_PATTERN.replace_all(text, format!(r#"${{1}}"{REDACTED}""#));
+    KEY_PATTERN.replace_all(&text, REDACTED).into_owned()
+}
+
+/// 按字节数截断（不截断在 UTF-8 字符中间）
+fn truncate_utf8(mut text: String, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    (text, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_auth_headers_and_key_like_values() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer sk-secret"),
+        );
+        headers.insert("x-api-key", HeaderValue::from_static("sk-ant-123456789"));
+        headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+        headers.insert(
+            "x-debug",
+            HeaderValue::from_static("key=sk-abcdefghijklmnop"),
+        );
+
+        let redacted: Value = serde_json::from_str(&redact_headers(&headers)).unwrap();
+        assert_eq!(redacted["authorization"], REDACTED);
+        assert_eq!(redacted["x-api-key"], REDACTED);
+        assert_eq!(redacted["anthropic-version"], "2023-06-01");
+        assert_eq!(redacted["x-debug"], "key=[REDACTED]");
+    }
+
+    #[test]
+    fn redacts_json_fields_and_sse_text() {
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "api_key": "plain-secret",
+            "metadata": {"clientSecret": "s3cret", "user_id": "u1"},
+            "messages": [{"role": "user", "content": "my key is AIzaSyA1234567890abcdefghij"}]
+        });
+        let (text, truncated) = redact_body(body.to_string().as_bytes(), 1024);
+        assert!(!truncated);
+        let redacted: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(redacted["api_key"], REDACTED);
+        assert_eq!(redacted["metadata"]["clientSecret"], REDACTED);
+        assert_eq!(redacted["metadata"]["user_id"], "u1");
+        assert_eq!(redacted["messages"][0]["content"], "my key is [REDACTED]");
+
+        let sse = "data: {\"access_token\":\"abc\\\"def\",\"text\":\"hi\"}\n\n";
+        let (text, _) = redact_body(sse.as_bytes(), 1024);
+        assert_eq!(
+            text,
+            "data: {\"access_token\":\"[REDACTED]\",\"text\":\"hi\"}\n\n"
+        );
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        let (text, truncated) = redact_body("你好世界".as_bytes(), 7);
+        assert!(truncated);
+        assert_eq!(text, "你好");
+    }
+
+    #[test]
+    fn maps_paths_to_app_types_and_endpoints() {
+        assert_eq!(app_type_for_path("/claude/v1/messages"), Some("claude"));
+        assert_eq!(app_type_for_path("/v1/v1/chat/completions"), Some("codex"));
+        assert_eq!(app_type_for_path("/codex/v1/responses"), Some("codex"));
+        assert_eq!(
+            app_type_for_path("/gemini/v1beta/models/gemini-pro:generateContent"),
+            Some("gemini")
+        );
+        assert_eq!(app_type_for_path("/health"), None);
+
+        assert_eq!(
+            forward_endpoint("codex", "/v1/responses?x=1").as_deref(),
+            Some("/responses")
+        );
+        assert_eq!(
+            forward_endpoint("claude", "/claude/v1/messages").as_deref(),
+            Some("/v1/messages")
+        );
+
+        let gemini =
+            redact_query("/v1beta/models/gemini-pro:streamGenerateContent?alt=sse&key=abc");
+        assert_eq!(
+            gemini,
+            "/v1beta/models/gemini-pro:streamGenerateContent?alt=sse&key=[REDACTED]"
+        );
+        assert_eq!(
+            forward_endpoint("gemini", &gemini).as_deref(),
+            Some("/v1beta/models/gemini-pro:streamGenerateContent?alt=sse")
+        );
+    }
+}
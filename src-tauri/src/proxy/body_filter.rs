@@ -36,7 +36,6 @@ use std::collections::HashSet;
 /// let output = filter_private_params(input);
 /// // output 中不包含 _internal_id 和 _token
 /// ```
-#[cfg(test)]
 pub fn filter_private_params(body: Value) -> Value {
     filter_private_params_with_whitelist(body, &[])
 }
@@ -1,11 +1,14 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
 
+use super::rate_limit::RateLimitInfo;
+use super::session::ClientFormat;
+
 #[derive(Debug, Error)]
 pub enum ProxyError {
     #[error("服务器已在运行")]
@@ -35,12 +38,24 @@ pub enum ProxyError {
     #[error("未配置供应商")]
     NoProvidersConfigured,
 
+    #[error("所有供应商均已超出消费限额")]
+    BudgetExceeded,
+
+    /// 虚拟 Key 超出自身的消费限额
+    #[error("虚拟 Key 已超出消费限额: {0}")]
+    KeyBudgetExceeded(String),
+
     #[allow(dead_code)]
     #[error("Provider不健康: {0}")]
     ProviderUnhealthy(String),
 
+    /// 上游 HTTP 错误；429/529 时附带从响应头解析出的限流信息
     #[error("上游错误 (状态码 {status}): {body:?}")]
-    UpstreamError { status: u16, body: Option<String> },
+    UpstreamError {
+        status: u16,
+        body: Option<String>,
+        rate_limit: Option<RateLimitInfo>,
+    },
 
     #[error("超过最大重试次数")]
     MaxRetriesExceeded,
@@ -55,7 +70,6 @@ pub enum ProxyError {
     #[error("格式转换错误: {0}")]
     TransformError(String),
 
-    #[allow(dead_code)]
     #[error("无效的请求: {0}")]
     InvalidRequest(String),
 
@@ -68,10 +82,13 @@ pub enum ProxyError {
     StreamIdleTimeout(u64),
 
     /// 认证错误
-    #[allow(dead_code)]
     #[error("认证失败: {0}")]
     AuthError(String),
 
+    /// 已认证但无权访问（如虚拟 Key 未授权该应用或模型）
+    #[error("无权访问: {0}")]
+    Forbidden(String),
+
     #[allow(dead_code)]
     #[error("内部错误: {0}")]
     Internal(String),
@@ -83,6 +100,7 @@ impl IntoResponse for ProxyError {
             ProxyError::UpstreamError {
                 status: upstream_status,
                 body: upstream_body,
+                ..
             } => {
                 let http_status =
                     StatusCode::from_u16(*upstream_status).unwrap_or(StatusCode::BAD_GATEWAY);
@@ -112,6 +130,20 @@ impl IntoResponse for ProxyError {
 
                 (http_status, error_body)
             }
+            ProxyError::BudgetExceeded | ProxyError::KeyBudgetExceeded(_) => {
+                // 同时满足 Anthropic（type/error.type）与 OpenAI（error.code）的错误结构，
+                // 客户端会直接展示 message，且不会把它当作可重试的限流错误
+                let error_body = json!({
+                    "type": "error",
+                    "error": {
+                        "type": "billing_error",
+                        "code": "insufficient_quota",
+                        "message": self.to_string(),
+                    }
+                });
+
+                (StatusCode::PAYMENT_REQUIRED, error_body)
+            }
             _ => {
                 let (http_status, message) = match &self {
                     ProxyError::AlreadyRunning => (StatusCode::CONFLICT, self.to_string()),
@@ -154,10 +186,15 @@ impl IntoResponse for ProxyError {
                         (StatusCode::GATEWAY_TIMEOUT, self.to_string())
                     }
                     ProxyError::AuthError(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+                    ProxyError::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
                     ProxyError::Internal(_) => {
                         (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
                     }
-                    ProxyError::UpstreamError { .. } => unreachable!(),
+                    ProxyError::UpstreamError { .. }
+                    | ProxyError::BudgetExceeded
+                    | ProxyError::KeyBudgetExceeded(_) => {
+                        unreachable!()
+                    }
                 };
 
                 let error_body = json!({
@@ -171,7 +208,62 @@ impl IntoResponse for ProxyError {
             }
         };
 
-        (status, Json(body)).into_response()
+        // 限流错误透传等待时间，客户端据此退避
+        let retry_after = match &self {
+            ProxyError::UpstreamError {
+                rate_limit: Some(info),
+                ..
+            } => info.wait(),
+            _ => None,
+        };
+
+        let mut response = (status, Json(body)).into_response();
+        if let Some(wait) = retry_after {
+            let secs = wait.as_secs_f64().ceil() as u64;
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+impl ProxyError {
+    /// 按客户端 API 格式构造错误响应
+    ///
+    /// 认证错误需要符合各客户端的错误结构，客户端才会提示"无效的 API Key"而不是重试；
+    /// 其他错误沿用 `into_response` 的通用结构
+    pub fn into_client_response(self, format: ClientFormat) -> Response {
+        let ProxyError::AuthError(_) = &self else {
+            return self.into_response();
+        };
+
+        let message = self.to_string();
+        let body = match format {
+            ClientFormat::Claude => json!({
+                "type": "error",
+                "error": {
+                    "type": "authentication_error",
+                    "message": message,
+                }
+            }),
+            ClientFormat::Gemini | ClientFormat::GeminiCli => json!({
+                "error": {
+                    "code": StatusCode::UNAUTHORIZED.as_u16(),
+                    "message": message,
+                    "status": "UNAUTHENTICATED",
+                }
+            }),
+            ClientFormat::Codex | ClientFormat::OpenAI | ClientFormat::Unknown => json!({
+                "error": {
+                    "message": message,
+                    "type": "invalid_request_error",
+                    "code": "invalid_api_key",
+                }
+            }),
+        };
+
+        (StatusCode::UNAUTHORIZED, Json(body)).into_response()
     }
 }
 
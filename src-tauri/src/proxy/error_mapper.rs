@@ -33,6 +33,15 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
         // 未配置供应商：503 Service Unavailable
         ProxyError::NoProvidersConfigured => 503,
 
+        // 所有供应商超出消费限额：402 Payment Required
+        ProxyError::BudgetExceeded => 402,
+
+        // 虚拟 Key 超出消费限额：402 Payment Required
+        ProxyError::KeyBudgetExceeded(_) => 402,
+
+        // 虚拟 Key 无权访问应用/模型：403 Forbidden
+        ProxyError::Forbidden(_) => 403,
+
         // 重试耗尽：503 Service Unavailable
         ProxyError::MaxRetriesExceeded => 503,
 
@@ -53,7 +62,7 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
 /// 将 ProxyError 转换为用户友好的错误消息
 pub fn get_error_message(error: &ProxyError) -> String {
     match error {
-        ProxyError::UpstreamError { status, body } => {
+        ProxyError::UpstreamError { status, body, .. } => {
             if let Some(body) = body {
                 format!("上游错误 ({status}): {body}")
             } else {
@@ -65,6 +74,7 @@ pub fn get_error_message(error: &ProxyError) -> String {
         ProxyError::NoAvailableProvider => "无可用 Provider".to_string(),
         ProxyError::AllProvidersCircuitOpen => "所有供应商已熔断，无可用渠道".to_string(),
         ProxyError::NoProvidersConfigured => "未配置供应商".to_string(),
+        ProxyError::BudgetExceeded => "所有供应商均已超出消费限额".to_string(),
         ProxyError::MaxRetriesExceeded => "所有 Provider 都失败，重试耗尽".to_string(),
         ProxyError::ProviderUnhealthy(msg) => format!("Provider 不健康: {msg}"),
         ProxyError::DatabaseError(msg) => format!("数据库错误: {msg}"),
@@ -82,16 +92,42 @@ mod tests {
         let error = ProxyError::UpstreamError {
             status: 401,
             body: Some("Unauthorized".to_string()),
+            rate_limit: None,
         };
         assert_eq!(map_proxy_error_to_status(&error), 401);
     }
 
+    #[test]
+    fn test_rate_limited_upstream_error_sets_retry_after() {
+        use crate::proxy::rate_limit::cooldown_error;
+        use axum::response::IntoResponse;
+        use std::time::Duration;
+
+        let error = cooldown_error(Duration::from_millis(2500));
+        assert_eq!(map_proxy_error_to_status(&error), 429);
+
+        let response = error.into_response();
+        assert_eq!(response.status().as_u16(), 429);
+        assert_eq!(
+            response
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok()),
+            Some("3")
+        );
+    }
+
     #[test]
     fn test_map_timeout_error() {
         let error = ProxyError::Timeout("Request timeout".to_string());
         assert_eq!(map_proxy_error_to_status(&error), 504);
     }
 
+    #[test]
+    fn test_map_budget_exceeded_error() {
+        assert_eq!(map_proxy_error_to_status(&ProxyError::BudgetExceeded), 402);
+    }
+
     #[test]
     fn test_map_connection_error() {
         let error = ProxyError::ForwardFailed("Connection refused".to_string());
@@ -109,6 +145,7 @@ mod tests {
         let error = ProxyError::UpstreamError {
             status: 500,
             body: Some("Internal Server Error".to_string()),
+            rate_limit: None,
         };
         let msg = get_error_message(&error);
         assert!(msg.contains("上游错误"));
@@ -5,9 +5,15 @@
 use super::{
     body_filter::filter_private_params_with_whitelist,
     error::*,
+    error_mapper::map_proxy_error_to_status,
     failover_switch::FailoverSwitchManager,
+    hedge::{await_first_chunk, is_streaming_request, HedgeLoser},
     provider_router::ProviderRouter,
-    providers::{get_adapter, ProviderAdapter, ProviderType},
+    providers::{get_adapter, AuthInfo, AuthStrategy, ProviderAdapter, ProviderType},
+    rate_limit::{
+        cooldown_error, is_rate_limit_status, parse_rate_limit_headers, MAX_RATE_LIMIT_RETRIES,
+        RATE_LIMIT_WAIT_BUDGET,
+    },
     thinking_rectifier::{rectify_anthropic_request, should_rectify_thinking_signature},
     types::{ProxyStatus, RectifierConfig},
     ProxyError,
@@ -15,7 +21,8 @@ use super::{
 use crate::{app_config::AppType, provider::Provider};
 use reqwest::Response;
 use serde_json::Value;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 /// Headers 黑名单 - 不透传到上游的 Headers
@@ -63,6 +70,10 @@ const HEADER_BLACKLIST: &[&str] = &[
     "x-b3-sampled",
     "traceparent",
     "tracestate",
+    // cc-switch 内部使用的请求 ID（由抓取中间件写入）
+    "x-cc-switch-request-id",
+    // cc-switch 内部使用的虚拟 Key ID（由认证中间件写入）
+    "x-cc-switch-virtual-key-id",
     // anthropic 特定头单独处理，避免重复
     "anthropic-beta",
     "anthropic-version",
@@ -74,11 +85,57 @@ const HEADER_BLACKLIST: &[&str] = &[
 pub struct ForwardResult {
     pub response: Response,
     pub provider: Provider,
+    /// 模型映射后实际发送给上游的模型（未映射时为 None）
+    pub mapped_model: Option<String>,
+    /// 实际使用的 API Key（脱敏）
+    pub masked_key: Option<String>,
+    /// 对冲请求中落败的一方（未触发对冲时为 None）
+    pub hedge_loser: Option<HedgeLoser>,
 }
 
 pub struct ForwardError {
     pub error: ProxyError,
     pub provider: Option<Provider>,
+    /// 失败请求使用的 API Key（脱敏）
+    pub masked_key: Option<String>,
+    /// 对冲请求中落败的一方（未触发对冲时为 None）
+    pub hedge_loser: Option<HedgeLoser>,
+}
+
+/// 单个供应商的转发结果（含实际使用的脱敏 Key）
+struct ForwardAttempt {
+    result: Result<Response, ProxyError>,
+    masked_key: Option<String>,
+}
+
+/// 转发过程中最近一次使用的脱敏 Key（对冲请求被取消时据此记录日志）
+type KeySlot = Mutex<Option<String>>;
+
+/// 对冲竞速的结果
+struct HedgeOutcome<'a> {
+    /// 返回结果的供应商
+    provider: &'a Provider,
+    used_half_open_permit: bool,
+    attempt: ForwardAttempt,
+    /// 已发起对冲的供应商（后续故障转移跳过）
+    hedge_provider_id: Option<String>,
+    loser: Option<HedgeLoser>,
+}
+
+impl<'a> HedgeOutcome<'a> {
+    fn single(
+        provider: &'a Provider,
+        used_half_open_permit: bool,
+        attempt: ForwardAttempt,
+    ) -> Self {
+        Self {
+            provider,
+            used_half_open_permit,
+            attempt,
+            hedge_provider_id: None,
+            loser: None,
+        }
+    }
 }
 
 pub struct RequestForwarder {
@@ -96,6 +153,14 @@ pub struct RequestForwarder {
     rectifier_config: RectifierConfig,
     /// 非流式请求超时（秒）
     non_streaming_timeout: std::time::Duration,
+    /// 客户端提供的 Session ID（成功后绑定到实际使用的供应商）
+    sticky_session: Option<String>,
+    /// 对冲等待时间（为 None 时不对冲）
+    hedge_delay: Option<Duration>,
+    /// 首选供应商由本次请求决定（模型路由/负载均衡）
+    per_request_target: bool,
+    /// 是否开启了自动故障转移
+    failover_enabled: bool,
 }
 
 impl RequestForwarder {
@@ -121,9 +186,63 @@ impl RequestForwarder {
             current_provider_id_at_start,
             rectifier_config,
             non_streaming_timeout: std::time::Duration::from_secs(non_streaming_timeout),
+            sticky_session: None,
+            hedge_delay: None,
+            per_request_target: false,
+            failover_enabled: false,
         }
     }
 
+    /// 设置需要保持供应商亲和的 Session ID
+    pub fn with_sticky_session(mut self, session_id: Option<String>) -> Self {
+        self.sticky_session = session_id;
+        self
+    }
+
+    /// 启用对冲：首选供应商在 `delay` 内未返回首个数据块时，向下一个供应商发起相同请求
+    pub fn with_hedge(mut self, delay: Option<Duration>) -> Self {
+        self.hedge_delay = delay;
+        self
+    }
+
+    /// 设置是否开启了自动故障转移（关闭时跳过熔断器检查）
+    pub fn with_failover(mut self, enabled: bool) -> Self {
+        self.failover_enabled = enabled;
+        self
+    }
+
+    /// 标记首选供应商由本次请求决定：故障转移到后备时不切换全局当前供应商
+    pub fn with_per_request_target(mut self, per_request_target: bool) -> Self {
+        self.per_request_target = per_request_target;
+        self
+    }
+
+    /// 将请求单独转发给指定供应商（用于重放抓取的请求）
+    ///
+    /// 不经过故障转移与熔断器，不记录请求日志；仍会按供应商的 Key 池选择 Key
+    pub async fn replay(
+        &self,
+        app_type: &AppType,
+        provider: &Provider,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+    ) -> Result<Response, ProxyError> {
+        let adapter = get_adapter(app_type);
+        self.forward_attempt(
+            app_type.as_str(),
+            provider,
+            endpoint,
+            body,
+            headers,
+            adapter.as_ref(),
+            false,
+            None,
+        )
+        .await
+        .result
+    }
+
     /// 转发请求（带故障转移）
     ///
     /// # Arguments
@@ -148,21 +267,37 @@ impl RequestForwarder {
             return Err(ForwardError {
                 error: ProxyError::NoAvailableProvider,
                 provider: None,
+                masked_key: None,
+                hedge_loser: None,
             });
         }
 
         let mut last_error = None;
         let mut last_provider = None;
+        let mut last_masked_key = None;
         let mut attempted_providers = 0usize;
 
         // 整流器重试标记：确保整流最多触发一次
         let mut rectifier_retried = false;
 
-        // 单 Provider 场景下跳过熔断器检查（故障转移关闭时）
-        let bypass_circuit_breaker = providers.len() == 1;
+        // 故障转移关闭时只有当前供应商，跳过熔断器检查
+        // （不能按过滤后的列表长度判断：限流冷却、限额、熔断可能把故障转移队列过滤到只剩一个）
+        let bypass_circuit_breaker = !self.failover_enabled;
+
+        // 对冲仅用于流式请求的首个供应商
+        let mut hedge_delay = self
+            .hedge_delay
+            .filter(|_| !bypass_circuit_breaker && is_streaming_request(endpoint, &body));
+        let mut hedge_provider_id = None;
+        let mut hedge_loser = None;
 
         // 依次尝试每个供应商
-        for provider in providers.iter() {
+        for (index, provider) in providers.iter().enumerate() {
+            // 已作为对冲请求尝试过
+            if hedge_provider_id.as_deref() == Some(provider.id.as_str()) {
+                continue;
+            }
+
             // 发起请求前先获取熔断器放行许可（HalfOpen 会占用探测名额）
             // 单 Provider 场景下跳过此检查，避免熔断器阻塞所有请求
             let (allowed, used_half_open_permit) = if bypass_circuit_breaker {
@@ -190,11 +325,48 @@ impl RequestForwarder {
                 status.last_request_at = Some(chrono::Utc::now().to_rfc3339());
             }
 
-            // 转发请求（每个 Provider 只尝试一次，重试由客户端控制）
-            match self
-                .forward(provider, endpoint, &body, &headers, adapter.as_ref())
-                .await
-            {
+            // 转发请求（每个 Provider 只尝试一次，重试由客户端控制；
+            // 上游限流时仅在只剩这一个供应商时于等待预算内重试）
+            let (provider, used_half_open_permit, attempt) = match hedge_delay.take() {
+                Some(delay) => {
+                    let outcome = self
+                        .race_hedged(
+                            app_type_str,
+                            (provider, used_half_open_permit),
+                            &providers[index + 1..],
+                            endpoint,
+                            &body,
+                            &headers,
+                            adapter.as_ref(),
+                            delay,
+                        )
+                        .await;
+                    hedge_provider_id = outcome.hedge_provider_id;
+                    hedge_loser = outcome.loser;
+                    (
+                        outcome.provider,
+                        outcome.used_half_open_permit,
+                        outcome.attempt,
+                    )
+                }
+                None => {
+                    let attempt = self
+                        .forward_attempt(
+                            app_type_str,
+                            provider,
+                            endpoint,
+                            &body,
+                            &headers,
+                            adapter.as_ref(),
+                            bypass_circuit_breaker,
+                            None,
+                        )
+                        .await;
+                    (provider, used_half_open_permit, attempt)
+                }
+            };
+            let masked_key = attempt.masked_key;
+            match attempt.result {
                 Ok(response) => {
                     // 成功：记录成功并更新熔断器
                     let _ = self
@@ -208,6 +380,12 @@ impl RequestForwarder {
                         )
                         .await;
 
+                    // 同一 Session 的后续请求沿用该供应商
+                    if let Some(session_id) = &self.sticky_session {
+                        self.router
+                            .bind_session(app_type_str, session_id, &provider.id);
+                    }
+
                     // 更新当前应用类型使用的 provider
                     {
                         let mut current_providers = self.current_providers.write().await;
@@ -226,7 +404,9 @@ impl RequestForwarder {
                             self.current_provider_id_at_start.as_str() != provider.id.as_str();
                         if should_switch {
                             status.failover_count += 1;
-
+                        }
+                        // 按请求路由选出的供应商只用于本次请求，不切换全局当前供应商
+                        if should_switch && !self.per_request_target {
                             // 异步触发供应商切换，更新 UI/托盘，并把“当前供应商”同步为实际使用的 provider
                             let fm = self.failover_manager.clone();
                             let ah = self.app_handle.clone();
@@ -249,6 +429,11 @@ impl RequestForwarder {
                     return Ok(ForwardResult {
                         response,
                         provider: provider.clone(),
+                        mapped_model: super::model_mapper::resolve_request_model(
+                            &body, endpoint, provider,
+                        ),
+                        masked_key,
+                        hedge_loser,
                     });
                 }
                 Err(e) => {
@@ -287,6 +472,8 @@ impl RequestForwarder {
                                 return Err(ForwardError {
                                     error: e,
                                     provider: Some(provider.clone()),
+                                    masked_key,
+                                    hedge_loser: hedge_loser.take(),
                                 });
                             }
 
@@ -317,6 +504,8 @@ impl RequestForwarder {
                                 return Err(ForwardError {
                                     error: e,
                                     provider: Some(provider.clone()),
+                                    masked_key,
+                                    hedge_loser: hedge_loser.take(),
                                 });
                             }
 
@@ -332,10 +521,20 @@ impl RequestForwarder {
                             let _ = std::mem::replace(&mut rectifier_retried, true);
 
                             // 使用同一供应商重试（不计入熔断器）
-                            match self
-                                .forward(provider, endpoint, &body, &headers, adapter.as_ref())
-                                .await
-                            {
+                            let retry = self
+                                .forward_attempt(
+                                    app_type_str,
+                                    provider,
+                                    endpoint,
+                                    &body,
+                                    &headers,
+                                    adapter.as_ref(),
+                                    bypass_circuit_breaker,
+                                    None,
+                                )
+                                .await;
+                            let masked_key = retry.masked_key;
+                            match retry.result {
                                 Ok(response) => {
                                     log::info!("[{app_type_str}] [RECT-002] 整流重试成功");
                                     // 记录成功
@@ -350,6 +549,14 @@ impl RequestForwarder {
                                         )
                                         .await;
 
+                                    if let Some(session_id) = &self.sticky_session {
+                                        self.router.bind_session(
+                                            app_type_str,
+                                            session_id,
+                                            &provider.id,
+                                        );
+                                    }
+
                                     // 更新当前应用类型使用的 provider
                                     {
                                         let mut current_providers =
@@ -370,7 +577,9 @@ impl RequestForwarder {
                                                 != provider.id.as_str();
                                         if should_switch {
                                             status.failover_count += 1;
-
+                                        }
+                                        // 按请求路由选出的供应商只用于本次请求，不切换全局当前供应商
+                                        if should_switch && !self.per_request_target {
                                             // 异步触发供应商切换，更新 UI/托盘
                                             let fm = self.failover_manager.clone();
                                             let ah = self.app_handle.clone();
@@ -394,6 +603,11 @@ impl RequestForwarder {
                                     return Ok(ForwardResult {
                                         response,
                                         provider: provider.clone(),
+                                        mapped_model: super::model_mapper::resolve_request_model(
+                                            &body, endpoint, provider,
+                                        ),
+                                        masked_key,
+                                        hedge_loser: hedge_loser.take(),
                                     });
                                 }
                                 Err(retry_err) => {
@@ -407,7 +621,9 @@ impl RequestForwarder {
                                         ProxyError::Timeout(_) | ProxyError::ForwardFailed(_) => {
                                             true
                                         }
-                                        ProxyError::UpstreamError { status, .. } => *status >= 500,
+                                        ProxyError::UpstreamError { status, .. } => {
+                                            *status >= 500 && !is_rate_limit_status(*status)
+                                        }
                                         _ => false,
                                     };
 
@@ -445,23 +661,36 @@ impl RequestForwarder {
                                     return Err(ForwardError {
                                         error: retry_err,
                                         provider: Some(provider.clone()),
+                                        masked_key,
+                                        hedge_loser: hedge_loser.take(),
                                     });
                                 }
                             }
                         }
                     }
 
-                    // 失败：记录失败并更新熔断器
-                    let _ = self
-                        .router
-                        .record_result(
-                            &provider.id,
-                            app_type_str,
-                            used_half_open_permit,
-                            false,
-                            Some(e.to_string()),
-                        )
-                        .await;
+                    if is_rate_limited(&e) {
+                        // 限流：供应商已进入限时冷却，不计入熔断器
+                        self.router
+                            .release_permit_neutral(
+                                &provider.id,
+                                app_type_str,
+                                used_half_open_permit,
+                            )
+                            .await;
+                    } else {
+                        // 失败：记录失败并更新熔断器
+                        let _ = self
+                            .router
+                            .record_result(
+                                &provider.id,
+                                app_type_str,
+                                used_half_open_permit,
+                                false,
+                                Some(e.to_string()),
+                            )
+                            .await;
+                    }
 
                     // 分类错误
                     let category = self.categorize_proxy_error(&e);
@@ -485,6 +714,7 @@ impl RequestForwarder {
 
                             last_error = Some(e);
                             last_provider = Some(provider.clone());
+                            last_masked_key = masked_key;
                             // 继续尝试下一个供应商
                             continue;
                         }
@@ -503,6 +733,8 @@ impl RequestForwarder {
                             return Err(ForwardError {
                                 error: e,
                                 provider: Some(provider.clone()),
+                                masked_key,
+                                hedge_loser: hedge_loser.take(),
                             });
                         }
                     }
@@ -524,6 +756,8 @@ impl RequestForwarder {
             return Err(ForwardError {
                 error: ProxyError::NoAvailableProvider,
                 provider: None,
+                masked_key: None,
+                hedge_loser,
             });
         }
 
@@ -543,9 +777,206 @@ impl RequestForwarder {
         Err(ForwardError {
             error: last_error.unwrap_or(ProxyError::MaxRetriesExceeded),
             provider: last_provider,
+            masked_key: last_masked_key,
+            hedge_loser,
         })
     }
 
+    /// 对冲转发：首选供应商在 `delay` 内未返回首个数据块时，向后续首个可用供应商发起相同请求
+    ///
+    /// 先返回首个数据块的一方胜出，另一方被取消（释放熔断器许可，不计入熔断器）；
+    /// 先结束的一方失败时计入熔断器，继续等待另一方
+    #[allow(clippy::too_many_arguments)]
+    async fn race_hedged<'a>(
+        &self,
+        app_type: &str,
+        (primary, primary_permit): (&'a Provider, bool),
+        candidates: &'a [Provider],
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        adapter: &dyn ProviderAdapter,
+        delay: Duration,
+    ) -> HedgeOutcome<'a> {
+        let primary_key = KeySlot::default();
+        let hedge_key = KeySlot::default();
+        let primary_started = Instant::now();
+        let mut primary_fut = Box::pin(self.forward_until_first_chunk(
+            app_type,
+            primary,
+            endpoint,
+            body,
+            headers,
+            adapter,
+            &primary_key,
+        ));
+        tokio::select! {
+            attempt = &mut primary_fut => {
+                return HedgeOutcome::single(primary, primary_permit, attempt);
+            }
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        let mut hedge = None;
+        for candidate in candidates {
+            let permit = self
+                .router
+                .allow_provider_request(&candidate.id, app_type)
+                .await;
+            if permit.allowed {
+                hedge = Some((candidate, permit.used_half_open_permit));
+                break;
+            }
+        }
+        let Some((hedge_provider, hedge_permit)) = hedge else {
+            return HedgeOutcome::single(primary, primary_permit, primary_fut.await);
+        };
+
+        log::info!(
+            "[{app_type}] [FWD-003] Provider {} {}ms 内未返回首个数据块，对冲请求 Provider {}",
+            primary.name,
+            delay.as_millis(),
+            hedge_provider.name
+        );
+        let hedge_started = Instant::now();
+        let mut hedge_fut = Box::pin(self.forward_until_first_chunk(
+            app_type,
+            hedge_provider,
+            endpoint,
+            body,
+            headers,
+            adapter,
+            &hedge_key,
+        ));
+
+        let (first, first_is_primary) = tokio::select! {
+            attempt = &mut primary_fut => (attempt, true),
+            attempt = &mut hedge_fut => (attempt, false),
+        };
+        let (
+            (first_provider, first_permit, first_started),
+            (other_provider, other_permit, other_started, other_key),
+            other_fut,
+        ) = if first_is_primary {
+            (
+                (primary, primary_permit, primary_started),
+                (hedge_provider, hedge_permit, hedge_started, &hedge_key),
+                hedge_fut,
+            )
+        } else {
+            (
+                (hedge_provider, hedge_permit, hedge_started),
+                (primary, primary_permit, primary_started, &primary_key),
+                primary_fut,
+            )
+        };
+        let hedge_provider_id = Some(hedge_provider.id.clone());
+
+        let error = match first.result {
+            Ok(response) => {
+                // 先返回首个数据块的一方胜出，取消另一方（记录其已发出请求所用的 Key）
+                drop(other_fut);
+                let other_masked_key = other_key.lock().ok().and_then(|key| key.clone());
+                self.router
+                    .release_permit_neutral(&other_provider.id, app_type, other_permit)
+                    .await;
+                return HedgeOutcome {
+                    provider: first_provider,
+                    used_half_open_permit: first_permit,
+                    attempt: ForwardAttempt {
+                        result: Ok(response),
+                        masked_key: first.masked_key,
+                    },
+                    hedge_provider_id,
+                    loser: Some(HedgeLoser {
+                        provider: other_provider.clone(),
+                        model: super::model_mapper::resolve_request_model(
+                            body,
+                            endpoint,
+                            other_provider,
+                        ),
+                        masked_key: other_masked_key,
+                        latency_ms: other_started.elapsed().as_millis() as u64,
+                        failure: None,
+                    }),
+                };
+            }
+            Err(error) => error,
+        };
+
+        // 先结束的一方失败：计入熔断器，结果以另一方为准
+        let loser = HedgeLoser {
+            provider: first_provider.clone(),
+            model: super::model_mapper::resolve_request_model(body, endpoint, first_provider),
+            masked_key: first.masked_key,
+            latency_ms: first_started.elapsed().as_millis() as u64,
+            failure: Some((map_proxy_error_to_status(&error), error.to_string())),
+        };
+        log::warn!(
+            "[{app_type}] [FWD-004] 对冲请求中 Provider {} 失败，等待 Provider {}: {error}",
+            first_provider.name,
+            other_provider.name
+        );
+        if is_rate_limited(&error) {
+            self.router
+                .release_permit_neutral(&first_provider.id, app_type, first_permit)
+                .await;
+        } else {
+            let _ = self
+                .router
+                .record_result(
+                    &first_provider.id,
+                    app_type,
+                    first_permit,
+                    false,
+                    Some(error.to_string()),
+                )
+                .await;
+        }
+
+        HedgeOutcome {
+            provider: other_provider,
+            used_half_open_permit: other_permit,
+            attempt: other_fut.await,
+            hedge_provider_id,
+            loser: Some(loser),
+        }
+    }
+
+    /// 转发并等待上游返回首个数据块（对冲竞速以首个数据块为准）
+    #[allow(clippy::too_many_arguments)]
+    async fn forward_until_first_chunk(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        adapter: &dyn ProviderAdapter,
+        key_slot: &KeySlot,
+    ) -> ForwardAttempt {
+        let attempt = self
+            .forward_attempt(
+                app_type,
+                provider,
+                endpoint,
+                body,
+                headers,
+                adapter,
+                false,
+                Some(key_slot),
+            )
+            .await;
+        let result = match attempt.result {
+            Ok(response) => await_first_chunk(response).await,
+            Err(e) => Err(e),
+        };
+        ForwardAttempt {
+            result,
+            masked_key: attempt.masked_key,
+        }
+    }
+
     /// 转发单个请求（使用适配器）
     async fn forward(
         &self,
@@ -554,6 +985,7 @@ impl RequestForwarder {
         body: &Value,
         headers: &axum::http::HeaderMap,
         adapter: &dyn ProviderAdapter,
+        auth: Option<&AuthInfo>,
     ) -> Result<Response, ProxyError> {
         // 使用适配器提取 base_url
         let base_url = adapter.extract_base_url(provider)?;
@@ -561,20 +993,22 @@ impl RequestForwarder {
         // 检查是否需要格式转换
         let needs_transform = adapter.needs_transform(provider);
 
-        let effective_endpoint =
-            if needs_transform && adapter.name() == "Claude" && endpoint == "/v1/messages" {
-                "/v1/chat/completions"
-            } else {
-                endpoint
-            };
-
-        // 使用适配器构建 URL
-        let url = adapter.build_url(&base_url, effective_endpoint);
-
         // 应用模型映射（独立于格式转换）
         let (mapped_body, _original_model, _mapped_model) =
             super::model_mapper::apply_model_mapping(body.clone(), provider);
 
+        // 转换模式下端点可能依赖映射后的模型名（如 Gemini 的 /models/{model}:generateContent）；
+        // 透传时模型名在路径中的请求（Gemini 原生请求）直接改写路径中的模型名
+        let effective_endpoint = if needs_transform {
+            adapter.transform_endpoint(endpoint, &mapped_body, provider)
+        } else {
+            super::model_mapper::map_endpoint_model(endpoint, body, provider)
+                .unwrap_or_else(|| endpoint.to_string())
+        };
+
+        // 使用适配器构建 URL
+        let url = adapter.build_url(&base_url, &effective_endpoint);
+
         // 转换请求体（如果需要）
         let request_body = if needs_transform {
             adapter.transform_request(mapped_body, provider)?
@@ -650,8 +1084,8 @@ impl RequestForwarder {
         request = request.header("accept-encoding", "identity");
 
         // 使用适配器添加认证头
-        if let Some(auth) = adapter.extract_auth(provider) {
-            request = adapter.add_auth_headers(request, &auth);
+        if let Some(auth) = auth {
+            request = adapter.add_auth_headers(request, auth);
         }
 
         // anthropic-version 统一处理（仅 Claude）：优先使用客户端的版本号，否则使用默认值
@@ -697,15 +1131,177 @@ impl RequestForwarder {
             Ok(response)
         } else {
             let status_code = status.as_u16();
+            let rate_limit = is_rate_limit_status(status_code)
+                .then(|| parse_rate_limit_headers(response.headers()));
             let body_text = response.text().await.ok();
 
             Err(ProxyError::UpstreamError {
                 status: status_code,
                 body: body_text,
+                rate_limit,
             })
         }
     }
 
+    /// 向单个供应商转发一次请求，并处理 Key 池与上游限流
+    ///
+    /// - 上游返回 401：当前 Key 进入冷却，Key 池中还有可用 Key 时换 Key 重试
+    /// - 上游返回 429/529：Key 池中还有可用 Key 时换 Key 重试；否则供应商进入限流冷却
+    /// - `wait_allowed`（只有这一个供应商）时，在等待预算内等冷却结束后重试；
+    ///   否则冷却中的供应商直接返回带 Retry-After 的 429，由调用方切换下一个供应商
+    ///
+    /// `key_slot` 随时记录最近一次使用的 Key，请求中途被取消时调用方仍能取到
+    #[allow(clippy::too_many_arguments)]
+    async fn forward_attempt(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        adapter: &dyn ProviderAdapter,
+        wait_allowed: bool,
+        key_slot: Option<&KeySlot>,
+    ) -> ForwardAttempt {
+        let mut waited = std::time::Duration::ZERO;
+        let mut retries = 0u32;
+        // 上一轮请求使用的 Key（等待冷却超出预算时随错误返回）
+        let mut last_masked_key = None;
+
+        loop {
+            if let Some(remaining) = self.router.rate_limit_remaining(app_type, &provider.id) {
+                if !wait_allowed || waited + remaining > RATE_LIMIT_WAIT_BUDGET {
+                    return ForwardAttempt {
+                        result: Err(cooldown_error(remaining)),
+                        masked_key: last_masked_key,
+                    };
+                }
+                log::info!(
+                    "[{app_type}] 供应商 {} 限流冷却中，等待 {}ms 后重试",
+                    provider.name,
+                    remaining.as_millis()
+                );
+                tokio::time::sleep(remaining).await;
+                waited += remaining;
+            }
+
+            // 解析认证信息（配置了 Key 池时按轮换方式选择 Key）
+            let auth = self.resolve_auth(app_type, provider, adapter);
+            let masked_key = auth.as_ref().map(AuthInfo::masked_key);
+            last_masked_key.clone_from(&masked_key);
+            if let Some(Ok(mut slot)) = key_slot.map(Mutex::lock) {
+                slot.clone_from(&masked_key);
+            }
+            let result = self
+                .forward(provider, endpoint, body, headers, adapter, auth.as_ref())
+                .await;
+
+            let error = match &result {
+                Ok(_) => {
+                    self.router.clear_rate_limit(app_type, &provider.id);
+                    return ForwardAttempt { result, masked_key };
+                }
+                Err(e) => e,
+            };
+            self.report_key_failure(app_type, provider, auth.as_ref(), error);
+
+            // 401 只说明当前 Key 失效（已进入冷却）：Key 池中还有可用 Key 时直接换 Key 重试，
+            // 每次失败都会冷却一个 Key，重试次数不超过池中 Key 的数量
+            if matches!(error, ProxyError::UpstreamError { status: 401, .. })
+                && self.has_available_pool_key(app_type, provider, adapter)
+            {
+                log::info!(
+                    "[{app_type}] 供应商 {} 的 Key {} 认证失败，换 Key 重试",
+                    provider.name,
+                    masked_key.as_deref().unwrap_or_default()
+                );
+                continue;
+            }
+
+            let ProxyError::UpstreamError {
+                status,
+                rate_limit: Some(info),
+                ..
+            } = error
+            else {
+                return ForwardAttempt { result, masked_key };
+            };
+            if retries >= MAX_RATE_LIMIT_RETRIES {
+                self.router
+                    .record_rate_limit(app_type, &provider.id, *status, info);
+                return ForwardAttempt { result, masked_key };
+            }
+
+            // 429 只限制了当前 Key：Key 池中还有可用 Key 时直接换 Key 重试
+            let key_rotated =
+                *status == 429 && self.has_available_pool_key(app_type, provider, adapter);
+            if key_rotated {
+                retries += 1;
+                log::info!(
+                    "[{app_type}] 供应商 {} 的 Key 被限流，换 Key 重试",
+                    provider.name
+                );
+                continue;
+            }
+
+            let cooldown = self
+                .router
+                .record_rate_limit(app_type, &provider.id, *status, info);
+            if !wait_allowed || waited + cooldown > RATE_LIMIT_WAIT_BUDGET {
+                return ForwardAttempt { result, masked_key };
+            }
+            // 冷却结束后重试（等待在下一轮循环开头进行）
+            retries += 1;
+        }
+    }
+
+    /// 提取供应商认证信息；供应商配置了 Key 池时替换为本次轮换到的 Key
+    fn resolve_auth(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        adapter: &dyn ProviderAdapter,
+    ) -> Option<AuthInfo> {
+        let mut auth = adapter.extract_auth(provider)?;
+        // OAuth 凭证不参与 Key 池
+        if auth.strategy != AuthStrategy::GoogleOAuth {
+            if let Some(key) = self
+                .router
+                .select_api_key(app_type, provider, &auth.api_key)
+            {
+                auth.api_key = key;
+            }
+        }
+        Some(auth)
+    }
+
+    /// 供应商的 Key 池中是否还有未冷却的 Key
+    fn has_available_pool_key(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        adapter: &dyn ProviderAdapter,
+    ) -> bool {
+        adapter.extract_auth(provider).is_some_and(|primary| {
+            self.router
+                .has_available_api_key(app_type, provider, &primary.api_key)
+        })
+    }
+
+    /// 上游返回 429/401 时让本次使用的 Key 进入冷却
+    fn report_key_failure(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        auth: Option<&AuthInfo>,
+        error: &ProxyError,
+    ) {
+        if let (Some(auth), ProxyError::UpstreamError { status, .. }) = (auth, error) {
+            self.router
+                .report_api_key_status(app_type, &provider.id, &auth.api_key, *status);
+        }
+    }
+
     fn categorize_proxy_error(&self, error: &ProxyError) -> ErrorCategory {
         match error {
             // 网络和上游错误：都应该尝试下一个供应商
@@ -729,6 +1325,11 @@ impl RequestForwarder {
     }
 }
 
+/// 是否为上游限流错误（429/529）
+fn is_rate_limited(error: &ProxyError) -> bool {
+    matches!(error, ProxyError::UpstreamError { status, .. } if is_rate_limit_status(*status))
+}
+
 /// 从 ProxyError 中提取错误消息
 fn extract_error_message(error: &ProxyError) -> Option<String> {
     match error {
@@ -5,14 +5,16 @@
 use crate::app_config::AppType;
 use crate::provider::Provider;
 use crate::proxy::{
+    body_capture::REQUEST_ID_HEADER,
     extract_session_id,
     forwarder::RequestForwarder,
     server::ProxyState,
-    types::{AppProxyConfig, RectifierConfig},
-    ProxyError,
+    types::{AppProxyConfig, BudgetAlert, HedgeConfig, RectifierConfig},
+    virtual_key, ProxyError,
 };
 use axum::http::HeaderMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
 
 /// 流式超时配置
 #[derive(Debug, Clone, Copy)]
@@ -46,8 +48,14 @@ pub struct RequestContext {
     /// 这里使用本地 settings 的设备级 current provider。
     /// 代理模式下如果实际使用的 provider 与此不一致，会触发切换以确保 UI 始终准确。
     pub current_provider_id: String,
+    /// 首选供应商由本次请求决定（模型路由/负载均衡），转到后备时不改变全局当前供应商
+    pub per_request_target: bool,
     /// 请求中的模型名称
     pub request_model: String,
+    /// 模型映射后实际发送给上游的模型（转发成功后由 ForwardResult 回填）
+    pub mapped_model: Option<String>,
+    /// 实际使用的 API Key（脱敏，转发结束后由 ForwardResult/ForwardError 回填）
+    pub masked_key: Option<String>,
     /// 日志标签（如 "Claude"、"Codex"、"Gemini"）
     pub tag: &'static str,
     /// 应用类型字符串（如 "claude"、"codex"、"gemini"）
@@ -55,10 +63,20 @@ pub struct RequestContext {
     /// 应用类型（预留，目前通过 app_type_str 使用）
     #[allow(dead_code)]
     pub app_type: AppType,
+    /// 请求 ID（由抓取中间件分配，同时作为请求日志与抓取记录的主键）
+    pub request_id: String,
+    /// 发起请求的虚拟 Key ID（由认证中间件识别）
+    pub virtual_key_id: Option<String>,
     /// Session ID（从客户端请求提取或新生成）
     pub session_id: String,
+    /// 参与供应商亲和的 Session ID（仅客户端提供时存在）
+    pub sticky_session: Option<String>,
     /// 整流器配置
     pub rectifier_config: RectifierConfig,
+    /// 对冲请求配置
+    pub hedge_config: HedgeConfig,
+    /// 对冲角色（发生对冲时记录到请求日志，见 [`super::hedge`]）
+    pub hedge_role: Option<&'static str>,
 }
 
 impl RequestContext {
@@ -81,8 +99,46 @@ impl RequestContext {
         app_type: AppType,
         tag: &'static str,
         app_type_str: &'static str,
+    ) -> Result<Self, ProxyError> {
+        // 从请求体提取模型名称
+        let request_model = body
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        Self::with_request_model(
+            state,
+            body,
+            headers,
+            app_type,
+            tag,
+            app_type_str,
+            request_model,
+        )
+        .await
+    }
+
+    /// 使用指定的请求模型创建上下文
+    ///
+    /// 模型名参与跨供应商模型路由；Gemini 的模型名在 URI 中，由调用方预先解析后传入
+    pub async fn with_request_model(
+        state: &ProxyState,
+        body: &serde_json::Value,
+        headers: &HeaderMap,
+        app_type: AppType,
+        tag: &'static str,
+        app_type_str: &'static str,
+        request_model: String,
     ) -> Result<Self, ProxyError> {
         let start_time = Instant::now();
+        let request_id = request_id_from_headers(headers);
+
+        // 虚拟 Key 的模型权限与消费限额在选择供应商之前校验
+        let virtual_key_id = virtual_key::key_id_from_headers(headers);
+        if let Some(key_id) = virtual_key_id.as_deref() {
+            virtual_key::authorize(&state.db, key_id, &request_model)?;
+        }
 
         // 从数据库读取应用级代理配置（per-app）
         let app_config = state
@@ -93,20 +149,20 @@ impl RequestContext {
 
         // 从数据库读取整流器配置
         let rectifier_config = state.db.get_rectifier_config().unwrap_or_default();
+        let hedge_config = state
+            .db
+            .get_hedge_config(app_type_str)
+            .await
+            .unwrap_or_default();
 
         let current_provider_id =
             crate::settings::get_current_provider(&app_type).unwrap_or_default();
 
-        // 从请求体提取模型名称
-        let request_model = body
-            .get("model")
-            .and_then(|m| m.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-
         // 提取 Session ID
         let session_result = extract_session_id(headers, body, app_type_str);
         let session_id = session_result.session_id.clone();
+        // 仅客户端提供的 Session ID 参与供应商亲和（新生成的 ID 每次请求都不同）
+        let sticky_session = session_result.client_provided.then(|| session_id.clone());
 
         log::debug!(
             "[{}] Session ID: {} (from {:?}, client_provided: {})",
@@ -118,23 +174,39 @@ impl RequestContext {
 
         // 使用共享的 ProviderRouter 选择 Provider（熔断器状态跨请求保持）
         // 注意：只在这里调用一次，结果传递给 forwarder，避免重复消耗 HalfOpen 名额
-        let providers = state
+        let selection = state
             .provider_router
-            .select_providers(app_type_str)
-            .await
-            .map_err(|e| match e {
-                crate::error::AppError::AllProvidersCircuitOpen => {
-                    ProxyError::AllProvidersCircuitOpen
-                }
-                crate::error::AppError::NoProvidersConfigured => ProxyError::NoProvidersConfigured,
-                _ => ProxyError::DatabaseError(e.to_string()),
-            })?;
+            .select_providers(
+                app_type_str,
+                Some(&request_model),
+                sticky_session.as_deref(),
+            )
+            .await;
+
+        // 无论选择成功与否，都先通知新产生的消费限额告警
+        emit_budget_alerts(state, state.provider_router.take_budget_alerts());
+
+        let selection = selection.map_err(|e| match e {
+            crate::error::AppError::AllProvidersCircuitOpen => ProxyError::AllProvidersCircuitOpen,
+            crate::error::AppError::NoProvidersConfigured => ProxyError::NoProvidersConfigured,
+            crate::error::AppError::AllProvidersOverBudget => ProxyError::BudgetExceeded,
+            _ => ProxyError::DatabaseError(e.to_string()),
+        })?;
+
+        let providers = selection.providers;
 
         let provider = providers
             .first()
             .cloned()
             .ok_or(ProxyError::NoAvailableProvider)?;
 
+        // 首选供应商按请求决定时（模型路由/负载均衡），只有首选失败转到后备才视为故障转移
+        let current_provider_id = if selection.per_request_target {
+            provider.id.clone()
+        } else {
+            current_provider_id
+        };
+
         log::debug!(
             "[{}] Provider: {}, model: {}, failover chain: {} providers, session: {}",
             tag,
@@ -150,12 +222,20 @@ impl RequestContext {
             provider,
             providers,
             current_provider_id,
+            per_request_target: selection.per_request_target,
             request_model,
+            mapped_model: None,
+            masked_key: None,
             tag,
             app_type_str,
             app_type,
+            request_id,
+            virtual_key_id,
             session_id,
+            sticky_session,
             rectifier_config,
+            hedge_config,
+            hedge_role: None,
         })
     }
 
@@ -163,21 +243,15 @@ impl RequestContext {
     ///
     /// Gemini API 的模型名称在 URI 中，格式如：
     /// `/v1beta/models/gemini-pro:generateContent`
-    pub fn with_model_from_uri(mut self, uri: &axum::http::Uri) -> Self {
-        let endpoint = uri
-            .path_and_query()
-            .map(|pq| pq.as_str())
-            .unwrap_or(uri.path());
-
-        self.request_model = endpoint
-            .split('/')
-            .find(|s| s.starts_with("models/"))
-            .and_then(|s| s.strip_prefix("models/"))
+    pub fn model_from_uri(uri: &axum::http::Uri) -> String {
+        let mut segments = uri.path().split('/');
+        segments
+            .find(|s| *s == "models")
+            .and_then(|_| segments.next())
             .map(|s| s.split(':').next().unwrap_or(s))
+            .filter(|s| !s.is_empty())
             .unwrap_or("unknown")
-            .to_string();
-
-        self
+            .to_string()
     }
 
     /// 创建 RequestForwarder
@@ -187,6 +261,7 @@ impl RequestContext {
     /// 配置生效规则：
     /// - 故障转移开启：超时配置正常生效（0 表示禁用超时）
     /// - 故障转移关闭：超时配置不生效（全部传入 0）
+    /// - 对冲仅在故障转移开启且等待时间小于流式首字超时时生效
     pub fn create_forwarder(&self, state: &ProxyState) -> RequestForwarder {
         let (non_streaming_timeout, first_byte_timeout, idle_timeout) =
             if self.app_config.auto_failover_enabled {
@@ -205,6 +280,12 @@ impl RequestContext {
                 (0, 0, 0)
             };
 
+        let hedge_delay = (self.app_config.auto_failover_enabled
+            && self.hedge_config.enabled
+            && (first_byte_timeout == 0
+                || u64::from(self.hedge_config.delay_ms) < first_byte_timeout * 1000))
+            .then(|| Duration::from_millis(u64::from(self.hedge_config.delay_ms)));
+
         RequestForwarder::new(
             state.provider_router.clone(),
             non_streaming_timeout,
@@ -217,6 +298,10 @@ impl RequestContext {
             idle_timeout,
             self.rectifier_config.clone(),
         )
+        .with_sticky_session(self.sticky_session.clone())
+        .with_hedge(hedge_delay)
+        .with_per_request_target(self.per_request_target)
+        .with_failover(self.app_config.auto_failover_enabled)
     }
 
     /// 获取 Provider 列表（用于故障转移）
@@ -226,6 +311,13 @@ impl RequestContext {
         self.providers.clone()
     }
 
+    /// 实际使用的模型（有映射时为映射后的模型，否则为请求模型）
+    ///
+    /// 响应中未携带模型名时，用量日志以此作为 `model`，与 `request_model` 对照即可看出映射结果
+    pub fn effective_model(&self) -> &str {
+        self.mapped_model.as_deref().unwrap_or(&self.request_model)
+    }
+
     /// 计算请求延迟（毫秒）
     #[inline]
     pub fn latency_ms(&self) -> u64 {
@@ -254,3 +346,25 @@ impl RequestContext {
         }
     }
 }
+
+/// 发送消费限额告警事件（前端据此弹出提示）
+fn emit_budget_alerts(state: &ProxyState, alerts: Vec<BudgetAlert>) {
+    let Some(app) = state.app_handle.as_ref() else {
+        return;
+    };
+    for alert in alerts {
+        if let Err(e) = app.emit("provider-budget-alert", &alert) {
+            log::error!("[Proxy] 发射 provider-budget-alert 事件失败: {e}");
+        }
+    }
+}
+
+/// 读取中间件分配的请求 ID（未经过中间件时新生成）
+fn request_id_from_headers(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
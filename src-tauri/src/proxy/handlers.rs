@@ -5,7 +5,8 @@
 //! 重构后的结构：
 //! - 通用逻辑提取到 `handler_context` 和 `response_processor` 模块
 //! - 各 handler 只保留独特的业务逻辑
-//! - Claude 的格式转换逻辑保留在此文件（用于 OpenRouter 旧接口回退）
+//! - Claude 的格式转换逻辑保留在此文件（openai_chat / gemini 格式供应商）
+//! - Codex 的 Responses ↔ Chat 转换逻辑同样保留在此文件（openai_chat 格式供应商）
 
 use super::{
     error_mapper::{get_error_message, map_proxy_error_to_status},
@@ -13,8 +14,18 @@ use super::{
         CLAUDE_PARSER_CONFIG, CODEX_PARSER_CONFIG, GEMINI_PARSER_CONFIG, OPENAI_PARSER_CONFIG,
     },
     handler_context::RequestContext,
-    providers::{get_adapter, streaming::create_anthropic_sse_stream, transform},
-    response_processor::{create_logged_passthrough_stream, process_response, SseUsageCollector},
+    hedge,
+    providers::{
+        get_adapter, streaming::create_anthropic_sse_stream,
+        streaming_gemini::create_anthropic_sse_stream_from_gemini,
+        streaming_responses::create_responses_sse_stream, transform, transform_gemini,
+        transform_responses, ClaudeAdapter, CodexAdapter, ProviderAdapter,
+    },
+    response_cache::ResponseCacheLookup,
+    response_processor::{
+        create_logged_passthrough_stream, create_usage_collector, process_response,
+        spawn_log_usage, SseUsageCollector,
+    },
     server::ProxyState,
     types::*,
     usage::parser::TokenUsage,
@@ -22,7 +33,14 @@ use super::{
 };
 use crate::app_config::AppType;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use bytes::Bytes;
+use futures::stream::Stream;
 use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::pin::Pin;
+
+/// 格式转换后的 Anthropic SSE 流（不同上游格式的转换流类型不同，统一装箱）
+type AnthropicSseStream = Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>;
 
 // ============================================================================
 // 健康检查和状态查询（简单端点）
@@ -45,6 +63,23 @@ pub async fn get_status(State(state): State<ProxyState>) -> Result<Json<ProxySta
     Ok(Json(status))
 }
 
+/// 导出 Prometheus 指标
+pub async fn metrics(State(state): State<ProxyState>) -> impl IntoResponse {
+    let mut status = state.status.read().await.clone();
+    if let Some(start) = *state.start_time.read().await {
+        status.uptime_seconds = start.elapsed().as_secs();
+    }
+    let breakers = state.provider_router.circuit_breaker_snapshot().await;
+
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        super::metrics::global().render(&status, &breakers),
+    )
+}
+
 // ============================================================================
 // Claude API 处理器（包含格式转换逻辑）
 // ============================================================================
@@ -67,6 +102,17 @@ pub async fn handle_messages(
         .and_then(|s| s.as_bool())
         .unwrap_or(false);
 
+    // 响应缓存（按应用启用，仅非流式请求）
+    let cache = ResponseCacheLookup::new(&state, &ctx, "/v1/messages", &body).await;
+    if let Some(cache) = &cache {
+        if let Some(response) = cache
+            .cached_response(&state, &ctx, &CLAUDE_PARSER_CONFIG)
+            .await
+        {
+            return Ok(response);
+        }
+    }
+
     // 转发请求
     let forwarder = ctx.create_forwarder(&state);
     let result = match forwarder
@@ -84,49 +130,79 @@ pub async fn handle_messages(
             if let Some(provider) = err.provider.take() {
                 ctx.provider = provider;
             }
+            ctx.masked_key = err.masked_key.take();
+            if let Some(loser) = err.hedge_loser.take() {
+                hedge::log_loser(&state, &mut ctx, loser);
+            }
             log_forward_error(&state, &ctx, is_stream, &err.error);
             return Err(err.error);
         }
     };
 
     ctx.provider = result.provider;
+    ctx.mapped_model = result.mapped_model;
+    ctx.masked_key = result.masked_key;
+    if let Some(loser) = result.hedge_loser {
+        hedge::log_loser(&state, &mut ctx, loser);
+    }
     let response = result.response;
 
     // 检查是否需要格式转换（OpenRouter 等中转服务）
     let adapter = get_adapter(&AppType::Claude);
     let needs_transform = adapter.needs_transform(&ctx.provider);
 
-    // Claude 特有：格式转换处理
-    if needs_transform {
-        return handle_claude_transform(response, &ctx, &state, &body, is_stream).await;
-    }
+    // Claude 特有：格式转换处理；其余走通用响应处理（透传模式）
+    let response = if needs_transform {
+        let api_format = ClaudeAdapter::new().get_api_format(&ctx.provider);
+        handle_claude_transform(response, &ctx, &state, api_format, is_stream).await?
+    } else {
+        process_response(response, &ctx, &state, &CLAUDE_PARSER_CONFIG).await?
+    };
 
-    // 通用响应处理（透传模式）
-    process_response(response, &ctx, &state, &CLAUDE_PARSER_CONFIG).await
+    match cache {
+        Some(cache) => Ok(cache.store(&state, &ctx, response).await),
+        None => Ok(response),
+    }
 }
 
 /// Claude 格式转换处理（独有逻辑）
 ///
-/// 处理 OpenRouter 旧 OpenAI 兼容接口的回退方案（当前默认不启用）
+/// 将 openai_chat / gemini 格式供应商的响应转换回 Anthropic 格式
 async fn handle_claude_transform(
     response: reqwest::Response,
     ctx: &RequestContext,
     state: &ProxyState,
-    _original_body: &Value,
+    api_format: &str,
     is_stream: bool,
 ) -> Result<axum::response::Response, ProxyError> {
     let status = response.status();
+    let is_gemini = api_format == "gemini";
 
     if is_stream {
-        // 流式响应转换 (OpenAI SSE → Anthropic SSE)
+        // 流式响应转换 (OpenAI SSE / Gemini SSE → Anthropic SSE)
         let stream = response.bytes_stream();
-        let sse_stream = create_anthropic_sse_stream(stream);
+        let (sse_stream, tag): (AnthropicSseStream, &'static str) = if is_gemini {
+            (
+                Box::pin(create_anthropic_sse_stream_from_gemini(stream)),
+                "Claude/Gemini",
+            )
+        } else {
+            (
+                Box::pin(create_anthropic_sse_stream(stream)),
+                "Claude/OpenRouter",
+            )
+        };
 
         // 创建使用量收集器
         let usage_collector = {
             let state = state.clone();
             let provider_id = ctx.provider.id.clone();
-            let model = ctx.request_model.clone();
+            let model = ctx.effective_model().to_string();
+            let request_model = ctx.request_model.clone();
+            let masked_key = ctx.masked_key.clone();
+            let request_id = ctx.request_id.clone();
+            let virtual_key_id = ctx.virtual_key_id.clone();
+            let hedge_role = ctx.hedge_role;
             let status_code = status.as_u16();
             let start_time = ctx.start_time;
 
@@ -135,25 +211,33 @@ async fn handle_claude_transform(
                     let latency_ms = start_time.elapsed().as_millis() as u64;
                     let state = state.clone();
                     let provider_id = provider_id.clone();
-                    let model = model.clone();
+                    let model = usage.model.clone().unwrap_or_else(|| model.clone());
+                    let request_model = request_model.clone();
+                    let masked_key = masked_key.clone();
+                    let request_id = request_id.clone();
+                    let virtual_key_id = virtual_key_id.clone();
 
                     tokio::spawn(async move {
                         log_usage(
                             &state,
+                            &request_id,
                             &provider_id,
                             "claude",
                             &model,
-                            &model,
+                            &request_model,
                             usage,
                             latency_ms,
                             first_token_ms,
                             true,
                             status_code,
+                            masked_key,
+                            virtual_key_id,
+                            hedge_role,
                         )
                         .await;
                     });
                 } else {
-                    log::debug!("[Claude] OpenRouter 流式响应缺少 usage 统计，跳过消费记录");
+                    log::debug!("[Claude] 转换后的流式响应缺少 usage 统计，跳过消费记录");
                 }
             })
         };
@@ -163,7 +247,7 @@ async fn handle_claude_transform(
 
         let logged_stream = create_logged_passthrough_stream(
             sse_stream,
-            "Claude/OpenRouter",
+            tag,
             Some(usage_collector),
             timeout_config,
         );
@@ -186,7 +270,7 @@ async fn handle_claude_transform(
         return Ok((headers, body).into_response());
     }
 
-    // 非流式响应转换 (OpenAI → Anthropic)
+    // 非流式响应转换 (OpenAI / Gemini → Anthropic)
     let response_headers = response.headers().clone();
 
     let body_bytes = response.bytes().await.map_err(|e| {
@@ -196,12 +280,17 @@ async fn handle_claude_transform(
 
     let body_str = String::from_utf8_lossy(&body_bytes);
 
-    let openai_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
-        log::error!("[Claude] 解析 OpenAI 响应失败: {e}, body: {body_str}");
-        ProxyError::TransformError(format!("Failed to parse OpenAI response: {e}"))
+    let upstream_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
+        log::error!("[Claude] 解析上游响应失败: {e}, body: {body_str}");
+        ProxyError::TransformError(format!("Failed to parse upstream response: {e}"))
     })?;
 
-    let anthropic_response = transform::openai_to_anthropic(openai_response).map_err(|e| {
+    let anthropic_response = if is_gemini {
+        transform_gemini::gemini_to_anthropic(upstream_response, ctx.effective_model())
+    } else {
+        transform::openai_to_anthropic(upstream_response)
+    }
+    .map_err(|e| {
         log::error!("[Claude] 转换响应失败: {e}");
         e
     })?;
@@ -219,9 +308,14 @@ async fn handle_claude_transform(
             let state = state.clone();
             let provider_id = ctx.provider.id.clone();
             let model = model.to_string();
+            let masked_key = ctx.masked_key.clone();
+            let request_id = ctx.request_id.clone();
+            let virtual_key_id = ctx.virtual_key_id.clone();
+            let hedge_role = ctx.hedge_role;
             async move {
                 log_usage(
                     &state,
+                    &request_id,
                     &provider_id,
                     "claude",
                     &model,
@@ -231,6 +325,9 @@ async fn handle_claude_transform(
                     None,
                     false,
                     status.as_u16(),
+                    masked_key,
+                    virtual_key_id,
+                    hedge_role,
                 )
                 .await;
             }
@@ -280,6 +377,17 @@ pub async fn handle_chat_completions(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
+    // 响应缓存（按应用启用，仅非流式请求）
+    let cache = ResponseCacheLookup::new(&state, &ctx, "/chat/completions", &body).await;
+    if let Some(cache) = &cache {
+        if let Some(response) = cache
+            .cached_response(&state, &ctx, &OPENAI_PARSER_CONFIG)
+            .await
+        {
+            return Ok(response);
+        }
+    }
+
     let forwarder = ctx.create_forwarder(&state);
     let result = match forwarder
         .forward_with_retry(
@@ -296,15 +404,28 @@ pub async fn handle_chat_completions(
             if let Some(provider) = err.provider.take() {
                 ctx.provider = provider;
             }
+            ctx.masked_key = err.masked_key.take();
+            if let Some(loser) = err.hedge_loser.take() {
+                hedge::log_loser(&state, &mut ctx, loser);
+            }
             log_forward_error(&state, &ctx, is_stream, &err.error);
             return Err(err.error);
         }
     };
 
     ctx.provider = result.provider;
+    ctx.mapped_model = result.mapped_model;
+    ctx.masked_key = result.masked_key;
+    if let Some(loser) = result.hedge_loser {
+        hedge::log_loser(&state, &mut ctx, loser);
+    }
     let response = result.response;
 
-    process_response(response, &ctx, &state, &OPENAI_PARSER_CONFIG).await
+    let response = process_response(response, &ctx, &state, &OPENAI_PARSER_CONFIG).await?;
+    match cache {
+        Some(cache) => Ok(cache.store(&state, &ctx, response).await),
+        None => Ok(response),
+    }
 }
 
 /// 处理 /v1/responses 请求（OpenAI Responses API - Codex CLI 透传）
@@ -321,6 +442,20 @@ pub async fn handle_responses(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
+    // openai_chat 格式供应商需要在响应侧还原 custom 工具并保存会话历史，
+    // 请求体会被转发消耗，这里提前提取
+    let codex_adapter = CodexAdapter::new();
+    let transform_state = ctx
+        .get_providers()
+        .iter()
+        .any(|p| codex_adapter.is_chat_format(p))
+        .then(|| {
+            let history_input = transform_responses::should_store(&body)
+                .then(|| transform_responses::expand_input(&body).ok())
+                .flatten();
+            (transform_responses::custom_tool_names(&body), history_input)
+        });
+
     let forwarder = ctx.create_forwarder(&state);
     let result = match forwarder
         .forward_with_retry(
@@ -337,17 +472,147 @@ pub async fn handle_responses(
             if let Some(provider) = err.provider.take() {
                 ctx.provider = provider;
             }
+            ctx.masked_key = err.masked_key.take();
+            if let Some(loser) = err.hedge_loser.take() {
+                hedge::log_loser(&state, &mut ctx, loser);
+            }
             log_forward_error(&state, &ctx, is_stream, &err.error);
             return Err(err.error);
         }
     };
 
     ctx.provider = result.provider;
+    ctx.mapped_model = result.mapped_model;
+    ctx.masked_key = result.masked_key;
+    if let Some(loser) = result.hedge_loser {
+        hedge::log_loser(&state, &mut ctx, loser);
+    }
     let response = result.response;
 
+    // Codex 特有：Chat Completions 上游的响应需要转换回 Responses 格式
+    if codex_adapter.needs_transform(&ctx.provider) {
+        let (custom_tools, history_input) = transform_state.unwrap_or_default();
+        return handle_codex_transform(
+            response,
+            &ctx,
+            &state,
+            custom_tools,
+            history_input,
+            is_stream,
+        )
+        .await;
+    }
+
     process_response(response, &ctx, &state, &CODEX_PARSER_CONFIG).await
 }
 
+/// Codex 格式转换处理（独有逻辑）
+///
+/// 将 openai_chat 格式供应商的 Chat Completions 响应转换回 Responses 格式，
+/// 用量仍由 Codex 解析器从 response.completed 中读取
+async fn handle_codex_transform(
+    response: reqwest::Response,
+    ctx: &RequestContext,
+    state: &ProxyState,
+    custom_tools: HashSet<String>,
+    history_input: Option<Vec<Value>>,
+    is_stream: bool,
+) -> Result<axum::response::Response, ProxyError> {
+    let status = response.status();
+
+    if is_stream {
+        // 流式响应转换 (OpenAI SSE → Responses SSE)
+        let sse_stream =
+            create_responses_sse_stream(response.bytes_stream(), custom_tools, history_input);
+        let usage_collector =
+            create_usage_collector(ctx, state, status.as_u16(), &CODEX_PARSER_CONFIG);
+        let logged_stream = create_logged_passthrough_stream(
+            sse_stream,
+            "Codex/Chat",
+            Some(usage_collector),
+            ctx.streaming_timeout_config(),
+        );
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "Content-Type",
+            axum::http::HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(
+            "Cache-Control",
+            axum::http::HeaderValue::from_static("no-cache"),
+        );
+        headers.insert(
+            "Connection",
+            axum::http::HeaderValue::from_static("keep-alive"),
+        );
+
+        let body = axum::body::Body::from_stream(logged_stream);
+        return Ok((headers, body).into_response());
+    }
+
+    // 非流式响应转换 (OpenAI → Responses)
+    let body_bytes = response.bytes().await.map_err(|e| {
+        log::error!("[Codex] 读取响应体失败: {e}");
+        ProxyError::ForwardFailed(format!("Failed to read response body: {e}"))
+    })?;
+
+    let upstream_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
+        log::error!(
+            "[Codex] 解析上游响应失败: {e}, body: {}",
+            String::from_utf8_lossy(&body_bytes)
+        );
+        ProxyError::TransformError(format!("Failed to parse upstream response: {e}"))
+    })?;
+
+    let responses_body = transform_responses::chat_to_responses(upstream_response, &custom_tools)
+        .map_err(|e| {
+        log::error!("[Codex] 转换响应失败: {e}");
+        e
+    })?;
+
+    if let Some(input) = history_input {
+        if let (Some(id), Some(output)) = (
+            responses_body.get("id").and_then(|v| v.as_str()),
+            responses_body.get("output").and_then(|v| v.as_array()),
+        ) {
+            transform_responses::remember_response(id, input, output);
+        }
+    }
+
+    // 记录使用量
+    let usage = TokenUsage::from_codex_response_auto(&responses_body).unwrap_or_default();
+    let model = responses_body
+        .get("model")
+        .and_then(|m| m.as_str())
+        .filter(|m| !m.is_empty())
+        .unwrap_or(ctx.effective_model())
+        .to_string();
+    spawn_log_usage(
+        state,
+        ctx,
+        usage,
+        &model,
+        &ctx.request_model,
+        status.as_u16(),
+        false,
+    );
+
+    let response_body = serde_json::to_vec(&responses_body).map_err(|e| {
+        log::error!("[Codex] 序列化响应失败: {e}");
+        ProxyError::TransformError(format!("Failed to serialize response: {e}"))
+    })?;
+
+    axum::response::Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(axum::body::Body::from(response_body))
+        .map_err(|e| {
+            log::error!("[Codex] 构建响应失败: {e}");
+            ProxyError::Internal(format!("Failed to build response: {e}"))
+        })
+}
+
 // ============================================================================
 // Gemini API 处理器
 // ============================================================================
@@ -360,9 +625,16 @@ pub async fn handle_gemini(
     Json(body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
     // Gemini 的模型名称在 URI 中
-    let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Gemini, "Gemini", "gemini")
-        .await?
-        .with_model_from_uri(&uri);
+    let mut ctx = RequestContext::with_request_model(
+        &state,
+        &body,
+        &headers,
+        AppType::Gemini,
+        "Gemini",
+        "gemini",
+        RequestContext::model_from_uri(&uri),
+    )
+    .await?;
 
     // 提取完整的路径和查询参数
     let endpoint = uri
@@ -391,12 +663,21 @@ pub async fn handle_gemini(
             if let Some(provider) = err.provider.take() {
                 ctx.provider = provider;
             }
+            ctx.masked_key = err.masked_key.take();
+            if let Some(loser) = err.hedge_loser.take() {
+                hedge::log_loser(&state, &mut ctx, loser);
+            }
             log_forward_error(&state, &ctx, is_stream, &err.error);
             return Err(err.error);
         }
     };
 
     ctx.provider = result.provider;
+    ctx.mapped_model = result.mapped_model;
+    ctx.masked_key = result.masked_key;
+    if let Some(loser) = result.hedge_loser {
+        hedge::log_loser(&state, &mut ctx, loser);
+    }
     let response = result.response;
 
     process_response(response, &ctx, &state, &GEMINI_PARSER_CONFIG).await
@@ -417,10 +698,9 @@ fn log_forward_error(
     let logger = UsageLogger::new(&state.db);
     let status_code = map_proxy_error_to_status(error);
     let error_message = get_error_message(error);
-    let request_id = uuid::Uuid::new_v4().to_string();
 
     if let Err(e) = logger.log_error_with_context(
-        request_id,
+        ctx.request_id.clone(),
         ctx.provider.id.clone(),
         ctx.app_type_str.to_string(),
         ctx.request_model.clone(),
@@ -430,6 +710,9 @@ fn log_forward_error(
         is_streaming,
         Some(ctx.session_id.clone()),
         None,
+        ctx.masked_key.clone(),
+        ctx.virtual_key_id.clone(),
+        ctx.hedge_role,
     ) {
         log::warn!("记录失败请求日志失败: {e}");
     }
@@ -439,6 +722,7 @@ fn log_forward_error(
 #[allow(clippy::too_many_arguments)]
 async fn log_usage(
     state: &ProxyState,
+    request_id: &str,
     provider_id: &str,
     app_type: &str,
     model: &str,
@@ -448,6 +732,9 @@ async fn log_usage(
     first_token_ms: Option<u64>,
     is_streaming: bool,
     status_code: u16,
+    masked_key: Option<String>,
+    virtual_key_id: Option<String>,
+    hedge_role: Option<&'static str>,
 ) {
     use super::usage::logger::UsageLogger;
 
@@ -461,10 +748,8 @@ async fn log_usage(
         model
     };
 
-    let request_id = uuid::Uuid::new_v4().to_string();
-
     if let Err(e) = logger.log_with_calculation(
-        request_id,
+        request_id.to_string(),
         provider_id.to_string(),
         app_type.to_string(),
         model.to_string(),
@@ -478,7 +763,13 @@ async fn log_usage(
         None,
         None, // provider_type
         is_streaming,
+        masked_key,
+        virtual_key_id,
+        hedge_role,
     ) {
         log::warn!("[USG-001] 记录使用量失败: {e}");
     }
+    state
+        .provider_router
+        .invalidate_budget_status(app_type, provider_id);
 }
@@ -1,7 +1,167 @@
-//! 健康检查器
+//! 后台健康探测
 //!
-//! 负责定期检查Provider健康状态（占位实现）
+//! 代理运行期间按 [`StreamCheckConfig::probe_interval_secs`] 定期探测已启用代理的应用
+//! 故障转移队列中的供应商（使用供应商的 `testConfig` 覆盖全局检查配置）：
+//!
+//! - 结果写入 `stream_check_logs`，与手动检查共用一张表
+//! - 结果计入熔断器与 `provider_health`：失效的供应商在真实请求到来前即被熔断，
+//!   熔断恢复时间到达后由探测占用 HalfOpen 名额，探测成功即逐步关闭熔断器
+//!
+//! 熔断器处于 Open 且未到恢复时间的供应商本轮跳过，避免无意义的请求。
+//! 间隔为 0 时不探测，配置每轮重新读取，修改后无需重启代理。
+
+use super::provider_router::ProviderRouter;
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::provider::Provider;
+use crate::services::stream_check::{StreamCheckConfig, StreamCheckResult, StreamCheckService};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// 探测关闭时重新检查配置的间隔
+const IDLE_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 最小探测间隔，避免配置过小时持续消耗额度
+const MIN_PROBE_INTERVAL_SECS: u64 = 30;
+
+/// 后台健康探测器
+pub struct HealthChecker {
+    db: Arc<Database>,
+    router: Arc<ProviderRouter>,
+}
+
+impl HealthChecker {
+    pub fn new(db: Arc<Database>, router: Arc<ProviderRouter>) -> Self {
+        Self { db, router }
+    }
+
+    /// 启动后台探测任务，代理停止时由调用方 abort
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let interval = match self.db.get_stream_check_config() {
+                    Ok(config) if config.probe_interval_secs > 0 => {
+                        self.probe_all(&config).await;
+                        Duration::from_secs(config.probe_interval_secs.max(MIN_PROBE_INTERVAL_SECS))
+                    }
+                    Ok(_) => IDLE_RECHECK_INTERVAL,
+                    Err(e) => {
+                        log::warn!("读取健康检查配置失败，跳过本轮探测: {e}");
+                        IDLE_RECHECK_INTERVAL
+                    }
+                };
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    /// 探测一轮全部目标供应商
+    async fn probe_all(&self, config: &StreamCheckConfig) {
+        for (app_type, provider) in probe_targets(&self.db).await {
+            self.probe(&app_type, &provider, config).await;
+        }
+    }
+
+    async fn probe(&self, app_type: &AppType, provider: &Provider, config: &StreamCheckConfig) {
+        let app = app_type.as_str();
+        let permit = self.router.allow_provider_request(&provider.id, app).await;
+        if !permit.allowed {
+            log::debug!("供应商 {} 熔断中，跳过本轮健康探测", provider.name);
+            return;
+        }
+
+        let result = StreamCheckService::check_with_retry(app_type, provider, config)
+            .await
+            .unwrap_or_else(|e| StreamCheckResult::failed(e.to_string()));
+
+        if let Err(e) = self
+            .db
+            .save_stream_check_log(&provider.id, &provider.name, app, &result)
+        {
+            log::warn!("保存健康探测日志失败: {e}");
+        }
+
+        if !result.success {
+            log::warn!(
+                "健康探测失败: app={app}, provider={}, reason={}",
+                provider.name,
+                result.message
+            );
+        }
+        if let Err(e) = self
+            .router
+            .record_result(
+                &provider.id,
+                app,
+                permit.used_half_open_permit,
+                result.success,
+                (!result.success).then(|| format!("健康探测失败: {}", result.message)),
+            )
+            .await
+        {
+            log::warn!("记录健康探测结果失败: {e}");
+        }
+    }
+}
+
+/// 需要探测的供应商：已启用代理的应用的故障转移队列
+async fn probe_targets(db: &Database) -> Vec<(AppType, Provider)> {
+    let mut targets = Vec::new();
+
+    for app in ["claude", "codex", "gemini"] {
+        match db.get_proxy_config_for_app(app).await {
+            Ok(config) if config.enabled => {}
+            _ => continue,
+        }
+        let (Ok(app_type), Ok(queue), Ok(providers)) = (
+            AppType::from_str(app),
+            db.get_failover_queue(app),
+            db.get_all_providers(app),
+        ) else {
+            continue;
+        };
+
+        for item in queue {
+            if let Some(provider) = providers.get(&item.provider_id) {
+                targets.push((app_type.clone(), provider.clone()));
+            }
+        }
+    }
+
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn probes_failover_queue_of_enabled_apps_only() {
+        let db = Database::memory().unwrap();
+        for id in ["a", "b", "c"] {
+            let provider = Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+            db.save_provider("claude", &provider).unwrap();
+        }
+        db.add_to_failover_queue("claude", "b").unwrap();
+        db.add_to_failover_queue("claude", "a").unwrap();
+        assert!(probe_targets(&db).await.is_empty());
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
 
-// 占位实现，稍后添加完整逻辑
-#[allow(dead_code)]
-pub struct HealthChecker;
+        let mut ids: Vec<String> = probe_targets(&db)
+            .await
+            .into_iter()
+            .map(|(app_type, provider)| {
+                assert_eq!(app_type, AppType::Claude);
+                provider.id
+            })
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+}
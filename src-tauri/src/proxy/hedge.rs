@@ -0,0 +1,156 @@
+//! 对冲请求
+//!
+//! 交互式会话更看重首字延迟。启用对冲后（见 [`HedgeConfig`](super::types::HedgeConfig)），
+//! 流式请求的首选供应商在等待时间内未返回首个数据块时，转发器会向队列中下一个供应商
+//! 发起相同请求，先返回首个数据块的一方胜出，另一方被取消：
+//!
+//! - 胜出的请求照常记录日志，`hedge_role` 为 [`HEDGE_WINNER`]
+//! - 落败的请求单独记录一条日志（`hedge_role` 为 [`HEDGE_LOSER`]，请求 ID 追加 `:hedge`），
+//!   被取消时状态码记为 499，不产生费用
+
+use super::{handler_context::RequestContext, server::ProxyState, usage::logger::RequestLog};
+use super::{usage::logger::UsageLogger, usage::parser::TokenUsage, ProxyError};
+use crate::provider::Provider;
+use bytes::Bytes;
+use futures::StreamExt;
+use serde_json::Value;
+
+/// 对冲胜出的请求
+pub const HEDGE_WINNER: &str = "winner";
+/// 对冲落败（被取消或失败）的请求
+pub const HEDGE_LOSER: &str = "loser";
+
+/// 被取消的对冲请求记录的状态码（同 nginx 的 Client Closed Request）
+const CANCELLED_STATUS: u16 = 499;
+
+/// 对冲落败的一方
+pub struct HedgeLoser {
+    pub provider: Provider,
+    /// 模型映射后发送给该供应商的模型（未映射时为 None）
+    pub model: Option<String>,
+    /// 使用的 API Key（被取消时为已发出请求所用的 Key）
+    pub masked_key: Option<String>,
+    pub latency_ms: u64,
+    /// 失败时的状态码与错误信息；为 None 表示被取消
+    pub failure: Option<(u16, String)>,
+}
+
+/// 是否为流式请求（只有流式请求参与对冲）
+pub fn is_streaming_request(endpoint: &str, body: &Value) -> bool {
+    body.get("stream").and_then(Value::as_bool).unwrap_or(false)
+        || endpoint.contains("streamGenerateContent")
+}
+
+/// 等待上游返回首个数据块，并把它拼回响应体
+pub async fn await_first_chunk(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, ProxyError> {
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+
+    let mut stream = response.bytes_stream();
+    let first = match stream.next().await {
+        Some(Ok(chunk)) => chunk,
+        Some(Err(e)) => {
+            return Err(ProxyError::ForwardFailed(format!(
+                "读取首个数据块失败: {e}"
+            )))
+        }
+        None => Bytes::new(),
+    };
+    let body = futures::stream::once(async move { Ok::<_, reqwest::Error>(first) }).chain(stream);
+
+    let mut rebuilt = axum::http::Response::new(reqwest::Body::wrap_stream(body));
+    *rebuilt.status_mut() = status;
+    *rebuilt.version_mut() = version;
+    *rebuilt.headers_mut() = headers;
+    Ok(reqwest::Response::from(rebuilt))
+}
+
+/// 记录落败的对冲请求，并把当前请求标记为对冲胜出
+pub fn log_loser(state: &ProxyState, ctx: &mut RequestContext, loser: HedgeLoser) {
+    ctx.hedge_role = Some(HEDGE_WINNER);
+
+    let (status_code, error_message) = loser.failure.unwrap_or_else(|| {
+        (
+            CANCELLED_STATUS,
+            "对冲请求已取消：另一供应商先返回首个数据块".to_string(),
+        )
+    });
+    let log = RequestLog {
+        request_id: format!("{}:hedge", ctx.request_id),
+        provider_id: loser.provider.id,
+        app_type: ctx.app_type_str.to_string(),
+        model: loser.model.unwrap_or_else(|| ctx.request_model.clone()),
+        request_model: ctx.request_model.clone(),
+        usage: TokenUsage::default(),
+        cost: None,
+        latency_ms: loser.latency_ms,
+        first_token_ms: None,
+        status_code,
+        error_message: Some(error_message),
+        session_id: Some(ctx.session_id.clone()),
+        provider_type: None,
+        is_streaming: true,
+        cost_multiplier: "1.0".to_string(),
+        masked_key: loser.masked_key,
+        cache_hit: false,
+        virtual_key_id: ctx.virtual_key_id.clone(),
+        hedge_role: Some(HEDGE_LOSER),
+    };
+
+    if let Err(e) = UsageLogger::new(&state.db).log_request(&log) {
+        log::warn!("记录对冲请求日志失败: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn only_streaming_requests_are_hedged() {
+        assert!(is_streaming_request(
+            "/v1/messages",
+            &json!({"stream": true})
+        ));
+        assert!(!is_streaming_request(
+            "/v1/messages",
+            &json!({"stream": false})
+        ));
+        assert!(!is_streaming_request("/v1/responses", &json!({})));
+        assert!(is_streaming_request(
+            "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse",
+            &json!({})
+        ));
+    }
+
+    #[tokio::test]
+    async fn first_chunk_is_kept_in_response_body() {
+        let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+            Ok(Bytes::from_static(b"event: message_start\n\n")),
+            Ok(Bytes::from_static(b"event: message_stop\n\n")),
+        ];
+        let mut upstream =
+            axum::http::Response::new(reqwest::Body::wrap_stream(futures::stream::iter(chunks)));
+        *upstream.status_mut() = axum::http::StatusCode::OK;
+        upstream
+            .headers_mut()
+            .insert("content-type", "text/event-stream".parse().unwrap());
+
+        let response = await_first_chunk(reqwest::Response::from(upstream))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(
+            response.text().await.unwrap(),
+            "event: message_start\n\nevent: message_stop\n\n"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! API Key 池
+//!
+//! 供应商可在 `meta.apiKeys` 中配置额外的 Key，与 settings_config 中的 Key 组成 Key 池。
+//! 代理按供应商的 `keyRotation` 轮换使用；上游返回 429/401 的 Key 进入冷却期，
+//! 冷却中的 Key 不参与选择（全部冷却时选用最早解除冷却的 Key）。
+
+use crate::provider::{KeyRotation, Provider};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 429（限流）后的冷却时长
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+/// 401（认证失败）后的冷却时长：Key 失效通常不会很快恢复
+const AUTH_FAILURE_COOLDOWN: Duration = Duration::from_secs(600);
+
+/// 单个 Key 的状态
+#[derive(Debug, Default, Clone, Copy)]
+struct KeyState {
+    /// 冷却截止时间
+    cooldown_until: Option<Instant>,
+    /// 最近一次被限流（429/401）的时间
+    last_limited_at: Option<Instant>,
+}
+
+/// API Key 池（轮询游标与冷却状态跨请求保持）
+#[derive(Default)]
+pub struct KeyPool {
+    /// 轮询游标 - key 格式: "app_type:provider_id"
+    cursors: Mutex<HashMap<String, usize>>,
+    /// 各 Key 的状态 - 外层 key 同上，内层 key 为 API Key
+    states: Mutex<HashMap<String, HashMap<String, KeyState>>>,
+}
+
+impl KeyPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为本次请求选择 API Key
+    ///
+    /// `primary` 为从 settings_config 提取的 Key；供应商未配置额外 Key 时返回 None（沿用原 Key）
+    pub fn select(&self, app_type: &str, provider: &Provider, primary: &str) -> Option<String> {
+        let keys = pool_keys(provider, primary);
+        if keys.len() < 2 {
+            return None;
+        }
+        let rotation = provider
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.key_rotation)
+            .unwrap_or_default();
+        self.select_at(
+            &format!("{app_type}:{}", provider.id),
+            &keys,
+            rotation,
+            Instant::now(),
+        )
+    }
+
+    /// Key 池中是否还有未在冷却中的 Key（未配置额外 Key 时为 false）
+    pub fn has_available(&self, app_type: &str, provider: &Provider, primary: &str) -> bool {
+        let keys = pool_keys(provider, primary);
+        if keys.len() < 2 {
+            return false;
+        }
+        let scope = format!("{app_type}:{}", provider.id);
+        let now = Instant::now();
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        keys.iter().any(|key| {
+            states
+                .get(&scope)
+                .and_then(|s| s.get(key))
+                .and_then(|state| state.cooldown_until)
+                .is_none_or(|until| until <= now)
+        })
+    }
+
+    /// 记录上游响应状态：429/401 时让对应 Key 进入冷却
+    pub fn report(&self, app_type: &str, provider_id: &str, api_key: &str, status: u16) {
+        self.report_at(
+            &format!("{app_type}:{provider_id}"),
+            api_key,
+            status,
+            Instant::now(),
+        );
+    }
+
+    fn select_at(
+        &self,
+        scope: &str,
+        keys: &[String],
+        rotation: KeyRotation,
+        now: Instant,
+    ) -> Option<String> {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let state_of = |key: &String| {
+            states
+                .get(scope)
+                .and_then(|s| s.get(key))
+                .copied()
+                .unwrap_or_default()
+        };
+        let available = |key: &String| {
+            state_of(key)
+                .cooldown_until
+                .is_none_or(|until| until <= now)
+        };
+
+        // 全部冷却中：选择最早解除冷却的 Key
+        if !keys.iter().any(available) {
+            return keys
+                .iter()
+                .min_by_key(|key| state_of(key).cooldown_until)
+                .cloned();
+        }
+
+        match rotation {
+            KeyRotation::RoundRobin => {
+                let mut cursors = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+                let cursor = cursors.entry(scope.to_string()).or_insert(0);
+                let start = *cursor % keys.len();
+                let index = (0..keys.len())
+                    .map(|offset| (start + offset) % keys.len())
+                    .find(|&index| available(&keys[index]))?;
+                *cursor = index + 1;
+                Some(keys[index].clone())
+            }
+            // 从未被限流的 Key 优先（None 小于 Some），其次是最早被限流的
+            KeyRotation::LeastRecentlyRateLimited => keys
+                .iter()
+                .filter(|key| available(key))
+                .min_by_key(|key| state_of(key).last_limited_at)
+                .cloned(),
+        }
+    }
+
+    fn report_at(&self, scope: &str, api_key: &str, status: u16, now: Instant) {
+        let cooldown = match status {
+            429 => RATE_LIMIT_COOLDOWN,
+            401 => AUTH_FAILURE_COOLDOWN,
+            _ => return,
+        };
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let state = states
+            .entry(scope.to_string())
+            .or_default()
+            .entry(api_key.to_string())
+            .or_default();
+        state.cooldown_until = Some(now + cooldown);
+        state.last_limited_at = Some(now);
+    }
+}
+
+/// Key 池：配置中的 Key 在前，额外 Key 按配置顺序追加（去重、忽略空白）
+fn pool_keys(provider: &Provider, primary: &str) -> Vec<String> {
+    let extra = provider
+        .meta
+        .as_ref()
+        .map(|meta| meta.api_keys.as_slice())
+        .unwrap_or_default();
+
+    let mut keys: Vec<String> = Vec::with_capacity(extra.len() + 1);
+    for key in std::iter::once(primary).chain(extra.iter().map(String::as_str)) {
+        let key = key.trim();
+        if !key.is_empty() && !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(extra: &[&str]) -> Provider {
+        let mut provider = Provider::with_id("p1".to_string(), "P1".to_string(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            api_keys: extra.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        });
+        provider
+    }
+
+    fn keys(list: &[&str]) -> Vec<String> {
+        list.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn single_key_is_not_pooled() {
+        let pool = KeyPool::new();
+        assert_eq!(pool.select("claude", &provider(&[]), "sk-a"), None);
+        assert_eq!(
+            pool.select("claude", &provider(&["sk-a", " "]), "sk-a"),
+            None
+        );
+        assert_eq!(
+            pool_keys(&provider(&["sk-b", "sk-a", "sk-b"]), "sk-a"),
+            keys(&["sk-a", "sk-b"])
+        );
+    }
+
+    #[test]
+    fn round_robin_skips_cooling_keys() {
+        let pool = KeyPool::new();
+        let list = keys(&["a", "b", "c"]);
+        let now = Instant::now();
+        let pick = |at| pool.select_at("claude:p1", &list, KeyRotation::RoundRobin, at);
+
+        assert_eq!(pick(now).as_deref(), Some("a"));
+        pool.report_at("claude:p1", "b", 429, now);
+        assert_eq!(pick(now).as_deref(), Some("c"));
+        assert_eq!(pick(now).as_deref(), Some("a"));
+        // 非 429/401 不影响冷却
+        pool.report_at("claude:p1", "a", 500, now);
+        assert_eq!(pick(now).as_deref(), Some("c"));
+
+        // 冷却结束后重新参与轮询
+        let later = now + RATE_LIMIT_COOLDOWN;
+        assert_eq!(pick(later).as_deref(), Some("a"));
+        assert_eq!(pick(later).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn least_recently_rate_limited_prefers_clean_keys() {
+        let pool = KeyPool::new();
+        let list = keys(&["a", "b", "c"]);
+        let now = Instant::now();
+        let pick =
+            |at| pool.select_at("codex:p1", &list, KeyRotation::LeastRecentlyRateLimited, at);
+
+        assert_eq!(pick(now).as_deref(), Some("a"));
+        pool.report_at("codex:p1", "a", 429, now);
+        pool.report_at("codex:p1", "b", 429, now + Duration::from_secs(10));
+        assert_eq!(pick(now).as_deref(), Some("c"));
+
+        // a、b 都已解除冷却，c 因 401 仍在冷却：最早被限流的 a 优先
+        let later = now + Duration::from_secs(120);
+        pool.report_at("codex:p1", "c", 401, now);
+        assert_eq!(pick(later).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn all_cooling_picks_earliest_recovery() {
+        let pool = KeyPool::new();
+        let list = keys(&["a", "b"]);
+        let now = Instant::now();
+
+        pool.report_at("gemini:p1", "a", 401, now);
+        pool.report_at("gemini:p1", "b", 429, now);
+
+        assert_eq!(
+            pool.select_at("gemini:p1", &list, KeyRotation::RoundRobin, now)
+                .as_deref(),
+            Some("b")
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! 负载均衡模块
+//!
+//! 在故障转移队列（或模型路由）内按策略重排可用供应商。
+//! 策略只决定尝试顺序，排在后面的供应商仍作为后备；
+//! 每个供应商在真正转发前仍由 RequestForwarder 通过 `CircuitBreaker::allow_request` 放行。
+
+use crate::database::Database;
+use crate::provider::Provider;
+use crate::proxy::model_mapper::resolve_model;
+use crate::proxy::types::LoadBalanceStrategy;
+use crate::proxy::usage::logger::UsageLogger;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// least_latency 策略统计首字延迟的时间窗口（秒）
+const LATENCY_WINDOW_SECS: i64 = 3600;
+
+/// 负载均衡器（轮询游标与加权状态跨请求保持）
+#[derive(Default)]
+pub struct LoadBalancer {
+    /// 轮询游标 - key 格式: "app_type:scope"
+    cursors: Mutex<HashMap<String, usize>>,
+    /// 平滑加权轮询的当前权重 - key 同上
+    current_weights: Mutex<HashMap<String, HashMap<String, i64>>>,
+}
+
+impl LoadBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按策略重排供应商
+    ///
+    /// `scope` 区分同一应用下的不同供应商集合（故障转移队列 / 各模型路由），
+    /// 避免它们共用轮询状态
+    pub async fn order(
+        &self,
+        db: &Database,
+        app_type: &str,
+        scope: &str,
+        strategy: LoadBalanceStrategy,
+        providers: Vec<Provider>,
+        model: Option<&str>,
+    ) -> Vec<Provider> {
+        if providers.len() < 2 {
+            return providers;
+        }
+
+        let key = format!("{app_type}:{scope}");
+        match strategy {
+            LoadBalanceStrategy::Priority => providers,
+            LoadBalanceStrategy::RoundRobin => self.round_robin(&key, providers),
+            LoadBalanceStrategy::Weighted => self.weighted(&key, providers),
+            LoadBalanceStrategy::LeastLatency => {
+                let since = chrono::Utc::now().timestamp() - LATENCY_WINDOW_SECS;
+                match db.get_provider_avg_first_token_ms(app_type, since) {
+                    // 没有近期数据的供应商排在前面，以便获得首个样本
+                    Ok(latency) => sort_by_score(providers, |p| latency.get(&p.id).copied(), true),
+                    Err(e) => {
+                        log::warn!("[{app_type}] 读取首字延迟统计失败: {e}，按队列顺序");
+                        providers
+                    }
+                }
+            }
+            LoadBalanceStrategy::LowestCost => match model {
+                Some(model) => lowest_cost(db, app_type, providers, model).await,
+                None => providers,
+            },
+        }
+    }
+
+    fn round_robin(&self, key: &str, mut providers: Vec<Provider>) -> Vec<Provider> {
+        let mut cursors = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        let cursor = cursors.entry(key.to_string()).or_insert(0);
+        let start = *cursor % providers.len();
+        *cursor = cursor.wrapping_add(1);
+        providers.rotate_left(start);
+        providers
+    }
+
+    /// 平滑加权轮询（与 nginx 相同的算法）：选出首选，其余保持原顺序作为后备
+    fn weighted(&self, key: &str, mut providers: Vec<Provider>) -> Vec<Provider> {
+        let weights: Vec<i64> = providers.iter().map(|p| weight_of(p) as i64).collect();
+        let total: i64 = weights.iter().sum();
+        if total == 0 {
+            return providers;
+        }
+
+        let mut all_weights = self
+            .current_weights
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let current = all_weights.entry(key.to_string()).or_default();
+
+        let mut best: Option<(usize, i64)> = None;
+        for (index, (provider, weight)) in providers.iter().zip(&weights).enumerate() {
+            if *weight == 0 {
+                continue;
+            }
+            let value = current.entry(provider.id.clone()).or_insert(0);
+            *value += weight;
+            if best.is_none_or(|(_, best_value)| *value > best_value) {
+                best = Some((index, *value));
+            }
+        }
+
+        if let Some((index, _)) = best {
+            if let Some(value) = current.get_mut(&providers[index].id) {
+                *value -= total;
+            }
+            let chosen = providers.remove(index);
+            providers.insert(0, chosen);
+        }
+        providers
+    }
+}
+
+/// 供应商的负载均衡权重（未配置时为 1）
+fn weight_of(provider: &Provider) -> u32 {
+    provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.load_balance_weight)
+        .unwrap_or(1)
+}
+
+/// 按分数升序稳定排序；无分数的供应商按 `unknown_first` 放在最前或最后
+fn sort_by_score<T: PartialOrd>(
+    providers: Vec<Provider>,
+    score: impl Fn(&Provider) -> Option<T>,
+    unknown_first: bool,
+) -> Vec<Provider> {
+    let mut scored: Vec<(Option<T>, Provider)> =
+        providers.into_iter().map(|p| (score(&p), p)).collect();
+    scored.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) if unknown_first => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) if unknown_first => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+    });
+    scored.into_iter().map(|(_, p)| p).collect()
+}
+
+/// 按每百万 tokens 的输入 + 输出单价（乘以成本倍率）升序排列，无定价的排在最后
+async fn lowest_cost(
+    db: &Database,
+    app_type: &str,
+    providers: Vec<Provider>,
+    model: &str,
+) -> Vec<Provider> {
+    let logger = UsageLogger::new(db);
+    let request_body = serde_json::json!({ "model": model });
+    let mut costs: HashMap<String, Decimal> = HashMap::new();
+
+    for provider in &providers {
+        let (multiplier, pricing_model_source) =
+            logger.resolve_pricing_config(&provider.id, app_type).await;
+        let pricing_model = if pricing_model_source == "request" {
+            model.to_string()
+        } else {
+            resolve_model(&request_body, provider).unwrap_or_else(|| model.to_string())
+        };
+
+        match logger.get_model_pricing(&pricing_model) {
+            Ok(Some(pricing)) => {
+                let unit = pricing.input_cost_per_million + pricing.output_cost_per_million;
+                costs.insert(provider.id.clone(), unit * multiplier);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("[{app_type}] 读取模型 {pricing_model} 定价失败: {e}"),
+        }
+    }
+
+    sort_by_score(providers, |p| costs.get(&p.id).copied(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(id: &str, weight: Option<u32>) -> Provider {
+        let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            load_balance_weight: weight,
+            ..Default::default()
+        });
+        provider
+    }
+
+    fn ids(providers: &[Provider]) -> Vec<&str> {
+        providers.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn round_robin_rotates_head_and_keeps_fallbacks() {
+        let balancer = LoadBalancer::new();
+        let list = vec![
+            provider("a", None),
+            provider("b", None),
+            provider("c", None),
+        ];
+
+        let first = balancer.round_robin("claude:queue", list.clone());
+        let second = balancer.round_robin("claude:queue", list.clone());
+        let third = balancer.round_robin("claude:queue", list.clone());
+        let other_scope = balancer.round_robin("claude:route", list);
+
+        assert_eq!(ids(&first), vec!["a", "b", "c"]);
+        assert_eq!(ids(&second), vec!["b", "c", "a"]);
+        assert_eq!(ids(&third), vec!["c", "a", "b"]);
+        assert_eq!(ids(&other_scope), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn weighted_distributes_by_share() {
+        let balancer = LoadBalancer::new();
+        let list = vec![
+            provider("a", Some(3)),
+            provider("b", Some(1)),
+            provider("c", Some(0)),
+        ];
+
+        let mut heads = HashMap::new();
+        for _ in 0..8 {
+            let ordered = balancer.weighted("claude:queue", list.clone());
+            assert_eq!(ordered.len(), 3);
+            *heads.entry(ordered[0].id.clone()).or_insert(0) += 1;
+        }
+
+        assert_eq!(heads.get("a"), Some(&6));
+        assert_eq!(heads.get("b"), Some(&2));
+        assert_eq!(heads.get("c"), None);
+    }
+
+    #[test]
+    fn sort_by_score_places_unknown_first_or_last() {
+        let list = vec![
+            provider("a", None),
+            provider("b", None),
+            provider("c", None),
+        ];
+        let scores = HashMap::from([("a".to_string(), 900.0), ("c".to_string(), 300.0)]);
+
+        let unknown_first = sort_by_score(list.clone(), |p| scores.get(&p.id).copied(), true);
+        let unknown_last = sort_by_score(list, |p| scores.get(&p.id).copied(), false);
+
+        assert_eq!(ids(&unknown_first), vec!["b", "c", "a"]);
+        assert_eq!(ids(&unknown_last), vec!["c", "a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn lowest_cost_uses_pricing_and_multiplier() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = crate::database::lock_conn!(db.conn);
+            conn.execute(
+                "INSERT OR REPLACE INTO model_pricing (model_id, display_name, input_cost_per_million, output_cost_per_million)
+                 VALUES ('cheap-model', 'Cheap', '1', '2')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO model_pricing (model_id, display_name, input_cost_per_million, output_cost_per_million)
+                 VALUES ('big-model', 'Big', '3', '15')",
+                [],
+            )?;
+        }
+
+        // a: big-model ×1 = 18；b: big-model ×0.1 = 1.8；c: 映射到 cheap-model = 3；d: 无定价
+        let a = provider("a", None);
+        let mut b = provider("b", None);
+        b.meta.as_mut().unwrap().cost_multiplier = Some("0.1".to_string());
+        let mut c = provider("c", None);
+        c.settings_config = json!({ "env": { "ANTHROPIC_MODEL": "cheap-model" } });
+        let mut d = provider("d", None);
+        d.settings_config = json!({ "env": { "ANTHROPIC_MODEL": "unknown-model" } });
+        for p in [&a, &b, &c, &d] {
+            db.save_provider("claude", p)?;
+        }
+
+        let ordered = lowest_cost(&db, "claude", vec![d, a, c, b], "big-model").await;
+        assert_eq!(ids(&ordered), vec!["b", "c", "a", "d"]);
+
+        Ok(())
+    }
+}
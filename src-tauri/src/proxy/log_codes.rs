@@ -32,6 +32,8 @@ pub mod srv {
 pub mod fwd {
     pub const PROVIDER_FAILED_RETRY: &str = "FWD-001";
     pub const ALL_PROVIDERS_FAILED: &str = "FWD-002";
+    pub const HEDGE_STARTED: &str = "FWD-003";
+    pub const HEDGE_ATTEMPT_FAILED: &str = "FWD-004";
 }
 
 /// 故障转移日志码
@@ -0,0 +1,495 @@
+//! Prometheus 指标
+//!
+//! 进程内的指标注册表：每条请求日志写入时（`UsageLogger::log_request`）记录一次，
+//! 由代理服务器的 `/metrics` 端点按 Prometheus 文本格式导出。
+//!
+//! - 请求数、延迟与首字延迟直方图、Token 与成本计数按 应用/供应商/模型 分组
+//! - 熔断器状态与代理运行状态在导出时从 ProviderRouter / ProxyStatus 读取
+
+use super::circuit_breaker::{CircuitBreakerStats, CircuitState};
+use super::types::ProxyStatus;
+use super::usage::logger::RequestLog;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+
+/// 请求延迟直方图的桶（秒）
+const LATENCY_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// 首字延迟直方图的桶（秒）
+const FIRST_TOKEN_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0];
+
+static METRICS: LazyLock<ProxyMetrics> = LazyLock::new(ProxyMetrics::default);
+
+/// 全局指标注册表
+pub fn global() -> &'static ProxyMetrics {
+    &METRICS
+}
+
+/// 指标分组（应用 / 供应商 / 模型）
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    app_type: String,
+    provider_id: String,
+    model: String,
+}
+
+impl SeriesKey {
+    fn labels(&self) -> String {
+        format!(
+            r#"app="{}",provider="{}",model="{}""#,
+            escape_label(&self.app_type),
+            escape_label(&self.provider_id),
+            escape_label(&self.model)
+        )
+    }
+}
+
+/// 累积直方图（buckets[i] 为 <= bounds[i] 的观测数）
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, bounds: &[f64], value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; bounds.len()];
+        }
+        for (bucket, bound) in self.buckets.iter_mut().zip(bounds) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct SeriesMetrics {
+    /// 状态码 -> 请求数
+    requests: BTreeMap<u16, u64>,
+    cache_hits: u64,
+    latency: Histogram,
+    first_token: Histogram,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    cost_usd: Decimal,
+}
+
+/// 代理请求指标
+#[derive(Debug, Default)]
+pub struct ProxyMetrics {
+    series: Mutex<BTreeMap<SeriesKey, SeriesMetrics>>,
+}
+
+impl ProxyMetrics {
+    /// 记录一条请求日志
+    pub fn record_request(&self, log: &RequestLog) {
+        let key = SeriesKey {
+            app_type: log.app_type.clone(),
+            provider_id: log.provider_id.clone(),
+            model: log.model.clone(),
+        };
+        let mut series = match self.series.lock() {
+            Ok(series) => series,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let entry = series.entry(key).or_default();
+
+        *entry.requests.entry(log.status_code).or_default() += 1;
+        if log.cache_hit {
+            entry.cache_hits += 1;
+        }
+        entry
+            .latency
+            .observe(LATENCY_BUCKETS, log.latency_ms as f64 / 1000.0);
+        if let Some(first_token_ms) = log.first_token_ms {
+            entry
+                .first_token
+                .observe(FIRST_TOKEN_BUCKETS, first_token_ms as f64 / 1000.0);
+        }
+        entry.input_tokens += log.usage.input_tokens as u64;
+        entry.output_tokens += log.usage.output_tokens as u64;
+        entry.cache_read_tokens += log.usage.cache_read_tokens as u64;
+        entry.cache_creation_tokens += log.usage.cache_creation_tokens as u64;
+        if let Some(cost) = &log.cost {
+            entry.cost_usd += cost.total_cost;
+        }
+    }
+
+    /// 按 Prometheus 文本格式（0.0.4）导出全部指标
+    ///
+    /// `breakers` 为 (app_type, provider_id, 熔断器状态)
+    pub fn render(
+        &self,
+        status: &ProxyStatus,
+        breakers: &[(String, String, CircuitBreakerStats)],
+    ) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "cc_switch_proxy_up",
+            "gauge",
+            "代理服务器是否运行",
+        );
+        let _ = writeln!(out, "cc_switch_proxy_up {}", status.running as u8);
+        write_header(
+            &mut out,
+            "cc_switch_proxy_uptime_seconds",
+            "gauge",
+            "代理服务器运行时间（秒）",
+        );
+        let _ = writeln!(
+            out,
+            "cc_switch_proxy_uptime_seconds {}",
+            status.uptime_seconds
+        );
+        write_header(
+            &mut out,
+            "cc_switch_proxy_active_connections",
+            "gauge",
+            "活跃连接数",
+        );
+        let _ = writeln!(
+            out,
+            "cc_switch_proxy_active_connections {}",
+            status.active_connections
+        );
+        write_header(
+            &mut out,
+            "cc_switch_proxy_failovers_total",
+            "counter",
+            "供应商故障转移次数",
+        );
+        let _ = writeln!(
+            out,
+            "cc_switch_proxy_failovers_total {}",
+            status.failover_count
+        );
+
+        self.render_series(&mut out);
+        render_breakers(&mut out, breakers);
+        out
+    }
+
+    fn render_series(&self, out: &mut String) {
+        let series = match self.series.lock() {
+            Ok(series) => series,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        write_header(
+            out,
+            "cc_switch_proxy_requests_total",
+            "counter",
+            "代理请求数（按状态码）",
+        );
+        for (key, metrics) in series.iter() {
+            for (status, count) in &metrics.requests {
+                let _ = writeln!(
+                    out,
+                    r#"cc_switch_proxy_requests_total{{{},status="{status}"}} {count}"#,
+                    key.labels()
+                );
+            }
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_cache_hits_total",
+            "counter",
+            "响应缓存命中次数",
+        );
+        for (key, metrics) in series.iter().filter(|(_, m)| m.cache_hits > 0) {
+            let _ = writeln!(
+                out,
+                "cc_switch_proxy_cache_hits_total{{{}}} {}",
+                key.labels(),
+                metrics.cache_hits
+            );
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_request_duration_seconds",
+            "histogram",
+            "请求延迟（秒）",
+        );
+        for (key, metrics) in series.iter() {
+            write_histogram(
+                out,
+                "cc_switch_proxy_request_duration_seconds",
+                &key.labels(),
+                LATENCY_BUCKETS,
+                &metrics.latency,
+            );
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_first_token_seconds",
+            "histogram",
+            "流式请求首字延迟（秒）",
+        );
+        for (key, metrics) in series.iter() {
+            write_histogram(
+                out,
+                "cc_switch_proxy_first_token_seconds",
+                &key.labels(),
+                FIRST_TOKEN_BUCKETS,
+                &metrics.first_token,
+            );
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_tokens_total",
+            "counter",
+            "Token 用量（按类型）",
+        );
+        for (key, metrics) in series.iter() {
+            for (kind, value) in [
+                ("input", metrics.input_tokens),
+                ("output", metrics.output_tokens),
+                ("cache_read", metrics.cache_read_tokens),
+                ("cache_creation", metrics.cache_creation_tokens),
+            ] {
+                let _ = writeln!(
+                    out,
+                    r#"cc_switch_proxy_tokens_total{{{},type="{kind}"}} {value}"#,
+                    key.labels()
+                );
+            }
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_cost_usd_total",
+            "counter",
+            "请求成本（USD，含成本倍率）",
+        );
+        for (key, metrics) in series.iter() {
+            let _ = writeln!(
+                out,
+                "cc_switch_proxy_cost_usd_total{{{}}} {}",
+                key.labels(),
+                metrics.cost_usd.normalize()
+            );
+        }
+    }
+}
+
+fn render_breakers(out: &mut String, breakers: &[(String, String, CircuitBreakerStats)]) {
+    write_header(
+        out,
+        "cc_switch_proxy_circuit_breaker_state",
+        "gauge",
+        "熔断器状态（当前状态为 1）",
+    );
+    for (app_type, provider_id, stats) in breakers {
+        for (state, name) in [
+            (CircuitState::Closed, "closed"),
+            (CircuitState::Open, "open"),
+            (CircuitState::HalfOpen, "half_open"),
+        ] {
+            let _ = writeln!(
+                out,
+                r#"cc_switch_proxy_circuit_breaker_state{{app="{}",provider="{}",state="{name}"}} {}"#,
+                escape_label(app_type),
+                escape_label(provider_id),
+                (stats.state == state) as u8
+            );
+        }
+    }
+
+    write_breaker_gauge(
+        out,
+        "cc_switch_proxy_circuit_breaker_consecutive_failures",
+        "熔断器连续失败次数",
+        breakers,
+        |stats| stats.consecutive_failures,
+    );
+    write_breaker_gauge(
+        out,
+        "cc_switch_proxy_circuit_breaker_requests",
+        "熔断器统计窗口内的请求数",
+        breakers,
+        |stats| stats.total_requests,
+    );
+    write_breaker_gauge(
+        out,
+        "cc_switch_proxy_circuit_breaker_failed_requests",
+        "熔断器统计窗口内的失败请求数",
+        breakers,
+        |stats| stats.failed_requests,
+    );
+}
+
+fn write_breaker_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    breakers: &[(String, String, CircuitBreakerStats)],
+    value: impl Fn(&CircuitBreakerStats) -> u32,
+) {
+    write_header(out, name, "gauge", help);
+    for (app_type, provider_id, stats) in breakers {
+        let _ = writeln!(
+            out,
+            r#"{name}{{app="{}",provider="{}"}} {}"#,
+            escape_label(app_type),
+            escape_label(provider_id),
+            value(stats)
+        );
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn write_histogram(
+    out: &mut String,
+    name: &str,
+    labels: &str,
+    bounds: &[f64],
+    histogram: &Histogram,
+) {
+    if histogram.count == 0 {
+        return;
+    }
+    for (bound, count) in bounds.iter().zip(&histogram.buckets) {
+        let _ = writeln!(out, r#"{name}_bucket{{{labels},le="{bound}"}} {count}"#);
+    }
+    let _ = writeln!(
+        out,
+        r#"{name}_bucket{{{labels},le="+Inf"}} {}"#,
+        histogram.count
+    );
+    let _ = writeln!(out, "{name}_sum{{{labels}}} {}", histogram.sum);
+    let _ = writeln!(out, "{name}_count{{{labels}}} {}", histogram.count);
+}
+
+/// 转义标签值中的反斜杠、双引号与换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::usage::calculator::CostBreakdown;
+    use crate::proxy::usage::parser::TokenUsage;
+    use std::str::FromStr;
+
+    fn request_log(status_code: u16, latency_ms: u64, first_token_ms: Option<u64>) -> RequestLog {
+        RequestLog {
+            request_id: "req".to_string(),
+            provider_id: "p\"1".to_string(),
+            app_type: "claude".to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+            request_model: "claude-sonnet-4-5".to_string(),
+            usage: TokenUsage {
+                input_tokens: 100,
+                output_tokens: 20,
+                cache_read_tokens: 5,
+                cache_creation_tokens: 0,
+                model: None,
+            },
+            cost: Some(CostBreakdown {
+                input_cost: Decimal::ZERO,
+                output_cost: Decimal::ZERO,
+                cache_read_cost: Decimal::ZERO,
+                cache_creation_cost: Decimal::ZERO,
+                total_cost: Decimal::from_str("0.0015").unwrap(),
+            }),
+            latency_ms,
+            first_token_ms,
+            status_code,
+            error_message: None,
+            session_id: None,
+            provider_type: None,
+            is_streaming: first_token_ms.is_some(),
+            cost_multiplier: "1".to_string(),
+            masked_key: None,
+            cache_hit: false,
+            virtual_key_id: None,
+            hedge_role: None,
+        }
+    }
+
+    #[test]
+    fn renders_request_series_and_histograms() {
+        let metrics = ProxyMetrics::default();
+        metrics.record_request(&request_log(200, 800, Some(300)));
+        metrics.record_request(&request_log(200, 3000, None));
+        metrics.record_request(&request_log(502, 50, None));
+
+        let text = metrics.render(&ProxyStatus::default(), &[]);
+        let labels = r#"app="claude",provider="p\"1",model="claude-sonnet-4-5""#;
+
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_requests_total{{{labels},status=\"200\"}} 2"
+        )));
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_requests_total{{{labels},status=\"502\"}} 1"
+        )));
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_request_duration_seconds_bucket{{{labels},le=\"1\"}} 2"
+        )));
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3"
+        )));
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_first_token_seconds_count{{{labels}}} 1"
+        )));
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_tokens_total{{{labels},type=\"input\"}} 300"
+        )));
+        assert!(text.contains(&format!(
+            "cc_switch_proxy_cost_usd_total{{{labels}}} 0.0045"
+        )));
+    }
+
+    #[test]
+    fn renders_circuit_breaker_states() {
+        let metrics = ProxyMetrics::default();
+        let stats = CircuitBreakerStats {
+            state: CircuitState::Open,
+            consecutive_failures: 4,
+            consecutive_successes: 0,
+            total_requests: 10,
+            failed_requests: 6,
+        };
+
+        let text = metrics.render(
+            &ProxyStatus::default(),
+            &[("codex".to_string(), "p2".to_string(), stats)],
+        );
+
+        assert!(text.contains(
+            r#"cc_switch_proxy_circuit_breaker_state{app="codex",provider="p2",state="open"} 1"#
+        ));
+        assert!(text.contains(
+            r#"cc_switch_proxy_circuit_breaker_state{app="codex",provider="p2",state="closed"} 0"#
+        ));
+        assert!(text.contains(
+            r#"cc_switch_proxy_circuit_breaker_consecutive_failures{app="codex",provider="p2"} 4"#
+        ));
+        assert!(text.contains("cc_switch_proxy_up 0"));
+    }
+}
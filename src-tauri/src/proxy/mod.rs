@@ -2,6 +2,8 @@
 //!
 //! 提供本地HTTP代理服务，支持多Provider故障转移和请求透传
 
+pub mod auth;
+pub mod body_capture;
 pub mod body_filter;
 pub mod circuit_breaker;
 pub mod error;
@@ -12,18 +14,27 @@ pub mod handler_config;
 pub mod handler_context;
 mod handlers;
 mod health;
+pub mod hedge;
+pub mod key_pool;
+pub mod load_balancer;
 pub mod http_client;
 pub mod log_codes;
+pub mod metrics;
 pub mod model_mapper;
+pub mod otlp;
 pub mod provider_router;
 pub mod providers;
+pub mod rate_limit;
+pub mod response_cache;
 pub mod response_handler;
 pub mod response_processor;
 pub(crate) mod server;
 pub mod session;
 pub mod thinking_rectifier;
+pub mod tls;
 pub(crate) mod types;
 pub mod usage;
+pub mod virtual_key;
 
 // 公开导出给外部使用（commands, services等模块需要）
 #[allow(unused_imports)]
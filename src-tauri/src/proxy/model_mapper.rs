@@ -1,9 +1,148 @@
 //! 模型映射模块
 //!
 //! 在请求转发前，根据 Provider 配置替换请求中的模型名称
+//!
+//! 映射顺序：
+//! 1. `meta.modelRoutes` 路由规则（按顺序匹配，命中第一条即生效）
+//! 2. env 中的 `ANTHROPIC_*` 模型映射（haiku / sonnet / opus / reasoning / default）
+//!
+//! Gemini 原生请求的模型名在 URL 路径中（`/models/{model}:generateContent`），由
+//! [`map_endpoint_model`] 按同样的规则改写路径。
 
-use crate::provider::Provider;
+use crate::provider::{ModelMatchType, ModelRouteRule, Provider};
+use regex::Regex;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// 已编译的模型匹配模式（按正则源串缓存，无效模式缓存为 None）
+static COMPILED_PATTERNS: LazyLock<RwLock<HashMap<String, Option<Regex>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// 缓存的模式数上限（模式只来自配置，超出说明配置频繁变化，清空重建即可）
+const MAX_COMPILED_PATTERNS: usize = 1024;
+
+/// 估算 prompt 大小时参与统计的请求字段（覆盖 Anthropic / OpenAI / Responses / Gemini）
+const PROMPT_FIELDS: &[&str] = &[
+    "system",
+    "messages",
+    "instructions",
+    "input",
+    "contents",
+    "systemInstruction",
+    "tools",
+];
+
+/// 路由规则匹配所需的请求特征
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTraits {
+    /// 是否开启 thinking / reasoning
+    pub thinking: bool,
+    /// 工具数量
+    pub tool_count: u32,
+    /// 估算的 prompt tokens
+    pub prompt_tokens: u64,
+}
+
+impl RequestTraits {
+    /// 从请求体提取特征
+    pub fn from_body(body: &Value) -> Self {
+        Self {
+            thinking: has_reasoning_enabled(body),
+            tool_count: count_tools(body),
+            prompt_tokens: estimate_prompt_tokens(body),
+        }
+    }
+}
+
+/// 模型名是否匹配模式（供应商内的模型路由规则与跨供应商路由共用）
+pub fn model_matches(pattern: &str, match_type: ModelMatchType, model: &str) -> bool {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return false;
+    }
+    let source = match match_type {
+        ModelMatchType::Glob => glob_to_regex(pattern),
+        ModelMatchType::Regex => pattern.to_string(),
+    };
+    compiled_pattern(source).is_some_and(|re| re.is_match(model))
+}
+
+/// 获取编译后的正则（每个模式只编译一次）
+fn compiled_pattern(source: String) -> Option<Regex> {
+    if let Some(cached) = COMPILED_PATTERNS
+        .read()
+        .ok()
+        .and_then(|cache| cache.get(&source).cloned())
+    {
+        return cached;
+    }
+
+    let compiled = match Regex::new(&source) {
+        Ok(re) => Some(re),
+        Err(e) => {
+            log::warn!("[ModelMapper] 无效的模型匹配模式 `{source}`: {e}");
+            None
+        }
+    };
+    if let Ok(mut cache) = COMPILED_PATTERNS.write() {
+        if cache.len() >= MAX_COMPILED_PATTERNS {
+            cache.clear();
+        }
+        cache.insert(source, compiled.clone());
+    }
+    compiled
+}
+
+impl ModelRouteRule {
+    /// 模型名是否匹配规则的模式
+    pub fn matches_model(&self, model: &str) -> bool {
+        model_matches(&self.pattern, self.match_type, model)
+    }
+
+    /// 请求特征是否满足规则的附加条件
+    pub fn matches_traits(&self, traits: &RequestTraits) -> bool {
+        if self.thinking.is_some_and(|t| t != traits.thinking) {
+            return false;
+        }
+        if self.min_tools.is_some_and(|min| traits.tool_count < min)
+            || self.max_tools.is_some_and(|max| traits.tool_count > max)
+        {
+            return false;
+        }
+        if self
+            .min_prompt_tokens
+            .is_some_and(|min| traits.prompt_tokens < min)
+            || self
+                .max_prompt_tokens
+                .is_some_and(|max| traits.prompt_tokens > max)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// glob → 整串匹配、不区分大小写的正则
+fn glob_to_regex(pattern: &str) -> String {
+    let escaped = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+    format!("(?i)^{escaped}$")
+}
+
+/// 按顺序查找第一条命中的路由规则，返回 (规则序号, 目标模型)
+pub fn match_route<'a>(
+    rules: &'a [ModelRouteRule],
+    model: &str,
+    traits: &RequestTraits,
+) -> Option<(usize, &'a str)> {
+    rules.iter().enumerate().find_map(|(index, rule)| {
+        let target = rule.target_model.trim();
+        (!target.is_empty() && rule.matches_model(model) && rule.matches_traits(traits))
+            .then_some((index, target))
+    })
+}
 
 /// 模型映射配置
 pub struct ModelMapping {
@@ -104,6 +243,125 @@ pub fn has_thinking_enabled(body: &Value) -> bool {
         == Some("enabled")
 }
 
+/// 检测请求是否启用了推理（Anthropic thinking / OpenAI reasoning effort / Gemini thinkingConfig）
+fn has_reasoning_enabled(body: &Value) -> bool {
+    if has_thinking_enabled(body) {
+        return true;
+    }
+    let effort = body
+        .pointer("/reasoning/effort")
+        .or_else(|| body.get("reasoning_effort"))
+        .and_then(|v| v.as_str());
+    if effort.is_some_and(|e| e != "none") {
+        return true;
+    }
+    body.pointer("/generationConfig/thinkingConfig/thinkingBudget")
+        .and_then(|v| v.as_i64())
+        .is_some_and(|budget| budget != 0)
+}
+
+/// 统计请求中声明的工具数量（Gemini 的 functionDeclarations 逐个计数）
+fn count_tools(body: &Value) -> u32 {
+    body.get("tools")
+        .and_then(|t| t.as_array())
+        .map(|tools| {
+            tools
+                .iter()
+                .map(|tool| {
+                    tool.get("functionDeclarations")
+                        .and_then(|d| d.as_array())
+                        .map_or(1, |d| d.len())
+                })
+                .sum::<usize>() as u32
+        })
+        .unwrap_or(0)
+}
+
+/// 粗略估算 prompt tokens（文本字符数 / 4，忽略 base64 等内联数据）
+fn estimate_prompt_tokens(body: &Value) -> u64 {
+    fn text_len(value: &Value) -> u64 {
+        match value {
+            Value::String(s) if s.starts_with("data:") => 0,
+            Value::String(s) => s.chars().count() as u64,
+            Value::Array(items) => items.iter().map(text_len).sum(),
+            Value::Object(map) => map
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "data" | "inlineData" | "signature"))
+                .map(|(_, v)| text_len(v))
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    let chars: u64 = PROMPT_FIELDS
+        .iter()
+        .filter_map(|field| body.get(*field))
+        .map(text_len)
+        .sum();
+    chars.div_ceil(4)
+}
+
+/// 计算请求在该 Provider 下实际使用的模型
+///
+/// 返回 `None` 表示不做映射（未配置或映射结果与原模型相同）
+pub fn resolve_model(body: &Value, provider: &Provider) -> Option<String> {
+    let original = body.get("model").and_then(|m| m.as_str())?;
+    resolve_model_with_source(body, original, provider).map(|(mapped, _)| mapped)
+}
+
+/// 同 [`resolve_model`]，请求体中没有模型名时取 URL 路径中的模型名（Gemini 原生请求）
+pub fn resolve_request_model(body: &Value, endpoint: &str, provider: &Provider) -> Option<String> {
+    if body.get("model").is_some() {
+        return resolve_model(body, provider);
+    }
+    let original = model_from_endpoint(endpoint)?;
+    resolve_model_with_source(body, original, provider).map(|(mapped, _)| mapped)
+}
+
+/// URL 路径中的模型名（`/models/{model}:action`）
+fn model_from_endpoint(endpoint: &str) -> Option<&str> {
+    let path = endpoint.split('?').next().unwrap_or(endpoint);
+    let (_, rest) = path.split_once("/models/")?;
+    let model = rest.split([':', '/']).next().unwrap_or(rest);
+    (!model.is_empty()).then_some(model)
+}
+
+/// 计算命中的映射，额外返回命中来源（用于日志）
+fn resolve_model_with_source(
+    body: &Value,
+    original: &str,
+    provider: &Provider,
+) -> Option<(String, String)> {
+    // 1. 路由规则
+    let rules = provider
+        .meta
+        .as_ref()
+        .map(|m| m.model_routes.as_slice())
+        .unwrap_or_default();
+    if !rules.is_empty() {
+        let traits = RequestTraits::from_body(body);
+        if let Some((index, target)) = match_route(rules, original, &traits) {
+            let source = format!(
+                "rule #{} ({}), thinking={}, tools={}, prompt≈{}",
+                index + 1,
+                rules[index].pattern,
+                traits.thinking,
+                traits.tool_count,
+                traits.prompt_tokens
+            );
+            return (target != original).then(|| (target.to_string(), source));
+        }
+    }
+
+    // 2. env 模型映射
+    let mapping = ModelMapping::from_provider(provider);
+    if !mapping.has_mapping() {
+        return None;
+    }
+    let mapped = mapping.map_model(original, has_thinking_enabled(body));
+    (mapped != original).then(|| (mapped, "env mapping".to_string()))
+}
+
 /// 对请求体应用模型映射
 ///
 /// 返回 (映射后的请求体, 原始模型名, 映射后模型名)
@@ -111,31 +369,42 @@ pub fn apply_model_mapping(
     mut body: Value,
     provider: &Provider,
 ) -> (Value, Option<String>, Option<String>) {
-    let mapping = ModelMapping::from_provider(provider);
-
-    // 如果没有配置映射，直接返回
-    if !mapping.has_mapping() {
-        let original = body.get("model").and_then(|m| m.as_str()).map(String::from);
-        return (body, original, None);
-    }
-
-    // 提取原始模型名
     let original_model = body.get("model").and_then(|m| m.as_str()).map(String::from);
 
-    if let Some(ref original) = original_model {
-        let has_thinking = has_thinking_enabled(&body);
-        let mapped = mapping.map_model(original, has_thinking);
-
-        if mapped != *original {
-            log::debug!("[ModelMapper] 模型映射: {original} → {mapped}");
-            body["model"] = serde_json::json!(mapped);
-            return (body, Some(original.clone()), Some(mapped));
-        }
+    if let Some((original, (mapped, source))) = original_model.as_ref().and_then(|original| {
+        resolve_model_with_source(&body, original, provider).map(|mapped| (original, mapped))
+    }) {
+        log::info!(
+            "[ModelMapper] 模型映射: {original} → {mapped} (provider={}, {source})",
+            provider.name
+        );
+        body["model"] = serde_json::json!(mapped);
+        return (body, Some(original.clone()), Some(mapped));
     }
 
     (body, original_model, None)
 }
 
+/// 对 URL 路径中的模型名应用模型映射（请求体中没有 model 字段时，如 Gemini 原生请求）
+///
+/// 返回改写后的端点，`None` 表示无需改写
+pub fn map_endpoint_model(endpoint: &str, body: &Value, provider: &Provider) -> Option<String> {
+    if body.get("model").is_some() {
+        return None;
+    }
+    let original = model_from_endpoint(endpoint)?;
+    let (mapped, source) = resolve_model_with_source(body, original, provider)?;
+    log::info!(
+        "[ModelMapper] 模型映射: {original} → {mapped} (provider={}, {source})",
+        provider.name
+    );
+    Some(endpoint.replacen(
+        &format!("/models/{original}"),
+        &format!("/models/{mapped}"),
+        1,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -300,6 +569,149 @@ mod tests {
         assert!(mapped.is_none());
     }
 
+    fn create_provider_with_routes(routes: serde_json::Value) -> Provider {
+        let mut provider = create_provider_with_mapping();
+        provider.meta = Some(crate::provider::ProviderMeta {
+            model_routes: serde_json::from_value(routes).unwrap(),
+            ..Default::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn test_route_glob_match_takes_priority() {
+        let provider = create_provider_with_routes(json!([
+            {"pattern": "claude-*-haiku-*", "targetModel": "glm-4.5-air"},
+            {"pattern": "claude-sonnet-?-5*", "targetModel": "glm-4.6"}
+        ]));
+
+        let body = json!({"model": "Claude-Sonnet-4-5-20250929"});
+        let (result, original, mapped) = apply_model_mapping(body, &provider);
+        assert_eq!(result["model"], "glm-4.6");
+        assert_eq!(original, Some("Claude-Sonnet-4-5-20250929".to_string()));
+        assert_eq!(mapped, Some("glm-4.6".to_string()));
+
+        // 未命中规则时回退到 env 映射
+        let body = json!({"model": "claude-opus-4-5"});
+        let (result, _, _) = apply_model_mapping(body, &provider);
+        assert_eq!(result["model"], "opus-mapped");
+    }
+
+    #[test]
+    fn test_route_regex_and_conditions() {
+        let provider = create_provider_with_routes(json!([
+            {"pattern": "^claude-(sonnet|opus)", "matchType": "regex", "thinking": true, "targetModel": "deepseek-reasoner"},
+            {"pattern": "*", "minTools": 2, "targetModel": "tool-model"},
+            {"pattern": "*", "minPromptTokens": 1000, "targetModel": "long-context-model"},
+            {"pattern": "*", "maxPromptTokens": 999, "targetModel": "small-model"}
+        ]));
+
+        let thinking = json!({
+            "model": "claude-opus-4-5",
+            "thinking": {"type": "enabled", "budget_tokens": 1024}
+        });
+        assert_eq!(
+            resolve_model(&thinking, &provider).as_deref(),
+            Some("deepseek-reasoner")
+        );
+
+        let tools = json!({
+            "model": "claude-opus-4-5",
+            "tools": [{"name": "a"}, {"name": "b"}]
+        });
+        assert_eq!(
+            resolve_model(&tools, &provider).as_deref(),
+            Some("tool-model")
+        );
+
+        let long_prompt = json!({
+            "model": "claude-opus-4-5",
+            "messages": [{"role": "user", "content": "x".repeat(4000)}]
+        });
+        assert_eq!(
+            resolve_model(&long_prompt, &provider).as_deref(),
+            Some("long-context-model")
+        );
+
+        let short_prompt = json!({
+            "model": "claude-opus-4-5",
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+        assert_eq!(
+            resolve_model(&short_prompt, &provider).as_deref(),
+            Some("small-model")
+        );
+    }
+
+    #[test]
+    fn test_route_to_same_model_skips_env_mapping() {
+        let provider = create_provider_with_routes(json!([
+            {"pattern": "claude-sonnet-*", "targetModel": "claude-sonnet-4-5"}
+        ]));
+        let body = json!({"model": "claude-sonnet-4-5"});
+        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        assert_eq!(result["model"], "claude-sonnet-4-5");
+        assert!(mapped.is_none());
+    }
+
+    #[test]
+    fn test_request_traits_across_formats() {
+        let codex = json!({
+            "model": "gpt-5-codex",
+            "reasoning": {"effort": "high"},
+            "tools": [{"type": "function", "name": "shell"}]
+        });
+        let traits = RequestTraits::from_body(&codex);
+        assert!(traits.thinking);
+        assert_eq!(traits.tool_count, 1);
+
+        let gemini = json!({
+            "contents": [{"role": "user", "parts": [
+                {"text": "abcdefgh"},
+                {"inlineData": {"mimeType": "image/png", "data": "A".repeat(1000)}}
+            ]}],
+            "tools": [{"functionDeclarations": [{"name": "a"}, {"name": "b"}, {"name": "c"}]}]
+        });
+        let traits = RequestTraits::from_body(&gemini);
+        assert!(!traits.thinking);
+        assert_eq!(traits.tool_count, 3);
+        assert!(traits.prompt_tokens < 20);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_skipped() {
+        let provider = create_provider_with_routes(json!([
+            {"pattern": "claude-(", "matchType": "regex", "targetModel": "broken"}
+        ]));
+        let body = json!({"model": "claude-sonnet-4-5"});
+        assert_eq!(
+            resolve_model(&body, &provider).as_deref(),
+            Some("sonnet-mapped")
+        );
+    }
+
+    #[test]
+    fn test_route_applies_to_gemini_endpoint_model() {
+        let provider = create_provider_with_routes(json!([
+            {"pattern": "gemini-*-pro", "targetModel": "gemini-2.5-flash"}
+        ]));
+        let body = json!({"contents": [{"role": "user", "parts": [{"text": "hi"}]}]});
+        let endpoint = "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse";
+
+        assert_eq!(
+            map_endpoint_model(endpoint, &body, &provider).as_deref(),
+            Some("/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse")
+        );
+        assert_eq!(
+            resolve_request_model(&body, endpoint, &provider).as_deref(),
+            Some("gemini-2.5-flash")
+        );
+
+        // 请求体带 model 时按请求体映射，不改写路径
+        let body = json!({"model": "claude-sonnet-4-5"});
+        assert!(map_endpoint_model(endpoint, &body, &provider).is_none());
+    }
+
     #[test]
     fn test_case_insensitive() {
         let provider = create_provider_with_mapping();
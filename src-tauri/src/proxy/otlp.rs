@@ -0,0 +1,373 @@
+//! OTLP 链路追踪导出
+//!
+//! 每条请求日志写入时（`UsageLogger::log_request`）生成一个 span，后台任务按批通过
+//! OTLP/HTTP（JSON 编码）发送到本地 Collector。默认关闭，配置存储在 settings 表的
+//! telemetry_config 字段中。
+//!
+//! - trace_id 取自请求 ID（UUID 去掉连字符），可与请求日志、抓取记录互相对应
+//! - span 属性遵循 OpenTelemetry GenAI 语义约定（`gen_ai.*`），另附 `cc_switch.*` 扩展属性
+//! - 队列有上限，Collector 不可用时丢弃最早的 span，不影响代理请求
+
+use super::types::TelemetryConfig;
+use super::usage::logger::RequestLog;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 批量发送间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 单批最多发送的 span 数
+const MAX_BATCH_SIZE: usize = 256;
+
+/// 队列中最多保留的 span 数
+const MAX_QUEUED_SPANS: usize = 2048;
+
+/// 发送超时
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// OTLP span 状态码：ERROR（成功请求保持 UNSET）
+const STATUS_CODE_ERROR: u8 = 2;
+
+/// OTLP span 类型：SERVER
+const SPAN_KIND_SERVER: u8 = 2;
+
+static EXPORTER: LazyLock<SpanExporter> = LazyLock::new(SpanExporter::new);
+
+/// 待发送的 span
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    trace_id: String,
+    span_id: String,
+    name: String,
+    start_unix_nano: u128,
+    end_unix_nano: u128,
+    attributes: Vec<(&'static str, Value)>,
+    error_message: Option<String>,
+}
+
+struct SpanExporter {
+    config: RwLock<TelemetryConfig>,
+    queue: Mutex<VecDeque<SpanRecord>>,
+    flush_started: AtomicBool,
+    /// Collector 通常在本机，不走全局代理
+    client: reqwest::Client,
+}
+
+impl SpanExporter {
+    fn new() -> Self {
+        Self {
+            config: RwLock::new(TelemetryConfig::default()),
+            queue: Mutex::new(VecDeque::new()),
+            flush_started: AtomicBool::new(false),
+            client: reqwest::Client::builder()
+                .no_proxy()
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 更新导出配置（关闭时清空队列）
+pub fn configure(config: TelemetryConfig) {
+    if !config.otlp_enabled {
+        lock_queue().clear();
+    }
+    match EXPORTER.config.write() {
+        Ok(mut current) => *current = config,
+        Err(poisoned) => *poisoned.into_inner() = config,
+    }
+}
+
+/// 启动后台批量发送任务（幂等，需在 tokio 运行时中调用）
+pub fn start_flush_task() {
+    if EXPORTER.flush_started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            flush().await;
+        }
+    });
+}
+
+/// 为一条请求日志生成 span（未启用导出时忽略）
+pub fn record_span(log: &RequestLog) {
+    if !current_config().otlp_enabled {
+        return;
+    }
+
+    let mut queue = lock_queue();
+    if queue.len() >= MAX_QUEUED_SPANS {
+        queue.pop_front();
+    }
+    queue.push_back(span_from_log(log, now_unix_nano()));
+}
+
+fn current_config() -> TelemetryConfig {
+    match EXPORTER.config.read() {
+        Ok(config) => config.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+fn lock_queue() -> std::sync::MutexGuard<'static, VecDeque<SpanRecord>> {
+    match EXPORTER.queue.lock() {
+        Ok(queue) => queue,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// 发送队列中的 span（每次最多 MAX_BATCH_SIZE 条，失败时丢弃该批）
+async fn flush() {
+    let config = current_config();
+    if !config.otlp_enabled {
+        return;
+    }
+
+    loop {
+        let batch: Vec<SpanRecord> = {
+            let mut queue = lock_queue();
+            let n = queue.len().min(MAX_BATCH_SIZE);
+            queue.drain(..n).collect()
+        };
+        if batch.is_empty() {
+            return;
+        }
+
+        let payload = build_payload(&config.service_name, &batch);
+        let result = EXPORTER
+            .client
+            .post(&config.otlp_endpoint)
+            .timeout(EXPORT_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => {
+                log::warn!(
+                    "[OTLP] 导出 {} 个 span 失败: HTTP {}",
+                    batch.len(),
+                    response.status()
+                );
+                return;
+            }
+            Err(e) => {
+                log::warn!("[OTLP] 导出 {} 个 span 失败: {e}", batch.len());
+                return;
+            }
+        }
+    }
+}
+
+fn span_from_log(log: &RequestLog, end_unix_nano: u128) -> SpanRecord {
+    let trace_id = trace_id_for_request(&log.request_id);
+    let span_id = uuid::Uuid::new_v4().simple().to_string()[..16].to_string();
+
+    let mut attributes = vec![
+        ("gen_ai.system", json!(log.app_type)),
+        ("gen_ai.request.model", json!(log.request_model)),
+        ("gen_ai.response.model", json!(log.model)),
+        ("gen_ai.usage.input_tokens", json!(log.usage.input_tokens)),
+        ("gen_ai.usage.output_tokens", json!(log.usage.output_tokens)),
+        ("http.response.status_code", json!(log.status_code)),
+        ("cc_switch.request_id", json!(log.request_id)),
+        ("cc_switch.app_type", json!(log.app_type)),
+        ("cc_switch.provider_id", json!(log.provider_id)),
+        ("cc_switch.streaming", json!(log.is_streaming)),
+        ("cc_switch.cache_hit", json!(log.cache_hit)),
+        (
+            "cc_switch.usage.cache_read_tokens",
+            json!(log.usage.cache_read_tokens),
+        ),
+        (
+            "cc_switch.usage.cache_creation_tokens",
+            json!(log.usage.cache_creation_tokens),
+        ),
+    ];
+    if let Some(first_token_ms) = log.first_token_ms {
+        attributes.push(("cc_switch.first_token_ms", json!(first_token_ms)));
+    }
+    if let Some(cost) = &log.cost {
+        attributes.push((
+            "cc_switch.cost_usd",
+            json!(cost.total_cost.normalize().to_string()),
+        ));
+    }
+    if let Some(session_id) = &log.session_id {
+        attributes.push(("session.id", json!(session_id)));
+    }
+
+    let error_message = if log.status_code >= 400 {
+        Some(
+            log.error_message
+                .clone()
+                .unwrap_or_else(|| format!("HTTP {}", log.status_code)),
+        )
+    } else {
+        None
+    };
+
+    SpanRecord {
+        trace_id,
+        span_id,
+        name: format!("{} {}", log.app_type, log.request_model),
+        start_unix_nano: end_unix_nano.saturating_sub(log.latency_ms as u128 * 1_000_000),
+        end_unix_nano,
+        attributes,
+        error_message,
+    }
+}
+
+/// 请求 ID 为 UUID 时直接作为 trace_id，否则随机生成
+fn trace_id_for_request(request_id: &str) -> String {
+    match uuid::Uuid::parse_str(request_id) {
+        Ok(id) => id.simple().to_string(),
+        Err(_) => uuid::Uuid::new_v4().simple().to_string(),
+    }
+}
+
+/// 构造 OTLP/HTTP JSON 请求体（ExportTraceServiceRequest）
+fn build_payload(service_name: &str, spans: &[SpanRecord]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let attributes: Vec<Value> = span
+                .attributes
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": any_value(value) }))
+                .collect();
+            let status = match &span.error_message {
+                Some(message) => json!({ "code": STATUS_CODE_ERROR, "message": message }),
+                None => json!({}),
+            };
+            json!({
+                "traceId": span.trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": SPAN_KIND_SERVER,
+                // OTLP JSON 中 64 位整数以字符串表示
+                "startTimeUnixNano": span.start_unix_nano.to_string(),
+                "endTimeUnixNano": span.end_unix_nano.to_string(),
+                "attributes": attributes,
+                "status": status,
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": service_name } },
+                    { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } },
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "cc-switch.proxy" },
+                "spans": spans,
+            }]
+        }]
+    })
+}
+
+/// JSON 值转为 OTLP AnyValue
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+        Value::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    }
+}
+
+fn now_unix_nano() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::usage::parser::TokenUsage;
+
+    fn request_log(status_code: u16) -> RequestLog {
+        RequestLog {
+            request_id: "0b5e7c4e-2f7a-4d43-9a55-3f8e6f1d2c10".to_string(),
+            provider_id: "p1".to_string(),
+            app_type: "claude".to_string(),
+            model: "claude-sonnet-4-5-20250929".to_string(),
+            request_model: "claude-sonnet-4-5".to_string(),
+            usage: TokenUsage {
+                input_tokens: 120,
+                output_tokens: 30,
+                ..Default::default()
+            },
+            cost: None,
+            latency_ms: 1500,
+            first_token_ms: Some(400),
+            status_code,
+            error_message: None,
+            session_id: None,
+            provider_type: None,
+            is_streaming: true,
+            cost_multiplier: "1".to_string(),
+            masked_key: None,
+            cache_hit: false,
+            virtual_key_id: None,
+            hedge_role: None,
+        }
+    }
+
+    #[test]
+    fn span_uses_request_id_as_trace_id_and_latency_as_duration() {
+        let span = span_from_log(&request_log(200), 10_000_000_000);
+
+        assert_eq!(span.trace_id, "0b5e7c4e2f7a4d439a553f8e6f1d2c10");
+        assert_eq!(span.span_id.len(), 16);
+        assert_eq!(span.start_unix_nano, 8_500_000_000);
+        assert_eq!(span.end_unix_nano, 10_000_000_000);
+        assert!(span.error_message.is_none());
+        assert_eq!(trace_id_for_request("not-a-uuid").len(), 32);
+    }
+
+    #[test]
+    fn payload_follows_otlp_json_encoding() {
+        let span = span_from_log(&request_log(502), 10_000_000_000);
+        let payload = build_payload("cc-switch-proxy", &[span]);
+
+        let resource = &payload["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "cc-switch-proxy"
+        );
+        let span = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["name"], "claude claude-sonnet-4-5");
+        assert_eq!(span["startTimeUnixNano"], "8500000000");
+        assert_eq!(span["status"]["code"], STATUS_CODE_ERROR);
+        assert_eq!(span["status"]["message"], "HTTP 502");
+
+        let attributes = span["attributes"].as_array().unwrap();
+        let attr = |key: &str| {
+            attributes
+                .iter()
+                .find(|a| a["key"] == key)
+                .map(|a| a["value"].clone())
+                .unwrap()
+        };
+        assert_eq!(attr("gen_ai.usage.input_tokens")["intValue"], "120");
+        assert_eq!(attr("cc_switch.streaming")["boolValue"], true);
+        assert_eq!(
+            attr("gen_ai.response.model")["stringValue"],
+            "claude-sonnet-4-5-20250929"
+        );
+    }
+}
@@ -7,17 +7,59 @@ use crate::database::Database;
 use crate::error::AppError;
 use crate::provider::Provider;
 use crate::proxy::circuit_breaker::{AllowResult, CircuitBreaker, CircuitBreakerConfig};
+use crate::proxy::key_pool::KeyPool;
+use crate::proxy::load_balancer::LoadBalancer;
+use crate::proxy::model_mapper::model_matches;
+use crate::proxy::rate_limit::{RateLimitInfo, RateLimiter};
+use crate::proxy::session::SessionAffinity;
+use crate::proxy::types::{BudgetAlert, LoadBalanceStrategy, ModelRoute, RateLimitState};
+use crate::services::ProviderLimitStatus;
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// 消费达到限额的该比例时发出预警
+const BUDGET_WARNING_RATIO: f64 = 0.8;
+
+/// 供应商消费统计的缓存时间（本代理记录用量时立即失效，超时用于兜底其他来源的变化，如修改限额）
+const BUDGET_STATUS_TTL: Duration = Duration::from_secs(60);
+
+/// 单个供应商各周期（daily/monthly）的告警状态：周期标识及是否已告警超额
+type BudgetAlertState = HashMap<&'static str, (String, bool)>;
+
+/// 供应商选择结果
+#[derive(Debug, Clone)]
+pub struct ProviderSelection {
+    /// 按尝试顺序排列的可用供应商
+    pub providers: Vec<Provider>,
+    /// 首选供应商是否按请求决定（命中模型路由、启用了负载均衡或 Session 亲和生效）
+    ///
+    /// 此时首选供应商与"当前供应商"不同不代表发生了故障转移
+    pub per_request_target: bool,
+}
+
 /// 供应商路由器
 pub struct ProviderRouter {
     /// 数据库连接
     db: Arc<Database>,
     /// 熔断器管理器 - key 格式: "app_type:provider_id"
     circuit_breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+    /// 负载均衡器（轮询/加权状态跨请求保持）
+    balancer: LoadBalancer,
+    /// Session → Provider 亲和表
+    affinity: SessionAffinity,
+    /// 供应商 API Key 池（轮换游标与冷却状态）
+    key_pool: KeyPool,
+    /// 上游限流冷却表
+    rate_limiter: RateLimiter,
+    /// 消费限额状态缓存 - key 格式: "app_type:provider_id"
+    budget_status: Mutex<HashMap<String, (Instant, ProviderLimitStatus)>>,
+    /// 已告警过的消费限额 - key 格式: "app_type:provider_id"，进入新周期时重新告警
+    budget_alerted: Mutex<HashMap<String, BudgetAlertState>>,
+    /// 待通知的消费限额告警
+    pending_budget_alerts: Mutex<Vec<BudgetAlert>>,
 }
 
 impl ProviderRouter {
@@ -26,18 +68,35 @@ impl ProviderRouter {
         Self {
             db,
             circuit_breakers: Arc::new(RwLock::new(HashMap::new())),
+            balancer: LoadBalancer::new(),
+            affinity: SessionAffinity::default(),
+            key_pool: KeyPool::new(),
+            rate_limiter: RateLimiter::new(),
+            budget_status: Mutex::new(HashMap::new()),
+            budget_alerted: Mutex::new(HashMap::new()),
+            pending_budget_alerts: Mutex::new(Vec::new()),
         }
     }
 
     /// 选择可用的供应商（支持故障转移）
     ///
     /// 返回按优先级排序的可用供应商列表：
-    /// - 故障转移关闭时：仅返回当前供应商
+    /// - 请求模型命中模型路由时：仅使用该路由的供应商，按路由内顺序依次尝试
+    /// - 故障转移关闭时：仅返回当前供应商（命中路由时为路由的首个供应商）
     /// - 故障转移开启时：仅使用故障转移队列，按队列顺序依次尝试（P1 → P2 → ...）
-    pub async fn select_providers(&self, app_type: &str) -> Result<Vec<Provider>, AppError> {
+    /// - 故障转移开启且配置了负载均衡策略时：在可用供应商内按策略重排尝试顺序
+    /// - 传入 `session_id` 且该 Session 已绑定到某个可用供应商时：优先使用该供应商；
+    ///   绑定的供应商熔断或不再可选时解除绑定
+    /// - 上游限流冷却中的供应商：还有其他可用供应商时跳过；全部冷却时按恢复时间先后排列
+    pub async fn select_providers(
+        &self,
+        app_type: &str,
+        model: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<ProviderSelection, AppError> {
         let mut result = Vec::new();
-        let mut total_providers = 0usize;
         let mut circuit_open_count = 0usize;
+        let mut over_budget_count = 0usize;
 
         // 检查该应用的自动故障转移开关是否开启（从 proxy_config 表读取）
         let auto_failover_enabled = match self.db.get_proxy_config_for_app(app_type).await {
@@ -48,55 +107,75 @@ impl ProviderRouter {
             }
         };
 
-        if auto_failover_enabled {
+        let route = self.match_route(app_type, model);
+        let ordered_ids: Vec<String> = if let Some(route) = route.clone() {
+            log::debug!(
+                "[{app_type}] 模型 {} 命中路由 {} ({})",
+                model.unwrap_or_default(),
+                route.name,
+                route.id
+            );
+            if auto_failover_enabled {
+                route.provider_ids
+            } else {
+                route.provider_ids.into_iter().take(1).collect()
+            }
+        } else if auto_failover_enabled {
             // 故障转移开启：仅按队列顺序依次尝试（P1 → P2 → ...）
-            let all_providers = self.db.get_all_providers(app_type)?;
-
             // 使用 DAO 返回的排序结果，确保和前端展示一致
-            let ordered_ids: Vec<String> = self
-                .db
+            self.db
                 .get_failover_queue(app_type)?
                 .into_iter()
                 .map(|item| item.provider_id)
-                .collect();
+                .collect()
+        } else {
+            // 故障转移关闭：仅使用当前供应商
+            AppType::from_str(app_type)
+                .ok()
+                .and_then(|app_enum| {
+                    crate::settings::get_effective_current_provider(&self.db, &app_enum)
+                        .ok()
+                        .flatten()
+                })
+                .or_else(|| self.db.get_current_provider(app_type).ok().flatten())
+                .into_iter()
+                .collect()
+        };
 
-            total_providers = ordered_ids.len();
+        let all_providers = self.db.get_all_providers(app_type)?;
+        let mut total_providers = 0usize;
 
-            for provider_id in ordered_ids {
-                let Some(provider) = all_providers.get(&provider_id).cloned() else {
-                    continue;
-                };
+        for provider_id in ordered_ids {
+            let Some(provider) = all_providers.get(&provider_id).cloned() else {
+                continue;
+            };
+            total_providers += 1;
 
+            // 故障转移关闭时跳过熔断器检查
+            if auto_failover_enabled {
                 let circuit_key = format!("{app_type}:{}", provider.id);
                 let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
 
-                if breaker.is_available().await {
-                    result.push(provider);
-                } else {
+                if !breaker.is_available().await {
                     circuit_open_count += 1;
+                    continue;
                 }
             }
-        } else {
-            // 故障转移关闭：仅使用当前供应商，跳过熔断器检查
-            let current_id = AppType::from_str(app_type)
-                .ok()
-                .and_then(|app_enum| {
-                    crate::settings::get_effective_current_provider(&self.db, &app_enum)
-                        .ok()
-                        .flatten()
-                })
-                .or_else(|| self.db.get_current_provider(app_type).ok().flatten());
 
-            if let Some(current_id) = current_id {
-                if let Some(current) = self.db.get_provider_by_id(&current_id, app_type)? {
-                    total_providers = 1;
-                    result.push(current);
-                }
+            // 超出消费限额的供应商视为不可用
+            if self.is_over_budget(app_type, &provider) {
+                over_budget_count += 1;
+                continue;
             }
+
+            result.push(provider);
         }
 
         if result.is_empty() {
-            if total_providers > 0 && circuit_open_count == total_providers {
+            if over_budget_count > 0 && circuit_open_count + over_budget_count == total_providers {
+                log::warn!("[{app_type}] 所有供应商均已超出消费限额");
+                return Err(AppError::AllProvidersOverBudget);
+            } else if total_providers > 0 && circuit_open_count == total_providers {
                 log::warn!("[{app_type}] [FO-004] 所有供应商均已熔断");
                 return Err(AppError::AllProvidersCircuitOpen);
             } else {
@@ -105,7 +184,313 @@ impl ProviderRouter {
             }
         }
 
-        Ok(result)
+        let mut per_request_target = route.is_some();
+        if auto_failover_enabled && result.len() > 1 {
+            let strategy = match self.db.get_load_balance_strategy(app_type).await {
+                Ok(strategy) => strategy,
+                Err(e) => {
+                    log::warn!("[{app_type}] 读取负载均衡策略失败: {e}，按队列顺序");
+                    LoadBalanceStrategy::Priority
+                }
+            };
+            if strategy != LoadBalanceStrategy::Priority {
+                let scope = route.as_ref().map_or("queue", |r| r.id.as_str());
+                result = self
+                    .balancer
+                    .order(&self.db, app_type, scope, strategy, result, model)
+                    .await;
+                per_request_target = true;
+            }
+        }
+
+        if let Some(session_id) = session_id {
+            if let Some(bound_id) = self.affinity.get(app_type, session_id) {
+                match result.iter().position(|p| p.id == bound_id) {
+                    Some(index) => {
+                        if index > 0 {
+                            let bound = result.remove(index);
+                            result.insert(0, bound);
+                            per_request_target = true;
+                        }
+                        log::debug!("[{app_type}] Session {session_id} 沿用供应商 {bound_id}");
+                    }
+                    None => {
+                        log::debug!(
+                            "[{app_type}] Session {session_id} 绑定的供应商 {bound_id} 不可用，解除绑定"
+                        );
+                        self.affinity.unbind(app_type, session_id);
+                    }
+                }
+            }
+        }
+
+        let cooling: HashMap<String, Duration> = result
+            .iter()
+            .filter_map(|p| {
+                self.rate_limiter
+                    .remaining(app_type, &p.id)
+                    .map(|remaining| (p.id.clone(), remaining))
+            })
+            .collect();
+        if !cooling.is_empty() {
+            if cooling.len() < result.len() {
+                log::debug!("[{app_type}] 跳过 {} 个限流冷却中的供应商", cooling.len());
+                result.retain(|p| !cooling.contains_key(&p.id));
+            } else {
+                result.sort_by_key(|p| cooling.get(&p.id).copied());
+            }
+        }
+
+        Ok(ProviderSelection {
+            providers: result,
+            per_request_target,
+        })
+    }
+
+    /// 检查供应商是否已超出每日/每月消费限额
+    ///
+    /// 同时记录新跨过预警阈值（默认 80%）或超额的告警，由调用方通过
+    /// `take_budget_alerts()` 取出并通知前端；同一周期内每个阈值只告警一次
+    fn is_over_budget(&self, app_type: &str, provider: &Provider) -> bool {
+        let has_limit = provider
+            .meta
+            .as_ref()
+            .is_some_and(|meta| meta.limit_daily_usd.is_some() || meta.limit_monthly_usd.is_some());
+        if !has_limit {
+            return false;
+        }
+
+        let key = format!("{app_type}:{}", provider.id);
+        let Some(status) = self.budget_status(app_type, &key, &provider.id) else {
+            return false;
+        };
+
+        let now = chrono::Local::now();
+        let periods = [
+            (
+                "daily",
+                now.format("%Y-%m-%d").to_string(),
+                &status.daily_usage,
+                &status.daily_limit,
+            ),
+            (
+                "monthly",
+                now.format("%Y-%m").to_string(),
+                &status.monthly_usage,
+                &status.monthly_limit,
+            ),
+        ];
+
+        for (period, period_key, usage, limit) in periods {
+            let (Ok(usage_value), Some(Ok(limit_value))) = (
+                usage.parse::<f64>(),
+                limit.as_deref().map(str::parse::<f64>),
+            ) else {
+                continue;
+            };
+            if limit_value <= 0.0 {
+                continue;
+            }
+
+            let exceeded = usage_value >= limit_value;
+            if !exceeded && usage_value < limit_value * BUDGET_WARNING_RATIO {
+                continue;
+            }
+
+            if self.mark_budget_alerted(&key, period, period_key, exceeded) {
+                log::warn!(
+                    "[{app_type}] 供应商 {} {period} 消费 {usage} / {} USD{}",
+                    provider.name,
+                    limit.as_deref().unwrap_or_default(),
+                    if exceeded {
+                        "，已超额"
+                    } else {
+                        "，已达到预警阈值"
+                    }
+                );
+                self.pending_budget_alerts
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(BudgetAlert {
+                        app_type: app_type.to_string(),
+                        provider_id: provider.id.clone(),
+                        provider_name: provider.name.clone(),
+                        period: period.to_string(),
+                        usage_usd: usage.clone(),
+                        limit_usd: limit.clone().unwrap_or_default(),
+                        exceeded,
+                    });
+            }
+        }
+
+        status.daily_exceeded || status.monthly_exceeded
+    }
+
+    /// 读取供应商的消费限额状态（带缓存，见 [`BUDGET_STATUS_TTL`]）
+    fn budget_status(
+        &self,
+        app_type: &str,
+        key: &str,
+        provider_id: &str,
+    ) -> Option<ProviderLimitStatus> {
+        {
+            let cache = self.budget_status.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((checked_at, status)) = cache.get(key) {
+                if checked_at.elapsed() < BUDGET_STATUS_TTL {
+                    return Some(status.clone());
+                }
+            }
+        }
+
+        // 查询数据库时不持有锁，避免所有请求排队等待
+        match self.db.check_provider_limits(provider_id, app_type) {
+            Ok(status) => {
+                self.budget_status
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(key.to_string(), (Instant::now(), status.clone()));
+                Some(status)
+            }
+            Err(e) => {
+                log::warn!("[{app_type}] 检查供应商 {provider_id} 限额失败: {e}");
+                None
+            }
+        }
+    }
+
+    /// 记录用量后使供应商的消费限额状态缓存失效
+    pub fn invalidate_budget_status(&self, app_type: &str, provider_id: &str) {
+        self.budget_status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&format!("{app_type}:{provider_id}"));
+    }
+
+    /// 登记一次告警，返回是否需要通知（同一周期内预警、超额各通知一次）
+    fn mark_budget_alerted(
+        &self,
+        key: &str,
+        period: &'static str,
+        period_key: String,
+        exceeded: bool,
+    ) -> bool {
+        let mut alerted = self
+            .budget_alerted
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let periods = alerted.entry(key.to_string()).or_default();
+        match periods.get(period) {
+            Some((current, alerted_exceeded))
+                if *current == period_key && (*alerted_exceeded || !exceeded) =>
+            {
+                false
+            }
+            _ => {
+                periods.insert(period, (period_key, exceeded));
+                true
+            }
+        }
+    }
+
+    /// 取出待通知的消费限额告警
+    pub fn take_budget_alerts(&self) -> Vec<BudgetAlert> {
+        std::mem::take(
+            &mut *self
+                .pending_budget_alerts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        )
+    }
+
+    /// 将 Session 绑定到成功响应的供应商（后续请求优先沿用）
+    pub fn bind_session(&self, app_type: &str, session_id: &str, provider_id: &str) {
+        self.affinity.bind(app_type, session_id, provider_id);
+    }
+
+    /// 从供应商的 Key 池中选择本次请求使用的 API Key（未配置额外 Key 时返回 None）
+    pub fn select_api_key(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        primary: &str,
+    ) -> Option<String> {
+        self.key_pool.select(app_type, provider, primary)
+    }
+
+    /// 上游返回 429/401 时让对应的 API Key 进入冷却
+    pub fn report_api_key_status(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        api_key: &str,
+        status: u16,
+    ) {
+        self.key_pool.report(app_type, provider_id, api_key, status);
+    }
+
+    /// 供应商的 Key 池中是否还有未冷却的 Key
+    pub fn has_available_api_key(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+        primary: &str,
+    ) -> bool {
+        self.key_pool.has_available(app_type, provider, primary)
+    }
+
+    /// 上游返回 429/529 时让供应商进入限流冷却（不计入熔断器），返回冷却时长
+    pub fn record_rate_limit(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        status: u16,
+        info: &RateLimitInfo,
+    ) -> Duration {
+        let cooldown = self
+            .rate_limiter
+            .record(app_type, provider_id, status, info);
+        log::warn!(
+            "[{app_type}] 供应商 {provider_id} 被上游限流 ({status})，冷却 {}ms",
+            cooldown.as_millis()
+        );
+        cooldown
+    }
+
+    /// 供应商剩余的限流冷却时长（未在冷却中时返回 None）
+    pub fn rate_limit_remaining(&self, app_type: &str, provider_id: &str) -> Option<Duration> {
+        self.rate_limiter.remaining(app_type, provider_id)
+    }
+
+    /// 供应商恢复正常响应后清除限流状态
+    pub fn clear_rate_limit(&self, app_type: &str, provider_id: &str) {
+        self.rate_limiter.clear(app_type, provider_id);
+    }
+
+    /// 获取供应商的限流状态
+    pub fn get_rate_limit_state(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+    ) -> Option<RateLimitState> {
+        self.rate_limiter.state(app_type, provider_id)
+    }
+
+    /// 查找请求模型命中的第一条启用的模型路由
+    fn match_route(&self, app_type: &str, model: Option<&str>) -> Option<ModelRoute> {
+        let model = model.filter(|m| !m.is_empty() && *m != "unknown")?;
+        let routes = match self.db.get_model_routes(app_type) {
+            Ok(routes) => routes,
+            Err(e) => {
+                log::error!("[{app_type}] 读取模型路由失败: {e}，忽略路由");
+                return None;
+            }
+        };
+
+        routes.into_iter().find(|route| {
+            route.enabled
+                && !route.provider_ids.is_empty()
+                && model_matches(&route.pattern, route.match_type, model)
+        })
     }
 
     /// 请求执行前获取熔断器“放行许可”
@@ -218,6 +603,39 @@ impl ProviderRouter {
         }
     }
 
+    /// 获取所有已创建熔断器的状态（用于 /metrics 导出）
+    ///
+    /// 返回 (app_type, provider_id, 熔断器状态)，按 key 排序
+    pub async fn circuit_breaker_snapshot(
+        &self,
+    ) -> Vec<(
+        String,
+        String,
+        crate::proxy::circuit_breaker::CircuitBreakerStats,
+    )> {
+        let breakers: Vec<(String, Arc<CircuitBreaker>)> = {
+            let breakers = self.circuit_breakers.read().await;
+            breakers
+                .iter()
+                .map(|(key, breaker)| (key.clone(), breaker.clone()))
+                .collect()
+        };
+
+        let mut snapshot = Vec::with_capacity(breakers.len());
+        for (key, breaker) in breakers {
+            let Some((app_type, provider_id)) = key.split_once(':') else {
+                continue;
+            };
+            snapshot.push((
+                app_type.to_string(),
+                provider_id.to_string(),
+                breaker.get_stats().await,
+            ));
+        }
+        snapshot.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        snapshot
+    }
+
     /// 获取或创建熔断器
     async fn get_or_create_circuit_breaker(&self, key: &str) -> Arc<CircuitBreaker> {
         // 先尝试读锁获取
@@ -334,7 +752,11 @@ mod tests {
         db.add_to_failover_queue("claude", "b").unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router
+            .select_providers("claude", None, None)
+            .await
+            .unwrap()
+            .providers;
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].id, "a");
@@ -367,7 +789,11 @@ mod tests {
         db.update_proxy_config_for_app(config).await.unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router
+            .select_providers("claude", None, None)
+            .await
+            .unwrap()
+            .providers;
 
         assert_eq!(providers.len(), 2);
         // 故障转移开启时：仅按队列顺序选择（忽略当前供应商）
@@ -399,7 +825,11 @@ mod tests {
         db.update_proxy_config_for_app(config).await.unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router
+            .select_providers("claude", None, None)
+            .await
+            .unwrap()
+            .providers;
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].id, "b");
@@ -442,7 +872,11 @@ mod tests {
             .await
             .unwrap();
 
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router
+            .select_providers("claude", None, None)
+            .await
+            .unwrap()
+            .providers;
         assert_eq!(providers.len(), 2);
 
         assert!(router.allow_provider_request("b", "claude").await.allowed);
@@ -500,4 +934,315 @@ mod tests {
         assert!(third.allowed);
         assert!(third.used_half_open_permit);
     }
+
+    fn route(id: &str, pattern: &str, provider_ids: &[&str]) -> ModelRoute {
+        ModelRoute {
+            id: id.to_string(),
+            app_type: "claude".to_string(),
+            name: id.to_string(),
+            pattern: pattern.to_string(),
+            match_type: Default::default(),
+            provider_ids: provider_ids.iter().map(|s| s.to_string()).collect(),
+            enabled: true,
+            sort_index: 0,
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_model_route_overrides_queue_with_own_order() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for id in ["a", "b", "c"] {
+            let provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            db.save_provider("claude", &provider).unwrap();
+        }
+        db.add_to_failover_queue("claude", "a").unwrap();
+        db.save_model_route(&route("haiku", "claude-haiku-*", &["c", "b"]))
+            .unwrap();
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+
+        let providers = router
+            .select_providers("claude", Some("claude-haiku-4-5"), None)
+            .await
+            .unwrap()
+            .providers;
+        let ids: Vec<_> = providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b"]);
+
+        // 路由内的熔断器独立生效
+        db.update_circuit_breaker_config(&CircuitBreakerConfig {
+            failure_threshold: 1,
+            timeout_seconds: 60,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let router = ProviderRouter::new(db.clone());
+        router
+            .record_result("c", "claude", false, false, Some("fail".to_string()))
+            .await
+            .unwrap();
+        let providers = router
+            .select_providers("claude", Some("claude-haiku-4-5"), None)
+            .await
+            .unwrap()
+            .providers;
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "b");
+
+        // 未命中路由的模型仍走故障转移队列
+        let providers = router
+            .select_providers("claude", Some("claude-opus-4"), None)
+            .await
+            .unwrap()
+            .providers;
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "a");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_model_route_with_failover_disabled_uses_first_route_provider() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for id in ["a", "b", "c"] {
+            let provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            db.save_provider("claude", &provider).unwrap();
+        }
+        db.set_current_provider("claude", "a").unwrap();
+
+        let mut disabled = route("disabled", "*opus*", &["c"]);
+        disabled.enabled = false;
+        db.save_model_route(&disabled).unwrap();
+        let mut opus = route("opus", "*opus*", &["b", "c"]);
+        opus.sort_index = 1;
+        db.save_model_route(&opus).unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+
+        let providers = router
+            .select_providers("claude", Some("claude-opus-4-1"), None)
+            .await
+            .unwrap()
+            .providers;
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "b");
+
+        let providers = router
+            .select_providers("claude", Some("claude-sonnet-4"), None)
+            .await
+            .unwrap()
+            .providers;
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "a");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_round_robin_strategy_rotates_available_providers() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for (index, id) in ["a", "b", "c"].into_iter().enumerate() {
+            let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            provider.sort_index = Some(index);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+        db.set_load_balance_strategy("claude", LoadBalanceStrategy::RoundRobin)
+            .await
+            .unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+
+        let first = router.select_providers("claude", None, None).await.unwrap();
+        assert!(first.per_request_target);
+        assert_eq!(first.providers[0].id, "a");
+        assert_eq!(first.providers.len(), 3);
+
+        let second = router.select_providers("claude", None, None).await.unwrap();
+        let ids: Vec<_> = second.providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c", "a"]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_rate_limited_provider_is_skipped_until_cooldown_clears() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for (index, id) in ["a", "b"].into_iter().enumerate() {
+            let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            provider.sort_index = Some(index);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        let info = RateLimitInfo {
+            retry_after: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let cooldown = router.record_rate_limit("claude", "a", 429, &info);
+        assert_eq!(cooldown, Duration::from_secs(30));
+
+        let selection = router.select_providers("claude", None, None).await.unwrap();
+        let ids: Vec<_> = selection.providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["b"]);
+
+        let state = router.get_rate_limit_state("claude", "a").unwrap();
+        assert_eq!(state.hits, 1);
+        assert!(router.get_rate_limit_state("codex", "a").is_none());
+
+        router.clear_rate_limit("claude", "a");
+        let selection = router.select_providers("claude", None, None).await.unwrap();
+        assert_eq!(selection.providers.len(), 2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_session_affinity_keeps_provider_until_breaker_opens() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        db.update_circuit_breaker_config(&CircuitBreakerConfig {
+            failure_threshold: 1,
+            timeout_seconds: 60,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        for (index, id) in ["a", "b"].into_iter().enumerate() {
+            let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            provider.sort_index = Some(index);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        router.bind_session("claude", "session-1", "b");
+
+        let selection = router
+            .select_providers("claude", None, Some("session-1"))
+            .await
+            .unwrap();
+        assert!(selection.per_request_target);
+        let ids: Vec<_> = selection.providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+
+        // 其他 Session 不受影响
+        let other = router
+            .select_providers("claude", None, Some("session-2"))
+            .await
+            .unwrap();
+        assert_eq!(other.providers[0].id, "a");
+
+        // 绑定的供应商熔断后回到正常顺序，并解除绑定
+        router
+            .record_result("b", "claude", false, false, Some("fail".to_string()))
+            .await
+            .unwrap();
+        let selection = router
+            .select_providers("claude", None, Some("session-1"))
+            .await
+            .unwrap();
+        assert_eq!(selection.providers.len(), 1);
+        assert_eq!(selection.providers[0].id, "a");
+        assert_eq!(router.affinity.get("claude", "session-1"), None);
+    }
+
+    fn insert_cost(db: &Database, provider_id: &str, cost: &str) -> Result<(), AppError> {
+        let conn = crate::database::lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, total_cost_usd,
+                latency_ms, status_code, created_at
+            ) VALUES (?1, ?2, 'claude', 'm', ?3, 100, 200, ?4)",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                provider_id,
+                cost,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_over_budget_providers_are_skipped_and_alerted() -> Result<(), AppError> {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory()?);
+
+        for (index, id) in ["a", "b"].into_iter().enumerate() {
+            let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+            provider.sort_index = Some(index);
+            provider.meta = Some(crate::provider::ProviderMeta {
+                limit_daily_usd: Some("10".to_string()),
+                ..Default::default()
+            });
+            db.save_provider("claude", &provider)?;
+            db.add_to_failover_queue("claude", id)?;
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await?;
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await?;
+
+        let router = ProviderRouter::new(db.clone());
+
+        // a 超额、b 达到 80% 预警：跳过 a，转到 b
+        insert_cost(&db, "a", "12")?;
+        insert_cost(&db, "b", "8.5")?;
+        let selection = router.select_providers("claude", None, None).await?;
+        let ids: Vec<_> = selection.providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["b"]);
+
+        let alerts = router.take_budget_alerts();
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts.iter().any(|a| a.provider_id == "a" && a.exceeded));
+        assert!(alerts.iter().any(|a| a.provider_id == "b" && !a.exceeded));
+
+        // 同一周期内不重复告警
+        router.select_providers("claude", None, None).await?;
+        assert!(router.take_budget_alerts().is_empty());
+
+        // 全部超额时返回专门的错误（记录用量时使缓存失效）
+        insert_cost(&db, "b", "2")?;
+        router.invalidate_budget_status("claude", "b");
+        let err = router
+            .select_providers("claude", None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::AllProvidersOverBudget));
+        assert_eq!(router.take_budget_alerts().len(), 1);
+
+        // 每个供应商每种周期只保留当前周期的记录，进入新周期后重新告警
+        assert!(router.mark_budget_alerted("claude:a", "daily", "2000-01-01".to_string(), true));
+        assert!(!router.mark_budget_alerted("claude:a", "daily", "2000-01-01".to_string(), true));
+        assert_eq!(router.budget_alerted.lock().unwrap()["claude:a"].len(), 1);
+
+        Ok(())
+    }
 }
@@ -95,6 +95,19 @@ pub trait ProviderAdapter: Send + Sync {
         false
     }
 
+    /// 转换请求端点
+    ///
+    /// 仅在 `needs_transform` 为 `true` 时调用，用于把客户端端点改写为上游格式对应的端点
+    /// （如 `/v1/messages` → `/v1/chat/completions`）。默认实现保持原端点。
+    ///
+    /// # Arguments
+    /// * `endpoint` - 客户端请求的端点
+    /// * `body` - 已完成模型映射的请求体（部分格式的端点依赖模型名）
+    /// * `provider` - Provider 配置
+    fn transform_endpoint(&self, endpoint: &str, _body: &Value, _provider: &Provider) -> String {
+        endpoint.to_string()
+    }
+
     /// 转换请求体
     ///
     /// 将请求体从一种格式转换为另一种格式（如 Anthropic → OpenAI）。
@@ -5,16 +5,18 @@
 //! ## API 格式
 //! - **anthropic** (默认): Anthropic Messages API 格式，直接透传
 //! - **openai_chat**: OpenAI Chat Completions 格式，需要 Anthropic ↔ OpenAI 转换
+//! - **gemini**: Gemini generateContent 格式，需要 Anthropic ↔ Gemini 转换（Google 认证）
 //!
 //! ## 认证模式
 //! - **Claude**: Anthropic 官方 API (x-api-key + anthropic-version)
 //! - **ClaudeAuth**: 中转服务 (仅 Bearer 认证，无 x-api-key)
 //! - **OpenRouter**: 已支持 Claude Code 兼容接口，默认透传
 
-use super::{AuthInfo, AuthStrategy, ProviderAdapter, ProviderType};
+use super::{AuthInfo, AuthStrategy, GeminiAdapter, ProviderAdapter, ProviderType};
 use crate::provider::Provider;
 use crate::proxy::error::ProxyError;
 use reqwest::RequestBuilder;
+use serde_json::Value;
 
 /// Claude 适配器
 pub struct ClaudeAdapter;
@@ -26,11 +28,17 @@ impl ClaudeAdapter {
 
     /// 获取供应商类型
     ///
-    /// 根据 base_url 和 auth_mode 检测具体的供应商类型：
+    /// 根据 api_format、base_url 和 auth_mode 检测具体的供应商类型：
+    /// - Gemini: api_format 为 gemini
     /// - OpenRouter: base_url 包含 openrouter.ai
     /// - ClaudeAuth: auth_mode 为 bearer_only
     /// - Claude: 默认 Anthropic 官方
     pub fn provider_type(&self, provider: &Provider) -> ProviderType {
+        // 检测 Gemini 格式
+        if self.get_api_format(provider) == "gemini" {
+            return ProviderType::Gemini;
+        }
+
         // 检测 OpenRouter
         if self.is_openrouter(provider) {
             return ProviderType::OpenRouter;
@@ -57,15 +65,12 @@ impl ClaudeAdapter {
     /// 从 provider.meta.api_format 读取格式设置：
     /// - "anthropic" (默认): Anthropic Messages API 格式，直接透传
     /// - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
-    fn get_api_format(&self, provider: &Provider) -> &'static str {
+    /// - "gemini": Gemini generateContent 格式，需要格式转换
+    pub(crate) fn get_api_format(&self, provider: &Provider) -> &'static str {
         // 1) Preferred: meta.apiFormat (SSOT, never written to Claude Code config)
         if let Some(meta) = provider.meta.as_ref() {
             if let Some(api_format) = meta.api_format.as_deref() {
-                return if api_format == "openai_chat" {
-                    "openai_chat"
-                } else {
-                    "anthropic"
-                };
+                return normalize_api_format(api_format);
             }
         }
 
@@ -75,11 +80,7 @@ impl ClaudeAdapter {
             .get("api_format")
             .and_then(|v| v.as_str())
         {
-            return if api_format == "openai_chat" {
-                "openai_chat"
-            } else {
-                "anthropic"
-            };
+            return normalize_api_format(api_format);
         }
 
         // 3) Backward compatibility: legacy openrouter_compat_mode (bool/number/string)
@@ -164,6 +165,15 @@ impl ClaudeAdapter {
                 log::debug!("[Claude] 使用 OPENAI_API_KEY");
                 return Some(key.to_string());
             }
+            // Gemini key (用于 gemini 格式)
+            if let Some(key) = env
+                .get("GEMINI_API_KEY")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+            {
+                log::debug!("[Claude] 使用 GEMINI_API_KEY");
+                return Some(key.to_string());
+            }
         }
 
         // 尝试直接获取
@@ -183,6 +193,15 @@ impl ClaudeAdapter {
     }
 }
 
+/// 归一化 api_format，未知值按 anthropic 处理
+fn normalize_api_format(api_format: &str) -> &'static str {
+    match api_format {
+        "openai_chat" => "openai_chat",
+        "gemini" => "gemini",
+        _ => "anthropic",
+    }
+}
+
 impl Default for ClaudeAdapter {
     fn default() -> Self {
         Self::new()
@@ -237,6 +256,14 @@ impl ProviderAdapter for ClaudeAdapter {
         let strategy = match provider_type {
             ProviderType::OpenRouter => AuthStrategy::Bearer,
             ProviderType::ClaudeAuth => AuthStrategy::ClaudeAuth,
+            ProviderType::Gemini => {
+                // Gemini 格式：API Key 或 OAuth access_token（与 GeminiAdapter 规则一致）
+                let key = self.extract_key(provider)?;
+                return match GeminiAdapter::new().parse_oauth_credentials(&key) {
+                    Some(creds) => Some(AuthInfo::with_access_token(key, creds.access_token)),
+                    None => Some(AuthInfo::new(key, AuthStrategy::Google)),
+                };
+            }
             _ => AuthStrategy::Anthropic,
         };
 
@@ -262,6 +289,10 @@ impl ProviderAdapter for ClaudeAdapter {
         while base.contains("/v1/v1") {
             base = base.replace("/v1/v1", "/v1");
         }
+        // gemini 格式：base_url 可能已带 /v1beta
+        while base.contains("/v1beta/v1beta") {
+            base = base.replace("/v1beta/v1beta", "/v1beta");
+        }
 
         // 为 Claude 相关端点添加 ?beta=true 参数
         // 这是某些上游服务（如 DuckCoding）验证请求来源的关键参数
@@ -291,7 +322,10 @@ impl ProviderAdapter for ClaudeAdapter {
             AuthStrategy::Bearer => {
                 request.header("Authorization", format!("Bearer {}", auth.api_key))
             }
-            _ => request,
+            // gemini 格式: x-goog-api-key 或 OAuth Bearer
+            AuthStrategy::Google | AuthStrategy::GoogleOAuth => {
+                GeminiAdapter::new().add_auth_headers(request, auth)
+            }
         }
     }
 
@@ -299,15 +333,47 @@ impl ProviderAdapter for ClaudeAdapter {
         // 根据 api_format 配置决定是否需要格式转换
         // - "anthropic" (默认): 直接透传，无需转换
         // - "openai_chat": 需要 Anthropic ↔ OpenAI 格式转换
-        self.get_api_format(provider) == "openai_chat"
+        // - "gemini": 需要 Anthropic ↔ Gemini 格式转换
+        self.get_api_format(provider) != "anthropic"
+    }
+
+    fn transform_endpoint(&self, endpoint: &str, body: &Value, provider: &Provider) -> String {
+        if endpoint != "/v1/messages" {
+            return endpoint.to_string();
+        }
+
+        match self.get_api_format(provider) {
+            "openai_chat" => "/v1/chat/completions".to_string(),
+            "gemini" => {
+                // Gemini 的模型名与流式开关都在 URL 中
+                let model = body
+                    .get("model")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("")
+                    .trim_start_matches("models/");
+                let is_stream = body
+                    .get("stream")
+                    .and_then(|s| s.as_bool())
+                    .unwrap_or(false);
+                if is_stream {
+                    format!("/v1beta/models/{model}:streamGenerateContent?alt=sse")
+                } else {
+                    format!("/v1beta/models/{model}:generateContent")
+                }
+            }
+            _ => endpoint.to_string(),
+        }
     }
 
     fn transform_request(
         &self,
         body: serde_json::Value,
-        _provider: &Provider,
+        provider: &Provider,
     ) -> Result<serde_json::Value, ProxyError> {
-        super::transform::anthropic_to_openai(body)
+        match self.get_api_format(provider) {
+            "gemini" => super::transform_gemini::anthropic_to_gemini(body),
+            _ => super::transform::anthropic_to_openai(body),
+        }
     }
 
     fn transform_response(&self, body: serde_json::Value) -> Result<serde_json::Value, ProxyError> {
@@ -617,4 +683,53 @@ mod tests {
         );
         assert!(!adapter.needs_transform(&unknown_format));
     }
+
+    #[test]
+    fn test_gemini_api_format() {
+        let adapter = ClaudeAdapter::new();
+        let provider = create_provider_with_meta(
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://generativelanguage.googleapis.com/v1beta",
+                    "ANTHROPIC_AUTH_TOKEN": "AIza-test-key"
+                }
+            }),
+            ProviderMeta {
+                api_format: Some("gemini".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(adapter.needs_transform(&provider));
+        assert_eq!(adapter.provider_type(&provider), ProviderType::Gemini);
+
+        let auth = adapter.extract_auth(&provider).unwrap();
+        assert_eq!(auth.strategy, AuthStrategy::Google);
+        assert_eq!(auth.api_key, "AIza-test-key");
+
+        let body = json!({"model": "gemini-2.5-pro", "stream": true});
+        let endpoint = adapter.transform_endpoint("/v1/messages", &body, &provider);
+        assert_eq!(
+            endpoint,
+            "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+        assert_eq!(
+            adapter.build_url(
+                "https://generativelanguage.googleapis.com/v1beta",
+                &endpoint
+            ),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+
+        let body = json!({"model": "models/gemini-2.5-flash"});
+        assert_eq!(
+            adapter.transform_endpoint("/v1/messages", &body, &provider),
+            "/v1beta/models/gemini-2.5-flash:generateContent"
+        );
+        // 其他端点不改写
+        assert_eq!(
+            adapter.transform_endpoint("/v1/messages/count_tokens", &body, &provider),
+            "/v1/messages/count_tokens"
+        );
+    }
 }
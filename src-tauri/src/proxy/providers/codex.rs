@@ -1,6 +1,7 @@
 //! Codex (OpenAI) Provider Adapter
 //!
-//! 仅透传模式，支持直连 OpenAI API
+//! 默认透传模式，支持直连 OpenAI API；
+//! `apiFormat = "openai_chat"` 时把 Responses API 请求桥接到 Chat Completions 上游
 //!
 //! ## 客户端检测
 //! 支持检测官方 Codex 客户端 (codex_vscode, codex_cli_rs)
@@ -10,6 +11,7 @@ use crate::provider::Provider;
 use crate::proxy::error::ProxyError;
 use regex::Regex;
 use reqwest::RequestBuilder;
+use serde_json::Value;
 use std::sync::LazyLock;
 
 /// 官方 Codex 客户端 User-Agent 正则
@@ -33,6 +35,26 @@ impl CodexAdapter {
         CODEX_CLIENT_REGEX.is_match(user_agent)
     }
 
+    /// 上游是否只支持 Chat Completions（需要 Responses ↔ Chat 转换）
+    ///
+    /// 从 provider.meta.api_format 读取，兼容旧版 settings_config.api_format
+    pub(crate) fn is_chat_format(&self, provider: &Provider) -> bool {
+        let api_format = provider
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.api_format.as_deref())
+            .or_else(|| {
+                provider
+                    .settings_config
+                    .get("api_format")
+                    .and_then(|v| v.as_str())
+            });
+        matches!(
+            api_format,
+            Some("openai_chat") | Some("openai") | Some("chat")
+        )
+    }
+
     /// 从 Provider 配置中提取 API Key
     fn extract_key(&self, provider: &Provider) -> Option<String> {
         // 1. 尝试从 env 中获取
@@ -177,6 +199,25 @@ impl ProviderAdapter for CodexAdapter {
     fn add_auth_headers(&self, request: RequestBuilder, auth: &AuthInfo) -> RequestBuilder {
         request.header("Authorization", format!("Bearer {}", auth.api_key))
     }
+
+    fn needs_transform(&self, provider: &Provider) -> bool {
+        self.is_chat_format(provider)
+    }
+
+    fn transform_endpoint(&self, endpoint: &str, _body: &Value, provider: &Provider) -> String {
+        match endpoint.strip_suffix("/responses") {
+            Some(prefix) if self.is_chat_format(provider) => format!("{prefix}/chat/completions"),
+            _ => endpoint.to_string(),
+        }
+    }
+
+    fn transform_request(&self, body: Value, _provider: &Provider) -> Result<Value, ProxyError> {
+        super::transform_responses::responses_to_chat(body)
+    }
+
+    fn transform_response(&self, body: Value) -> Result<Value, ProxyError> {
+        super::transform_responses::chat_to_responses(body, &Default::default())
+    }
 }
 
 #[cfg(test)]
@@ -201,6 +242,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_openai_chat_format_transform() {
+        let adapter = CodexAdapter::new();
+        let mut provider = create_provider(json!({
+            "base_url": "https://api.deepseek.com/v1"
+        }));
+        assert!(!adapter.needs_transform(&provider));
+        assert_eq!(
+            adapter.transform_endpoint("/responses", &json!({}), &provider),
+            "/responses"
+        );
+
+        provider.meta = Some(crate::provider::ProviderMeta {
+            api_format: Some("openai_chat".to_string()),
+            ..Default::default()
+        });
+        assert!(adapter.needs_transform(&provider));
+        assert_eq!(
+            adapter.transform_endpoint("/responses", &json!({}), &provider),
+            "/chat/completions"
+        );
+        assert_eq!(
+            adapter.build_url(
+                "https://api.deepseek.com/v1",
+                &adapter.transform_endpoint("/responses", &json!({}), &provider)
+            ),
+            "https://api.deepseek.com/v1/chat/completions"
+        );
+    }
+
     #[test]
     fn test_extract_base_url_direct() {
         let adapter = CodexAdapter::new();
@@ -9,7 +9,9 @@
 //! - `codex`: Codex (OpenAI) 适配器
 //! - `gemini`: Gemini (Google) 适配器
 //! - `models`: API 数据模型
-//! - `transform`: 格式转换
+//! - `transform`: 格式转换（Anthropic ↔ OpenAI Chat）
+//! - `transform_gemini`: 格式转换（Anthropic ↔ Gemini）
+//! - `transform_responses`: 格式转换（Responses ↔ OpenAI Chat，Codex 使用）
 
 mod adapter;
 mod auth;
@@ -18,7 +20,11 @@ mod codex;
 mod gemini;
 pub mod models;
 pub mod streaming;
+pub mod streaming_gemini;
+pub mod streaming_responses;
 pub mod transform;
+pub mod transform_gemini;
+pub mod transform_responses;
 
 use crate::app_config::AppType;
 use crate::provider::Provider;
@@ -85,8 +91,12 @@ impl ProviderType {
     pub fn from_app_type_and_config(app_type: &AppType, provider: &Provider) -> Self {
         match app_type {
             AppType::Claude => {
-                // 检测是否为 OpenRouter
                 let adapter = ClaudeAdapter::new();
+                // 检测是否为 Gemini 格式（Anthropic ↔ Gemini 转换）
+                if adapter.get_api_format(provider) == "gemini" {
+                    return ProviderType::Gemini;
+                }
+                // 检测是否为 OpenRouter
                 if let Ok(base_url) = adapter.extract_base_url(provider) {
                     if base_url.contains("openrouter.ai") {
                         return ProviderType::OpenRouter;
@@ -1,65 +1,355 @@
 //! 流式响应转换模块
 //!
 //! 实现 OpenAI SSE → Anthropic SSE 格式转换
+//!
+//! - 按行缓冲字节，跨 chunk 截断的 UTF-8 字符与 SSE 行都能正确拼接
+//! - `reasoning_content` / `reasoning` → thinking 块，`content` → text 块
+//! - 按 OpenAI `index` 跟踪并行工具调用，每个 index 只开启一个 tool_use 块
+//! - `message_delta` 延迟到 usage chunk / `[DONE]` / 流结束时发送，以携带完整 usage
 
+use super::transform::{convert_usage, map_finish_reason, reasoning_text};
 use bytes::Bytes;
 use futures::stream::{Stream, StreamExt};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-
-/// OpenAI 流式响应数据结构
-#[derive(Debug, Deserialize)]
-struct OpenAIStreamChunk {
-    id: String,
-    model: String,
-    choices: Vec<StreamChoice>,
-    #[serde(default)]
-    usage: Option<Usage>,
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// 当前打开的内容块类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Thinking,
+    Text,
+    /// 对应 OpenAI tool_calls 的 index
+    Tool(u64),
 }
 
-#[derive(Debug, Deserialize)]
-struct StreamChoice {
-    delta: Delta,
-    #[serde(default)]
+/// OpenAI Chat Completions SSE → Anthropic Messages SSE 状态机
+#[derive(Debug, Default)]
+pub(crate) struct AnthropicStreamConverter {
+    line_buffer: Vec<u8>,
+    started: bool,
+    /// 下一个 Anthropic 内容块的 index
+    next_index: u32,
+    open_block: Option<(BlockKind, u32)>,
+    seen_tool_indexes: HashSet<u64>,
     finish_reason: Option<String>,
+    usage: Option<Value>,
+    message_delta_sent: bool,
+    stopped: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct Delta {
-    #[serde(default)]
-    content: Option<String>,
-    #[serde(default)]
-    reasoning: Option<String>, // OpenRouter 的推理内容
-    #[serde(default)]
-    tool_calls: Option<Vec<DeltaToolCall>>,
-}
+impl AnthropicStreamConverter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否已发送 message_stop 或 error，之后的上游数据都将被忽略
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// 处理一段上游字节，返回转换后的 Anthropic SSE 事件
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) -> Vec<Bytes> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.line_buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.process_line(line.trim_end_matches(['\r', '\n']), &mut events);
+        }
+
+        events.into_iter().map(to_sse).collect()
+    }
+
+    /// 上游流结束：处理残留数据并补齐 content_block_stop / message_delta / message_stop
+    pub(crate) fn finish(&mut self) -> Vec<Bytes> {
+        let mut events = Vec::new();
+
+        if !self.line_buffer.is_empty() {
+            let rest = std::mem::take(&mut self.line_buffer);
+            let rest = String::from_utf8_lossy(&rest);
+            self.process_line(rest.trim_end_matches(['\r', '\n']), &mut events);
+        }
+        self.finish_message(&mut events);
+
+        events.into_iter().map(to_sse).collect()
+    }
+
+    /// 上游连接出错：输出 Anthropic error 事件并停止
+    pub(crate) fn stream_error(&mut self, message: &str) -> Vec<Bytes> {
+        if self.stopped {
+            return Vec::new();
+        }
+        self.stopped = true;
+        vec![to_sse(error_event("stream_error", message))]
+    }
+
+    fn process_line(&mut self, line: &str, events: &mut Vec<Value>) {
+        if self.stopped {
+            return;
+        }
+        let Some(data) = line.strip_prefix("data:") else {
+            return;
+        };
+        let data = data.trim();
+        if data.is_empty() {
+            return;
+        }
+
+        if data == "[DONE]" {
+            log::debug!("[Claude/OpenRouter] <<< OpenAI SSE: [DONE]");
+            self.finish_message(events);
+            return;
+        }
+
+        match serde_json::from_str::<Value>(data) {
+            Ok(chunk) => self.process_chunk(&chunk, events),
+            Err(e) => log::warn!("[Claude/OpenRouter] 无法解析 SSE chunk: {e}"),
+        }
+    }
+
+    fn process_chunk(&mut self, chunk: &Value, events: &mut Vec<Value>) {
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            let error_type = error
+                .get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or("api_error");
+            events.push(error_event(error_type, &message));
+            self.stopped = true;
+            return;
+        }
+
+        self.ensure_started(chunk, events);
+
+        if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+            self.usage = Some(usage.clone());
+        }
+
+        let Some(choice) = chunk
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+        else {
+            // 仅携带 usage 的尾部 chunk（choices 为空）
+            if self.finish_reason.is_some() && self.usage.is_some() {
+                self.emit_message_delta(events);
+            }
+            return;
+        };
+
+        if let Some(delta) = choice.get("delta") {
+            if let Some(reasoning) = reasoning_text(delta) {
+                self.ensure_block(BlockKind::Thinking, events);
+                events.push(self.block_delta(json!({
+                    "type": "thinking_delta",
+                    "thinking": reasoning
+                })));
+            }
+
+            if let Some(text) = delta
+                .get("content")
+                .and_then(|c| c.as_str())
+                .filter(|s| !s.is_empty())
+            {
+                self.ensure_block(BlockKind::Text, events);
+                events.push(self.block_delta(json!({
+                    "type": "text_delta",
+                    "text": text
+                })));
+            }
+
+            if let Some(tool_calls) = delta.get("tool_calls").and_then(|t| t.as_array()) {
+                for tool_call in tool_calls {
+                    self.process_tool_call(tool_call, events);
+                }
+            }
+        }
+
+        if let Some(finish_reason) = choice.get("finish_reason").and_then(|f| f.as_str()) {
+            self.close_block(events);
+            self.finish_reason = Some(finish_reason.to_string());
+        }
+    }
+
+    fn process_tool_call(&mut self, tool_call: &Value, events: &mut Vec<Value>) {
+        let id = tool_call
+            .get("id")
+            .and_then(|i| i.as_str())
+            .filter(|s| !s.is_empty());
+        let index = match tool_call.get("index").and_then(|i| i.as_u64()) {
+            Some(index) => index,
+            // 部分供应商省略 index：带 id 视为新调用，否则续接当前调用
+            None => match (id, self.open_block) {
+                (None, Some((BlockKind::Tool(open), _))) => open,
+                _ => self.seen_tool_indexes.len() as u64,
+            },
+        };
+        let function = tool_call.get("function");
+
+        let is_open = matches!(self.open_block, Some((BlockKind::Tool(open), _)) if open == index);
+        if !is_open {
+            if self.seen_tool_indexes.contains(&index) {
+                log::warn!("[Claude/OpenRouter] 工具调用 index={index} 的块已关闭，忽略后续参数");
+                return;
+            }
+            self.close_block(events);
+            self.seen_tool_indexes.insert(index);
+
+            let id = id
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("toolu_{}", uuid::Uuid::new_v4().simple()));
+            let name = function
+                .and_then(|f| f.get("name"))
+                .and_then(|n| n.as_str())
+                .unwrap_or("");
+            self.open_block(
+                BlockKind::Tool(index),
+                json!({"type": "tool_use", "id": id, "name": name, "input": {}}),
+                events,
+            );
+        }
+
+        if let Some(arguments) = function
+            .and_then(|f| f.get("arguments"))
+            .and_then(|a| a.as_str())
+            .filter(|s| !s.is_empty())
+        {
+            events.push(self.block_delta(json!({
+                "type": "input_json_delta",
+                "partial_json": arguments
+            })));
+        }
+    }
+
+    fn ensure_started(&mut self, chunk: &Value, events: &mut Vec<Value>) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+
+        let id = chunk
+            .get("id")
+            .and_then(|i| i.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("msg_{}", uuid::Uuid::new_v4().simple()));
+        let model = chunk.get("model").and_then(|m| m.as_str()).unwrap_or("");
 
-#[derive(Debug, Deserialize, Serialize)]
-struct DeltaToolCall {
-    index: usize,
-    #[serde(default)]
-    id: Option<String>,
-    #[serde(rename = "type", default)]
-    call_type: Option<String>,
-    #[serde(default)]
-    function: Option<DeltaFunction>,
+        events.push(json!({
+            "type": "message_start",
+            "message": {
+                "id": id,
+                "type": "message",
+                "role": "assistant",
+                "model": model,
+                "content": [],
+                "stop_reason": null,
+                "stop_sequence": null,
+                "usage": {
+                    "input_tokens": 0,
+                    "output_tokens": 0
+                }
+            }
+        }));
+    }
+
+    fn ensure_block(&mut self, kind: BlockKind, events: &mut Vec<Value>) {
+        if matches!(self.open_block, Some((open, _)) if open == kind) {
+            return;
+        }
+        self.close_block(events);
+        let content_block = match kind {
+            BlockKind::Thinking => json!({"type": "thinking", "thinking": "", "signature": ""}),
+            _ => json!({"type": "text", "text": ""}),
+        };
+        self.open_block(kind, content_block, events);
+    }
+
+    fn open_block(&mut self, kind: BlockKind, content_block: Value, events: &mut Vec<Value>) {
+        let index = self.next_index;
+        self.next_index += 1;
+        self.open_block = Some((kind, index));
+        events.push(json!({
+            "type": "content_block_start",
+            "index": index,
+            "content_block": content_block
+        }));
+    }
+
+    fn close_block(&mut self, events: &mut Vec<Value>) {
+        if let Some((_, index)) = self.open_block.take() {
+            events.push(json!({"type": "content_block_stop", "index": index}));
+        }
+    }
+
+    fn block_delta(&self, delta: Value) -> Value {
+        let index = self.open_block.map(|(_, index)| index).unwrap_or(0);
+        json!({"type": "content_block_delta", "index": index, "delta": delta})
+    }
+
+    fn emit_message_delta(&mut self, events: &mut Vec<Value>) {
+        if self.message_delta_sent {
+            return;
+        }
+        self.message_delta_sent = true;
+
+        let stop_reason = if !self.seen_tool_indexes.is_empty() {
+            "tool_use"
+        } else {
+            map_finish_reason(self.finish_reason.as_deref().unwrap_or("stop"))
+        };
+        let usage = self
+            .usage
+            .as_ref()
+            .map(convert_usage)
+            .unwrap_or_else(|| json!({"output_tokens": 0}));
+
+        events.push(json!({
+            "type": "message_delta",
+            "delta": {
+                "stop_reason": stop_reason,
+                "stop_sequence": null
+            },
+            "usage": usage
+        }));
+    }
+
+    fn finish_message(&mut self, events: &mut Vec<Value>) {
+        if self.stopped || !self.started {
+            return;
+        }
+        self.close_block(events);
+        self.emit_message_delta(events);
+        events.push(json!({"type": "message_stop"}));
+        self.stopped = true;
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct DeltaFunction {
-    #[serde(default)]
-    name: Option<String>,
-    #[serde(default)]
-    arguments: Option<String>,
+fn error_event(error_type: &str, message: &str) -> Value {
+    json!({
+        "type": "error",
+        "error": {
+            "type": error_type,
+            "message": message
+        }
+    })
 }
 
-/// OpenAI 流式响应的 usage 信息（完整版）
-#[derive(Debug, Deserialize)]
-struct Usage {
-    #[serde(default)]
-    prompt_tokens: u32,
-    #[serde(default)]
-    completion_tokens: u32,
+fn to_sse(event: Value) -> Bytes {
+    let event_type = event
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("message")
+        .to_string();
+    log::debug!("[Claude/OpenRouter] >>> Anthropic SSE: {event_type}");
+    Bytes::from(format!(
+        "event: {event_type}\ndata: {}\n\n",
+        serde_json::to_string(&event).unwrap_or_default()
+    ))
 }
 
 /// 创建 Anthropic SSE 流
@@ -67,262 +357,218 @@ pub fn create_anthropic_sse_stream(
     stream: impl Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
 ) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
     async_stream::stream! {
-        let mut buffer = String::new();
-        let mut message_id = None;
-        let mut current_model = None;
-        let mut content_index = 0;
-        let mut has_sent_message_start = false;
-        let mut current_block_type: Option<String> = None;
-        let mut tool_call_id = None;
+        let mut converter = AnthropicStreamConverter::new();
 
         tokio::pin!(stream);
 
         while let Some(chunk) = stream.next().await {
             match chunk {
                 Ok(bytes) => {
-                    let text = String::from_utf8_lossy(&bytes);
-                    buffer.push_str(&text);
-
-                    while let Some(pos) = buffer.find("\n\n") {
-                        let line = buffer[..pos].to_string();
-                        buffer = buffer[pos + 2..].to_string();
-
-                        if line.trim().is_empty() {
-                            continue;
-                        }
-
-                        for l in line.lines() {
-                            if let Some(data) = l.strip_prefix("data: ") {
-                                if data.trim() == "[DONE]" {
-                                    log::debug!("[Claude/OpenRouter] <<< OpenAI SSE: [DONE]");
-                                    let event = json!({"type": "message_stop"});
-                                    let sse_data = format!("event: message_stop\ndata: {}\n\n",
-                                        serde_json::to_string(&event).unwrap_or_default());
-                                    log::debug!("[Claude/OpenRouter] >>> Anthropic SSE: message_stop");
-                                    yield Ok(Bytes::from(sse_data));
-                                    continue;
-                                }
-
-                                if let Ok(chunk) = serde_json::from_str::<OpenAIStreamChunk>(data) {
-                                    log::debug!("[Claude/OpenRouter] <<< SSE chunk received");
-
-                                    if message_id.is_none() {
-                                        message_id = Some(chunk.id.clone());
-                                    }
-                                    if current_model.is_none() {
-                                        current_model = Some(chunk.model.clone());
-                                    }
-
-                                    if let Some(choice) = chunk.choices.first() {
-                                        if !has_sent_message_start {
-                                            let event = json!({
-                                                "type": "message_start",
-                                                "message": {
-                                                    "id": message_id.clone().unwrap_or_default(),
-                                                    "type": "message",
-                                                    "role": "assistant",
-                                                    "model": current_model.clone().unwrap_or_default(),
-                                                    "usage": {
-                                                        "input_tokens": 0,
-                                                        "output_tokens": 0
-                                                    }
-                                                }
-                                            });
-                                            let sse_data = format!("event: message_start\ndata: {}\n\n",
-                                                serde_json::to_string(&event).unwrap_or_default());
-                                            yield Ok(Bytes::from(sse_data));
-                                            has_sent_message_start = true;
-                                        }
-
-                                        // 处理 reasoning（thinking）
-                                        if let Some(reasoning) = &choice.delta.reasoning {
-                                            if current_block_type.is_none() {
-                                                let event = json!({
-                                                    "type": "content_block_start",
-                                                    "index": content_index,
-                                                    "content_block": {
-                                                        "type": "thinking",
-                                                        "thinking": ""
-                                                    }
-                                                });
-                                                let sse_data = format!("event: content_block_start\ndata: {}\n\n",
-                                                    serde_json::to_string(&event).unwrap_or_default());
-                                                yield Ok(Bytes::from(sse_data));
-                                                current_block_type = Some("thinking".to_string());
-                                            }
-
-                                            let event = json!({
-                                                "type": "content_block_delta",
-                                                "index": content_index,
-                                                "delta": {
-                                                    "type": "thinking_delta",
-                                                    "thinking": reasoning
-                                                }
-                                            });
-                                            let sse_data = format!("event: content_block_delta\ndata: {}\n\n",
-                                                serde_json::to_string(&event).unwrap_or_default());
-                                            yield Ok(Bytes::from(sse_data));
-                                        }
-
-                                        // 处理文本内容
-                                        if let Some(content) = &choice.delta.content {
-                                            if !content.is_empty() {
-                                                if current_block_type.as_deref() != Some("text") {
-                                                    if current_block_type.is_some() {
-                                                        let event = json!({
-                                                            "type": "content_block_stop",
-                                                            "index": content_index
-                                                        });
-                                                        let sse_data = format!("event: content_block_stop\ndata: {}\n\n",
-                                                            serde_json::to_string(&event).unwrap_or_default());
-                                                        yield Ok(Bytes::from(sse_data));
-                                                        content_index += 1;
-                                                    }
-
-                                                    let event = json!({
-                                                        "type": "content_block_start",
-                                                        "index": content_index,
-                                                        "content_block": {
-                                                            "type": "text",
-                                                            "text": ""
-                                                        }
-                                                    });
-                                                    let sse_data = format!("event: content_block_start\ndata: {}\n\n",
-                                                        serde_json::to_string(&event).unwrap_or_default());
-                                                    yield Ok(Bytes::from(sse_data));
-                                                    current_block_type = Some("text".to_string());
-                                                }
-
-                                                let event = json!({
-                                                    "type": "content_block_delta",
-                                                    "index": content_index,
-                                                    "delta": {
-                                                        "type": "text_delta",
-                                                        "text": content
-                                                    }
-                                                });
-                                                let sse_data = format!("event: content_block_delta\ndata: {}\n\n",
-                                                    serde_json::to_string(&event).unwrap_or_default());
-                                                yield Ok(Bytes::from(sse_data));
-                                            }
-                                        }
-
-                                        // 处理工具调用
-                                        if let Some(tool_calls) = &choice.delta.tool_calls {
-                                            for tool_call in tool_calls {
-                                                if let Some(id) = &tool_call.id {
-                                                    if current_block_type.is_some() {
-                                                        let event = json!({
-                                                            "type": "content_block_stop",
-                                                            "index": content_index
-                                                        });
-                                                        let sse_data = format!("event: content_block_stop\ndata: {}\n\n",
-                                                            serde_json::to_string(&event).unwrap_or_default());
-                                                        yield Ok(Bytes::from(sse_data));
-                                                        content_index += 1;
-                                                    }
-
-                                                    tool_call_id = Some(id.clone());
-                                                }
-
-                                                if let Some(function) = &tool_call.function {
-                                                    if let Some(name) = &function.name {
-                                                        let event = json!({
-                                                            "type": "content_block_start",
-                                                            "index": content_index,
-                                                            "content_block": {
-                                                                "type": "tool_use",
-                                                                "id": tool_call_id.clone().unwrap_or_default(),
-                                                                "name": name
-                                                            }
-                                                        });
-                                                        let sse_data = format!("event: content_block_start\ndata: {}\n\n",
-                                                            serde_json::to_string(&event).unwrap_or_default());
-                                                        yield Ok(Bytes::from(sse_data));
-                                                        current_block_type = Some("tool_use".to_string());
-                                                    }
-
-                                                    if let Some(args) = &function.arguments {
-                                                        let event = json!({
-                                                            "type": "content_block_delta",
-                                                            "index": content_index,
-                                                            "delta": {
-                                                                "type": "input_json_delta",
-                                                                "partial_json": args
-                                                            }
-                                                        });
-                                                        let sse_data = format!("event: content_block_delta\ndata: {}\n\n",
-                                                            serde_json::to_string(&event).unwrap_or_default());
-                                                        yield Ok(Bytes::from(sse_data));
-                                                    }
-                                                }
-                                            }
-                                        }
-
-                                        // 处理 finish_reason
-                                        if let Some(finish_reason) = &choice.finish_reason {
-                                            if current_block_type.is_some() {
-                                                let event = json!({
-                                                    "type": "content_block_stop",
-                                                    "index": content_index
-                                                });
-                                                let sse_data = format!("event: content_block_stop\ndata: {}\n\n",
-                                                    serde_json::to_string(&event).unwrap_or_default());
-                                                yield Ok(Bytes::from(sse_data));
-                                            }
-
-                                            let stop_reason = map_stop_reason(Some(finish_reason));
-                                            // 构建 usage 信息，包含 input_tokens 和 output_tokens
-                                            let usage_json = chunk.usage.as_ref().map(|u| json!({
-                                                "input_tokens": u.prompt_tokens,
-                                                "output_tokens": u.completion_tokens
-                                            }));
-                                            let event = json!({
-                                                "type": "message_delta",
-                                                "delta": {
-                                                    "stop_reason": stop_reason,
-                                                    "stop_sequence": null
-                                                },
-                                                "usage": usage_json
-                                            });
-                                            let sse_data = format!("event: message_delta\ndata: {}\n\n",
-                                                serde_json::to_string(&event).unwrap_or_default());
-                                            yield Ok(Bytes::from(sse_data));
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                    for event in converter.push_bytes(&bytes) {
+                        yield Ok(event);
+                    }
+                    if converter.is_stopped() {
+                        break;
                     }
                 }
                 Err(e) => {
                     log::error!("Stream error: {e}");
-                    let error_event = json!({
-                        "type": "error",
-                        "error": {
-                            "type": "stream_error",
-                            "message": format!("Stream error: {e}")
-                        }
-                    });
-                    let sse_data = format!("event: error\ndata: {}\n\n",
-                        serde_json::to_string(&error_event).unwrap_or_default());
-                    yield Ok(Bytes::from(sse_data));
+                    for event in converter.stream_error(&format!("Stream error: {e}")) {
+                        yield Ok(event);
+                    }
                     break;
                 }
             }
         }
+
+        for event in converter.finish() {
+            yield Ok(event);
+        }
     }
 }
 
-/// 映射停止原因
-fn map_stop_reason(finish_reason: Option<&str>) -> Option<String> {
-    finish_reason.map(|r| {
-        match r {
-            "tool_calls" => "tool_use",
-            "stop" => "end_turn",
-            "length" => "max_tokens",
-            _ => "end_turn",
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 将上游 SSE 文本（可分多段）转换为 Anthropic 事件列表
+    fn convert(chunks: &[&[u8]]) -> Vec<Value> {
+        let mut converter = AnthropicStreamConverter::new();
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output.extend(converter.push_bytes(chunk));
         }
-        .to_string()
-    })
+        output.extend(converter.finish());
+        output
+            .iter()
+            .map(|b| {
+                let text = std::str::from_utf8(b).unwrap();
+                let data = text.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+                serde_json::from_str(data).unwrap()
+            })
+            .collect()
+    }
+
+    fn types(events: &[Value]) -> Vec<&str> {
+        events.iter().map(|e| e["type"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_text_stream_with_usage_chunk() {
+        let sse = concat!(
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":2,\"prompt_tokens_details\":{\"cached_tokens\":4}}}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(
+            types(&events),
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+        assert_eq!(events[0]["message"]["id"], "chatcmpl-1");
+        assert_eq!(events[0]["message"]["content"], json!([]));
+        assert_eq!(events[3]["delta"]["text"], "lo");
+        assert_eq!(events[5]["delta"]["stop_reason"], "end_turn");
+        assert_eq!(events[5]["usage"]["input_tokens"], 8);
+        assert_eq!(events[5]["usage"]["cache_read_input_tokens"], 4);
+        assert_eq!(events[5]["usage"]["output_tokens"], 2);
+    }
+
+    #[test]
+    fn test_split_utf8_and_data_prefix_without_space() {
+        let sse = "data:{\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"你好\"}}]}\n\n".as_bytes();
+        // 在“你”的 UTF-8 字节中间切开
+        let split = sse.iter().position(|b| *b == 0xE4).unwrap() + 1;
+        let events = convert(&[&sse[..split], &sse[split..]]);
+
+        assert_eq!(events[2]["delta"]["text"], "你好");
+        assert_eq!(types(&events).last(), Some(&"message_stop"));
+        assert_eq!(events[events.len() - 2]["usage"]["output_tokens"], 0);
+    }
+
+    #[test]
+    fn test_reasoning_then_text_blocks() {
+        let sse = concat!(
+            "data: {\"id\":\"c\",\"model\":\"deepseek-reasoner\",\"choices\":[{\"delta\":{\"reasoning_content\":\"hmm\"}}]}\n",
+            "data: {\"id\":\"c\",\"model\":\"deepseek-reasoner\",\"choices\":[{\"delta\":{\"content\":\"ok\"},\"finish_reason\":\"stop\"}]}\n",
+            "data: [DONE]\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(events[1]["content_block"]["type"], "thinking");
+        assert_eq!(events[2]["delta"]["thinking"], "hmm");
+        assert_eq!(events[3], json!({"type": "content_block_stop", "index": 0}));
+        assert_eq!(events[4]["index"], 1);
+        assert_eq!(events[4]["content_block"]["type"], "text");
+    }
+
+    #[test]
+    fn test_parallel_tool_calls_tracked_by_index() {
+        let sse = concat!(
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"Reading\"}}]}\n\n",
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",\"type\":\"function\",\"function\":{\"name\":\"Read\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",\"function\":{\"name\":\"Read\",\"arguments\":\"{\\\"path\\\":\"}}]}}]}\n\n",
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"a\\\"}\"}}]}}]}\n\n",
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":1,\"function\":{\"name\":\"Read\",\"arguments\":\"{}\"}}]}}]}\n\n",
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        let starts: Vec<&Value> = events
+            .iter()
+            .filter(|e| e["type"] == "content_block_start")
+            .collect();
+        assert_eq!(starts.len(), 3);
+        assert_eq!(starts[1]["index"], 1);
+        assert_eq!(starts[1]["content_block"]["id"], "call_a");
+        assert_eq!(starts[1]["content_block"]["name"], "Read");
+        assert_eq!(starts[2]["index"], 2);
+        assert!(starts[2]["content_block"]["id"]
+            .as_str()
+            .unwrap()
+            .starts_with("toolu_"));
+
+        let args: String = events
+            .iter()
+            .filter(|e| e["delta"]["type"] == "input_json_delta" && e["index"] == 1)
+            .map(|e| e["delta"]["partial_json"].as_str().unwrap())
+            .collect();
+        assert_eq!(args, "{\"path\":\"a\"}");
+
+        let message_delta = events
+            .iter()
+            .find(|e| e["type"] == "message_delta")
+            .unwrap();
+        assert_eq!(message_delta["delta"]["stop_reason"], "tool_use");
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e["type"] == "message_stop")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_stream_end_without_done_finishes_message() {
+        let sse = "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"x\"},\"finish_reason\":\"length\"}]}";
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(
+            types(&events)[types(&events).len() - 2..],
+            ["message_delta", "message_stop"]
+        );
+        assert_eq!(
+            events[events.len() - 2]["delta"]["stop_reason"],
+            "max_tokens"
+        );
+    }
+
+    #[test]
+    fn test_upstream_error_chunk() {
+        let sse = concat!(
+            "data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"x\"}}]}\n\n",
+            "data: {\"error\":{\"message\":\"overloaded\",\"type\":\"server_error\"}}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        let last = events.last().unwrap();
+        assert_eq!(last["type"], "error");
+        assert_eq!(last["error"]["message"], "overloaded");
+        assert!(!types(&events).contains(&"message_stop"));
+    }
+
+    #[test]
+    fn test_create_anthropic_sse_stream() {
+        let chunks = vec![
+            Ok::<Bytes, reqwest::Error>(Bytes::from_static(
+                b"data: {\"id\":\"c\",\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"hi\"},\"finish_reason\":\"stop\"}]}\n\ndata: [DO",
+            )),
+            Ok(Bytes::from_static(b"NE]\n\n")),
+        ];
+        let output: Vec<Bytes> = futures::executor::block_on(
+            create_anthropic_sse_stream(futures::stream::iter(chunks))
+                .map(|r| r.unwrap())
+                .collect(),
+        );
+        let text: String = output
+            .iter()
+            .map(|b| String::from_utf8_lossy(b).into_owned())
+            .collect();
+
+        assert!(text.starts_with("event: message_start\n"));
+        assert_eq!(text.matches("event: message_stop\n").count(), 1);
+    }
 }
@@ -0,0 +1,477 @@
+//! Gemini 流式响应转换模块
+//!
+//! 实现 Gemini `streamGenerateContent?alt=sse` → Anthropic SSE 格式转换
+//!
+//! - Gemini 每个 chunk 都是完整的 GenerateContentResponse，没有 `[DONE]`，以流结束为准
+//! - `thought: true` 的 part → thinking 块，thoughtSignature → signature_delta
+//! - functionCall 一次性给出完整参数，直接输出完整的 tool_use 块
+
+use super::transform_gemini::{convert_usage, map_finish_reason, tool_use_id};
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+/// 当前打开的内容块类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Thinking,
+    Text,
+}
+
+/// Gemini SSE → Anthropic Messages SSE 状态机
+#[derive(Debug, Default)]
+pub(crate) struct GeminiStreamConverter {
+    line_buffer: Vec<u8>,
+    started: bool,
+    /// 下一个 Anthropic 内容块的 index
+    next_index: u32,
+    open_block: Option<(BlockKind, u32)>,
+    has_tool_use: bool,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+    stopped: bool,
+}
+
+impl GeminiStreamConverter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否已发送 message_stop 或 error
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// 处理一段上游字节，返回转换后的 Anthropic SSE 事件
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) -> Vec<Bytes> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.line_buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.process_line(line.trim_end_matches(['\r', '\n']), &mut events);
+        }
+
+        events.into_iter().map(to_sse).collect()
+    }
+
+    /// 上游流结束：补齐 content_block_stop / message_delta / message_stop
+    pub(crate) fn finish(&mut self) -> Vec<Bytes> {
+        let mut events = Vec::new();
+
+        if !self.line_buffer.is_empty() {
+            let rest = std::mem::take(&mut self.line_buffer);
+            let rest = String::from_utf8_lossy(&rest);
+            self.process_line(rest.trim_end_matches(['\r', '\n']), &mut events);
+        }
+
+        if !self.stopped && self.started {
+            self.close_block(&mut events);
+
+            let stop_reason = if self.has_tool_use {
+                "tool_use"
+            } else {
+                map_finish_reason(self.finish_reason.as_deref().unwrap_or("STOP"))
+            };
+            let usage = self
+                .usage
+                .as_ref()
+                .map(convert_usage)
+                .unwrap_or_else(|| json!({"output_tokens": 0}));
+            events.push(json!({
+                "type": "message_delta",
+                "delta": {
+                    "stop_reason": stop_reason,
+                    "stop_sequence": null
+                },
+                "usage": usage
+            }));
+            events.push(json!({"type": "message_stop"}));
+            self.stopped = true;
+        }
+
+        events.into_iter().map(to_sse).collect()
+    }
+
+    /// 上游连接出错：输出 Anthropic error 事件并停止
+    pub(crate) fn stream_error(&mut self, message: &str) -> Vec<Bytes> {
+        if self.stopped {
+            return Vec::new();
+        }
+        self.stopped = true;
+        vec![to_sse(error_event("stream_error", message))]
+    }
+
+    fn process_line(&mut self, line: &str, events: &mut Vec<Value>) {
+        if self.stopped {
+            return;
+        }
+        let Some(data) = line.strip_prefix("data:") else {
+            return;
+        };
+        let data = data.trim();
+        if data.is_empty() {
+            return;
+        }
+
+        match serde_json::from_str::<Value>(data) {
+            Ok(chunk) => self.process_chunk(&chunk, events),
+            Err(e) => log::warn!("[Claude/Gemini] 无法解析 SSE chunk: {e}"),
+        }
+    }
+
+    fn process_chunk(&mut self, chunk: &Value, events: &mut Vec<Value>) {
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            events.push(error_event("api_error", &message));
+            self.stopped = true;
+            return;
+        }
+
+        self.ensure_started(chunk, events);
+
+        if let Some(usage) = chunk.get("usageMetadata").filter(|u| u.is_object()) {
+            self.usage = Some(usage.clone());
+        }
+
+        let Some(candidate) = chunk
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+        else {
+            if chunk.get("promptFeedback").is_some() {
+                self.finish_reason = Some("SAFETY".to_string());
+            }
+            return;
+        };
+
+        if let Some(parts) = candidate
+            .get("content")
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.as_array())
+        {
+            for part in parts {
+                self.process_part(part, events);
+            }
+        }
+
+        if let Some(finish_reason) = candidate.get("finishReason").and_then(|f| f.as_str()) {
+            self.finish_reason = Some(finish_reason.to_string());
+        }
+    }
+
+    fn process_part(&mut self, part: &Value, events: &mut Vec<Value>) {
+        let signature = part
+            .get("thoughtSignature")
+            .and_then(|s| s.as_str())
+            .filter(|s| !s.is_empty());
+        let text = part.get("text").and_then(|t| t.as_str());
+
+        if part.get("thought").and_then(|t| t.as_bool()) == Some(true) {
+            self.ensure_block(BlockKind::Thinking, events);
+            if let Some(text) = text.filter(|t| !t.is_empty()) {
+                events.push(self.block_delta(json!({"type": "thinking_delta", "thinking": text})));
+            }
+            if let Some(signature) = signature {
+                events.push(
+                    self.block_delta(json!({"type": "signature_delta", "signature": signature})),
+                );
+            }
+            return;
+        }
+
+        // 非思考 part 上的签名：写入前一个 thinking 块，没有则补一个空 thinking 块
+        if let Some(signature) = signature {
+            self.ensure_block(BlockKind::Thinking, events);
+            events
+                .push(self.block_delta(json!({"type": "signature_delta", "signature": signature})));
+            self.close_block(events);
+        }
+
+        if let Some(text) = text {
+            if !text.is_empty() {
+                self.ensure_block(BlockKind::Text, events);
+                events.push(self.block_delta(json!({"type": "text_delta", "text": text})));
+            }
+        } else if let Some(function_call) = part.get("functionCall") {
+            self.close_block(events);
+            self.has_tool_use = true;
+
+            let index = self.next_index;
+            self.next_index += 1;
+            let args = function_call.get("args").cloned().unwrap_or(json!({}));
+            events.push(json!({
+                "type": "content_block_start",
+                "index": index,
+                "content_block": {
+                    "type": "tool_use",
+                    "id": tool_use_id(function_call),
+                    "name": function_call.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                    "input": {}
+                }
+            }));
+            events.push(json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": {
+                    "type": "input_json_delta",
+                    "partial_json": serde_json::to_string(&args).unwrap_or_default()
+                }
+            }));
+            events.push(json!({"type": "content_block_stop", "index": index}));
+        }
+    }
+
+    fn ensure_started(&mut self, chunk: &Value, events: &mut Vec<Value>) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+
+        let id = chunk
+            .get("responseId")
+            .and_then(|i| i.as_str())
+            .map(|id| format!("msg_{id}"))
+            .unwrap_or_else(|| format!("msg_{}", uuid::Uuid::new_v4().simple()));
+        let model = chunk
+            .get("modelVersion")
+            .and_then(|m| m.as_str())
+            .unwrap_or("");
+
+        events.push(json!({
+            "type": "message_start",
+            "message": {
+                "id": id,
+                "type": "message",
+                "role": "assistant",
+                "model": model,
+                "content": [],
+                "stop_reason": null,
+                "stop_sequence": null,
+                "usage": {
+                    "input_tokens": 0,
+                    "output_tokens": 0
+                }
+            }
+        }));
+    }
+
+    fn ensure_block(&mut self, kind: BlockKind, events: &mut Vec<Value>) {
+        if matches!(self.open_block, Some((open, _)) if open == kind) {
+            return;
+        }
+        self.close_block(events);
+
+        let index = self.next_index;
+        self.next_index += 1;
+        self.open_block = Some((kind, index));
+        let content_block = match kind {
+            BlockKind::Thinking => json!({"type": "thinking", "thinking": "", "signature": ""}),
+            BlockKind::Text => json!({"type": "text", "text": ""}),
+        };
+        events.push(json!({
+            "type": "content_block_start",
+            "index": index,
+            "content_block": content_block
+        }));
+    }
+
+    fn close_block(&mut self, events: &mut Vec<Value>) {
+        if let Some((_, index)) = self.open_block.take() {
+            events.push(json!({"type": "content_block_stop", "index": index}));
+        }
+    }
+
+    fn block_delta(&self, delta: Value) -> Value {
+        let index = self.open_block.map(|(_, index)| index).unwrap_or(0);
+        json!({"type": "content_block_delta", "index": index, "delta": delta})
+    }
+}
+
+fn error_event(error_type: &str, message: &str) -> Value {
+    json!({
+        "type": "error",
+        "error": {
+            "type": error_type,
+            "message": message
+        }
+    })
+}
+
+fn to_sse(event: Value) -> Bytes {
+    let event_type = event
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("message")
+        .to_string();
+    log::debug!("[Claude/Gemini] >>> Anthropic SSE: {event_type}");
+    Bytes::from(format!(
+        "event: {event_type}\ndata: {}\n\n",
+        serde_json::to_string(&event).unwrap_or_default()
+    ))
+}
+
+/// 创建 Anthropic SSE 流（上游为 Gemini SSE）
+pub fn create_anthropic_sse_stream_from_gemini(
+    stream: impl Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut converter = GeminiStreamConverter::new();
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    for event in converter.push_bytes(&bytes) {
+                        yield Ok(event);
+                    }
+                    if converter.is_stopped() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    log::error!("Stream error: {e}");
+                    for event in converter.stream_error(&format!("Stream error: {e}")) {
+                        yield Ok(event);
+                    }
+                    break;
+                }
+            }
+        }
+
+        for event in converter.finish() {
+            yield Ok(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(chunks: &[&[u8]]) -> Vec<Value> {
+        let mut converter = GeminiStreamConverter::new();
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output.extend(converter.push_bytes(chunk));
+        }
+        output.extend(converter.finish());
+        output
+            .iter()
+            .map(|b| {
+                let text = std::str::from_utf8(b).unwrap();
+                let data = text.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+                serde_json::from_str(data).unwrap()
+            })
+            .collect()
+    }
+
+    fn types(events: &[Value]) -> Vec<&str> {
+        events.iter().map(|e| e["type"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_text_stream() {
+        let sse = concat!(
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hel\"}]}}],\"modelVersion\":\"gemini-2.5-flash\",\"responseId\":\"r1\"}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"lo\"}]},\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":10,\"candidatesTokenCount\":3}}\r\n\r\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(
+            types(&events),
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+        assert_eq!(events[0]["message"]["id"], "msg_r1");
+        assert_eq!(events[0]["message"]["model"], "gemini-2.5-flash");
+        assert_eq!(events[5]["delta"]["stop_reason"], "end_turn");
+        assert_eq!(
+            events[5]["usage"],
+            json!({"input_tokens": 10, "output_tokens": 3})
+        );
+    }
+
+    #[test]
+    fn test_thoughts_and_function_calls() {
+        let sse = concat!(
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"plan\",\"thought\":true}]}}]}\n\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"functionCall\":{\"name\":\"Read\",\"args\":{\"path\":\"a\"}},\"thoughtSignature\":\"sig\"},{\"functionCall\":{\"name\":\"Bash\",\"args\":{}}}]},\"finishReason\":\"STOP\"}]}\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(events[1]["content_block"]["type"], "thinking");
+        assert_eq!(events[2]["delta"]["thinking"], "plan");
+        assert_eq!(
+            events[3]["delta"],
+            json!({"type": "signature_delta", "signature": "sig"})
+        );
+        assert_eq!(events[3]["index"], 0);
+
+        let tool_starts: Vec<&Value> = events
+            .iter()
+            .filter(|e| e["content_block"]["type"] == "tool_use")
+            .collect();
+        assert_eq!(tool_starts.len(), 2);
+        assert_eq!(tool_starts[0]["index"], 1);
+        assert_eq!(tool_starts[0]["content_block"]["name"], "Read");
+        assert_eq!(tool_starts[1]["index"], 2);
+
+        let args = events
+            .iter()
+            .find(|e| e["delta"]["type"] == "input_json_delta")
+            .unwrap();
+        assert_eq!(args["delta"]["partial_json"], "{\"path\":\"a\"}");
+
+        let message_delta = events
+            .iter()
+            .find(|e| e["type"] == "message_delta")
+            .unwrap();
+        assert_eq!(message_delta["delta"]["stop_reason"], "tool_use");
+    }
+
+    #[test]
+    fn test_error_chunk() {
+        let sse = "data: {\"error\":{\"code\":429,\"message\":\"quota exceeded\"}}\n\n";
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(types(&events), vec!["error"]);
+        assert_eq!(events[0]["error"]["message"], "quota exceeded");
+    }
+
+    #[test]
+    fn test_create_anthropic_sse_stream_from_gemini() {
+        let chunks = vec![
+            Ok::<Bytes, reqwest::Error>(Bytes::from_static(
+                b"data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"hi\"}]},\"finishReason\":\"MAX_",
+            )),
+            Ok(Bytes::from_static(b"TOKENS\"}]}\r\n\r\n")),
+        ];
+        let output: Vec<Bytes> = futures::executor::block_on(
+            create_anthropic_sse_stream_from_gemini(futures::stream::iter(chunks))
+                .map(|r| r.unwrap())
+                .collect(),
+        );
+        let text: String = output
+            .iter()
+            .map(|b| String::from_utf8_lossy(b).into_owned())
+            .collect();
+
+        assert!(text.starts_with("event: message_start\n"));
+        assert!(text.contains("\"stop_reason\":\"max_tokens\""));
+        assert_eq!(text.matches("event: message_stop\n").count(), 1);
+    }
+}
@@ -0,0 +1,727 @@
+//! Responses 流式转换模块
+//!
+//! 实现 OpenAI Chat Completions SSE → Responses API SSE 格式转换（Codex `apiFormat = "openai_chat"`）
+//!
+//! - 每个事件都带递增的 `sequence_number`
+//! - `reasoning_content` / `reasoning` → reasoning item（summary_text），`content` → message item
+//! - 按 Chat `index` 跟踪并行工具调用，每个调用对应一个 function_call / custom_tool_call item
+//! - `response.completed` 延迟到 `[DONE]` / 流结束时发送，以携带完整 usage 与全部 output items
+
+use super::transform::reasoning_text;
+use super::transform_responses::{
+    build_response, convert_usage, incomplete_reason, new_id, remember_response, tool_call_item,
+};
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+
+/// 正在输出的文本类 item（reasoning 或 message）
+#[derive(Debug)]
+struct OpenItem {
+    output_index: usize,
+    item_id: String,
+    text: String,
+}
+
+/// 正在输出的工具调用 item
+#[derive(Debug)]
+struct ToolItem {
+    output_index: usize,
+    item_id: String,
+    call_id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Chat Completions SSE → Responses SSE 状态机
+#[derive(Debug)]
+pub(crate) struct ResponsesStreamConverter {
+    line_buffer: Vec<u8>,
+    response_id: String,
+    model: String,
+    created_at: u64,
+    sequence_number: u64,
+    started: bool,
+    /// 已完成的 output items，按 output_index 排列（未完成的位置为 None）
+    output: Vec<Option<Value>>,
+    reasoning: Option<OpenItem>,
+    message: Option<OpenItem>,
+    /// Chat tool_calls index → 工具调用
+    tools: BTreeMap<u64, ToolItem>,
+    custom_tools: HashSet<String>,
+    /// 本次请求的完整 input items（需要保存会话时才有值）
+    history_input: Option<Vec<Value>>,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+    stopped: bool,
+}
+
+impl ResponsesStreamConverter {
+    pub(crate) fn new(custom_tools: HashSet<String>, history_input: Option<Vec<Value>>) -> Self {
+        Self {
+            line_buffer: Vec::new(),
+            response_id: new_id("resp"),
+            model: String::new(),
+            created_at: chrono::Utc::now().timestamp() as u64,
+            sequence_number: 0,
+            started: false,
+            output: Vec::new(),
+            reasoning: None,
+            message: None,
+            tools: BTreeMap::new(),
+            custom_tools,
+            history_input,
+            finish_reason: None,
+            usage: None,
+            stopped: false,
+        }
+    }
+
+    /// 是否已发送终止事件，之后的上游数据都将被忽略
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// 处理一段上游字节，返回转换后的 Responses SSE 事件
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) -> Vec<Bytes> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.line_buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.process_line(line.trim_end_matches(['\r', '\n']), &mut events);
+        }
+
+        self.serialize(events)
+    }
+
+    /// 上游流结束：处理残留数据并补齐各 item 的 done 事件与 response.completed
+    pub(crate) fn finish(&mut self) -> Vec<Bytes> {
+        let mut events = Vec::new();
+
+        if !self.line_buffer.is_empty() {
+            let rest = std::mem::take(&mut self.line_buffer);
+            let rest = String::from_utf8_lossy(&rest);
+            self.process_line(rest.trim_end_matches(['\r', '\n']), &mut events);
+        }
+        self.finish_response(&mut events);
+
+        self.serialize(events)
+    }
+
+    /// 上游连接出错：输出 response.failed 并停止
+    pub(crate) fn stream_error(&mut self, message: &str) -> Vec<Bytes> {
+        let mut events = Vec::new();
+        self.fail("stream_error", message, &mut events);
+        self.serialize(events)
+    }
+
+    fn process_line(&mut self, line: &str, events: &mut Vec<Value>) {
+        if self.stopped {
+            return;
+        }
+        let Some(data) = line.strip_prefix("data:") else {
+            return;
+        };
+        let data = data.trim();
+        if data.is_empty() {
+            return;
+        }
+
+        if data == "[DONE]" {
+            log::debug!("[Codex/Chat] <<< OpenAI SSE: [DONE]");
+            self.finish_response(events);
+            return;
+        }
+
+        match serde_json::from_str::<Value>(data) {
+            Ok(chunk) => self.process_chunk(&chunk, events),
+            Err(e) => log::warn!("[Codex/Chat] 无法解析 SSE chunk: {e}"),
+        }
+    }
+
+    fn process_chunk(&mut self, chunk: &Value, events: &mut Vec<Value>) {
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            let code = error
+                .get("code")
+                .and_then(|c| c.as_str())
+                .unwrap_or("server_error");
+            self.fail(code, &message, events);
+            return;
+        }
+
+        if self.model.is_empty() {
+            if let Some(model) = chunk.get("model").and_then(|m| m.as_str()) {
+                self.model = model.to_string();
+            }
+        }
+        self.ensure_started(events);
+
+        if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+            self.usage = Some(usage.clone());
+        }
+
+        let Some(choice) = chunk
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+        else {
+            return;
+        };
+
+        if let Some(delta) = choice.get("delta") {
+            if let Some(reasoning) = reasoning_text(delta) {
+                self.reasoning_delta(reasoning, events);
+            }
+            if let Some(text) = delta
+                .get("content")
+                .and_then(|c| c.as_str())
+                .filter(|s| !s.is_empty())
+            {
+                self.text_delta(text, events);
+            }
+            if let Some(tool_calls) = delta.get("tool_calls").and_then(|t| t.as_array()) {
+                for (position, tool_call) in tool_calls.iter().enumerate() {
+                    self.process_tool_call(position as u64, tool_call, events);
+                }
+            }
+        }
+
+        if let Some(reason) = choice.get("finish_reason").and_then(|r| r.as_str()) {
+            self.finish_reason = Some(reason.to_string());
+        }
+    }
+
+    fn ensure_started(&mut self, events: &mut Vec<Value>) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        let response = self.response_object("in_progress", Vec::new(), None);
+        events.push(json!({"type": "response.created", "response": response}));
+        events.push(json!({"type": "response.in_progress", "response": response}));
+    }
+
+    fn reasoning_delta(&mut self, text: &str, events: &mut Vec<Value>) {
+        if self.reasoning.is_none() {
+            self.close_message(events);
+            let item = self.open_item("rs");
+            events.push(json!({
+                "type": "response.output_item.added",
+                "output_index": item.output_index,
+                "item": {"type": "reasoning", "id": item.item_id, "summary": []}
+            }));
+            events.push(json!({
+                "type": "response.reasoning_summary_part.added",
+                "item_id": item.item_id,
+                "output_index": item.output_index,
+                "summary_index": 0,
+                "part": {"type": "summary_text", "text": ""}
+            }));
+            self.reasoning = Some(item);
+        }
+        if let Some(item) = self.reasoning.as_mut() {
+            item.text.push_str(text);
+            events.push(json!({
+                "type": "response.reasoning_summary_text.delta",
+                "item_id": item.item_id,
+                "output_index": item.output_index,
+                "summary_index": 0,
+                "delta": text
+            }));
+        }
+    }
+
+    fn text_delta(&mut self, text: &str, events: &mut Vec<Value>) {
+        if self.message.is_none() {
+            self.close_reasoning(events);
+            let item = self.open_item("msg");
+            events.push(json!({
+                "type": "response.output_item.added",
+                "output_index": item.output_index,
+                "item": {
+                    "type": "message",
+                    "id": item.item_id,
+                    "status": "in_progress",
+                    "role": "assistant",
+                    "content": []
+                }
+            }));
+            events.push(json!({
+                "type": "response.content_part.added",
+                "item_id": item.item_id,
+                "output_index": item.output_index,
+                "content_index": 0,
+                "part": {"type": "output_text", "text": "", "annotations": []}
+            }));
+            self.message = Some(item);
+        }
+        if let Some(item) = self.message.as_mut() {
+            item.text.push_str(text);
+            events.push(json!({
+                "type": "response.output_text.delta",
+                "item_id": item.item_id,
+                "output_index": item.output_index,
+                "content_index": 0,
+                "delta": text
+            }));
+        }
+    }
+
+    fn process_tool_call(&mut self, position: u64, tool_call: &Value, events: &mut Vec<Value>) {
+        let index = tool_call
+            .get("index")
+            .and_then(|i| i.as_u64())
+            .unwrap_or(position);
+
+        if !self.tools.contains_key(&index) {
+            self.close_reasoning(events);
+            self.close_message(events);
+
+            let call_id = tool_call
+                .get("id")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| new_id("call"));
+            let name = tool_call
+                .pointer("/function/name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let item = self.open_item("fc");
+            let mut added = tool_call_item(&item.item_id, &call_id, &name, "", &self.custom_tools);
+            added["status"] = json!("in_progress");
+            events.push(json!({
+                "type": "response.output_item.added",
+                "output_index": item.output_index,
+                "item": added
+            }));
+            self.tools.insert(
+                index,
+                ToolItem {
+                    output_index: item.output_index,
+                    item_id: item.item_id,
+                    call_id,
+                    name,
+                    arguments: String::new(),
+                },
+            );
+        }
+
+        let Some(tool) = self.tools.get_mut(&index) else {
+            return;
+        };
+        if let Some(arguments) = tool_call
+            .pointer("/function/arguments")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+        {
+            tool.arguments.push_str(arguments);
+            // custom 工具的输入需要从完整 JSON 中解包，只在完成时整体输出
+            if !self.custom_tools.contains(&tool.name) {
+                events.push(json!({
+                    "type": "response.function_call_arguments.delta",
+                    "item_id": tool.item_id,
+                    "output_index": tool.output_index,
+                    "delta": arguments
+                }));
+            }
+        }
+    }
+
+    /// 预留一个 output_index 并生成 item id
+    fn open_item(&mut self, prefix: &str) -> OpenItem {
+        self.output.push(None);
+        OpenItem {
+            output_index: self.output.len() - 1,
+            item_id: new_id(prefix),
+            text: String::new(),
+        }
+    }
+
+    fn close_reasoning(&mut self, events: &mut Vec<Value>) {
+        let Some(item) = self.reasoning.take() else {
+            return;
+        };
+        let part = json!({"type": "summary_text", "text": item.text});
+        events.push(json!({
+            "type": "response.reasoning_summary_text.done",
+            "item_id": item.item_id,
+            "output_index": item.output_index,
+            "summary_index": 0,
+            "text": item.text
+        }));
+        events.push(json!({
+            "type": "response.reasoning_summary_part.done",
+            "item_id": item.item_id,
+            "output_index": item.output_index,
+            "summary_index": 0,
+            "part": part
+        }));
+        let done = json!({"type": "reasoning", "id": item.item_id, "summary": [part]});
+        self.complete_item(item.output_index, done, events);
+    }
+
+    fn close_message(&mut self, events: &mut Vec<Value>) {
+        let Some(item) = self.message.take() else {
+            return;
+        };
+        let part = json!({"type": "output_text", "text": item.text, "annotations": []});
+        events.push(json!({
+            "type": "response.output_text.done",
+            "item_id": item.item_id,
+            "output_index": item.output_index,
+            "content_index": 0,
+            "text": item.text
+        }));
+        events.push(json!({
+            "type": "response.content_part.done",
+            "item_id": item.item_id,
+            "output_index": item.output_index,
+            "content_index": 0,
+            "part": part
+        }));
+        let done = json!({
+            "type": "message",
+            "id": item.item_id,
+            "status": "completed",
+            "role": "assistant",
+            "content": [part]
+        });
+        self.complete_item(item.output_index, done, events);
+    }
+
+    fn close_tools(&mut self, events: &mut Vec<Value>) {
+        let tools = std::mem::take(&mut self.tools);
+        for tool in tools.into_values() {
+            let arguments = if tool.arguments.is_empty() {
+                "{}"
+            } else {
+                tool.arguments.as_str()
+            };
+            let done = tool_call_item(
+                &tool.item_id,
+                &tool.call_id,
+                &tool.name,
+                arguments,
+                &self.custom_tools,
+            );
+            if done["type"] == "function_call" {
+                events.push(json!({
+                    "type": "response.function_call_arguments.done",
+                    "item_id": tool.item_id,
+                    "output_index": tool.output_index,
+                    "arguments": arguments
+                }));
+            }
+            self.complete_item(tool.output_index, done, events);
+        }
+    }
+
+    fn complete_item(&mut self, output_index: usize, item: Value, events: &mut Vec<Value>) {
+        events.push(json!({
+            "type": "response.output_item.done",
+            "output_index": output_index,
+            "item": item
+        }));
+        if let Some(slot) = self.output.get_mut(output_index) {
+            *slot = Some(item);
+        }
+    }
+
+    fn finish_response(&mut self, events: &mut Vec<Value>) {
+        if self.stopped {
+            return;
+        }
+        self.ensure_started(events);
+        self.close_reasoning(events);
+        self.close_message(events);
+        self.close_tools(events);
+        self.stopped = true;
+
+        let output: Vec<Value> = self.output.iter().flatten().cloned().collect();
+        let finish_reason = self.finish_reason.clone();
+        let (status, event_type) = if incomplete_reason(finish_reason.as_deref()).is_some() {
+            ("incomplete", "response.incomplete")
+        } else {
+            ("completed", "response.completed")
+        };
+
+        if let Some(input) = self.history_input.take() {
+            remember_response(&self.response_id, input, &output);
+        }
+
+        let usage = self.usage.as_ref().map(convert_usage);
+        let mut response = self.response_object(status, output, usage);
+        if let Some(reason) = incomplete_reason(finish_reason.as_deref()) {
+            response["incomplete_details"] = json!({ "reason": reason });
+        }
+        events.push(json!({"type": event_type, "response": response}));
+    }
+
+    fn fail(&mut self, code: &str, message: &str, events: &mut Vec<Value>) {
+        if self.stopped {
+            return;
+        }
+        self.ensure_started(events);
+        self.stopped = true;
+        let output: Vec<Value> = self.output.iter().flatten().cloned().collect();
+        let mut response = self.response_object("failed", output, None);
+        response["error"] = json!({"code": code, "message": message});
+        events.push(json!({"type": "response.failed", "response": response}));
+    }
+
+    fn response_object(&self, status: &str, output: Vec<Value>, usage: Option<Value>) -> Value {
+        build_response(
+            &self.response_id,
+            &self.model,
+            self.created_at,
+            status,
+            output,
+            usage,
+            None,
+        )
+    }
+
+    /// 添加 sequence_number 并序列化为 SSE
+    fn serialize(&mut self, events: Vec<Value>) -> Vec<Bytes> {
+        events
+            .into_iter()
+            .map(|mut event| {
+                event["sequence_number"] = json!(self.sequence_number);
+                self.sequence_number += 1;
+                to_sse(event)
+            })
+            .collect()
+    }
+}
+
+fn to_sse(event: Value) -> Bytes {
+    let event_type = event
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("message");
+    Bytes::from(format!(
+        "event: {event_type}\ndata: {}\n\n",
+        serde_json::to_string(&event).unwrap_or_default()
+    ))
+}
+
+/// 创建 Responses SSE 流
+///
+/// `custom_tools` 为请求中声明的 custom 工具名；`history_input` 为需要保存的会话输入（store=false 时为 None）。
+pub fn create_responses_sse_stream(
+    stream: impl Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
+    custom_tools: HashSet<String>,
+    history_input: Option<Vec<Value>>,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut converter = ResponsesStreamConverter::new(custom_tools, history_input);
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    for event in converter.push_bytes(&bytes) {
+                        yield Ok(event);
+                    }
+                    if converter.is_stopped() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    log::error!("Stream error: {e}");
+                    for event in converter.stream_error(&format!("Stream error: {e}")) {
+                        yield Ok(event);
+                    }
+                    break;
+                }
+            }
+        }
+
+        for event in converter.finish() {
+            yield Ok(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::providers::transform_responses::expand_input;
+    use crate::proxy::usage::parser::TokenUsage;
+
+    fn convert_with(
+        custom_tools: HashSet<String>,
+        history_input: Option<Vec<Value>>,
+        chunks: &[&[u8]],
+    ) -> Vec<Value> {
+        let mut converter = ResponsesStreamConverter::new(custom_tools, history_input);
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output.extend(converter.push_bytes(chunk));
+        }
+        output.extend(converter.finish());
+        output
+            .iter()
+            .map(|b| {
+                let text = std::str::from_utf8(b).unwrap();
+                let data = text.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+                serde_json::from_str(data).unwrap()
+            })
+            .collect()
+    }
+
+    fn convert(chunks: &[&[u8]]) -> Vec<Value> {
+        convert_with(HashSet::new(), None, chunks)
+    }
+
+    fn types(events: &[Value]) -> Vec<&str> {
+        events.iter().map(|e| e["type"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_text_stream_with_usage() {
+        let sse = concat!(
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":2,\"prompt_tokens_details\":{\"cached_tokens\":4}}}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        assert_eq!(
+            types(&events),
+            vec![
+                "response.created",
+                "response.in_progress",
+                "response.output_item.added",
+                "response.content_part.added",
+                "response.output_text.delta",
+                "response.output_text.delta",
+                "response.output_text.done",
+                "response.content_part.done",
+                "response.output_item.done",
+                "response.completed",
+            ]
+        );
+        for (i, event) in events.iter().enumerate() {
+            assert_eq!(event["sequence_number"], i as u64);
+        }
+
+        let completed = events.last().unwrap();
+        assert_eq!(completed["response"]["model"], "gpt-4o");
+        assert_eq!(
+            completed["response"]["output"][0]["content"][0]["text"],
+            "Hello"
+        );
+        assert_eq!(completed["response"]["usage"]["input_tokens"], 12);
+
+        // 用量解析器只认 response.completed 中的 Responses 格式 usage
+        let usage = TokenUsage::from_codex_stream_events(&events).unwrap();
+        assert_eq!(usage.input_tokens, 8);
+        assert_eq!(usage.output_tokens, 2);
+        assert_eq!(usage.cache_read_tokens, 4);
+        let usage = TokenUsage::from_codex_stream_events_auto(&events).unwrap();
+        assert_eq!(usage.input_tokens, 12);
+    }
+
+    #[test]
+    fn test_reasoning_then_parallel_tool_calls() {
+        let sse = concat!(
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"reasoning_content\":\"Plan\"}}]}\n\n",
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",\"type\":\"function\",\"function\":{\"name\":\"shell\",\"arguments\":\"{\\\"command\\\":\"}}]}}]}\n\n",
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":1,\"id\":\"call_b\",\"type\":\"function\",\"function\":{\"name\":\"shell\",\"arguments\":\"{}\"}}]}}]}\n\n",
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"[\\\"ls\\\"]}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        let completed = events.last().unwrap();
+        assert_eq!(completed["type"], "response.completed");
+        let output = completed["response"]["output"].as_array().unwrap();
+        assert_eq!(output.len(), 3);
+        assert_eq!(output[0]["type"], "reasoning");
+        assert_eq!(output[0]["summary"][0]["text"], "Plan");
+        assert_eq!(output[1]["call_id"], "call_a");
+        assert_eq!(output[1]["arguments"], "{\"command\":[\"ls\"]}");
+        assert_eq!(output[2]["call_id"], "call_b");
+
+        let argument_deltas = events
+            .iter()
+            .filter(|e| e["type"] == "response.function_call_arguments.delta")
+            .count();
+        assert_eq!(argument_deltas, 3);
+    }
+
+    #[test]
+    fn test_custom_tool_and_history() {
+        let request = json!({"model": "m", "input": "patch it"});
+        let history_input = expand_input(&request).unwrap();
+        let custom_tools: HashSet<String> = ["apply_patch".to_string()].into_iter().collect();
+        let sse = concat!(
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_p\",\"function\":{\"name\":\"apply_patch\",\"arguments\":\"{\\\"input\\\":\\\"*** Begin\"}}]}}]}\n\n",
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\" Patch\\\"}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let events = convert_with(custom_tools, Some(history_input), &[sse.as_bytes()]);
+
+        let completed = events.last().unwrap();
+        let item = &completed["response"]["output"][0];
+        assert_eq!(item["type"], "custom_tool_call");
+        assert_eq!(item["input"], "*** Begin Patch");
+        assert!(!types(&events).contains(&"response.function_call_arguments.delta"));
+
+        // 完成后可通过 previous_response_id 展开会话
+        let next = json!({
+            "previous_response_id": completed["response"]["id"],
+            "input": [{"type": "custom_tool_call_output", "call_id": "call_p", "output": "ok"}]
+        });
+        let expanded = expand_input(&next).unwrap();
+        assert_eq!(expanded.len(), 3);
+        assert_eq!(expanded[1]["call_id"], "call_p");
+    }
+
+    #[test]
+    fn test_length_finish_is_incomplete() {
+        let sse = concat!(
+            "data: {\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"cut\"},\"finish_reason\":\"length\"}]}\n\n",
+            "data: {\"model\":\"m\",\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":1}}\n\n"
+        );
+        let events = convert(&[sse.as_bytes()]);
+
+        let last = events.last().unwrap();
+        assert_eq!(last["type"], "response.incomplete");
+        assert_eq!(
+            last["response"]["incomplete_details"]["reason"],
+            "max_output_tokens"
+        );
+        let usage = TokenUsage::from_codex_stream_events_auto(&events).unwrap();
+        assert_eq!(usage.output_tokens, 1);
+    }
+
+    #[test]
+    fn test_upstream_error_chunk() {
+        let sse = "data: {\"error\":{\"message\":\"overloaded\",\"code\":\"server_error\"}}\n\n";
+        let events = convert(&[sse.as_bytes()]);
+
+        let last = events.last().unwrap();
+        assert_eq!(last["type"], "response.failed");
+        assert_eq!(last["response"]["error"]["message"], "overloaded");
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e["type"] == "response.failed")
+                .count(),
+            1
+        );
+    }
+}
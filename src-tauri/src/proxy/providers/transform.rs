@@ -1,34 +1,52 @@
 //! 格式转换模块
 //!
-//! 实现 Anthropic ↔ OpenAI 格式转换，用于 OpenRouter 支持
+//! 实现 Anthropic Messages ↔ OpenAI Chat Completions 格式转换，
+//! 用于把 Claude Code 请求转发到 OpenRouter / DeepSeek / Qwen / vLLM 等 OpenAI 兼容服务。
 //! 参考: anthropic-proxy-rs
+//!
+//! 覆盖范围：
+//! - system（字符串 / 文本块数组）、多模态图片（base64 / url）、文本型 document
+//! - tool_use / tool_result（含并行调用、数组形式的结果、is_error、结果中的图片）
+//! - thinking：请求中 assistant 消息的 thinking 块 → reasoning_content，`thinking` 参数 → reasoning_effort；
+//!   响应侧 reasoning_content → thinking。redacted_thinking 是只有 Anthropic 能解密的内容，丢弃
+//! - stop_sequences、tool_choice（auto / any / tool / none）、disable_parallel_tool_use
+//! - cache_control：仅当目标模型本身是 Claude（经 OpenRouter 等网关转发）时保留，
+//!   其余供应商大多只接受纯字符串 content，保留会直接 400
 
 use crate::proxy::error::ProxyError;
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
+
+/// thinking 预算低于该值时 reasoning_effort 为 low
+const REASONING_LOW_BUDGET: u64 = 4096;
+/// thinking 预算低于该值时 reasoning_effort 为 medium，否则为 high
+const REASONING_MEDIUM_BUDGET: u64 = 16384;
+
+/// 转换过程中需要的上下文
+struct ConvertOptions {
+    /// 是否在 content part 上保留 cache_control
+    keep_cache_control: bool,
+}
 
 /// Anthropic 请求 → OpenAI 请求
 pub fn anthropic_to_openai(body: Value) -> Result<Value, ProxyError> {
     let mut result = json!({});
 
     // NOTE: 模型映射由上游统一处理（proxy::model_mapper），格式转换层只做结构转换。
-    if let Some(model) = body.get("model").and_then(|m| m.as_str()) {
+    let model = body.get("model").and_then(|m| m.as_str()).unwrap_or("");
+    if !model.is_empty() {
         result["model"] = json!(model);
     }
 
+    let opts = ConvertOptions {
+        keep_cache_control: model.to_lowercase().contains("claude"),
+    };
+
     let mut messages = Vec::new();
 
-    // 处理 system prompt
+    // 处理 system prompt（合并为一条 system 消息，部分模型模板不支持多条 system）
     if let Some(system) = body.get("system") {
-        if let Some(text) = system.as_str() {
-            // 单个字符串
-            messages.push(json!({"role": "system", "content": text}));
-        } else if let Some(arr) = system.as_array() {
-            // 多个 system message
-            for msg in arr {
-                if let Some(text) = msg.get("text").and_then(|t| t.as_str()) {
-                    messages.push(json!({"role": "system", "content": text}));
-                }
-            }
+        if let Some(msg) = convert_system_to_openai(system, &opts) {
+            messages.push(msg);
         }
     }
 
@@ -37,7 +55,7 @@ pub fn anthropic_to_openai(body: Value) -> Result<Value, ProxyError> {
         for msg in msgs {
             let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
             let content = msg.get("content");
-            let converted = convert_message_to_openai(role, content)?;
+            let converted = convert_message_to_openai(role, content, &opts)?;
             messages.extend(converted);
         }
     }
@@ -54,18 +72,28 @@ pub fn anthropic_to_openai(body: Value) -> Result<Value, ProxyError> {
     if let Some(v) = body.get("top_p") {
         result["top_p"] = v.clone();
     }
+    if let Some(effort) = body.get("thinking").and_then(reasoning_effort) {
+        result["reasoning_effort"] = json!(effort);
+    }
     if let Some(v) = body.get("stop_sequences") {
-        result["stop"] = v.clone();
+        if v.as_array().is_some_and(|a| !a.is_empty()) {
+            result["stop"] = v.clone();
+        }
     }
     if let Some(v) = body.get("stream") {
         result["stream"] = v.clone();
+        if v.as_bool() == Some(true) {
+            // 让上游在最后一个 chunk 返回 usage，否则流式请求无法计费
+            result["stream_options"] = json!({"include_usage": true});
+        }
     }
 
-    // 转换 tools (过滤 BatchTool)
+    // 转换 tools（过滤 BatchTool 与 web_search 等 Anthropic 服务端工具）
+    let mut has_tools = false;
     if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
         let openai_tools: Vec<Value> = tools
             .iter()
-            .filter(|t| t.get("type").and_then(|v| v.as_str()) != Some("BatchTool"))
+            .filter(|t| is_client_tool(t))
             .map(|t| {
                 json!({
                     "type": "function",
@@ -80,20 +108,166 @@ pub fn anthropic_to_openai(body: Value) -> Result<Value, ProxyError> {
 
         if !openai_tools.is_empty() {
             result["tools"] = json!(openai_tools);
+            has_tools = true;
         }
     }
 
-    if let Some(v) = body.get("tool_choice") {
-        result["tool_choice"] = v.clone();
+    // tool_choice 只有在存在 tools 时才有意义，OpenAI 会拒绝孤立的 tool_choice
+    if has_tools {
+        if let Some(choice) = body.get("tool_choice") {
+            if let Some(converted) = convert_tool_choice(choice) {
+                result["tool_choice"] = converted;
+            }
+            if choice
+                .get("disable_parallel_tool_use")
+                .and_then(|v| v.as_bool())
+                == Some(true)
+            {
+                result["parallel_tool_calls"] = json!(false);
+            }
+        }
     }
 
     Ok(result)
 }
 
+/// Anthropic `thinking` 参数 → OpenAI reasoning_effort（按思考预算分档，未启用时为 None）
+fn reasoning_effort(thinking: &Value) -> Option<&'static str> {
+    if thinking.get("type").and_then(|t| t.as_str()) != Some("enabled") {
+        return None;
+    }
+    Some(
+        match thinking.get("budget_tokens").and_then(|b| b.as_u64()) {
+            Some(budget) if budget < REASONING_LOW_BUDGET => "low",
+            Some(budget) if budget >= REASONING_MEDIUM_BUDGET => "high",
+            _ => "medium",
+        },
+    )
+}
+
+/// 判断是否为可转发给 OpenAI 的客户端工具
+fn is_client_tool(tool: &Value) -> bool {
+    match tool.get("type").and_then(|v| v.as_str()) {
+        Some("BatchTool") => false,
+        None | Some("custom") => true,
+        // 其余带 type 的是 Anthropic 服务端工具（web_search_20250305、bash_20250124 等），没有 input_schema
+        Some(_) => tool.get("input_schema").is_some(),
+    }
+}
+
+/// Anthropic tool_choice → OpenAI tool_choice
+fn convert_tool_choice(choice: &Value) -> Option<Value> {
+    if choice.is_string() {
+        // 已是 OpenAI 形式（"auto" / "required" / "none"），直接透传
+        return Some(choice.clone());
+    }
+    match choice.get("type").and_then(|t| t.as_str()) {
+        Some("auto") => Some(json!("auto")),
+        Some("any") => Some(json!("required")),
+        Some("none") => Some(json!("none")),
+        Some("tool") => choice
+            .get("name")
+            .and_then(|n| n.as_str())
+            .map(|name| json!({"type": "function", "function": {"name": name}})),
+        _ => None,
+    }
+}
+
+/// 转换 system prompt
+fn convert_system_to_openai(system: &Value, opts: &ConvertOptions) -> Option<Value> {
+    if let Some(text) = system.as_str() {
+        return Some(json!({"role": "system", "content": text}));
+    }
+
+    let blocks = system.as_array()?;
+    let parts: Vec<Value> = blocks.iter().filter_map(|b| text_part(b, opts)).collect();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(json!({"role": "system", "content": collapse_parts(parts)}))
+}
+
+/// 构造 OpenAI 文本 content part（可选保留 cache_control）
+fn text_part(block: &Value, opts: &ConvertOptions) -> Option<Value> {
+    let text = block.get("text").and_then(|t| t.as_str())?;
+    let mut part = json!({"type": "text", "text": text});
+    if opts.keep_cache_control {
+        if let Some(cc) = block.get("cache_control") {
+            part["cache_control"] = cc.clone();
+        }
+    }
+    Some(part)
+}
+
+/// 构造 OpenAI 图片 content part
+fn image_part(block: &Value) -> Option<Value> {
+    let source = block.get("source")?;
+    let url = match source.get("type").and_then(|t| t.as_str()) {
+        Some("url") => source.get("url").and_then(|u| u.as_str())?.to_string(),
+        _ => {
+            let media_type = source
+                .get("media_type")
+                .and_then(|m| m.as_str())
+                .unwrap_or("image/png");
+            let data = source.get("data").and_then(|d| d.as_str()).unwrap_or("");
+            format!("data:{media_type};base64,{data}")
+        }
+    };
+    Some(json!({"type": "image_url", "image_url": {"url": url}}))
+}
+
+/// 纯文本且不带 cache_control 的 parts 合并为字符串，兼容只接受字符串 content 的供应商
+fn collapse_parts(parts: Vec<Value>) -> Value {
+    let plain_text = parts.iter().all(|p| {
+        p.get("type").and_then(|t| t.as_str()) == Some("text") && p.get("cache_control").is_none()
+    });
+    if !plain_text {
+        return json!(parts);
+    }
+    let texts: Vec<&str> = parts
+        .iter()
+        .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+        .collect();
+    json!(texts.join("\n\n"))
+}
+
+/// 将 tool_result 的 content 转为文本，图片块单独返回（tool 消息只能携带文本）
+fn convert_tool_result_content(content: Option<&Value>) -> (String, Vec<Value>) {
+    match content {
+        Some(Value::String(s)) => (s.clone(), Vec::new()),
+        Some(Value::Array(blocks)) => {
+            let mut texts = Vec::new();
+            let mut images = Vec::new();
+            for block in blocks {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                            texts.push(text.to_string());
+                        }
+                    }
+                    Some("image") => {
+                        if let Some(part) = image_part(block) {
+                            images.push(part);
+                        }
+                    }
+                    _ => texts.push(serde_json::to_string(block).unwrap_or_default()),
+                }
+            }
+            (texts.join("\n"), images)
+        }
+        Some(Value::Null) | None => (String::new(), Vec::new()),
+        Some(v) => (serde_json::to_string(v).unwrap_or_default(), Vec::new()),
+    }
+}
+
 /// 转换单条消息到 OpenAI 格式（可能产生多条消息）
+///
+/// tool_result 会拆成独立的 `tool` 消息并排在同一轮用户内容之前，
+/// 以满足 OpenAI “tool 消息必须紧跟在带 tool_calls 的 assistant 消息之后” 的约束。
 fn convert_message_to_openai(
     role: &str,
     content: Option<&Value>,
+    opts: &ConvertOptions,
 ) -> Result<Vec<Value>, ProxyError> {
     let mut result = Vec::new();
 
@@ -115,27 +289,31 @@ fn convert_message_to_openai(
     if let Some(blocks) = content.as_array() {
         let mut content_parts = Vec::new();
         let mut tool_calls = Vec::new();
+        let mut reasoning = Vec::new();
 
         for block in blocks {
             let block_type = block.get("type").and_then(|t| t.as_str()).unwrap_or("");
 
             match block_type {
                 "text" => {
-                    if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
-                        content_parts.push(json!({"type": "text", "text": text}));
+                    if let Some(part) = text_part(block, opts) {
+                        content_parts.push(part);
                     }
                 }
                 "image" => {
-                    if let Some(source) = block.get("source") {
-                        let media_type = source
-                            .get("media_type")
-                            .and_then(|m| m.as_str())
-                            .unwrap_or("image/png");
-                        let data = source.get("data").and_then(|d| d.as_str()).unwrap_or("");
-                        content_parts.push(json!({
-                            "type": "image_url",
-                            "image_url": {"url": format!("data:{};base64,{}", media_type, data)}
-                        }));
+                    if let Some(part) = image_part(block) {
+                        content_parts.push(part);
+                    }
+                }
+                "document" => {
+                    // 仅支持纯文本来源的 document，PDF 等二进制来源 OpenAI 兼容接口普遍不支持
+                    let source = block.get("source");
+                    if source.and_then(|s| s.get("type")).and_then(|t| t.as_str()) == Some("text") {
+                        if let Some(data) = source.and_then(|s| s.get("data")) {
+                            content_parts.push(json!({"type": "text", "text": data}));
+                        }
+                    } else {
+                        log::debug!("[Transform] 跳过非文本 document 块");
                     }
                 }
                 "tool_use" => {
@@ -157,47 +335,64 @@ fn convert_message_to_openai(
                         .get("tool_use_id")
                         .and_then(|i| i.as_str())
                         .unwrap_or("");
-                    let content_val = block.get("content");
-                    let content_str = match content_val {
-                        Some(Value::String(s)) => s.clone(),
-                        Some(v) => serde_json::to_string(v).unwrap_or_default(),
-                        None => String::new(),
+                    let (mut text, images) = convert_tool_result_content(block.get("content"));
+                    if block.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+                        text = format!("Error: {text}");
+                    }
+
+                    let tool_content = match block.get("cache_control") {
+                        Some(cc) if opts.keep_cache_control => {
+                            json!([{"type": "text", "text": text, "cache_control": cc}])
+                        }
+                        _ => json!(text),
                     };
                     result.push(json!({
                         "role": "tool",
                         "tool_call_id": tool_use_id,
-                        "content": content_str
+                        "content": tool_content
                     }));
+
+                    // 工具返回的图片只能放到随后的 user 消息里
+                    content_parts.extend(images);
                 }
                 "thinking" => {
-                    // 跳过 thinking blocks
+                    // 签名只对 Anthropic 有效，只回传思考内容
+                    if let Some(text) = block.get("thinking").and_then(|t| t.as_str()) {
+                        if !text.is_empty() {
+                            reasoning.push(text);
+                        }
+                    }
+                }
+                "redacted_thinking" => {
+                    // 加密内容只有 Anthropic 能解密，丢弃
                 }
                 _ => {}
             }
         }
 
-        // 添加带内容和/或工具调用的消息
-        if !content_parts.is_empty() || !tool_calls.is_empty() {
+        // 思考内容只随 assistant 消息回传（DeepSeek 等要求工具调用轮次带回 reasoning_content）
+        let reasoning =
+            (role == "assistant" && !reasoning.is_empty()).then(|| reasoning.join("\n\n"));
+
+        // 添加带内容、工具调用和/或思考内容的消息
+        if !content_parts.is_empty() || !tool_calls.is_empty() || reasoning.is_some() {
             let mut msg = json!({"role": role});
 
-            // 内容处理
-            if content_parts.is_empty() {
-                msg["content"] = Value::Null;
-            } else if content_parts.len() == 1 {
-                if let Some(text) = content_parts[0].get("text") {
-                    msg["content"] = text.clone();
-                } else {
-                    msg["content"] = json!(content_parts);
-                }
+            msg["content"] = if content_parts.is_empty() {
+                Value::Null
             } else {
-                msg["content"] = json!(content_parts);
-            }
+                collapse_parts(content_parts)
+            };
 
             // 工具调用
             if !tool_calls.is_empty() {
                 msg["tool_calls"] = json!(tool_calls);
             }
 
+            if let Some(reasoning) = reasoning {
+                msg["reasoning_content"] = json!(reasoning);
+            }
+
             result.push(msg);
         }
 
@@ -227,10 +422,74 @@ fn clean_schema(mut schema: Value) -> Value {
         if let Some(items) = obj.get_mut("items") {
             *items = clean_schema(items.clone());
         }
+
+        for key in ["anyOf", "oneOf", "allOf"] {
+            if let Some(variants) = obj.get_mut(key).and_then(|v| v.as_array_mut()) {
+                for variant in variants.iter_mut() {
+                    *variant = clean_schema(variant.clone());
+                }
+            }
+        }
     }
     schema
 }
 
+/// OpenAI finish_reason → Anthropic stop_reason
+pub(crate) fn map_finish_reason(finish_reason: &str) -> &'static str {
+    match finish_reason {
+        "stop" => "end_turn",
+        "length" => "max_tokens",
+        "tool_calls" | "function_call" => "tool_use",
+        "content_filter" => "refusal",
+        _ => "end_turn",
+    }
+}
+
+/// OpenAI usage → Anthropic usage
+///
+/// Anthropic 的 input_tokens 不含缓存命中部分，因此需要扣除 cached_tokens
+/// （OpenAI: prompt_tokens_details.cached_tokens；DeepSeek: prompt_cache_hit_tokens）。
+pub(crate) fn convert_usage(usage: &Value) -> Value {
+    let prompt_tokens = usage
+        .get("prompt_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let completion_tokens = usage
+        .get("completion_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let cached_tokens = usage
+        .get("prompt_tokens_details")
+        .and_then(|d| d.get("cached_tokens"))
+        .and_then(|v| v.as_u64())
+        .or_else(|| {
+            usage
+                .get("prompt_cache_hit_tokens")
+                .and_then(|v| v.as_u64())
+        })
+        .unwrap_or(0);
+
+    let mut result = Map::new();
+    result.insert(
+        "input_tokens".to_string(),
+        json!(prompt_tokens.saturating_sub(cached_tokens)),
+    );
+    result.insert("output_tokens".to_string(), json!(completion_tokens));
+    if cached_tokens > 0 {
+        result.insert("cache_read_input_tokens".to_string(), json!(cached_tokens));
+    }
+    Value::Object(result)
+}
+
+/// 提取推理内容（DeepSeek / Qwen / vLLM 使用 reasoning_content，OpenRouter 使用 reasoning）
+pub(crate) fn reasoning_text(value: &Value) -> Option<&str> {
+    value
+        .get("reasoning_content")
+        .and_then(|v| v.as_str())
+        .or_else(|| value.get("reasoning").and_then(|v| v.as_str()))
+        .filter(|s| !s.is_empty())
+}
+
 /// OpenAI 响应 → Anthropic 响应
 pub fn openai_to_anthropic(body: Value) -> Result<Value, ProxyError> {
     let choices = body
@@ -248,6 +507,11 @@ pub fn openai_to_anthropic(body: Value) -> Result<Value, ProxyError> {
 
     let mut content = Vec::new();
 
+    // 推理内容
+    if let Some(reasoning) = reasoning_text(message) {
+        content.push(json!({"type": "thinking", "thinking": reasoning, "signature": ""}));
+    }
+
     // 文本内容
     if let Some(text) = message.get("content").and_then(|c| c.as_str()) {
         if !text.is_empty() {
@@ -255,7 +519,17 @@ pub fn openai_to_anthropic(body: Value) -> Result<Value, ProxyError> {
         }
     }
 
-    // 工具调用
+    // 拒绝回答（OpenAI refusal 字段）
+    let refusal = message
+        .get("refusal")
+        .and_then(|r| r.as_str())
+        .filter(|r| !r.is_empty());
+    if let Some(refusal) = refusal {
+        content.push(json!({"type": "text", "text": refusal}));
+    }
+
+    // 工具调用（并行调用会产生多个 tool_use 块）
+    let mut has_tool_calls = false;
     if let Some(tool_calls) = message.get("tool_calls").and_then(|t| t.as_array()) {
         for tc in tool_calls {
             let id = tc.get("id").and_then(|i| i.as_str()).unwrap_or("");
@@ -274,30 +548,20 @@ pub fn openai_to_anthropic(body: Value) -> Result<Value, ProxyError> {
                 "name": name,
                 "input": input
             }));
+            has_tool_calls = true;
         }
     }
 
     // 映射 finish_reason → stop_reason
-    let stop_reason = choice
-        .get("finish_reason")
-        .and_then(|r| r.as_str())
-        .map(|r| match r {
-            "stop" => "end_turn",
-            "length" => "max_tokens",
-            "tool_calls" => "tool_use",
-            other => other,
-        });
-
-    // usage
-    let usage = body.get("usage").cloned().unwrap_or(json!({}));
-    let input_tokens = usage
-        .get("prompt_tokens")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as u32;
-    let output_tokens = usage
-        .get("completion_tokens")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as u32;
+    // 部分供应商在返回 tool_calls 时仍给出 "stop"，以实际内容为准
+    let stop_reason = match choice.get("finish_reason").and_then(|r| r.as_str()) {
+        _ if has_tool_calls => Some("tool_use"),
+        _ if refusal.is_some() => Some("refusal"),
+        Some(r) => Some(map_finish_reason(r)),
+        None => None,
+    };
+
+    let usage = convert_usage(body.get("usage").unwrap_or(&json!({})));
 
     let result = json!({
         "id": body.get("id").and_then(|i| i.as_str()).unwrap_or(""),
@@ -307,10 +571,7 @@ pub fn openai_to_anthropic(body: Value) -> Result<Value, ProxyError> {
         "model": body.get("model").and_then(|m| m.as_str()).unwrap_or(""),
         "stop_reason": stop_reason,
         "stop_sequence": null,
-        "usage": {
-            "input_tokens": input_tokens,
-            "output_tokens": output_tokens
-        }
+        "usage": usage
     });
 
     Ok(result)
@@ -320,164 +581,550 @@ pub fn openai_to_anthropic(body: Value) -> Result<Value, ProxyError> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_anthropic_to_openai_simple() {
-        let input = json!({
-            "model": "claude-3-opus",
-            "max_tokens": 1024,
-            "messages": [{"role": "user", "content": "Hello"}]
-        });
-
-        let result = anthropic_to_openai(input).unwrap();
-        assert_eq!(result["model"], "claude-3-opus");
-        assert_eq!(result["max_tokens"], 1024);
-        assert_eq!(result["messages"][0]["role"], "user");
-        assert_eq!(result["messages"][0]["content"], "Hello");
+    /// 请求夹具：Anthropic 请求 → 期望的完整 OpenAI 请求
+    struct RequestFixture {
+        name: &'static str,
+        anthropic: Value,
+        openai: Value,
     }
 
-    #[test]
-    fn test_anthropic_to_openai_with_system() {
-        let input = json!({
-            "model": "claude-3-sonnet",
-            "max_tokens": 1024,
-            "system": "You are a helpful assistant.",
-            "messages": [{"role": "user", "content": "Hello"}]
-        });
-
-        let result = anthropic_to_openai(input).unwrap();
-        assert_eq!(result["messages"][0]["role"], "system");
-        assert_eq!(
-            result["messages"][0]["content"],
-            "You are a helpful assistant."
-        );
-        assert_eq!(result["messages"][1]["role"], "user");
+    /// 响应夹具：OpenAI 响应 → 期望的完整 Anthropic 响应
+    ///
+    /// 客户端把 Anthropic 响应的 content 作为下一轮 assistant 消息回传时，
+    /// 转换结果应与 OpenAI 响应中的 message 一致
+    struct ResponseFixture {
+        name: &'static str,
+        openai: Value,
+        anthropic: Value,
     }
 
-    #[test]
-    fn test_anthropic_to_openai_with_tools() {
-        let input = json!({
-            "model": "claude-3-opus",
-            "max_tokens": 1024,
-            "messages": [{"role": "user", "content": "What's the weather?"}],
-            "tools": [{
-                "name": "get_weather",
-                "description": "Get weather info",
-                "input_schema": {"type": "object", "properties": {"location": {"type": "string"}}}
-            }]
-        });
-
-        let result = anthropic_to_openai(input).unwrap();
-        assert_eq!(result["tools"][0]["type"], "function");
-        assert_eq!(result["tools"][0]["function"]["name"], "get_weather");
+    fn request_fixtures() -> Vec<RequestFixture> {
+        vec![
+            RequestFixture {
+                name: "plain_text_and_sampling_params",
+                anthropic: json!({
+                    "model": "claude-3-opus",
+                    "max_tokens": 1024,
+                    "temperature": 0.2,
+                    "top_p": 0.9,
+                    "top_k": 40,
+                    "messages": [{"role": "user", "content": "Hello"}]
+                }),
+                openai: json!({
+                    "model": "claude-3-opus",
+                    "messages": [{"role": "user", "content": "Hello"}],
+                    "max_tokens": 1024,
+                    "temperature": 0.2,
+                    "top_p": 0.9
+                }),
+            },
+            RequestFixture {
+                name: "system_string",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "system": "You are a helpful assistant.",
+                    "messages": [{"role": "user", "content": "Hello"}]
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [
+                        {"role": "system", "content": "You are a helpful assistant."},
+                        {"role": "user", "content": "Hello"}
+                    ]
+                }),
+            },
+            RequestFixture {
+                // 非 Claude 模型：system 块合并为一条字符串消息，cache_control 去掉
+                name: "system_blocks_merged_without_cache_control",
+                anthropic: json!({
+                    "model": "qwen3-coder-plus",
+                    "system": [
+                        {"type": "text", "text": "You are Claude Code."},
+                        {"type": "text", "text": "Be concise.", "cache_control": {"type": "ephemeral"}}
+                    ],
+                    "messages": [{
+                        "role": "user",
+                        "content": [
+                            {"type": "text", "text": "a"},
+                            {"type": "text", "text": "b", "cache_control": {"type": "ephemeral"}}
+                        ]
+                    }]
+                }),
+                openai: json!({
+                    "model": "qwen3-coder-plus",
+                    "messages": [
+                        {"role": "system", "content": "You are Claude Code.\n\nBe concise."},
+                        {"role": "user", "content": "a\n\nb"}
+                    ]
+                }),
+            },
+            RequestFixture {
+                // 经网关转发给 Claude 时保留 cache_control
+                name: "cache_control_kept_for_claude_models",
+                anthropic: json!({
+                    "model": "anthropic/claude-sonnet-4",
+                    "system": [{"type": "text", "text": "sys", "cache_control": {"type": "ephemeral"}}],
+                    "messages": [{
+                        "role": "user",
+                        "content": [
+                            {"type": "text", "text": "a"},
+                            {"type": "text", "text": "b", "cache_control": {"type": "ephemeral"}}
+                        ]
+                    }]
+                }),
+                openai: json!({
+                    "model": "anthropic/claude-sonnet-4",
+                    "messages": [
+                        {"role": "system", "content": [
+                            {"type": "text", "text": "sys", "cache_control": {"type": "ephemeral"}}
+                        ]},
+                        {"role": "user", "content": [
+                            {"type": "text", "text": "a"},
+                            {"type": "text", "text": "b", "cache_control": {"type": "ephemeral"}}
+                        ]}
+                    ]
+                }),
+            },
+            RequestFixture {
+                name: "images_from_base64_and_url_sources",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "messages": [{
+                        "role": "user",
+                        "content": [
+                            {"type": "text", "text": "Compare"},
+                            {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "AAAA"}},
+                            {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}}
+                        ]
+                    }]
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [{
+                        "role": "user",
+                        "content": [
+                            {"type": "text", "text": "Compare"},
+                            {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,AAAA"}},
+                            {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+                        ]
+                    }]
+                }),
+            },
+            RequestFixture {
+                // 服务端工具与 BatchTool 被过滤，format: uri 被清理
+                name: "client_tools_with_forced_tool_choice",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "What's the weather?"}],
+                    "tools": [
+                        {"type": "web_search_20250305", "name": "web_search", "max_uses": 5},
+                        {"type": "BatchTool", "name": "batch", "input_schema": {"type": "object"}},
+                        {
+                            "name": "get_weather",
+                            "description": "Get weather info",
+                            "input_schema": {"type": "object", "properties": {
+                                "location": {"type": "string"},
+                                "source": {"type": "string", "format": "uri"}
+                            }}
+                        }
+                    ],
+                    "tool_choice": {"type": "tool", "name": "get_weather"}
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "What's the weather?"}],
+                    "tools": [{
+                        "type": "function",
+                        "function": {
+                            "name": "get_weather",
+                            "description": "Get weather info",
+                            "parameters": {"type": "object", "properties": {
+                                "location": {"type": "string"},
+                                "source": {"type": "string"}
+                            }}
+                        }
+                    }],
+                    "tool_choice": {"type": "function", "function": {"name": "get_weather"}}
+                }),
+            },
+            RequestFixture {
+                name: "tool_choice_any_without_parallel_calls",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "tools": [{"name": "read", "description": "Read a file", "input_schema": {"type": "object"}}],
+                    "tool_choice": {"type": "any", "disable_parallel_tool_use": true}
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "tools": [{
+                        "type": "function",
+                        "function": {"name": "read", "description": "Read a file", "parameters": {"type": "object"}}
+                    }],
+                    "tool_choice": "required",
+                    "parallel_tool_calls": false
+                }),
+            },
+            RequestFixture {
+                // 没有 tools 时不发送 tool_choice
+                name: "tool_choice_dropped_without_tools",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "tool_choice": {"type": "none"}
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "Hi"}]
+                }),
+            },
+            RequestFixture {
+                name: "tool_results_with_array_content_errors_and_images",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "messages": [{
+                        "role": "user",
+                        "content": [
+                            {
+                                "type": "tool_result",
+                                "tool_use_id": "call_1",
+                                "content": [
+                                    {"type": "text", "text": "line 1"},
+                                    {"type": "text", "text": "line 2"},
+                                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "BBBB"}}
+                                ]
+                            },
+                            {
+                                "type": "tool_result",
+                                "tool_use_id": "call_2",
+                                "content": "permission denied",
+                                "is_error": true
+                            },
+                            {"type": "text", "text": "Continue."}
+                        ]
+                    }]
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [
+                        {"role": "tool", "tool_call_id": "call_1", "content": "line 1\nline 2"},
+                        {"role": "tool", "tool_call_id": "call_2", "content": "Error: permission denied"},
+                        {"role": "user", "content": [
+                            {"type": "image_url", "image_url": {"url": "data:image/png;base64,BBBB"}},
+                            {"type": "text", "text": "Continue."}
+                        ]}
+                    ]
+                }),
+            },
+            RequestFixture {
+                // thinking 参数按预算映射为 reasoning_effort，stream 时要求返回 usage
+                name: "thinking_budget_stop_sequences_and_stream",
+                anthropic: json!({
+                    "model": "gpt-4o",
+                    "stream": true,
+                    "stop_sequences": ["</answer>"],
+                    "thinking": {"type": "enabled", "budget_tokens": 2048},
+                    "messages": [{"role": "user", "content": "Hi"}]
+                }),
+                openai: json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "reasoning_effort": "low",
+                    "stop": ["</answer>"],
+                    "stream": true,
+                    "stream_options": {"include_usage": true}
+                }),
+            },
+            RequestFixture {
+                // 历史 thinking 块作为 reasoning_content 回传，redacted_thinking 丢弃
+                name: "thinking_blocks_replayed_as_reasoning_content",
+                anthropic: json!({
+                    "model": "deepseek-reasoner",
+                    "thinking": {"type": "enabled", "budget_tokens": 32000},
+                    "messages": [
+                        {"role": "user", "content": "Question"},
+                        {"role": "assistant", "content": [
+                            {"type": "thinking", "thinking": "step 1", "signature": "sig"},
+                            {"type": "redacted_thinking", "data": "xxx"},
+                            {"type": "thinking", "thinking": "step 2", "signature": "sig"},
+                            {"type": "text", "text": "Answer"}
+                        ]},
+                        {"role": "user", "content": "Why?"}
+                    ]
+                }),
+                openai: json!({
+                    "model": "deepseek-reasoner",
+                    "messages": [
+                        {"role": "user", "content": "Question"},
+                        {"role": "assistant", "content": "Answer", "reasoning_content": "step 1\n\nstep 2"},
+                        {"role": "user", "content": "Why?"}
+                    ],
+                    "reasoning_effort": "high"
+                }),
+            },
+            RequestFixture {
+                // 未指定预算时取 medium；disabled 不发送 reasoning_effort
+                name: "thinking_without_budget_and_disabled",
+                anthropic: json!({
+                    "model": "gpt-5",
+                    "thinking": {"type": "enabled"},
+                    "messages": [{"role": "user", "content": [
+                        {"type": "thinking", "thinking": "user thinking is ignored", "signature": ""},
+                        {"type": "text", "text": "Hi"}
+                    ]}]
+                }),
+                openai: json!({
+                    "model": "gpt-5",
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "reasoning_effort": "medium"
+                }),
+            },
+            RequestFixture {
+                name: "thinking_disabled",
+                anthropic: json!({
+                    "model": "gpt-5",
+                    "thinking": {"type": "disabled"},
+                    "messages": [{"role": "user", "content": "Hi"}]
+                }),
+                openai: json!({
+                    "model": "gpt-5",
+                    "messages": [{"role": "user", "content": "Hi"}]
+                }),
+            },
+        ]
     }
 
-    #[test]
-    fn test_anthropic_to_openai_tool_use() {
-        let input = json!({
-            "model": "claude-3-opus",
-            "max_tokens": 1024,
-            "messages": [{
-                "role": "assistant",
-                "content": [
-                    {"type": "text", "text": "Let me check"},
-                    {"type": "tool_use", "id": "call_123", "name": "get_weather", "input": {"location": "Tokyo"}}
-                ]
-            }]
-        });
-
-        let result = anthropic_to_openai(input).unwrap();
-        let msg = &result["messages"][0];
-        assert_eq!(msg["role"], "assistant");
-        assert!(msg.get("tool_calls").is_some());
-        assert_eq!(msg["tool_calls"][0]["id"], "call_123");
+    fn response_fixtures() -> Vec<ResponseFixture> {
+        vec![
+            ResponseFixture {
+                name: "plain_text",
+                openai: json!({
+                    "id": "chatcmpl-123",
+                    "object": "chat.completion",
+                    "created": 1234567890,
+                    "model": "gpt-4",
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": "Hello!"},
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+                }),
+                anthropic: json!({
+                    "id": "chatcmpl-123",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Hello!"}],
+                    "model": "gpt-4",
+                    "stop_reason": "end_turn",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 10, "output_tokens": 5}
+                }),
+            },
+            ResponseFixture {
+                // 部分供应商返回 tool_calls 时 finish_reason 仍为 stop
+                name: "tool_call_without_text",
+                openai: json!({
+                    "id": "chatcmpl-124",
+                    "model": "gpt-4",
+                    "choices": [{
+                        "index": 0,
+                        "message": {
+                            "role": "assistant",
+                            "content": null,
+                            "tool_calls": [{
+                                "id": "call_123",
+                                "type": "function",
+                                "function": {"name": "get_weather", "arguments": "{\"location\":\"Tokyo\"}"}
+                            }]
+                        },
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"prompt_tokens": 10, "completion_tokens": 5}
+                }),
+                anthropic: json!({
+                    "id": "chatcmpl-124",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [
+                        {"type": "tool_use", "id": "call_123", "name": "get_weather", "input": {"location": "Tokyo"}}
+                    ],
+                    "model": "gpt-4",
+                    "stop_reason": "tool_use",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 10, "output_tokens": 5}
+                }),
+            },
+            ResponseFixture {
+                name: "parallel_tool_calls_with_text",
+                openai: json!({
+                    "id": "chatcmpl-rt",
+                    "model": "qwen3-coder",
+                    "choices": [{
+                        "index": 0,
+                        "message": {
+                            "role": "assistant",
+                            "content": "Reading both files.",
+                            "tool_calls": [
+                                {"id": "call_a", "type": "function", "function": {"name": "Read", "arguments": "{\"path\":\"a.rs\"}"}},
+                                {"id": "call_b", "type": "function", "function": {"name": "Read", "arguments": "{\"path\":\"b.rs\"}"}}
+                            ]
+                        },
+                        "finish_reason": "tool_calls"
+                    }],
+                    "usage": {"prompt_tokens": 20, "completion_tokens": 10, "prompt_tokens_details": {"cached_tokens": 8}}
+                }),
+                anthropic: json!({
+                    "id": "chatcmpl-rt",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [
+                        {"type": "text", "text": "Reading both files."},
+                        {"type": "tool_use", "id": "call_a", "name": "Read", "input": {"path": "a.rs"}},
+                        {"type": "tool_use", "id": "call_b", "name": "Read", "input": {"path": "b.rs"}}
+                    ],
+                    "model": "qwen3-coder",
+                    "stop_reason": "tool_use",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 12, "output_tokens": 10, "cache_read_input_tokens": 8}
+                }),
+            },
+            ResponseFixture {
+                // DeepSeek 推理模型：工具调用轮次需要在下一轮请求中带回 reasoning_content
+                name: "reasoning_with_tool_call_and_cached_usage",
+                openai: json!({
+                    "id": "chatcmpl-1",
+                    "model": "deepseek-reasoner",
+                    "choices": [{
+                        "index": 0,
+                        "message": {
+                            "role": "assistant",
+                            "content": "Let me check.",
+                            "tool_calls": [
+                                {"id": "call_1", "type": "function", "function": {"name": "Bash", "arguments": "{\"command\":\"ls\"}"}}
+                            ],
+                            "reasoning_content": "Need to list files first."
+                        },
+                        "finish_reason": "tool_calls"
+                    }],
+                    "usage": {"prompt_tokens": 100, "completion_tokens": 7, "prompt_cache_hit_tokens": 60}
+                }),
+                anthropic: json!({
+                    "id": "chatcmpl-1",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [
+                        {"type": "thinking", "thinking": "Need to list files first.", "signature": ""},
+                        {"type": "text", "text": "Let me check."},
+                        {"type": "tool_use", "id": "call_1", "name": "Bash", "input": {"command": "ls"}}
+                    ],
+                    "model": "deepseek-reasoner",
+                    "stop_reason": "tool_use",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 40, "output_tokens": 7, "cache_read_input_tokens": 60}
+                }),
+            },
+            ResponseFixture {
+                name: "content_filter_maps_to_refusal",
+                openai: json!({
+                    "choices": [{"message": {"role": "assistant", "content": "x"}, "finish_reason": "content_filter"}]
+                }),
+                anthropic: json!({
+                    "id": "",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "x"}],
+                    "model": "",
+                    "stop_reason": "refusal",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0}
+                }),
+            },
+            ResponseFixture {
+                name: "unknown_finish_reason_defaults_to_end_turn",
+                openai: json!({
+                    "choices": [{"message": {"role": "assistant", "content": "x"}, "finish_reason": "weird_vendor_reason"}]
+                }),
+                anthropic: json!({
+                    "id": "",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "x"}],
+                    "model": "",
+                    "stop_reason": "end_turn",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0}
+                }),
+            },
+            ResponseFixture {
+                name: "length_maps_to_max_tokens",
+                openai: json!({
+                    "choices": [{"message": {"role": "assistant", "content": "x"}, "finish_reason": "length"}]
+                }),
+                anthropic: json!({
+                    "id": "",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "x"}],
+                    "model": "",
+                    "stop_reason": "max_tokens",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0}
+                }),
+            },
+        ]
     }
 
     #[test]
-    fn test_anthropic_to_openai_tool_result() {
-        let input = json!({
-            "model": "claude-3-opus",
-            "max_tokens": 1024,
-            "messages": [{
-                "role": "user",
-                "content": [
-                    {"type": "tool_result", "tool_use_id": "call_123", "content": "Sunny, 25°C"}
-                ]
-            }]
-        });
-
-        let result = anthropic_to_openai(input).unwrap();
-        let msg = &result["messages"][0];
-        assert_eq!(msg["role"], "tool");
-        assert_eq!(msg["tool_call_id"], "call_123");
-        assert_eq!(msg["content"], "Sunny, 25°C");
+    fn test_request_fixtures() {
+        for fixture in request_fixtures() {
+            let result = anthropic_to_openai(fixture.anthropic).unwrap();
+            assert_eq!(result, fixture.openai, "request fixture: {}", fixture.name);
+        }
     }
 
     #[test]
-    fn test_openai_to_anthropic_simple() {
-        let input = json!({
-            "id": "chatcmpl-123",
-            "object": "chat.completion",
-            "created": 1234567890,
-            "model": "gpt-4",
-            "choices": [{
-                "index": 0,
-                "message": {"role": "assistant", "content": "Hello!"},
-                "finish_reason": "stop"
-            }],
-            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
-        });
-
-        let result = openai_to_anthropic(input).unwrap();
-        assert_eq!(result["id"], "chatcmpl-123");
-        assert_eq!(result["type"], "message");
-        assert_eq!(result["content"][0]["type"], "text");
-        assert_eq!(result["content"][0]["text"], "Hello!");
-        assert_eq!(result["stop_reason"], "end_turn");
-        assert_eq!(result["usage"]["input_tokens"], 10);
-        assert_eq!(result["usage"]["output_tokens"], 5);
+    fn test_response_fixtures() {
+        for fixture in response_fixtures() {
+            let result = openai_to_anthropic(fixture.openai).unwrap();
+            assert_eq!(
+                result, fixture.anthropic,
+                "response fixture: {}",
+                fixture.name
+            );
+        }
     }
 
+    /// 往返：OpenAI 响应 → Anthropic 响应 → 客户端回传为 assistant 消息 → OpenAI 请求，
+    /// 回传的 assistant 消息应与上游原始 message 一致
     #[test]
-    fn test_openai_to_anthropic_with_tool_calls() {
-        let input = json!({
-            "id": "chatcmpl-123",
-            "object": "chat.completion",
-            "created": 1234567890,
-            "model": "gpt-4",
-            "choices": [{
-                "index": 0,
-                "message": {
-                    "role": "assistant",
-                    "content": null,
-                    "tool_calls": [{
-                        "id": "call_123",
-                        "type": "function",
-                        "function": {"name": "get_weather", "arguments": "{\"location\": \"Tokyo\"}"}
-                    }]
-                },
-                "finish_reason": "tool_calls"
-            }],
-            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
-        });
+    fn test_response_round_trip() {
+        for fixture in response_fixtures() {
+            let original = fixture.openai["choices"][0]["message"].clone();
+            let model = fixture.openai["model"].as_str().unwrap_or("gpt-4o");
+            let response = openai_to_anthropic(fixture.openai.clone()).unwrap();
+
+            let follow_up = anthropic_to_openai(json!({
+                "model": model,
+                "messages": [
+                    {"role": "user", "content": "Hi"},
+                    {"role": "assistant", "content": response["content"]}
+                ]
+            }))
+            .unwrap();
 
-        let result = openai_to_anthropic(input).unwrap();
-        assert_eq!(result["content"][0]["type"], "tool_use");
-        assert_eq!(result["content"][0]["id"], "call_123");
-        assert_eq!(result["content"][0]["name"], "get_weather");
-        assert_eq!(result["content"][0]["input"]["location"], "Tokyo");
-        assert_eq!(result["stop_reason"], "tool_use");
+            assert_eq!(
+                follow_up["messages"][1], original,
+                "round trip fixture: {}",
+                fixture.name
+            );
+        }
     }
 
     #[test]
-    fn test_model_passthrough() {
-        // 格式转换层只做结构转换，模型映射由上游 proxy::model_mapper 处理
-        let input = json!({
-            "model": "gpt-4o",
-            "max_tokens": 1024,
-            "messages": [{"role": "user", "content": "Hello"}]
-        });
-
-        let result = anthropic_to_openai(input).unwrap();
-        assert_eq!(result["model"], "gpt-4o");
+    fn test_response_without_choices_is_error() {
+        assert!(openai_to_anthropic(json!({"id": "x"})).is_err());
+        assert!(openai_to_anthropic(json!({"choices": []})).is_err());
     }
 }
@@ -0,0 +1,739 @@
+//! Gemini 格式转换模块
+//!
+//! 实现 Anthropic Messages ↔ Gemini generateContent 格式转换，
+//! 用于把 Claude Code 请求转发到 Gemini 供应商（Claude 供应商 `apiFormat = "gemini"`）。
+//!
+//! 覆盖范围：
+//! - system → systemInstruction，assistant → model 角色，相邻同角色消息合并
+//! - 图片（base64 → inlineData，url → fileData）、文本型 document
+//! - tool_use ↔ functionCall，tool_result → functionResponse（按 tool_use_id 回查函数名）
+//! - tool_choice → toolConfig.functionCallingConfig，thinking → thinkingConfig
+//! - thoughtSignature：响应侧放入 thinking 块的 signature，请求侧再挂回其后的第一个 part
+
+use crate::proxy::error::ProxyError;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Gemini functionDeclarations 不接受的 JSON Schema 关键字
+const UNSUPPORTED_SCHEMA_KEYS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$ref",
+    "$defs",
+    "definitions",
+    "additionalProperties",
+    "default",
+    "examples",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "propertyNames",
+    "patternProperties",
+    "const",
+];
+
+/// Anthropic 请求 → Gemini 请求
+///
+/// 模型名与是否流式由 URL 决定（见 `ClaudeAdapter::transform_endpoint`），不写入请求体。
+pub fn anthropic_to_gemini(body: Value) -> Result<Value, ProxyError> {
+    let mut result = json!({});
+
+    if let Some(system) = body.get("system") {
+        if let Some(instruction) = convert_system(system) {
+            result["systemInstruction"] = instruction;
+        }
+    }
+
+    let messages = body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .ok_or_else(|| ProxyError::TransformError("Missing messages array".to_string()))?;
+
+    // tool_result 只带 tool_use_id，而 functionResponse 需要函数名
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+    let mut contents: Vec<Value> = Vec::new();
+
+    for msg in messages {
+        let role = match msg.get("role").and_then(|r| r.as_str()) {
+            Some("assistant") => "model",
+            _ => "user",
+        };
+        let parts = convert_content_to_parts(msg.get("content"), &mut tool_names);
+        if parts.is_empty() {
+            continue;
+        }
+
+        // Gemini 要求 user / model 交替出现
+        match contents.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(existing) = last["parts"].as_array_mut() {
+                    existing.extend(parts);
+                }
+            }
+            _ => contents.push(json!({"role": role, "parts": parts})),
+        }
+    }
+    result["contents"] = json!(contents);
+
+    let mut generation_config = Map::new();
+    if let Some(v) = body.get("max_tokens") {
+        generation_config.insert("maxOutputTokens".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("temperature") {
+        generation_config.insert("temperature".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("top_p") {
+        generation_config.insert("topP".to_string(), v.clone());
+    }
+    if let Some(v) = body.get("top_k") {
+        generation_config.insert("topK".to_string(), v.clone());
+    }
+    if let Some(stop) = body
+        .get("stop_sequences")
+        .and_then(|s| s.as_array())
+        .filter(|s| !s.is_empty())
+    {
+        generation_config.insert("stopSequences".to_string(), json!(stop));
+    }
+    if let Some(thinking) = body.get("thinking") {
+        if thinking.get("type").and_then(|t| t.as_str()) == Some("enabled") {
+            let mut thinking_config = json!({"includeThoughts": true});
+            if let Some(budget) = thinking.get("budget_tokens") {
+                thinking_config["thinkingBudget"] = budget.clone();
+            }
+            generation_config.insert("thinkingConfig".to_string(), thinking_config);
+        }
+    }
+    if !generation_config.is_empty() {
+        result["generationConfig"] = Value::Object(generation_config);
+    }
+
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        let declarations: Vec<Value> = tools
+            .iter()
+            .filter(|t| t.get("input_schema").is_some())
+            .map(|t| {
+                let mut declaration = json!({
+                    "name": t.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                    "parameters": clean_schema(t.get("input_schema").cloned().unwrap_or(json!({})))
+                });
+                if let Some(description) = t.get("description") {
+                    declaration["description"] = description.clone();
+                }
+                declaration
+            })
+            .collect();
+
+        if !declarations.is_empty() {
+            result["tools"] = json!([{"functionDeclarations": declarations}]);
+
+            if let Some(config) = body.get("tool_choice").and_then(convert_tool_choice) {
+                result["toolConfig"] = json!({"functionCallingConfig": config});
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// system（字符串或文本块数组）→ systemInstruction
+fn convert_system(system: &Value) -> Option<Value> {
+    let parts: Vec<Value> = match system {
+        Value::String(text) if !text.is_empty() => vec![json!({"text": text})],
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .filter(|t| !t.is_empty())
+            .map(|text| json!({"text": text}))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    (!parts.is_empty()).then(|| json!({"parts": parts}))
+}
+
+/// Anthropic tool_choice → Gemini functionCallingConfig
+fn convert_tool_choice(choice: &Value) -> Option<Value> {
+    match choice.get("type").and_then(|t| t.as_str())? {
+        "auto" => Some(json!({"mode": "AUTO"})),
+        "any" => Some(json!({"mode": "ANY"})),
+        "none" => Some(json!({"mode": "NONE"})),
+        "tool" => {
+            let name = choice.get("name").and_then(|n| n.as_str())?;
+            Some(json!({"mode": "ANY", "allowedFunctionNames": [name]}))
+        }
+        _ => None,
+    }
+}
+
+/// 图片块 → inlineData / fileData
+fn image_part(block: &Value) -> Option<Value> {
+    let source = block.get("source")?;
+    let media_type = source.get("media_type").and_then(|m| m.as_str());
+
+    match source.get("type").and_then(|t| t.as_str()) {
+        Some("base64") => Some(json!({
+            "inlineData": {
+                "mimeType": media_type.unwrap_or("image/png"),
+                "data": source.get("data").and_then(|d| d.as_str()).unwrap_or("")
+            }
+        })),
+        Some("url") => {
+            let mut file_data = json!({
+                "fileUri": source.get("url").and_then(|u| u.as_str()).unwrap_or("")
+            });
+            if let Some(media_type) = media_type {
+                file_data["mimeType"] = json!(media_type);
+            }
+            Some(json!({"fileData": file_data}))
+        }
+        _ => None,
+    }
+}
+
+/// tool_result 的 content（字符串 / 块数组）→ 文本 + 附带的图片 parts
+fn convert_tool_result_content(content: Option<&Value>) -> (String, Vec<Value>) {
+    match content {
+        Some(Value::String(text)) => (text.clone(), Vec::new()),
+        Some(Value::Array(blocks)) => {
+            let mut texts = Vec::new();
+            let mut images = Vec::new();
+            for block in blocks {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                            texts.push(text);
+                        }
+                    }
+                    Some("image") => images.extend(image_part(block)),
+                    _ => {}
+                }
+            }
+            (texts.join("\n"), images)
+        }
+        Some(other) if !other.is_null() => (other.to_string(), Vec::new()),
+        _ => (String::new(), Vec::new()),
+    }
+}
+
+/// 单条消息的 content → Gemini parts
+fn convert_content_to_parts(
+    content: Option<&Value>,
+    tool_names: &mut HashMap<String, String>,
+) -> Vec<Value> {
+    let blocks = match content {
+        Some(Value::String(text)) => return vec![json!({"text": text})],
+        Some(Value::Array(blocks)) => blocks,
+        _ => return Vec::new(),
+    };
+
+    let mut parts = Vec::new();
+    // 上一个 thinking 块携带的 thoughtSignature，需要挂到紧随其后的 part 上
+    let mut pending_signature: Option<String> = None;
+
+    for block in blocks {
+        let mut part = match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => match block.get("text").and_then(|t| t.as_str()) {
+                Some(text) if !text.is_empty() => json!({"text": text}),
+                _ => continue,
+            },
+            Some("image") => match image_part(block) {
+                Some(part) => part,
+                None => continue,
+            },
+            Some("document") => {
+                let source = block.get("source");
+                if source.and_then(|s| s.get("type")).and_then(|t| t.as_str()) != Some("text") {
+                    continue;
+                }
+                let text = source
+                    .and_then(|s| s.get("data"))
+                    .and_then(|d| d.as_str())
+                    .unwrap_or("");
+                json!({"text": text})
+            }
+            Some("tool_use") => {
+                let id = block.get("id").and_then(|i| i.as_str()).unwrap_or("");
+                let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                tool_names.insert(id.to_string(), name.to_string());
+                json!({
+                    "functionCall": {
+                        "name": name,
+                        "args": block.get("input").cloned().unwrap_or(json!({}))
+                    }
+                })
+            }
+            Some("tool_result") => {
+                let id = block
+                    .get("tool_use_id")
+                    .and_then(|i| i.as_str())
+                    .unwrap_or("");
+                let name = tool_names
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_else(|| id.to_string());
+                let (text, images) = convert_tool_result_content(block.get("content"));
+                let is_error = block
+                    .get("is_error")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false);
+                let response = if is_error {
+                    json!({"error": text})
+                } else {
+                    json!({"content": text})
+                };
+                parts.push(json!({
+                    "functionResponse": {"name": name, "response": response}
+                }));
+                parts.extend(images);
+                continue;
+            }
+            Some("thinking") => {
+                pending_signature = block
+                    .get("signature")
+                    .and_then(|s| s.as_str())
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string);
+                continue;
+            }
+            _ => continue,
+        };
+
+        if let Some(signature) = pending_signature.take() {
+            part["thoughtSignature"] = json!(signature);
+        }
+        parts.push(part);
+    }
+
+    parts
+}
+
+/// 清理 JSON schema（移除 Gemini 不支持的关键字）
+fn clean_schema(mut schema: Value) -> Value {
+    if let Some(obj) = schema.as_object_mut() {
+        for key in UNSUPPORTED_SCHEMA_KEYS {
+            obj.remove(*key);
+        }
+        // Gemini 仅支持 enum / date-time 两种 format
+        if let Some(format) = obj.get("format").and_then(|f| f.as_str()) {
+            if format != "enum" && format != "date-time" {
+                obj.remove("format");
+            }
+        }
+
+        if let Some(properties) = obj.get_mut("properties").and_then(|v| v.as_object_mut()) {
+            for (_, value) in properties.iter_mut() {
+                *value = clean_schema(value.take());
+            }
+        }
+
+        if let Some(items) = obj.get_mut("items") {
+            *items = clean_schema(items.take());
+        }
+
+        for key in ["anyOf", "oneOf", "allOf"] {
+            if let Some(variants) = obj.get_mut(key).and_then(|v| v.as_array_mut()) {
+                for variant in variants.iter_mut() {
+                    *variant = clean_schema(variant.take());
+                }
+            }
+        }
+    }
+    schema
+}
+
+/// Gemini finishReason → Anthropic stop_reason
+pub(crate) fn map_finish_reason(finish_reason: &str) -> &'static str {
+    match finish_reason {
+        "MAX_TOKENS" => "max_tokens",
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" | "IMAGE_SAFETY" => {
+            "refusal"
+        }
+        _ => "end_turn",
+    }
+}
+
+/// Gemini usageMetadata → Anthropic usage
+///
+/// promptTokenCount 包含缓存命中部分，需扣除 cachedContentTokenCount；
+/// 思考 token 计入 output_tokens。
+pub(crate) fn convert_usage(usage: &Value) -> Value {
+    let get = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let cached_tokens = get("cachedContentTokenCount");
+
+    let mut result = Map::new();
+    result.insert(
+        "input_tokens".to_string(),
+        json!(get("promptTokenCount").saturating_sub(cached_tokens)),
+    );
+    result.insert(
+        "output_tokens".to_string(),
+        json!(get("candidatesTokenCount") + get("thoughtsTokenCount")),
+    );
+    if cached_tokens > 0 {
+        result.insert("cache_read_input_tokens".to_string(), json!(cached_tokens));
+    }
+    Value::Object(result)
+}
+
+/// 生成 Anthropic 风格的 tool_use id（Gemini functionCall 通常不带 id）
+pub(crate) fn tool_use_id(function_call: &Value) -> String {
+    function_call
+        .get("id")
+        .and_then(|i| i.as_str())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("toolu_{}", uuid::Uuid::new_v4().simple()))
+}
+
+/// Gemini 响应 → Anthropic 响应
+///
+/// `model` 用于响应中缺少 modelVersion 时回填。
+pub fn gemini_to_anthropic(body: Value, model: &str) -> Result<Value, ProxyError> {
+    if let Some(error) = body.get("error") {
+        return Err(ProxyError::TransformError(format!(
+            "Gemini error: {}",
+            error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown")
+        )));
+    }
+
+    let candidate = body
+        .get("candidates")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first());
+
+    let mut content: Vec<Value> = Vec::new();
+    let mut has_tool_use = false;
+
+    if let Some(parts) = candidate
+        .and_then(|c| c.get("content"))
+        .and_then(|c| c.get("parts"))
+        .and_then(|p| p.as_array())
+    {
+        for part in parts {
+            let signature = part.get("thoughtSignature").and_then(|s| s.as_str());
+            let is_thought = part
+                .get("thought")
+                .and_then(|t| t.as_bool())
+                .unwrap_or(false);
+
+            if is_thought {
+                let text = part.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                content.push(json!({
+                    "type": "thinking",
+                    "thinking": text,
+                    "signature": signature.unwrap_or("")
+                }));
+                continue;
+            }
+
+            if let Some(signature) = signature {
+                attach_signature(&mut content, signature);
+            }
+
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                if !text.is_empty() {
+                    content.push(json!({"type": "text", "text": text}));
+                }
+            } else if let Some(function_call) = part.get("functionCall") {
+                has_tool_use = true;
+                content.push(json!({
+                    "type": "tool_use",
+                    "id": tool_use_id(function_call),
+                    "name": function_call.get("name").and_then(|n| n.as_str()).unwrap_or(""),
+                    "input": function_call.get("args").cloned().unwrap_or(json!({}))
+                }));
+            }
+        }
+    }
+
+    let stop_reason = if has_tool_use {
+        "tool_use"
+    } else if candidate.is_none() && body.get("promptFeedback").is_some() {
+        // 整个 prompt 被拦截时没有 candidates
+        "refusal"
+    } else {
+        map_finish_reason(
+            candidate
+                .and_then(|c| c.get("finishReason"))
+                .and_then(|f| f.as_str())
+                .unwrap_or("STOP"),
+        )
+    };
+
+    let usage = body
+        .get("usageMetadata")
+        .map(convert_usage)
+        .unwrap_or_else(|| json!({"input_tokens": 0, "output_tokens": 0}));
+
+    Ok(json!({
+        "id": body
+            .get("responseId")
+            .and_then(|i| i.as_str())
+            .map(|id| format!("msg_{id}"))
+            .unwrap_or_else(|| format!("msg_{}", uuid::Uuid::new_v4().simple())),
+        "type": "message",
+        "role": "assistant",
+        "content": content,
+        "model": body.get("modelVersion").and_then(|m| m.as_str()).unwrap_or(model),
+        "stop_reason": stop_reason,
+        "stop_sequence": null,
+        "usage": usage
+    }))
+}
+
+/// 非思考 part 上的 thoughtSignature：写入紧邻的空签名 thinking 块，没有则补一个
+fn attach_signature(content: &mut Vec<Value>, signature: &str) {
+    if let Some(last) = content.last_mut() {
+        if last["type"] == "thinking" && last["signature"].as_str().unwrap_or("").is_empty() {
+            last["signature"] = json!(signature);
+            return;
+        }
+    }
+    content.push(json!({"type": "thinking", "thinking": "", "signature": signature}));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anthropic_to_gemini_simple() {
+        let input = json!({
+            "model": "gemini-2.5-pro",
+            "max_tokens": 1024,
+            "temperature": 0.2,
+            "stop_sequences": ["END"],
+            "system": [{"type": "text", "text": "Be brief.", "cache_control": {"type": "ephemeral"}}],
+            "messages": [
+                {"role": "user", "content": "Hello"},
+                {"role": "assistant", "content": [{"type": "text", "text": "Hi"}]},
+                {"role": "user", "content": "Bye"}
+            ],
+            "stream": true
+        });
+
+        let result = anthropic_to_gemini(input).unwrap();
+        assert!(result.get("model").is_none());
+        assert!(result.get("stream").is_none());
+        assert_eq!(
+            result["systemInstruction"],
+            json!({"parts": [{"text": "Be brief."}]})
+        );
+        assert_eq!(
+            result["contents"],
+            json!([
+                {"role": "user", "parts": [{"text": "Hello"}]},
+                {"role": "model", "parts": [{"text": "Hi"}]},
+                {"role": "user", "parts": [{"text": "Bye"}]}
+            ])
+        );
+        assert_eq!(result["generationConfig"]["maxOutputTokens"], 1024);
+        assert_eq!(result["generationConfig"]["temperature"], 0.2);
+        assert_eq!(result["generationConfig"]["stopSequences"], json!(["END"]));
+    }
+
+    #[test]
+    fn test_anthropic_to_gemini_tools_and_choice() {
+        let input = json!({
+            "model": "gemini-2.5-pro",
+            "messages": [{"role": "user", "content": "Read it"}],
+            "tools": [
+                {"type": "web_search_20250305", "name": "web_search"},
+                {
+                    "name": "Read",
+                    "description": "Read a file",
+                    "input_schema": {
+                        "$schema": "http://json-schema.org/draft-07/schema#",
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "path": {"type": "string", "format": "uri", "default": "a"},
+                            "when": {"type": "string", "format": "date-time"}
+                        },
+                        "required": ["path"]
+                    }
+                }
+            ],
+            "tool_choice": {"type": "tool", "name": "Read"}
+        });
+
+        let result = anthropic_to_gemini(input).unwrap();
+        let declarations = result["tools"][0]["functionDeclarations"]
+            .as_array()
+            .unwrap();
+        assert_eq!(declarations.len(), 1);
+        assert_eq!(declarations[0]["name"], "Read");
+        assert_eq!(
+            declarations[0]["parameters"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string"},
+                    "when": {"type": "string", "format": "date-time"}
+                },
+                "required": ["path"]
+            })
+        );
+        assert_eq!(
+            result["toolConfig"]["functionCallingConfig"],
+            json!({"mode": "ANY", "allowedFunctionNames": ["Read"]})
+        );
+    }
+
+    #[test]
+    fn test_anthropic_to_gemini_thinking_config() {
+        let input = json!({
+            "model": "gemini-2.5-pro",
+            "thinking": {"type": "enabled", "budget_tokens": 4096},
+            "messages": [{"role": "user", "content": "Think"}]
+        });
+
+        let result = anthropic_to_gemini(input).unwrap();
+        assert_eq!(
+            result["generationConfig"]["thinkingConfig"],
+            json!({"includeThoughts": true, "thinkingBudget": 4096})
+        );
+    }
+
+    #[test]
+    fn test_anthropic_to_gemini_images() {
+        let input = json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "AAAA"}},
+                    {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}},
+                    {"type": "text", "text": "What is this?"}
+                ]
+            }]
+        });
+
+        let result = anthropic_to_gemini(input).unwrap();
+        let parts = result["contents"][0]["parts"].as_array().unwrap();
+        assert_eq!(
+            parts[0],
+            json!({"inlineData": {"mimeType": "image/jpeg", "data": "AAAA"}})
+        );
+        assert_eq!(
+            parts[1],
+            json!({"fileData": {"fileUri": "https://example.com/a.png"}})
+        );
+        assert_eq!(parts[2], json!({"text": "What is this?"}));
+    }
+
+    #[test]
+    fn test_gemini_to_anthropic_text_and_usage() {
+        let input = json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"text": "Let me think", "thought": true},
+                    {"text": "Answer"}
+                ]},
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 100,
+                "candidatesTokenCount": 20,
+                "thoughtsTokenCount": 5,
+                "cachedContentTokenCount": 40
+            },
+            "modelVersion": "gemini-2.5-pro",
+            "responseId": "abc"
+        });
+
+        let result = gemini_to_anthropic(input, "fallback").unwrap();
+        assert_eq!(result["id"], "msg_abc");
+        assert_eq!(result["model"], "gemini-2.5-pro");
+        assert_eq!(result["content"][0]["type"], "thinking");
+        assert_eq!(result["content"][0]["thinking"], "Let me think");
+        assert_eq!(
+            result["content"][1],
+            json!({"type": "text", "text": "Answer"})
+        );
+        assert_eq!(result["stop_reason"], "end_turn");
+        assert_eq!(
+            result["usage"],
+            json!({"input_tokens": 60, "output_tokens": 25, "cache_read_input_tokens": 40})
+        );
+    }
+
+    #[test]
+    fn test_gemini_to_anthropic_stop_reasons() {
+        let response = |finish: &str| {
+            gemini_to_anthropic(
+                json!({"candidates": [{"content": {"parts": [{"text": "x"}]}, "finishReason": finish}]}),
+                "m",
+            )
+            .unwrap()
+        };
+        assert_eq!(response("MAX_TOKENS")["stop_reason"], "max_tokens");
+        assert_eq!(response("SAFETY")["stop_reason"], "refusal");
+        assert_eq!(response("OTHER")["stop_reason"], "end_turn");
+
+        let blocked = gemini_to_anthropic(
+            json!({"promptFeedback": {"blockReason": "SAFETY"}}),
+            "gemini-2.5-pro",
+        )
+        .unwrap();
+        assert_eq!(blocked["stop_reason"], "refusal");
+        assert_eq!(blocked["model"], "gemini-2.5-pro");
+    }
+
+    /// 完整往返：Gemini functionCall（带 thoughtSignature）→ Anthropic tool_use → 回传 tool_result → Gemini 请求
+    #[test]
+    fn test_function_call_round_trip() {
+        let gemini_response = json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"functionCall": {"name": "Read", "args": {"path": "a.rs"}}, "thoughtSignature": "sig-1"},
+                    {"functionCall": {"name": "Bash", "args": {"command": "ls"}}}
+                ]},
+                "finishReason": "STOP"
+            }]
+        });
+
+        let anthropic_response = gemini_to_anthropic(gemini_response, "gemini-3-pro").unwrap();
+        assert_eq!(anthropic_response["stop_reason"], "tool_use");
+        let assistant_content = anthropic_response["content"].clone();
+        let blocks = assistant_content.as_array().unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0]["type"], "thinking");
+        assert_eq!(blocks[0]["signature"], "sig-1");
+        assert_eq!(blocks[1]["type"], "tool_use");
+        assert!(blocks[1]["id"].as_str().unwrap().starts_with("toolu_"));
+        let read_id = blocks[1]["id"].as_str().unwrap().to_string();
+        let bash_id = blocks[2]["id"].as_str().unwrap().to_string();
+
+        let follow_up = json!({
+            "model": "gemini-3-pro",
+            "messages": [
+                {"role": "user", "content": "Look around"},
+                {"role": "assistant", "content": assistant_content},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": read_id, "content": [{"type": "text", "text": "fn main() {}"}]},
+                    {"type": "tool_result", "tool_use_id": bash_id, "content": "not found", "is_error": true}
+                ]}
+            ]
+        });
+
+        let gemini_request = anthropic_to_gemini(follow_up).unwrap();
+        assert_eq!(
+            gemini_request["contents"],
+            json!([
+                {"role": "user", "parts": [{"text": "Look around"}]},
+                {"role": "model", "parts": [
+                    {"functionCall": {"name": "Read", "args": {"path": "a.rs"}}, "thoughtSignature": "sig-1"},
+                    {"functionCall": {"name": "Bash", "args": {"command": "ls"}}}
+                ]},
+                {"role": "user", "parts": [
+                    {"functionResponse": {"name": "Read", "response": {"content": "fn main() {}"}}},
+                    {"functionResponse": {"name": "Bash", "response": {"error": "not found"}}}
+                ]}
+            ])
+        );
+    }
+}
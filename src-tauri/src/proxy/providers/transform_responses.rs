@@ -0,0 +1,798 @@
+//! Responses 格式转换模块
+//!
+//! 实现 OpenAI Responses API ↔ Chat Completions 格式转换，
+//! 用于把 Codex 的 `/responses` 请求转发到只支持 `/chat/completions` 的上游
+//! （Codex 供应商 `apiFormat = "openai_chat"`）。
+//!
+//! 覆盖范围：
+//! - instructions → system 消息，developer 角色 → system
+//! - input items：message / function_call / function_call_output / custom_tool_call(_output)
+//! - function / custom（freeform）工具 → Chat function 工具，tool_choice 同步映射
+//! - reasoning.effort → reasoning_effort，max_output_tokens → max_tokens，text.format → response_format
+//! - previous_response_id：Chat Completions 无状态，代理在内存中保存会话历史并按 id 展开
+
+use crate::proxy::error::ProxyError;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// 内存中最多保留的响应历史条数
+const HISTORY_CAPACITY: usize = 256;
+
+/// 响应历史的保留时长
+const HISTORY_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// 自定义工具在 Chat 侧的唯一参数名
+const CUSTOM_TOOL_INPUT_KEY: &str = "input";
+
+/// previous_response_id → 完整会话 items（输入 + 输出）
+#[derive(Default)]
+struct ResponseHistory {
+    entries: HashMap<String, (Instant, Vec<Value>)>,
+    order: VecDeque<String>,
+}
+
+impl ResponseHistory {
+    fn insert(&mut self, id: String, items: Vec<Value>) {
+        if self
+            .entries
+            .insert(id.clone(), (Instant::now(), items))
+            .is_none()
+        {
+            self.order.push_back(id);
+        }
+        while self.order.len() > HISTORY_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<Vec<Value>> {
+        self.entries
+            .get(id)
+            .filter(|(stored_at, _)| stored_at.elapsed() < HISTORY_TTL)
+            .map(|(_, items)| items.clone())
+    }
+}
+
+static RESPONSE_HISTORY: LazyLock<Mutex<ResponseHistory>> =
+    LazyLock::new(|| Mutex::new(ResponseHistory::default()));
+
+/// 保存一次响应的完整会话，供后续 previous_response_id 展开
+pub(crate) fn remember_response(id: &str, mut input: Vec<Value>, output: &[Value]) {
+    input.extend(output.iter().cloned());
+    if let Ok(mut history) = RESPONSE_HISTORY.lock() {
+        history.insert(id.to_string(), input);
+    }
+}
+
+/// 请求是否允许保存会话（Responses API 的 store 默认 true）
+pub(crate) fn should_store(body: &Value) -> bool {
+    body.get("store").and_then(|v| v.as_bool()).unwrap_or(true)
+}
+
+/// 展开 previous_response_id 与 input，得到本次请求的完整 input items
+pub(crate) fn expand_input(body: &Value) -> Result<Vec<Value>, ProxyError> {
+    let mut items = Vec::new();
+
+    if let Some(previous_id) = body.get("previous_response_id").and_then(|v| v.as_str()) {
+        let history = RESPONSE_HISTORY
+            .lock()
+            .ok()
+            .and_then(|history| history.get(previous_id))
+            .ok_or_else(|| {
+                ProxyError::InvalidRequest(format!("previous_response_id not found: {previous_id}"))
+            })?;
+        items.extend(history);
+    }
+
+    match body.get("input") {
+        Some(Value::String(text)) => items.push(json!({
+            "type": "message",
+            "role": "user",
+            "content": [{"type": "input_text", "text": text}]
+        })),
+        Some(Value::Array(input)) => items.extend(input.iter().cloned()),
+        _ => {}
+    }
+
+    Ok(items)
+}
+
+/// 请求中声明的自定义（freeform）工具名
+///
+/// Chat 侧统一为 function 工具，响应回转时据此还原为 custom_tool_call。
+pub(crate) fn custom_tool_names(body: &Value) -> HashSet<String> {
+    body.get("tools")
+        .and_then(|t| t.as_array())
+        .map(|tools| {
+            tools
+                .iter()
+                .filter(|t| t.get("type").and_then(|v| v.as_str()) == Some("custom"))
+                .filter_map(|t| t.get("name").and_then(|v| v.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Responses 请求 → Chat Completions 请求
+pub fn responses_to_chat(body: Value) -> Result<Value, ProxyError> {
+    let mut result = json!({});
+
+    if let Some(model) = body.get("model") {
+        result["model"] = model.clone();
+    }
+
+    let mut messages: Vec<Value> = Vec::new();
+    if let Some(instructions) = body
+        .get("instructions")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+    {
+        messages.push(json!({"role": "system", "content": instructions}));
+    }
+    for item in expand_input(&body)? {
+        convert_input_item(&item, &mut messages);
+    }
+    result["messages"] = json!(messages);
+
+    if let Some(v) = body.get("max_output_tokens") {
+        result["max_tokens"] = v.clone();
+    }
+    for key in ["temperature", "top_p", "parallel_tool_calls", "user"] {
+        if let Some(v) = body.get(key) {
+            result[key] = v.clone();
+        }
+    }
+
+    if let Some(effort) = body
+        .get("reasoning")
+        .and_then(|r| r.get("effort"))
+        .and_then(|v| v.as_str())
+    {
+        result["reasoning_effort"] = json!(effort);
+    }
+
+    if let Some(format) = body.get("text").and_then(|t| t.get("format")) {
+        if let Some(response_format) = convert_text_format(format) {
+            result["response_format"] = response_format;
+        }
+    }
+
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        let chat_tools: Vec<Value> = tools.iter().filter_map(convert_tool).collect();
+        if !chat_tools.is_empty() {
+            result["tools"] = json!(chat_tools);
+            if let Some(choice) = body.get("tool_choice") {
+                result["tool_choice"] = convert_tool_choice(choice);
+            }
+        }
+    }
+
+    if body.get("stream").and_then(|v| v.as_bool()) == Some(true) {
+        result["stream"] = json!(true);
+        result["stream_options"] = json!({"include_usage": true});
+    }
+
+    Ok(result)
+}
+
+/// 单个 input item → Chat 消息
+fn convert_input_item(item: &Value, messages: &mut Vec<Value>) {
+    let item_type = item
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("message");
+
+    match item_type {
+        "message" => {
+            let role = match item.get("role").and_then(|r| r.as_str()) {
+                Some("assistant") => "assistant",
+                Some("system") | Some("developer") => "system",
+                _ => "user",
+            };
+            if let Some(content) = convert_message_content(item.get("content")) {
+                messages.push(json!({"role": role, "content": content}));
+            }
+        }
+        "function_call" | "custom_tool_call" => {
+            let call_id = item
+                .get("call_id")
+                .or_else(|| item.get("id"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let name = item.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            let arguments = if item_type == "custom_tool_call" {
+                let input = item.get("input").and_then(|v| v.as_str()).unwrap_or("");
+                json!({ CUSTOM_TOOL_INPUT_KEY: input }).to_string()
+            } else {
+                item.get("arguments")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("{}")
+                    .to_string()
+            };
+            let tool_call = json!({
+                "id": call_id,
+                "type": "function",
+                "function": {"name": name, "arguments": arguments}
+            });
+
+            // 连续的调用合并到同一条 assistant 消息（并行工具调用）
+            match messages.last_mut() {
+                Some(last) if last["role"] == "assistant" => {
+                    if let Some(calls) = last["tool_calls"].as_array_mut() {
+                        calls.push(tool_call);
+                    } else {
+                        last["tool_calls"] = json!([tool_call]);
+                    }
+                }
+                _ => messages.push(json!({
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [tool_call]
+                })),
+            }
+        }
+        "function_call_output" | "custom_tool_call_output" => {
+            let call_id = item
+                .get("call_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let output = match item.get("output") {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Array(parts)) => parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            messages.push(json!({"role": "tool", "tool_call_id": call_id, "content": output}));
+        }
+        // reasoning 为上游私有的推理摘要，Chat 接口无法回传
+        "reasoning" => {}
+        other => {
+            log::debug!("[Codex] Chat Completions 不支持的 input item 类型: {other}，已忽略");
+        }
+    }
+}
+
+/// message content → Chat content（纯文本合并为字符串，含图片时使用分段数组）
+fn convert_message_content(content: Option<&Value>) -> Option<Value> {
+    match content {
+        Some(Value::String(text)) => Some(json!(text)),
+        Some(Value::Array(parts)) => {
+            let mut chat_parts = Vec::new();
+            let mut has_image = false;
+            for part in parts {
+                match part.get("type").and_then(|t| t.as_str()) {
+                    Some("input_text") | Some("output_text") | Some("text") => {
+                        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                            chat_parts.push(json!({"type": "text", "text": text}));
+                        }
+                    }
+                    Some("refusal") => {
+                        if let Some(text) = part.get("refusal").and_then(|t| t.as_str()) {
+                            chat_parts.push(json!({"type": "text", "text": text}));
+                        }
+                    }
+                    Some("input_image") => {
+                        if let Some(url) = part.get("image_url").and_then(|u| u.as_str()) {
+                            has_image = true;
+                            let mut image_url = json!({"url": url});
+                            if let Some(detail) = part.get("detail") {
+                                image_url["detail"] = detail.clone();
+                            }
+                            chat_parts.push(json!({"type": "image_url", "image_url": image_url}));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if chat_parts.is_empty() {
+                None
+            } else if has_image {
+                Some(json!(chat_parts))
+            } else {
+                let text = chat_parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Some(json!(text))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Responses 工具 → Chat 工具
+///
+/// 内置工具（web_search、local_shell 等）无法在 Chat 接口上执行，直接丢弃。
+fn convert_tool(tool: &Value) -> Option<Value> {
+    let name = tool.get("name").and_then(|v| v.as_str())?;
+    match tool.get("type").and_then(|t| t.as_str()) {
+        Some("function") => {
+            let parameters = tool
+                .get("parameters")
+                .cloned()
+                .unwrap_or_else(|| json!({"type": "object", "properties": {}}));
+            let mut function = json!({"name": name, "parameters": parameters});
+            if let Some(description) = tool.get("description") {
+                function["description"] = description.clone();
+            }
+            Some(json!({"type": "function", "function": function}))
+        }
+        Some("custom") => {
+            let mut description = tool
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            // freeform 工具的语法定义（如 apply_patch 的 lark 语法）写进描述，供模型参考
+            if let Some(definition) = tool
+                .get("format")
+                .and_then(|f| f.get("definition"))
+                .and_then(|v| v.as_str())
+            {
+                description.push_str("\n\nInput format:\n");
+                description.push_str(definition);
+            }
+            Some(json!({
+                "type": "function",
+                "function": {
+                    "name": name,
+                    "description": description,
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            CUSTOM_TOOL_INPUT_KEY: {"type": "string", "description": "Raw tool input"}
+                        },
+                        "required": [CUSTOM_TOOL_INPUT_KEY]
+                    }
+                }
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// tool_choice 映射（Responses 的 function 选择是扁平结构）
+fn convert_tool_choice(choice: &Value) -> Value {
+    match choice {
+        Value::Object(obj) => match obj.get("name").and_then(|v| v.as_str()) {
+            Some(name) => json!({"type": "function", "function": {"name": name}}),
+            None => json!("auto"),
+        },
+        other => other.clone(),
+    }
+}
+
+/// text.format → response_format
+fn convert_text_format(format: &Value) -> Option<Value> {
+    match format.get("type").and_then(|t| t.as_str()) {
+        Some("json_schema") => {
+            let mut json_schema = json!({
+                "name": format.get("name").cloned().unwrap_or_else(|| json!("response")),
+                "schema": format.get("schema").cloned().unwrap_or_else(|| json!({})),
+            });
+            if let Some(strict) = format.get("strict") {
+                json_schema["strict"] = strict.clone();
+            }
+            Some(json!({"type": "json_schema", "json_schema": json_schema}))
+        }
+        Some("json_object") => Some(json!({"type": "json_object"})),
+        _ => None,
+    }
+}
+
+/// 生成 Responses 风格的 id（如 `resp_xxx`、`msg_xxx`）
+pub(crate) fn new_id(prefix: &str) -> String {
+    format!("{prefix}_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Chat usage → Responses usage
+///
+/// Responses 的 input_tokens 包含缓存命中部分，与 Chat 的 prompt_tokens 口径一致。
+pub(crate) fn convert_usage(usage: &Value) -> Value {
+    let input_tokens = usage
+        .get("prompt_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let output_tokens = usage
+        .get("completion_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let cached_tokens = usage
+        .pointer("/prompt_tokens_details/cached_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let reasoning_tokens = usage
+        .pointer("/completion_tokens_details/reasoning_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let total_tokens = usage
+        .get("total_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(input_tokens + output_tokens);
+
+    json!({
+        "input_tokens": input_tokens,
+        "input_tokens_details": {"cached_tokens": cached_tokens},
+        "output_tokens": output_tokens,
+        "output_tokens_details": {"reasoning_tokens": reasoning_tokens},
+        "total_tokens": total_tokens
+    })
+}
+
+/// finish_reason → Responses 的 incomplete 原因（正常结束返回 None）
+pub(crate) fn incomplete_reason(finish_reason: Option<&str>) -> Option<&'static str> {
+    match finish_reason {
+        Some("length") => Some("max_output_tokens"),
+        Some("content_filter") => Some("content_filter"),
+        _ => None,
+    }
+}
+
+/// 工具调用 → function_call / custom_tool_call output item
+pub(crate) fn tool_call_item(
+    item_id: &str,
+    call_id: &str,
+    name: &str,
+    arguments: &str,
+    custom_tools: &HashSet<String>,
+) -> Value {
+    if custom_tools.contains(name) {
+        // 模型偶尔不按 {"input": ...} 包装，此时直接把原始参数作为输入
+        let input = serde_json::from_str::<Value>(arguments)
+            .ok()
+            .and_then(|v| {
+                v.get(CUSTOM_TOOL_INPUT_KEY)
+                    .and_then(|i| i.as_str())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| arguments.to_string());
+        json!({
+            "type": "custom_tool_call",
+            "id": item_id,
+            "status": "completed",
+            "call_id": call_id,
+            "name": name,
+            "input": input
+        })
+    } else {
+        json!({
+            "type": "function_call",
+            "id": item_id,
+            "status": "completed",
+            "call_id": call_id,
+            "name": name,
+            "arguments": arguments
+        })
+    }
+}
+
+/// 组装 Responses 响应对象
+pub(crate) fn build_response(
+    id: &str,
+    model: &str,
+    created_at: u64,
+    status: &str,
+    output: Vec<Value>,
+    usage: Option<Value>,
+    finish_reason: Option<&str>,
+) -> Value {
+    let incomplete_details = match (status, incomplete_reason(finish_reason)) {
+        ("incomplete", Some(reason)) => json!({ "reason": reason }),
+        _ => Value::Null,
+    };
+    json!({
+        "id": id,
+        "object": "response",
+        "created_at": created_at,
+        "status": status,
+        "model": model,
+        "output": output,
+        "usage": usage.unwrap_or(Value::Null),
+        "incomplete_details": incomplete_details,
+        "error": null
+    })
+}
+
+/// Chat Completions 响应 → Responses 响应
+pub fn chat_to_responses(body: Value, custom_tools: &HashSet<String>) -> Result<Value, ProxyError> {
+    let choice = body
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first())
+        .ok_or_else(|| ProxyError::TransformError("No choices in response".to_string()))?;
+    let message = choice
+        .get("message")
+        .ok_or_else(|| ProxyError::TransformError("No message in choice".to_string()))?;
+
+    let mut output = Vec::new();
+
+    if let Some(reasoning) = super::transform::reasoning_text(message) {
+        output.push(json!({
+            "type": "reasoning",
+            "id": new_id("rs"),
+            "summary": [{"type": "summary_text", "text": reasoning}]
+        }));
+    }
+
+    if let Some(text) = message
+        .get("content")
+        .and_then(|c| c.as_str())
+        .filter(|s| !s.is_empty())
+    {
+        output.push(json!({
+            "type": "message",
+            "id": new_id("msg"),
+            "status": "completed",
+            "role": "assistant",
+            "content": [{"type": "output_text", "text": text, "annotations": []}]
+        }));
+    }
+
+    if let Some(tool_calls) = message.get("tool_calls").and_then(|t| t.as_array()) {
+        for call in tool_calls {
+            let call_id = call.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            let name = call
+                .pointer("/function/name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let arguments = call
+                .pointer("/function/arguments")
+                .and_then(|v| v.as_str())
+                .unwrap_or("{}");
+            output.push(tool_call_item(
+                &new_id("fc"),
+                call_id,
+                name,
+                arguments,
+                custom_tools,
+            ));
+        }
+    }
+
+    let finish_reason = choice.get("finish_reason").and_then(|v| v.as_str());
+    let status = if incomplete_reason(finish_reason).is_some() {
+        "incomplete"
+    } else {
+        "completed"
+    };
+    let created_at = body
+        .get("created")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64);
+    let model = body.get("model").and_then(|m| m.as_str()).unwrap_or("");
+
+    Ok(build_response(
+        &new_id("resp"),
+        model,
+        created_at,
+        status,
+        output,
+        body.get("usage").map(convert_usage),
+        finish_reason,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_responses_to_chat_basic() {
+        let input = json!({
+            "model": "gpt-5-codex",
+            "instructions": "You are Codex.",
+            "input": [
+                {"type": "message", "role": "developer", "content": [{"type": "input_text", "text": "sandbox: read-only"}]},
+                {"type": "message", "role": "user", "content": [{"type": "input_text", "text": "Hello"}]}
+            ],
+            "reasoning": {"effort": "high", "summary": "auto"},
+            "max_output_tokens": 1024,
+            "stream": true,
+            "store": false
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        assert_eq!(result["model"], "gpt-5-codex");
+        assert_eq!(result["messages"][0]["role"], "system");
+        assert_eq!(result["messages"][0]["content"], "You are Codex.");
+        assert_eq!(result["messages"][1]["role"], "system");
+        assert_eq!(result["messages"][2]["content"], "Hello");
+        assert_eq!(result["reasoning_effort"], "high");
+        assert_eq!(result["max_tokens"], 1024);
+        assert_eq!(result["stream_options"]["include_usage"], true);
+        assert!(result.get("store").is_none());
+    }
+
+    #[test]
+    fn test_responses_to_chat_string_input() {
+        let result = responses_to_chat(json!({"model": "m", "input": "hi"})).unwrap();
+        assert_eq!(result["messages"][0]["role"], "user");
+        assert_eq!(result["messages"][0]["content"], "hi");
+        assert!(result.get("stream").is_none());
+    }
+
+    #[test]
+    fn test_responses_to_chat_function_calls() {
+        let input = json!({
+            "model": "m",
+            "input": [
+                {"type": "message", "role": "user", "content": [{"type": "input_text", "text": "list files"}]},
+                {"type": "reasoning", "id": "rs_1", "summary": []},
+                {"type": "function_call", "call_id": "call_1", "name": "shell", "arguments": "{\"command\":[\"ls\"]}"},
+                {"type": "function_call", "call_id": "call_2", "name": "shell", "arguments": "{\"command\":[\"pwd\"]}"},
+                {"type": "function_call_output", "call_id": "call_1", "output": "a.txt"},
+                {"type": "function_call_output", "call_id": "call_2", "output": "/tmp"}
+            ],
+            "tools": [
+                {"type": "function", "name": "shell", "description": "Run", "parameters": {"type": "object"}},
+                {"type": "web_search"}
+            ],
+            "tool_choice": "auto",
+            "parallel_tool_calls": true
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["tool_calls"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[1]["tool_calls"][1]["id"], "call_2");
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+        assert_eq!(messages[3]["content"], "/tmp");
+
+        let tools = result["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["function"]["name"], "shell");
+        assert_eq!(result["tool_choice"], "auto");
+        assert_eq!(result["parallel_tool_calls"], true);
+    }
+
+    #[test]
+    fn test_custom_tool_round_trip() {
+        let request = json!({
+            "model": "m",
+            "input": [
+                {"type": "custom_tool_call", "call_id": "call_1", "name": "apply_patch", "input": "*** Begin Patch"},
+                {"type": "custom_tool_call_output", "call_id": "call_1", "output": "Done"}
+            ],
+            "tools": [{"type": "custom", "name": "apply_patch", "description": "Patch files", "format": {"type": "grammar", "definition": "start: patch"}}]
+        });
+        let custom_tools = custom_tool_names(&request);
+        assert!(custom_tools.contains("apply_patch"));
+
+        let chat = responses_to_chat(request).unwrap();
+        let tool = &chat["tools"][0]["function"];
+        assert_eq!(tool["parameters"]["required"][0], "input");
+        assert!(tool["description"]
+            .as_str()
+            .unwrap()
+            .contains("start: patch"));
+        let arguments = chat["messages"][0]["tool_calls"][0]["function"]["arguments"]
+            .as_str()
+            .unwrap();
+        let arguments: Value = serde_json::from_str(arguments).unwrap();
+        assert_eq!(arguments["input"], "*** Begin Patch");
+
+        let response = json!({
+            "model": "m",
+            "choices": [{
+                "message": {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_2",
+                    "type": "function",
+                    "function": {"name": "apply_patch", "arguments": "{\"input\":\"*** End Patch\"}"}
+                }]},
+                "finish_reason": "tool_calls"
+            }]
+        });
+        let result = chat_to_responses(response, &custom_tools).unwrap();
+        assert_eq!(result["output"][0]["type"], "custom_tool_call");
+        assert_eq!(result["output"][0]["call_id"], "call_2");
+        assert_eq!(result["output"][0]["input"], "*** End Patch");
+    }
+
+    #[test]
+    fn test_responses_to_chat_image_and_format() {
+        let input = json!({
+            "model": "m",
+            "input": [{"type": "message", "role": "user", "content": [
+                {"type": "input_text", "text": "What is this?"},
+                {"type": "input_image", "image_url": "data:image/png;base64,AAA"}
+            ]}],
+            "text": {"format": {"type": "json_schema", "name": "answer", "schema": {"type": "object"}, "strict": true}}
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        let content = result["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content[1]["type"], "image_url");
+        assert_eq!(content[1]["image_url"]["url"], "data:image/png;base64,AAA");
+        assert_eq!(result["response_format"]["json_schema"]["name"], "answer");
+        assert_eq!(result["response_format"]["json_schema"]["strict"], true);
+    }
+
+    #[test]
+    fn test_previous_response_id_expansion() {
+        let id = new_id("resp");
+        remember_response(
+            &id,
+            vec![json!({"type": "message", "role": "user", "content": "first"})],
+            &[
+                json!({"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": "answer"}]}),
+            ],
+        );
+
+        let result = responses_to_chat(json!({
+            "model": "m",
+            "previous_response_id": id,
+            "input": "second"
+        }))
+        .unwrap();
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"], "first");
+        assert_eq!(messages[1]["content"], "answer");
+        assert_eq!(messages[2]["content"], "second");
+
+        let missing = responses_to_chat(json!({
+            "model": "m",
+            "previous_response_id": "resp_missing",
+            "input": "x"
+        }));
+        assert!(matches!(missing, Err(ProxyError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_chat_to_responses_with_reasoning_and_usage() {
+        let response = json!({
+            "id": "chatcmpl-1",
+            "created": 1700000000,
+            "model": "deepseek-reasoner",
+            "choices": [{
+                "message": {"role": "assistant", "content": "Hi there", "reasoning_content": "Think"},
+                "finish_reason": "stop"
+            }],
+            "usage": {
+                "prompt_tokens": 100,
+                "completion_tokens": 20,
+                "total_tokens": 120,
+                "prompt_tokens_details": {"cached_tokens": 40},
+                "completion_tokens_details": {"reasoning_tokens": 5}
+            }
+        });
+
+        let result = chat_to_responses(response, &HashSet::new()).unwrap();
+        assert!(result["id"].as_str().unwrap().starts_with("resp_"));
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["output"][0]["type"], "reasoning");
+        assert_eq!(result["output"][0]["summary"][0]["text"], "Think");
+        assert_eq!(result["output"][1]["content"][0]["text"], "Hi there");
+        assert_eq!(result["usage"]["input_tokens"], 100);
+        assert_eq!(result["usage"]["input_tokens_details"]["cached_tokens"], 40);
+        assert_eq!(
+            result["usage"]["output_tokens_details"]["reasoning_tokens"],
+            5
+        );
+    }
+
+    #[test]
+    fn test_chat_to_responses_incomplete() {
+        let response = json!({
+            "model": "m",
+            "choices": [{"message": {"role": "assistant", "content": "cut"}, "finish_reason": "length"}]
+        });
+        let result = chat_to_responses(response, &HashSet::new()).unwrap();
+        assert_eq!(result["status"], "incomplete");
+        assert_eq!(result["incomplete_details"]["reason"], "max_output_tokens");
+    }
+}
@@ -0,0 +1,423 @@
+//! 上游限流处理
+//!
+//! 上游返回 429（限流）/ 529（过载）时，从 `retry-after`、`anthropic-ratelimit-*`、
+//! `x-ratelimit-*` 响应头解析等待时间，让供应商进入限时冷却，而不是计入熔断器。
+//! 冷却中的供应商在还有其他可用供应商时被跳过；只剩它时由转发器在等待预算内等待后重试。
+
+use super::types::RateLimitState;
+use super::ProxyError;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 响应头未给出等待时间时的初始冷却时长（连续限流时翻倍）
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(10);
+/// 未给出等待时间时冷却时长的上限
+const MAX_BACKOFF_COOLDOWN: Duration = Duration::from_secs(120);
+/// 单次冷却时长的上限（防止异常的响应头让供应商长期不可用）
+const MAX_COOLDOWN: Duration = Duration::from_secs(600);
+/// 单次冷却时长的下限
+const MIN_COOLDOWN: Duration = Duration::from_secs(1);
+
+/// 只剩一个供应商时，单个请求累计等待冷却的上限
+pub const RATE_LIMIT_WAIT_BUDGET: Duration = Duration::from_secs(30);
+/// 单个请求对同一供应商的限流重试次数上限
+pub const MAX_RATE_LIMIT_RETRIES: u32 = 2;
+
+/// 是否为限流类状态码（429 Too Many Requests / 529 Overloaded）
+pub fn is_rate_limit_status(status: u16) -> bool {
+    matches!(status, 429 | 529)
+}
+
+/// 从上游响应头解析出的限流信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// `retry-after` / `retry-after-ms` 给出的等待时长
+    pub retry_after: Option<Duration>,
+    /// 配额重置前的等待时长（取已耗尽维度中最晚的重置时间）
+    pub reset_after: Option<Duration>,
+    /// 剩余请求数
+    pub remaining_requests: Option<u64>,
+    /// 剩余 tokens
+    pub remaining_tokens: Option<u64>,
+}
+
+impl RateLimitInfo {
+    /// 建议的等待时长（`retry-after` 优先）
+    pub fn wait(&self) -> Option<Duration> {
+        self.retry_after.or(self.reset_after)
+    }
+}
+
+/// (剩余量响应头, 重置时间响应头)
+const RATE_LIMIT_DIMENSIONS: &[(&str, &str)] = &[
+    (
+        "anthropic-ratelimit-requests-remaining",
+        "anthropic-ratelimit-requests-reset",
+    ),
+    (
+        "anthropic-ratelimit-tokens-remaining",
+        "anthropic-ratelimit-tokens-reset",
+    ),
+    (
+        "anthropic-ratelimit-input-tokens-remaining",
+        "anthropic-ratelimit-input-tokens-reset",
+    ),
+    (
+        "anthropic-ratelimit-output-tokens-remaining",
+        "anthropic-ratelimit-output-tokens-reset",
+    ),
+    (
+        "x-ratelimit-remaining-requests",
+        "x-ratelimit-reset-requests",
+    ),
+    ("x-ratelimit-remaining-tokens", "x-ratelimit-reset-tokens"),
+    ("x-ratelimit-remaining", "x-ratelimit-reset"),
+];
+
+/// 解析限流相关响应头
+pub fn parse_rate_limit_headers(headers: &HeaderMap) -> RateLimitInfo {
+    parse_rate_limit_headers_at(headers, Utc::now())
+}
+
+fn parse_rate_limit_headers_at(headers: &HeaderMap, now: DateTime<Utc>) -> RateLimitInfo {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let number = |name: &str| header(name).and_then(|v| v.parse::<u64>().ok());
+
+    let retry_after = header("retry-after-ms")
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|ms| ms.is_finite() && *ms >= 0.0)
+        .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        .or_else(|| header("retry-after").and_then(|v| parse_retry_after(v, now)));
+
+    // 优先取已耗尽（剩余为 0）维度中最晚的重置时间；都未耗尽时取最早的重置时间
+    let mut exhausted_reset: Option<Duration> = None;
+    let mut earliest_reset: Option<Duration> = None;
+    for (remaining_name, reset_name) in RATE_LIMIT_DIMENSIONS {
+        let Some(reset) = header(reset_name).and_then(|v| parse_reset(v, now)) else {
+            continue;
+        };
+        if number(remaining_name) == Some(0) {
+            exhausted_reset = exhausted_reset.max(Some(reset));
+        }
+        earliest_reset = Some(earliest_reset.map_or(reset, |d| d.min(reset)));
+    }
+
+    RateLimitInfo {
+        retry_after,
+        reset_after: exhausted_reset.or(earliest_reset),
+        remaining_requests: number("anthropic-ratelimit-requests-remaining")
+            .or_else(|| number("x-ratelimit-remaining-requests")),
+        remaining_tokens: number("anthropic-ratelimit-tokens-remaining")
+            .or_else(|| number("anthropic-ratelimit-input-tokens-remaining"))
+            .or_else(|| number("x-ratelimit-remaining-tokens")),
+    }
+}
+
+/// `retry-after`：秒数或 HTTP 日期
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+/// 重置时间：RFC 3339 时间戳（Anthropic）、`6m0s` 形式的时长（OpenAI）、
+/// 秒数或 Unix 时间戳（其他中转服务）
+fn parse_reset(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default());
+    }
+    if let Ok(number) = value.parse::<f64>() {
+        if !number.is_finite() || number < 0.0 {
+            return None;
+        }
+        // 足够大的数字视为 Unix 时间戳（秒）
+        if number >= 1_000_000_000.0 {
+            let at = DateTime::from_timestamp(number as i64, 0)?;
+            return Some((at - now).to_std().unwrap_or_default());
+        }
+        return Some(Duration::from_secs_f64(number));
+    }
+    parse_go_duration(value)
+}
+
+/// 解析 `1h2m3.5s`、`20ms` 形式的时长
+fn parse_go_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0f64;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
+/// 冷却期间直接返回给客户端的 429 错误（附带 Retry-After）
+pub fn cooldown_error(remaining: Duration) -> ProxyError {
+    let secs = remaining.as_secs_f64().ceil() as u64;
+    ProxyError::UpstreamError {
+        status: 429,
+        body: Some(
+            json!({
+                "type": "error",
+                "error": {
+                    "type": "rate_limit_error",
+                    "message": format!("上游限流冷却中，请在 {secs} 秒后重试"),
+                }
+            })
+            .to_string(),
+        ),
+        rate_limit: Some(RateLimitInfo {
+            retry_after: Some(Duration::from_secs(secs)),
+            ..Default::default()
+        }),
+    }
+}
+
+/// 单个供应商的限流冷却记录
+#[derive(Debug, Clone)]
+struct CooldownEntry {
+    status: u16,
+    until: Instant,
+    until_at: DateTime<Utc>,
+    last_limited_at: DateTime<Utc>,
+    hits: u32,
+    info: RateLimitInfo,
+}
+
+/// 供应商限流冷却表（跨请求保持，成功响应后清除）
+#[derive(Default)]
+pub struct RateLimiter {
+    /// key 格式: "app_type:provider_id"
+    entries: Mutex<HashMap<String, CooldownEntry>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次限流响应，返回本次冷却时长
+    pub fn record(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        status: u16,
+        info: &RateLimitInfo,
+    ) -> Duration {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let key = format!("{app_type}:{provider_id}");
+        let hits = entries.get(&key).map_or(0, |entry| entry.hits) + 1;
+        let cooldown = cooldown_for(info, hits);
+        let now = Utc::now();
+        entries.insert(
+            key,
+            CooldownEntry {
+                status,
+                until: Instant::now() + cooldown,
+                until_at: now + chrono::Duration::from_std(cooldown).unwrap_or_default(),
+                last_limited_at: now,
+                hits,
+                info: info.clone(),
+            },
+        );
+        cooldown
+    }
+
+    /// 剩余冷却时长（未在冷却中时返回 None）
+    pub fn remaining(&self, app_type: &str, provider_id: &str) -> Option<Duration> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(&format!("{app_type}:{provider_id}"))
+            .map(|entry| entry.until.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// 供应商恢复正常响应后清除限流记录
+    pub fn clear(&self, app_type: &str, provider_id: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(&format!("{app_type}:{provider_id}"));
+    }
+
+    /// 获取供应商的限流状态（用于健康状态展示）
+    pub fn state(&self, app_type: &str, provider_id: &str) -> Option<RateLimitState> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.get(&format!("{app_type}:{provider_id}"))?;
+        Some(RateLimitState {
+            status: entry.status,
+            cooldown_until: entry.until_at.to_rfc3339(),
+            remaining_secs: entry
+                .until
+                .saturating_duration_since(Instant::now())
+                .as_secs_f64()
+                .ceil() as u64,
+            hits: entry.hits,
+            last_limited_at: entry.last_limited_at.to_rfc3339(),
+            remaining_requests: entry.info.remaining_requests,
+            remaining_tokens: entry.info.remaining_tokens,
+        })
+    }
+}
+
+/// 冷却时长：响应头给出等待时间时按其执行，否则按连续限流次数指数退避
+fn cooldown_for(info: &RateLimitInfo, hits: u32) -> Duration {
+    match info.wait() {
+        Some(wait) => wait.clamp(MIN_COOLDOWN, MAX_COOLDOWN),
+        None => DEFAULT_COOLDOWN
+            .saturating_mul(1u32 << hits.saturating_sub(1).min(8))
+            .min(MAX_BACKOFF_COOLDOWN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_date_and_ms() {
+        let info = parse_rate_limit_headers_at(&headers(&[("retry-after", "7")]), now());
+        assert_eq!(info.retry_after, Some(Duration::from_secs(7)));
+
+        let info = parse_rate_limit_headers_at(
+            &headers(&[("retry-after", "Sun, 01 Jun 2025 12:00:30 GMT")]),
+            now(),
+        );
+        assert_eq!(info.retry_after, Some(Duration::from_secs(30)));
+
+        let info = parse_rate_limit_headers_at(
+            &headers(&[("retry-after-ms", "1500"), ("retry-after", "9")]),
+            now(),
+        );
+        assert_eq!(info.retry_after, Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn parses_anthropic_ratelimit_headers() {
+        let info = parse_rate_limit_headers_at(
+            &headers(&[
+                ("anthropic-ratelimit-requests-remaining", "12"),
+                ("anthropic-ratelimit-requests-reset", "2025-06-01T12:00:05Z"),
+                ("anthropic-ratelimit-tokens-remaining", "0"),
+                ("anthropic-ratelimit-tokens-reset", "2025-06-01T12:00:40Z"),
+            ]),
+            now(),
+        );
+        assert_eq!(info.retry_after, None);
+        assert_eq!(info.reset_after, Some(Duration::from_secs(40)));
+        assert_eq!(info.remaining_requests, Some(12));
+        assert_eq!(info.remaining_tokens, Some(0));
+        assert_eq!(info.wait(), Some(Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn parses_openai_style_reset_durations() {
+        let info = parse_rate_limit_headers_at(
+            &headers(&[
+                ("x-ratelimit-remaining-requests", "0"),
+                ("x-ratelimit-reset-requests", "1m30s"),
+                ("x-ratelimit-remaining-tokens", "5000"),
+                ("x-ratelimit-reset-tokens", "250ms"),
+            ]),
+            now(),
+        );
+        assert_eq!(info.reset_after, Some(Duration::from_secs(90)));
+        assert_eq!(info.remaining_requests, Some(0));
+        assert_eq!(info.remaining_tokens, Some(5000));
+
+        // 都未耗尽时取最早的重置时间
+        let info = parse_rate_limit_headers_at(
+            &headers(&[
+                ("x-ratelimit-reset-tokens", "6m0s"),
+                ("x-ratelimit-reset", "3"),
+            ]),
+            now(),
+        );
+        assert_eq!(info.reset_after, Some(Duration::from_secs(3)));
+
+        assert_eq!(
+            parse_go_duration("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_go_duration("10x"), None);
+    }
+
+    #[test]
+    fn cooldown_uses_headers_or_backs_off() {
+        let hinted = RateLimitInfo {
+            retry_after: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        assert_eq!(cooldown_for(&hinted, 1), MAX_COOLDOWN);
+
+        let none = RateLimitInfo::default();
+        assert_eq!(cooldown_for(&none, 1), Duration::from_secs(10));
+        assert_eq!(cooldown_for(&none, 2), Duration::from_secs(20));
+        assert_eq!(cooldown_for(&none, 10), MAX_BACKOFF_COOLDOWN);
+    }
+
+    #[test]
+    fn limiter_tracks_hits_until_cleared() {
+        let limiter = RateLimiter::new();
+        assert!(limiter.remaining("claude", "p1").is_none());
+
+        let info = RateLimitInfo {
+            retry_after: Some(Duration::from_secs(30)),
+            remaining_requests: Some(0),
+            ..Default::default()
+        };
+        limiter.record("claude", "p1", 429, &info);
+        limiter.record("claude", "p1", 429, &info);
+
+        assert!(limiter.remaining("claude", "p1").is_some());
+        assert!(limiter.remaining("codex", "p1").is_none());
+        let state = limiter.state("claude", "p1").unwrap();
+        assert_eq!(state.status, 429);
+        assert_eq!(state.hits, 2);
+        assert_eq!(state.remaining_requests, Some(0));
+        assert!(state.remaining_secs > 0 && state.remaining_secs <= 30);
+
+        limiter.clear("claude", "p1");
+        assert!(limiter.state("claude", "p1").is_none());
+    }
+}
@@ -0,0 +1,376 @@
+//! 响应缓存
+//!
+//! 对完全相同的非流式请求直接返回缓存的上游响应（按应用启用，默认关闭）。
+//!
+//! - 缓存键：应用类型 + 端点 + 供应商 + 实际发送的模型 + 经 `filter_private_params` 过滤并规范化
+//!   （对象键排序）后的请求体的 SHA-256；切换供应商或修改模型映射后不会命中旧供应商的响应
+//! - 只缓存 2xx 的 JSON 响应，单条响应体超过 [`MAX_CACHED_BODY_BYTES`] 时不缓存
+//! - 命中时记录一条 `cache_hit` 请求日志，成本为 0
+
+use super::{
+    body_filter::filter_private_params, handler_config::UsageParserConfig,
+    handler_context::RequestContext, model_mapper::resolve_model, server::ProxyState,
+    types::ResponseCacheConfig, usage::logger::UsageLogger, usage::parser::TokenUsage,
+};
+use axum::body::{Body, HttpBody};
+use axum::response::Response;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// 单条缓存响应体的大小上限
+pub const MAX_CACHED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// 标记响应来自缓存的响应头
+const CACHE_HEADER: &str = "x-cc-switch-cache";
+
+/// 缓存的上游响应
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub cache_key: String,
+    pub app_type: String,
+    pub endpoint: String,
+    /// 产生该响应的供应商
+    pub provider_id: String,
+    pub model: String,
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// 过滤私有参数并规范化后的请求体
+fn normalize_body(body: &Value) -> String {
+    canonicalize(filter_private_params(body.clone())).to_string()
+}
+
+/// 计算缓存键
+///
+/// `model` 为发送给该供应商的模型（经模型映射后），`normalized_body` 由 [`normalize_body`] 得到
+fn cache_key(
+    app_type: &str,
+    endpoint: &str,
+    provider_id: &str,
+    model: &str,
+    normalized_body: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [app_type, endpoint, provider_id, model] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(normalized_body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// 规范化 JSON：对象键按字典序排列，使字段顺序不同的相同请求得到同一个缓存键
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        other => other,
+    }
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// 单个请求的缓存上下文（仅在应用启用了缓存且请求为非流式时存在）
+pub struct ResponseCacheLookup {
+    /// 首选供应商下的缓存键
+    key: String,
+    endpoint: &'static str,
+    /// 规范化后的请求体（故障转移到其他供应商时据此重新计算缓存键）
+    normalized_body: String,
+    config: ResponseCacheConfig,
+}
+
+impl ResponseCacheLookup {
+    pub async fn new(
+        state: &ProxyState,
+        ctx: &RequestContext,
+        endpoint: &'static str,
+        body: &Value,
+    ) -> Option<Self> {
+        let app_type = ctx.app_type_str;
+        let is_stream = body
+            .get("stream")
+            .and_then(|s| s.as_bool())
+            .unwrap_or(false);
+        if is_stream {
+            return None;
+        }
+
+        let config = match state.db.get_response_cache_config(app_type).await {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("[{app_type}] 读取响应缓存配置失败: {e}");
+                return None;
+            }
+        };
+        if !config.enabled || config.ttl_seconds == 0 || config.max_entries == 0 {
+            return None;
+        }
+
+        let normalized_body = normalize_body(body);
+        let model = resolve_model(body, &ctx.provider);
+        let key = cache_key(
+            app_type,
+            endpoint,
+            &ctx.provider.id,
+            model.as_deref().unwrap_or(&ctx.request_model),
+            &normalized_body,
+        );
+        Some(Self {
+            key,
+            endpoint,
+            normalized_body,
+            config,
+        })
+    }
+
+    /// 查找缓存；命中时记录零成本的请求日志并返回缓存的响应
+    pub async fn cached_response(
+        &self,
+        state: &ProxyState,
+        ctx: &RequestContext,
+        parser_config: &UsageParserConfig,
+    ) -> Option<Response> {
+        let cached = match state.db.get_cached_response(&self.key, now_secs()) {
+            Ok(cached) => cached?,
+            Err(e) => {
+                log::warn!("[{}] 读取响应缓存失败: {e}", ctx.tag);
+                return None;
+            }
+        };
+        log::info!(
+            "[{}] 响应缓存命中: endpoint={}, provider={}",
+            ctx.tag,
+            self.endpoint,
+            cached.provider_id
+        );
+
+        log_cache_hit(state, ctx, &cached, parser_config).await;
+
+        let mut builder = Response::builder()
+            .status(cached.status_code)
+            .header(CACHE_HEADER, "hit");
+        if let Some(content_type) = &cached.content_type {
+            builder = builder.header("content-type", content_type);
+        }
+        match builder.body(Body::from(cached.body)) {
+            Ok(response) => Some(response),
+            Err(e) => {
+                log::warn!("[{}] 构建缓存响应失败: {e}", ctx.tag);
+                None
+            }
+        }
+    }
+
+    /// 缓存成功的非流式响应，返回（重新组装的）原响应
+    pub async fn store(
+        self,
+        state: &ProxyState,
+        ctx: &RequestContext,
+        response: Response,
+    ) -> Response {
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let cacheable = response.status().is_success()
+            && !content_type
+                .as_deref()
+                .is_some_and(|ct| ct.contains("text/event-stream"))
+            && response
+                .body()
+                .size_hint()
+                .exact()
+                .is_some_and(|len| len as usize <= MAX_CACHED_BODY_BYTES);
+        if !cacheable {
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_CACHED_BODY_BYTES).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("[{}] 读取待缓存的响应体失败: {e}", ctx.tag);
+                return Response::from_parts(parts, Body::empty());
+            }
+        };
+
+        let model = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|json| json.get("model")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| ctx.effective_model().to_string());
+        // 按实际产生响应的供应商与模型计算缓存键（发生故障转移时与查找时的键不同）
+        let cache_key = cache_key(
+            ctx.app_type_str,
+            self.endpoint,
+            &ctx.provider.id,
+            ctx.effective_model(),
+            &self.normalized_body,
+        );
+        let cached = CachedResponse {
+            cache_key,
+            app_type: ctx.app_type_str.to_string(),
+            endpoint: self.endpoint.to_string(),
+            provider_id: ctx.provider.id.clone(),
+            model,
+            status_code: parts.status.as_u16(),
+            content_type,
+            body: bytes.to_vec(),
+        };
+        if let Err(e) = state
+            .db
+            .save_cached_response(&cached, &self.config, now_secs())
+        {
+            log::warn!("[{}] 写入响应缓存失败: {e}", ctx.tag);
+        }
+
+        Response::from_parts(parts, Body::from(bytes))
+    }
+}
+
+/// 记录缓存命中（token 用量取自缓存的响应，成本为 0）
+async fn log_cache_hit(
+    state: &ProxyState,
+    ctx: &RequestContext,
+    cached: &CachedResponse,
+    parser_config: &UsageParserConfig,
+) {
+    let usage = serde_json::from_slice::<Value>(&cached.body)
+        .ok()
+        .and_then(|json| (parser_config.response_parser)(&json))
+        .unwrap_or_default();
+    let model = usage.model.clone().unwrap_or_else(|| cached.model.clone());
+
+    let logger = UsageLogger::new(&state.db);
+    let (multiplier, _) = logger
+        .resolve_pricing_config(&cached.provider_id, ctx.app_type_str)
+        .await;
+
+    if let Err(e) = logger.log_cache_hit(
+        ctx.request_id.clone(),
+        cached.provider_id.clone(),
+        ctx.app_type_str.to_string(),
+        model,
+        ctx.request_model.clone(),
+        TokenUsage {
+            model: None,
+            ..usage
+        },
+        multiplier,
+        ctx.latency_ms(),
+        cached.status_code,
+        Some(ctx.session_id.clone()),
+        ctx.virtual_key_id.clone(),
+    ) {
+        log::warn!("[USG-001] 记录缓存命中失败: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key_for(
+        app_type: &str,
+        endpoint: &str,
+        provider_id: &str,
+        model: &str,
+        body: &Value,
+    ) -> String {
+        cache_key(
+            app_type,
+            endpoint,
+            provider_id,
+            model,
+            &normalize_body(body),
+        )
+    }
+
+    #[test]
+    fn cache_key_ignores_field_order_and_private_params() {
+        let a = json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 16,
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+        let b = json!({
+            "_trace_id": "abc",
+            "messages": [{"content": "hi", "role": "user", "_tag": 1}],
+            "max_tokens": 16,
+            "model": "claude-sonnet-4-5"
+        });
+        let key = |body| key_for("claude", "/v1/messages", "p1", "claude-sonnet-4-5", body);
+        assert_eq!(key(&a), key(&b));
+    }
+
+    #[test]
+    fn cache_key_distinguishes_endpoint_app_and_body() {
+        let body = json!({"model": "gpt-5", "messages": [{"role": "user", "content": "hi"}]});
+        let key = key_for("codex", "/chat/completions", "p1", "gpt-5", &body);
+
+        assert_ne!(
+            key,
+            key_for("claude", "/chat/completions", "p1", "gpt-5", &body)
+        );
+        assert_ne!(key, key_for("codex", "/responses", "p1", "gpt-5", &body));
+        assert_ne!(
+            key,
+            key_for(
+                "codex",
+                "/chat/completions",
+                "p1",
+                "gpt-5",
+                &json!({"model": "gpt-5", "messages": [{"role": "user", "content": "hello"}]})
+            )
+        );
+        // 消息顺序有意义，不参与规范化
+        assert_ne!(
+            key_for(
+                "codex",
+                "/chat/completions",
+                "p1",
+                "gpt-5",
+                &json!({"messages": [1, 2]})
+            ),
+            key_for(
+                "codex",
+                "/chat/completions",
+                "p1",
+                "gpt-5",
+                &json!({"messages": [2, 1]})
+            )
+        );
+    }
+
+    #[test]
+    fn cache_key_distinguishes_provider_and_mapped_model() {
+        let body =
+            json!({"model": "claude-sonnet-4-5", "messages": [{"role": "user", "content": "hi"}]});
+        let key = key_for("claude", "/v1/messages", "p1", "claude-sonnet-4-5", &body);
+
+        // 切换供应商或修改模型映射后不应命中旧响应
+        assert_ne!(
+            key,
+            key_for("claude", "/v1/messages", "p2", "claude-sonnet-4-5", &body)
+        );
+        assert_ne!(
+            key,
+            key_for("claude", "/v1/messages", "p1", "glm-4.6", &body)
+        );
+    }
+}
@@ -123,7 +123,7 @@ pub async fn handle_non_streaming(
             } else if let Some(m) = json_value.get("model").and_then(|m| m.as_str()) {
                 m.to_string()
             } else {
-                ctx.request_model.clone()
+                ctx.effective_model().to_string()
             };
 
             spawn_log_usage(
@@ -139,7 +139,7 @@ pub async fn handle_non_streaming(
             let model = json_value
                 .get("model")
                 .and_then(|m| m.as_str())
-                .unwrap_or(&ctx.request_model)
+                .unwrap_or(ctx.effective_model())
                 .to_string();
             spawn_log_usage(
                 state,
@@ -165,7 +165,7 @@ pub async fn handle_non_streaming(
             state,
             ctx,
             TokenUsage::default(),
-            &ctx.request_model,
+            ctx.effective_model(),
             &ctx.request_model,
             status.as_u16(),
             false,
@@ -277,7 +277,7 @@ impl SseUsageCollector {
 // ============================================================================
 
 /// 创建使用量收集器
-fn create_usage_collector(
+pub(crate) fn create_usage_collector(
     ctx: &RequestContext,
     state: &ProxyState,
     status_code: u16,
@@ -286,26 +286,35 @@ fn create_usage_collector(
     let state = state.clone();
     let provider_id = ctx.provider.id.clone();
     let request_model = ctx.request_model.clone();
+    let effective_model = ctx.effective_model().to_string();
     let app_type_str = parser_config.app_type_str;
     let tag = ctx.tag;
     let start_time = ctx.start_time;
     let stream_parser = parser_config.stream_parser;
     let model_extractor = parser_config.model_extractor;
     let session_id = ctx.session_id.clone();
+    let request_id = ctx.request_id.clone();
+    let masked_key = ctx.masked_key.clone();
+    let virtual_key_id = ctx.virtual_key_id.clone();
+    let hedge_role = ctx.hedge_role;
 
     SseUsageCollector::new(start_time, move |events, first_token_ms| {
         if let Some(usage) = stream_parser(&events) {
-            let model = model_extractor(&events, &request_model);
+            let model = model_extractor(&events, &effective_model);
             let latency_ms = start_time.elapsed().as_millis() as u64;
 
             let state = state.clone();
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let masked_key = masked_key.clone();
+            let request_id = request_id.clone();
+            let virtual_key_id = virtual_key_id.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
                     &state,
+                    &request_id,
                     &provider_id,
                     app_type_str,
                     &model,
@@ -316,20 +325,27 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    masked_key,
+                    virtual_key_id,
+                    hedge_role,
                 )
                 .await;
             });
         } else {
-            let model = model_extractor(&events, &request_model);
+            let model = model_extractor(&events, &effective_model);
             let latency_ms = start_time.elapsed().as_millis() as u64;
             let state = state.clone();
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let masked_key = masked_key.clone();
+            let request_id = request_id.clone();
+            let virtual_key_id = virtual_key_id.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
                     &state,
+                    &request_id,
                     &provider_id,
                     app_type_str,
                     &model,
@@ -340,6 +356,9 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    masked_key,
+                    virtual_key_id,
+                    hedge_role,
                 )
                 .await;
             });
@@ -349,7 +368,7 @@ fn create_usage_collector(
 }
 
 /// 异步记录使用量
-fn spawn_log_usage(
+pub(crate) fn spawn_log_usage(
     state: &ProxyState,
     ctx: &RequestContext,
     usage: TokenUsage,
@@ -365,10 +384,15 @@ fn spawn_log_usage(
     let request_model = request_model.to_string();
     let latency_ms = ctx.latency_ms();
     let session_id = ctx.session_id.clone();
+    let request_id = ctx.request_id.clone();
+    let masked_key = ctx.masked_key.clone();
+    let virtual_key_id = ctx.virtual_key_id.clone();
+    let hedge_role = ctx.hedge_role;
 
     tokio::spawn(async move {
         log_usage_internal(
             &state,
+            &request_id,
             &provider_id,
             &app_type_str,
             &model,
@@ -379,6 +403,9 @@ fn spawn_log_usage(
             is_streaming,
             status_code,
             Some(session_id),
+            masked_key,
+            virtual_key_id,
+            hedge_role,
         )
         .await;
     });
@@ -388,6 +415,7 @@ fn spawn_log_usage(
 #[allow(clippy::too_many_arguments)]
 async fn log_usage_internal(
     state: &ProxyState,
+    request_id: &str,
     provider_id: &str,
     app_type: &str,
     model: &str,
@@ -398,6 +426,9 @@ async fn log_usage_internal(
     is_streaming: bool,
     status_code: u16,
     session_id: Option<String>,
+    masked_key: Option<String>,
+    virtual_key_id: Option<String>,
+    hedge_role: Option<&'static str>,
 ) {
     use super::usage::logger::UsageLogger;
 
@@ -410,8 +441,6 @@ async fn log_usage_internal(
         model
     };
 
-    let request_id = uuid::Uuid::new_v4().to_string();
-
     log::debug!(
         "[{app_type}] 记录请求日志: id={request_id}, provider={provider_id}, model={model}, streaming={is_streaming}, status={status_code}, latency_ms={latency_ms}, first_token_ms={first_token_ms:?}, session={}, input={}, output={}, cache_read={}, cache_creation={}",
         session_id.as_deref().unwrap_or("none"),
@@ -422,7 +451,7 @@ async fn log_usage_internal(
     );
 
     if let Err(e) = logger.log_with_calculation(
-        request_id,
+        request_id.to_string(),
         provider_id.to_string(),
         app_type.to_string(),
         model.to_string(),
@@ -436,9 +465,15 @@ async fn log_usage_internal(
         session_id,
         None, // provider_type
         is_streaming,
+        masked_key,
+        virtual_key_id,
+        hedge_role,
     ) {
         log::warn!("[USG-001] 记录使用量失败: {e}");
     }
+    state
+        .provider_router
+        .invalidate_budget_status(app_type, provider_id);
 }
 
 /// 创建带日志记录和超时控制的透传流
@@ -569,7 +604,7 @@ mod tests {
     use crate::provider::ProviderMeta;
     use crate::proxy::failover_switch::FailoverSwitchManager;
     use crate::proxy::provider_router::ProviderRouter;
-    use crate::proxy::types::{ProxyConfig, ProxyStatus};
+    use crate::proxy::types::{ProxyAuthConfig, ProxyConfig, ProxyStatus};
     use rust_decimal::Decimal;
     use std::collections::HashMap;
     use std::str::FromStr;
@@ -586,6 +621,7 @@ mod tests {
             provider_router: Arc::new(ProviderRouter::new(db.clone())),
             app_handle: None,
             failover_manager: Arc::new(FailoverSwitchManager::new(db)),
+            auth: Arc::new(std::sync::RwLock::new(ProxyAuthConfig::default())),
         }
     }
 
@@ -649,6 +685,7 @@ mod tests {
 
         log_usage_internal(
             &state,
+            "req-1",
             "provider-1",
             app_type,
             "resp-model",
@@ -659,6 +696,9 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
+            None,
         )
         .await;
 
@@ -708,6 +748,7 @@ mod tests {
 
         log_usage_internal(
             &state,
+            "req-2",
             "provider-2",
             app_type,
             "resp-model",
@@ -718,6 +759,9 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
+            None,
         )
         .await;
 
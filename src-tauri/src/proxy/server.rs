@@ -3,12 +3,13 @@
 //! 基于Axum的HTTP服务器，处理代理请求
 
 use super::{
-    failover_switch::FailoverSwitchManager, handlers, log_codes::srv as log_srv,
-    provider_router::ProviderRouter, types::*, ProxyError,
+    auth, body_capture, failover_switch::FailoverSwitchManager, handlers, health::HealthChecker,
+    log_codes::srv as log_srv, otlp, provider_router::ProviderRouter, tls, types::*, ProxyError,
 };
 use crate::database::Database;
 use axum::{
     extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -16,7 +17,6 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::{oneshot, RwLock};
 use tokio::task::JoinHandle;
-use tower_http::cors::{Any, CorsLayer};
 
 /// 代理服务器状态（共享）
 #[derive(Clone)]
@@ -33,6 +33,8 @@ pub struct ProxyState {
     pub app_handle: Option<tauri::AppHandle>,
     /// 故障转移切换管理器
     pub failover_manager: Arc<FailoverSwitchManager>,
+    /// 入站访问控制配置（CORS 校验在同步回调中读取，使用标准库锁）
+    pub auth: Arc<std::sync::RwLock<ProxyAuthConfig>>,
 }
 
 /// 代理HTTP服务器
@@ -54,6 +56,10 @@ impl ProxyServer {
         let provider_router = Arc::new(ProviderRouter::new(db.clone()));
         // 创建故障转移切换管理器
         let failover_manager = Arc::new(FailoverSwitchManager::new(db.clone()));
+        let auth_config = db.get_proxy_auth_config().unwrap_or_else(|e| {
+            log::warn!("读取代理访问控制配置失败，使用默认配置: {e}");
+            ProxyAuthConfig::default()
+        });
 
         let state = ProxyState {
             db,
@@ -64,6 +70,7 @@ impl ProxyServer {
             provider_router,
             app_handle,
             failover_manager,
+            auth: Arc::new(std::sync::RwLock::new(auth_config)),
         };
 
         Self {
@@ -91,12 +98,29 @@ impl ProxyServer {
         // 构建路由
         let app = self.build_router();
 
+        // 启用 TLS 时先加载证书，证书有误直接启动失败，避免静默回退为明文 HTTP
+        let tls_config = match self.state.db.get_proxy_tls_config() {
+            Ok(config) if config.enabled => {
+                Some(tls::server_config(&config, &self.config.listen_address)?)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("读取代理 TLS 配置失败，使用 HTTP 监听: {e}");
+                None
+            }
+        };
+        let tls_enabled = tls_config.is_some();
+
         // 绑定监听器
         let listener = tokio::net::TcpListener::bind(&addr)
             .await
             .map_err(|e| ProxyError::BindFailed(e.to_string()))?;
 
-        log::info!("[{}] 代理服务器启动于 {addr}", log_srv::STARTED);
+        log::info!(
+            "[{}] 代理服务器启动于 {}://{addr}",
+            log_srv::STARTED,
+            if tls_enabled { "https" } else { "http" }
+        );
 
         // 更新全局代理端口，用于系统代理检测
         crate::proxy::http_client::set_proxy_port(self.config.listen_port);
@@ -109,20 +133,42 @@ impl ProxyServer {
         status.running = true;
         status.address = self.config.listen_address.clone();
         status.port = self.config.listen_port;
+        status.tls = tls_enabled;
         drop(status);
 
         // 记录启动时间
         *self.state.start_time.write().await = Some(std::time::Instant::now());
 
+        // 加载链路追踪配置并启动 OTLP 批量发送任务
+        match self.state.db.get_telemetry_config() {
+            Ok(config) => otlp::configure(config),
+            Err(e) => log::warn!("读取链路追踪配置失败，OTLP 导出保持关闭: {e}"),
+        }
+        otlp::start_flush_task();
+
+        // 后台健康探测（随服务器停止）
+        let probe_handle =
+            HealthChecker::new(self.state.db.clone(), self.state.provider_router.clone()).spawn();
+
         // 启动服务器
         let state = self.state.clone();
         let handle = tokio::spawn(async move {
-            axum::serve(listener, app)
-                .with_graceful_shutdown(async {
-                    shutdown_rx.await.ok();
-                })
-                .await
-                .ok();
+            match tls_config {
+                Some(tls_config) => tls::serve(listener, app, tls_config, shutdown_rx).await,
+                None => {
+                    axum::serve(
+                        listener,
+                        app.into_make_service_with_connect_info::<SocketAddr>(),
+                    )
+                    .with_graceful_shutdown(async {
+                        shutdown_rx.await.ok();
+                    })
+                    .await
+                    .ok();
+                }
+            }
+
+            probe_handle.abort();
 
             // 服务器停止后更新状态
             state.status.write().await.running = false;
@@ -136,6 +182,7 @@ impl ProxyServer {
             address: self.config.listen_address.clone(),
             port: self.config.listen_port,
             started_at: chrono::Utc::now().to_rfc3339(),
+            tls: tls_enabled,
         })
     }
 
@@ -206,15 +253,11 @@ impl ProxyServer {
     }
 
     fn build_router(&self) -> Router {
-        let cors = CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any);
-
         Router::new()
             // 健康检查
             .route("/health", get(handlers::health_check))
             .route("/status", get(handlers::get_status))
+            .route("/metrics", get(handlers::metrics))
             // Claude API (支持带前缀和不带前缀两种格式)
             .route("/v1/messages", post(handlers::handle_messages))
             .route("/claude/v1/messages", post(handlers::handle_messages))
@@ -240,9 +283,19 @@ impl ProxyServer {
             // Gemini API (支持带前缀和不带前缀)
             .route("/v1beta/*path", post(handlers::handle_gemini))
             .route("/gemini/v1beta/*path", post(handlers::handle_gemini))
+            // 分配请求 ID，按应用配置抓取请求/响应体
+            .route_layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                body_capture::capture_middleware,
+            ))
+            // 入站访问控制（最外层，先于抓取执行）
+            .route_layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                auth::auth_middleware,
+            ))
             // 提高默认请求体大小限制（避免 413 Payload Too Large）
             .layer(DefaultBodyLimit::max(200 * 1024 * 1024))
-            .layer(cors)
+            .layer(auth::cors_layer(self.state.auth.clone()))
             .with_state(self.state.clone())
     }
 
@@ -251,6 +304,14 @@ impl ProxyServer {
         *self.state.config.write().await = config.clone();
     }
 
+    /// 在不重启服务的情况下更新入站访问控制配置
+    pub fn apply_auth_config(&self, config: ProxyAuthConfig) {
+        match self.state.auth.write() {
+            Ok(mut current) => *current = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
+        }
+    }
+
     /// 热更新熔断器配置
     ///
     /// 将新配置应用到所有已创建的熔断器实例
@@ -268,4 +329,15 @@ impl ProxyServer {
             .reset_provider_breaker(provider_id, app_type)
             .await;
     }
+
+    /// 获取指定 Provider 的上游限流状态
+    pub fn get_provider_rate_limit(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+    ) -> Option<RateLimitState> {
+        self.state
+            .provider_router
+            .get_rate_limit_state(app_type, provider_id)
+    }
 }
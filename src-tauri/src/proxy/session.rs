@@ -10,7 +10,9 @@
 //! - 其他: 生成新的 UUID
 
 use axum::http::HeaderMap;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 /// 客户端请求格式
@@ -216,7 +218,7 @@ pub struct SessionIdResult {
 
 /// 从请求中提取或生成 Session ID
 ///
-/// 轻量化实现，仅提取 session_id 用于日志记录，不做复杂的 Session 管理。
+/// 轻量化实现，提取的 session_id 用于日志记录和供应商亲和（见 [`SessionAffinity`]）。
 ///
 /// ## 提取优先级
 ///
@@ -357,6 +359,89 @@ fn generate_new_session_id() -> SessionIdResult {
     }
 }
 
+// ============================================================================
+// Session 亲和
+// ============================================================================
+
+/// Session 亲和的默认有效期（秒），超过该时间没有新请求则解除绑定
+pub const SESSION_AFFINITY_TTL_SECS: u64 = 30 * 60;
+
+/// 最多保留的 Session 绑定数量
+const SESSION_AFFINITY_CAPACITY: usize = 4096;
+
+/// Session → Provider 亲和表
+///
+/// 同一对话的后续请求优先发往上一次成功响应的供应商，保持上游 prompt cache 命中，
+/// 也避免 thinking 签名在不同供应商之间不匹配。
+/// 只记录客户端提供的 Session ID（新生成的 ID 每次请求都不同，绑定没有意义）。
+pub struct SessionAffinity {
+    ttl: Duration,
+    /// key 格式: "app_type:session_id" → (provider_id, 最近一次绑定时间)
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl Default for SessionAffinity {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(SESSION_AFFINITY_TTL_SECS))
+    }
+}
+
+impl SessionAffinity {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 获取 Session 绑定的供应商（过期则清除并返回 None）
+    pub fn get(&self, app_type: &str, session_id: &str) -> Option<String> {
+        let key = format!("{app_type}:{session_id}");
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(&key) {
+            Some((provider_id, bound_at)) if bound_at.elapsed() < self.ttl => {
+                Some(provider_id.clone())
+            }
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 绑定（或刷新）Session 到供应商
+    pub fn bind(&self, app_type: &str, session_id: &str, provider_id: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if entries.len() >= SESSION_AFFINITY_CAPACITY {
+            let ttl = self.ttl;
+            entries.retain(|_, (_, bound_at)| bound_at.elapsed() < ttl);
+            // 仍然超出容量时淘汰最久未使用的绑定
+            if entries.len() >= SESSION_AFFINITY_CAPACITY {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, (_, bound_at))| *bound_at)
+                    .map(|(key, _)| key.clone())
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(
+            format!("{app_type}:{session_id}"),
+            (provider_id.to_string(), Instant::now()),
+        );
+    }
+
+    /// 解除 Session 绑定
+    pub fn unbind(&self, app_type: &str, session_id: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(&format!("{app_type}:{session_id}"));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -564,4 +649,30 @@ mod tests {
         assert_eq!(parse_session_from_user_id("user_john_abc123"), None);
         assert_eq!(parse_session_from_user_id("_session_"), None);
     }
+
+    #[test]
+    fn test_session_affinity_bind_get_and_unbind() {
+        let affinity = SessionAffinity::default();
+
+        assert_eq!(affinity.get("claude", "s1"), None);
+
+        affinity.bind("claude", "s1", "p1");
+        assert_eq!(affinity.get("claude", "s1"), Some("p1".to_string()));
+        // 不同应用之间互不影响
+        assert_eq!(affinity.get("codex", "s1"), None);
+
+        affinity.bind("claude", "s1", "p2");
+        assert_eq!(affinity.get("claude", "s1"), Some("p2".to_string()));
+
+        affinity.unbind("claude", "s1");
+        assert_eq!(affinity.get("claude", "s1"), None);
+    }
+
+    #[test]
+    fn test_session_affinity_expires_after_ttl() {
+        let affinity = SessionAffinity::new(Duration::ZERO);
+
+        affinity.bind("claude", "s1", "p1");
+        assert_eq!(affinity.get("claude", "s1"), None);
+    }
 }
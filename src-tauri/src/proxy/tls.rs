@@ -0,0 +1,222 @@
+//! 代理监听的 TLS 支持
+//!
+//! 代理暴露给其他机器时需要 HTTPS。启用 TLS 后：
+//!
+//! - 配置了证书与私钥路径时，直接加载用户提供的 PEM 文件
+//! - 未配置时，在应用配置目录的 `tls/` 下生成自签名证书并复用；
+//!   证书覆盖 localhost、回环地址与监听地址，监听地址变化时自动重新生成
+//!
+//! 自签名证书不受系统信任，客户端需要自行信任该证书（如 Node 的 `NODE_EXTRA_CA_CERTS`）。
+
+use super::{types::ProxyTlsConfig, ProxyError};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::fs;
+use std::io::BufReader;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+const SELF_SIGNED_CERT: &str = "proxy-cert.pem";
+const SELF_SIGNED_KEY: &str = "proxy-key.pem";
+/// 记录自签名证书覆盖的主机名，用于判断是否需要重新生成
+const SELF_SIGNED_HOSTS: &str = "proxy-cert.hosts";
+
+/// 自签名证书所在目录
+pub fn self_signed_dir() -> PathBuf {
+    crate::config::get_app_config_dir().join("tls")
+}
+
+/// 实际使用的证书与私钥路径（未配置时为自签名证书路径）
+pub fn resolve_paths(config: &ProxyTlsConfig) -> (PathBuf, PathBuf) {
+    let cert = config.cert_path.trim();
+    let key = config.key_path.trim();
+    if cert.is_empty() && key.is_empty() {
+        let dir = self_signed_dir();
+        (dir.join(SELF_SIGNED_CERT), dir.join(SELF_SIGNED_KEY))
+    } else {
+        (PathBuf::from(cert), PathBuf::from(key))
+    }
+}
+
+/// 按配置构造 rustls 服务端配置
+pub fn server_config(
+    config: &ProxyTlsConfig,
+    listen_address: &str,
+) -> Result<RustlsConfig, ProxyError> {
+    let (cert_path, key_path) = resolve_paths(config);
+    if config.cert_path.trim().is_empty() != config.key_path.trim().is_empty() {
+        return Err(ProxyError::ConfigError(
+            "TLS 证书与私钥路径需要同时配置".to_string(),
+        ));
+    }
+    if config.cert_path.trim().is_empty() {
+        ensure_self_signed(&self_signed_dir(), listen_address)?;
+    }
+
+    let certs = load_certs(&cert_path)?;
+    let key = load_private_key(&key_path)?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| ProxyError::ConfigError(format!("TLS 证书无效: {e}")))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    log::info!("代理 TLS 已启用，证书: {}", cert_path.display());
+    Ok(RustlsConfig::from_config(Arc::new(server_config)))
+}
+
+/// 以 HTTPS 提供服务，收到关闭信号后优雅停止
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    config: RustlsConfig,
+    shutdown_rx: oneshot::Receiver<()>,
+) {
+    let listener = match listener.into_std() {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("代理 TLS 监听器初始化失败: {e}");
+            return;
+        }
+    };
+
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown_rx.await.ok();
+        shutdown_handle.graceful_shutdown(None);
+    });
+
+    axum_server::from_tcp_rustls(listener, config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .ok();
+}
+
+/// 自签名证书需要覆盖的主机名
+fn self_signed_hosts(listen_address: &str) -> Vec<String> {
+    let mut hosts = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    if let Ok(ip) = listen_address.trim().parse::<IpAddr>() {
+        if !ip.is_unspecified() && !hosts.contains(&ip.to_string()) {
+            hosts.push(ip.to_string());
+        }
+    }
+    hosts
+}
+
+/// 确保自签名证书存在且覆盖当前监听地址
+fn ensure_self_signed(dir: &Path, listen_address: &str) -> Result<(), ProxyError> {
+    let hosts = self_signed_hosts(listen_address);
+    let hosts_record = hosts.join("\n");
+    let cert_path = dir.join(SELF_SIGNED_CERT);
+    let key_path = dir.join(SELF_SIGNED_KEY);
+    let hosts_path = dir.join(SELF_SIGNED_HOSTS);
+
+    if cert_path.exists()
+        && key_path.exists()
+        && fs::read_to_string(&hosts_path).ok().as_deref() == Some(hosts_record.as_str())
+    {
+        return Ok(());
+    }
+
+    let certified = rcgen::generate_simple_self_signed(hosts)
+        .map_err(|e| ProxyError::ConfigError(format!("生成自签名证书失败: {e}")))?;
+
+    let write = |path: &Path, content: &str| {
+        crate::config::write_text_file(path, content)
+            .map_err(|e| ProxyError::ConfigError(format!("保存自签名证书失败: {e}")))
+    };
+    write(&cert_path, &certified.cert.pem())?;
+    write(&key_path, &certified.key_pair.serialize_pem())?;
+    restrict_permissions(&key_path);
+    write(&hosts_path, &hosts_record)?;
+
+    log::info!("已生成代理自签名证书: {}", cert_path.display());
+    Ok(())
+}
+
+/// 私钥仅允许当前用户读取
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o600)) {
+        log::warn!("设置私钥文件权限失败: {e}");
+    }
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, ProxyError> {
+    let file = fs::File::open(path).map_err(|e| {
+        ProxyError::ConfigError(format!("读取 TLS 证书失败 {}: {e}", path.display()))
+    })?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ProxyError::ConfigError(format!("解析 TLS 证书失败: {e}")))?;
+    if certs.is_empty() {
+        return Err(ProxyError::ConfigError(format!(
+            "TLS 证书文件中没有证书: {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, ProxyError> {
+    let file = fs::File::open(path).map_err(|e| {
+        ProxyError::ConfigError(format!("读取 TLS 私钥失败 {}: {e}", path.display()))
+    })?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| ProxyError::ConfigError(format!("解析 TLS 私钥失败: {e}")))?
+        .ok_or_else(|| {
+            ProxyError::ConfigError(format!("TLS 私钥文件中没有私钥: {}", path.display()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_signed_cert_is_reused_until_listen_address_changes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        ensure_self_signed(dir.path(), "0.0.0.0").unwrap();
+        let first = fs::read_to_string(dir.path().join(SELF_SIGNED_CERT)).unwrap();
+        assert!(load_certs(&dir.path().join(SELF_SIGNED_CERT)).is_ok());
+        assert!(load_private_key(&dir.path().join(SELF_SIGNED_KEY)).is_ok());
+
+        ensure_self_signed(dir.path(), "0.0.0.0").unwrap();
+        let reused = fs::read_to_string(dir.path().join(SELF_SIGNED_CERT)).unwrap();
+        assert_eq!(first, reused);
+
+        ensure_self_signed(dir.path(), "192.168.1.10").unwrap();
+        let regenerated = fs::read_to_string(dir.path().join(SELF_SIGNED_CERT)).unwrap();
+        assert_ne!(first, regenerated);
+    }
+
+    #[test]
+    fn rejects_half_configured_paths() {
+        let config = ProxyTlsConfig {
+            enabled: true,
+            cert_path: "/tmp/cert.pem".to_string(),
+            key_path: String::new(),
+        };
+        assert!(matches!(
+            server_config(&config, "127.0.0.1"),
+            Err(ProxyError::ConfigError(_))
+        ));
+    }
+}
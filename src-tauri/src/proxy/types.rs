@@ -64,6 +64,9 @@ pub struct ProxyStatus {
     pub address: String,
     /// 监听端口
     pub port: u16,
+    /// 是否以 HTTPS 监听
+    #[serde(default)]
+    pub tls: bool,
     /// 活跃连接数
     pub active_connections: usize,
     /// 总请求数
@@ -105,6 +108,9 @@ pub struct ProxyServerInfo {
     pub address: String,
     pub port: u16,
     pub started_at: String,
+    /// 是否以 HTTPS 监听
+    #[serde(default)]
+    pub tls: bool,
 }
 
 /// 各应用的接管状态（是否改写该应用的 Live 配置指向本地代理）
@@ -135,6 +141,28 @@ pub struct ProviderHealth {
     pub last_failure_at: Option<String>,
     pub last_error: Option<String>,
     pub updated_at: String,
+    /// 限流冷却状态（代理运行中且上游返回过 429/529 时存在）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitState>,
+}
+
+/// 供应商限流冷却状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitState {
+    /// 触发冷却的上游状态码（429/529）
+    pub status: u16,
+    /// 冷却结束时间（RFC 3339）
+    pub cooldown_until: String,
+    /// 剩余冷却秒数（0 表示冷却已结束，尚未收到成功响应）
+    pub remaining_secs: u64,
+    /// 连续限流次数
+    pub hits: u32,
+    /// 最近一次限流时间（RFC 3339）
+    pub last_limited_at: String,
+    /// 上游告知的剩余请求数
+    pub remaining_requests: Option<u64>,
+    /// 上游告知的剩余 tokens
+    pub remaining_tokens: Option<u64>,
 }
 
 /// Live 配置备份记录
@@ -192,6 +220,173 @@ pub struct AppProxyConfig {
     pub circuit_min_requests: u32,
 }
 
+/// 故障转移队列的负载均衡策略
+///
+/// 存储在 proxy_config 表的 load_balance_strategy 字段中（每应用独立）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalanceStrategy {
+    /// 按队列顺序（P1 → P2 → ...），默认
+    #[default]
+    Priority,
+    /// 轮询
+    RoundRobin,
+    /// 按供应商配置的权重分配
+    Weighted,
+    /// 最低首字延迟（基于近期 first_token_ms）
+    LeastLatency,
+    /// 最低成本（基于 model_pricing 与成本倍率）
+    LowestCost,
+}
+
+impl LoadBalanceStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Priority => "priority",
+            Self::RoundRobin => "round_robin",
+            Self::Weighted => "weighted",
+            Self::LeastLatency => "least_latency",
+            Self::LowestCost => "lowest_cost",
+        }
+    }
+}
+
+impl std::str::FromStr for LoadBalanceStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "priority" => Ok(Self::Priority),
+            "round_robin" => Ok(Self::RoundRobin),
+            "weighted" => Ok(Self::Weighted),
+            "least_latency" => Ok(Self::LeastLatency),
+            "lowest_cost" => Ok(Self::LowestCost),
+            other => Err(format!("unknown load balance strategy: {other}")),
+        }
+    }
+}
+
+/// 响应缓存配置
+///
+/// 存储在 proxy_config 表的 response_cache_* 字段中（每应用独立，默认关闭）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseCacheConfig {
+    /// 是否缓存完全相同的非流式请求的响应
+    pub enabled: bool,
+    /// 缓存有效期（秒）
+    pub ttl_seconds: u32,
+    /// 该应用最多保留的缓存条数（超出时淘汰最早写入的条目）
+    pub max_entries: u32,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_seconds: 3600,
+            max_entries: 500,
+        }
+    }
+}
+
+/// 对冲请求配置
+///
+/// 存储在 proxy_config 表的 hedge_* 字段中（每应用独立，默认关闭）。
+/// 流式请求的首选供应商在 `delay_ms` 内未返回首个数据块时，同时向队列中下一个供应商
+/// 发起相同请求，先返回首个数据块的一方胜出，另一方被取消
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HedgeConfig {
+    pub enabled: bool,
+    /// 等待首个数据块的时间（毫秒），需小于流式首字超时
+    pub delay_ms: u32,
+}
+
+impl Default for HedgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_ms: 3000,
+        }
+    }
+}
+
+/// 请求/响应体抓取配置
+///
+/// 存储在 proxy_config 表的 body_capture_* 字段中（每应用独立，默认关闭）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BodyCaptureConfig {
+    /// 是否保存请求与响应体（用于排查中转服务的异常响应）
+    pub enabled: bool,
+    /// 单个请求体/响应体最多保存的字节数（超出部分截断）
+    pub max_body_bytes: u32,
+    /// 保留时长（小时），过期记录自动清理
+    pub retention_hours: u32,
+}
+
+impl Default for BodyCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_body_bytes: 256 * 1024,
+            retention_hours: 24,
+        }
+    }
+}
+
+/// 抓取的请求与响应（API Key 与认证头已脱敏）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestBodyCapture {
+    pub request_id: String,
+    pub app_type: String,
+    /// 客户端请求的路径（含查询参数）
+    pub endpoint: String,
+    /// 请求头（JSON 对象）
+    pub request_headers: String,
+    pub request_body: String,
+    pub request_truncated: bool,
+    pub response_status: u16,
+    /// 响应头（JSON 对象）
+    pub response_headers: String,
+    /// 响应体（流式响应为拼接后的完整 SSE 文本）
+    pub response_body: String,
+    pub response_truncated: bool,
+    pub created_at: i64,
+}
+
+/// 重放抓取请求的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub status_code: u16,
+    pub latency_ms: u64,
+    /// 上游原始响应（已脱敏，超出抓取上限时截断）
+    pub response_body: String,
+    pub truncated: bool,
+}
+
+/// 供应商消费限额告警
+///
+/// 消费达到限额预警阈值或超额时，通过 `provider-budget-alert` 事件发送给前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlert {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    /// 限额周期：daily / monthly
+    pub period: String,
+    pub usage_usd: String,
+    pub limit_usd: String,
+    /// 是否已超额（false 表示达到预警阈值）
+    pub exceeded: bool,
+}
+
 /// 整流器配置
 ///
 /// 存储在 settings 表中
@@ -256,6 +451,134 @@ impl LogConfig {
     }
 }
 
+/// 代理监听的入站访问控制
+///
+/// 存储在 settings 表的 proxy_auth_config 字段中（JSON 格式），修改后立即生效
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyAuthConfig {
+    /// 是否启用访问控制（访问令牌 + IP 白名单）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 代理访问令牌（为空时只校验 IP 白名单）
+    #[serde(default)]
+    pub access_token: String,
+    /// 是否信任回环地址的请求（本机接管模式写入的是占位 Token）
+    #[serde(default = "default_true")]
+    pub allow_loopback: bool,
+    /// 允许访问的客户端 IP 或 CIDR（为空时不限制）
+    #[serde(default)]
+    pub ip_allowlist: Vec<String>,
+    /// 允许的 CORS 来源（为空时允许任意来源）
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ProxyAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            access_token: String::new(),
+            allow_loopback: true,
+            ip_allowlist: Vec::new(),
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+/// 代理监听的 TLS 配置
+///
+/// 存储在 settings 表的 proxy_tls_config 字段中（JSON 格式），重新启动代理后生效。
+/// 证书与私钥路径都为空时，使用自动生成并保存在应用配置目录下的自签名证书
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyTlsConfig {
+    /// 是否以 HTTPS 监听
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM 格式的证书（链）路径
+    #[serde(default)]
+    pub cert_path: String,
+    /// PEM 格式的私钥路径
+    #[serde(default)]
+    pub key_path: String,
+}
+
+/// 代理虚拟 Key
+///
+/// 每个虚拟 Key 对应一位使用者，可限制允许的应用、模型与每日/每月消费。
+/// 数据库只保存 Key 的 SHA-256 摘要，明文仅在创建时返回一次
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualKey {
+    #[serde(default)]
+    pub id: String,
+    /// 使用者名称
+    pub name: String,
+    /// Key 前缀（用于界面展示与日志区分，不足以还原 Key）
+    #[serde(default)]
+    pub key_prefix: String,
+    /// 允许访问的应用（claude/codex/gemini，为空时不限制）
+    #[serde(default)]
+    pub allowed_apps: Vec<String>,
+    /// 允许使用的模型（glob 模式，为空时不限制）
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// 每日消费限额（USD）
+    #[serde(default)]
+    pub limit_daily_usd: Option<String>,
+    /// 每月消费限额（USD）
+    #[serde(default)]
+    pub limit_monthly_usd: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// 新建虚拟 Key 的结果（`secret` 为明文 Key，只在创建时返回）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedVirtualKey {
+    pub key: VirtualKey,
+    pub secret: String,
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://127.0.0.1:4318/v1/traces".to_string()
+}
+
+fn default_service_name() -> String {
+    "cc-switch-proxy".to_string()
+}
+
+/// 链路追踪配置
+///
+/// 存储在 settings 表的 telemetry_config 字段中（JSON 格式）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryConfig {
+    /// 是否通过 OTLP/HTTP 导出请求 span（默认关闭）
+    #[serde(default)]
+    pub otlp_enabled: bool,
+    /// OTLP/HTTP traces 端点
+    #[serde(default = "default_otlp_endpoint")]
+    pub otlp_endpoint: String,
+    /// 上报的 service.name
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_enabled: false,
+            otlp_endpoint: default_otlp_endpoint(),
+            service_name: default_service_name(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,3 +686,30 @@ mod tests {
         assert_eq!(parsed.level, "debug");
     }
 }
+
+/// 跨供应商模型路由
+///
+/// 每个 app 独立配置，按 sort_index 顺序匹配请求模型；命中后只在该路由的供应商中选择，
+/// `provider_ids` 的顺序即该路由自己的故障转移顺序（熔断器仍按供应商独立计算）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRoute {
+    pub id: String,
+    /// 应用类型 (claude/codex/gemini)
+    pub app_type: String,
+    /// 显示名称
+    #[serde(default)]
+    pub name: String,
+    /// 请求模型的匹配模式
+    pub pattern: String,
+    /// 匹配方式（默认 glob）
+    #[serde(default)]
+    pub match_type: crate::provider::ModelMatchType,
+    /// 按优先级排序的供应商 ID
+    #[serde(default)]
+    pub provider_ids: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sort_index: i64,
+}
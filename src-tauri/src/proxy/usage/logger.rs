@@ -29,6 +29,14 @@ pub struct RequestLog {
     pub is_streaming: bool,
     /// 成本倍数
     pub cost_multiplier: String,
+    /// 实际使用的 API Key（脱敏）
+    pub masked_key: Option<String>,
+    /// 是否由响应缓存直接返回（不产生费用）
+    pub cache_hit: bool,
+    /// 发起请求的虚拟 Key ID
+    pub virtual_key_id: Option<String>,
+    /// 对冲角色（winner / loser，未发生对冲时为 None）
+    pub hedge_role: Option<&'static str>,
 }
 
 /// 使用量记录器
@@ -41,8 +49,11 @@ impl<'a> UsageLogger<'a> {
         Self { db }
     }
 
-    /// 记录成功的请求
+    /// 记录请求（同时更新 /metrics 指标与 OTLP span）
     pub fn log_request(&self, log: &RequestLog) -> Result<(), AppError> {
+        crate::proxy::metrics::global().record_request(log);
+        crate::proxy::otlp::record_span(log);
+
         let conn = crate::database::lock_conn!(self.db.conn);
 
         let (input_cost, output_cost, cache_read_cost, cache_creation_cost, total_cost) =
@@ -78,8 +89,9 @@ impl<'a> UsageLogger<'a> {
                 input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                 input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                 latency_ms, first_token_ms, status_code, error_message, session_id,
-                provider_type, is_streaming, cost_multiplier, created_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+                provider_type, is_streaming, cost_multiplier, masked_key, cache_hit, virtual_key_id,
+                hedge_role, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)",
             rusqlite::params![
                 log.request_id,
                 log.provider_id,
@@ -103,6 +115,10 @@ impl<'a> UsageLogger<'a> {
                 log.provider_type,
                 log.is_streaming as i64,
                 log.cost_multiplier,
+                log.masked_key,
+                log.cache_hit as i64,
+                log.virtual_key_id,
+                log.hedge_role,
                 created_at,
             ],
         )
@@ -142,6 +158,10 @@ impl<'a> UsageLogger<'a> {
             provider_type: None,
             is_streaming: false,
             cost_multiplier: "1.0".to_string(),
+            masked_key: None,
+            cache_hit: false,
+            virtual_key_id: None,
+            hedge_role,
         };
 
         self.log_request(&log)
@@ -163,6 +183,9 @@ impl<'a> UsageLogger<'a> {
         is_streaming: bool,
         session_id: Option<String>,
         provider_type: Option<String>,
+        masked_key: Option<String>,
+        virtual_key_id: Option<String>,
+        hedge_role: Option<&'static str>,
     ) -> Result<(), AppError> {
         let request_model = model.clone();
         let log = RequestLog {
@@ -181,6 +204,10 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: "1.0".to_string(),
+            masked_key,
+            cache_hit: false,
+            virtual_key_id,
+            hedge_role: None,
         };
 
         self.log_request(&log)
@@ -300,6 +327,9 @@ impl<'a> UsageLogger<'a> {
         session_id: Option<String>,
         provider_type: Option<String>,
         is_streaming: bool,
+        masked_key: Option<String>,
+        virtual_key_id: Option<String>,
+        hedge_role: Option<&'static str>,
     ) -> Result<(), AppError> {
         let pricing = self.get_model_pricing(&pricing_model)?;
 
@@ -325,6 +355,53 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: cost_multiplier.to_string(),
+            masked_key,
+            cache_hit: false,
+            virtual_key_id,
+            hedge_role,
+        };
+
+        self.log_request(&log)
+    }
+
+    /// 记录响应缓存命中
+    ///
+    /// 保留缓存响应中的 token 用量，成本记为 0（节省的费用由统计服务按定价估算）
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_cache_hit(
+        &self,
+        request_id: String,
+        provider_id: String,
+        app_type: String,
+        model: String,
+        request_model: String,
+        usage: TokenUsage,
+        cost_multiplier: Decimal,
+        latency_ms: u64,
+        status_code: u16,
+        session_id: Option<String>,
+        virtual_key_id: Option<String>,
+    ) -> Result<(), AppError> {
+        let log = RequestLog {
+            request_id,
+            provider_id,
+            app_type,
+            model,
+            request_model,
+            usage,
+            cost: None,
+            latency_ms,
+            first_token_ms: None,
+            status_code,
+            error_message: None,
+            session_id,
+            provider_type: None,
+            is_streaming: false,
+            cost_multiplier: cost_multiplier.to_string(),
+            masked_key: None,
+            cache_hit: true,
+            virtual_key_id,
+            hedge_role: None,
         };
 
         self.log_request(&log)
@@ -375,19 +452,31 @@ mod tests {
             None,
             Some("claude".to_string()),
             false,
+            Some("sk-1...cdef".to_string()),
+            Some("vk-1".to_string()),
+            Some(crate::proxy::hedge::HEDGE_WINNER),
         )?;
 
         // 验证记录已插入
         let conn = crate::database::lock_conn!(db.conn);
-        let (count, request_model): (i64, String) = conn
+        let (count, request_model, masked_key, virtual_key_id, hedge_role): (
+            i64,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        ) = conn
             .query_row(
-                "SELECT COUNT(*), request_model FROM proxy_request_logs WHERE request_id = 'req-123'",
+                "SELECT COUNT(*), request_model, masked_key, virtual_key_id, hedge_role FROM proxy_request_logs WHERE request_id = 'req-123'",
                 [],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
             )
             .unwrap();
         assert_eq!(count, 1);
         assert_eq!(request_model, "req-model");
+        assert_eq!(masked_key.as_deref(), Some("sk-1...cdef"));
+        assert_eq!(virtual_key_id.as_deref(), Some("vk-1"));
+        assert_eq!(hedge_role.as_deref(), Some("winner"));
         Ok(())
     }
 
@@ -109,6 +109,15 @@ impl TokenUsage {
                                     usage.input_tokens = input as u32;
                                 }
                             }
+                            // 同理，缓存命中数也只在 message_delta 中提供
+                            if usage.cache_read_tokens == 0 {
+                                if let Some(cache_read) = delta_usage
+                                    .get("cache_read_input_tokens")
+                                    .and_then(|v| v.as_u64())
+                                {
+                                    usage.cache_read_tokens = cache_read as u32;
+                                }
+                            }
                         }
                     }
                     _ => {}
@@ -236,9 +245,10 @@ impl TokenUsage {
         for event in events {
             if let Some(event_type) = event.get("type").and_then(|v| v.as_str()) {
                 log::debug!("[Codex] 事件类型: {event_type}");
-                if event_type == "response.completed" {
+                // 截断（max_output_tokens）时以 response.incomplete 结束，同样携带 usage
+                if event_type == "response.completed" || event_type == "response.incomplete" {
                     if let Some(response) = event.get("response") {
-                        log::debug!("[Codex] 找到 response.completed 事件，解析 usage");
+                        log::debug!("[Codex] 找到 {event_type} 事件，解析 usage");
                         return Self::from_codex_response_adjusted(response);
                     }
                 }
@@ -276,12 +286,12 @@ impl TokenUsage {
     pub fn from_codex_stream_events_auto(events: &[Value]) -> Option<Self> {
         log::debug!("[Codex] 智能解析流式事件，共 {} 个事件", events.len());
 
-        // 先尝试 Codex Responses API 格式 (response.completed 事件)
+        // 先尝试 Codex Responses API 格式 (response.completed / response.incomplete 事件)
         for event in events {
             if let Some(event_type) = event.get("type").and_then(|v| v.as_str()) {
-                if event_type == "response.completed" {
+                if event_type == "response.completed" || event_type == "response.incomplete" {
                     if let Some(response) = event.get("response") {
-                        log::debug!("[Codex] 找到 response.completed 事件");
+                        log::debug!("[Codex] 找到 {event_type} 事件");
                         return Self::from_codex_response_auto(response);
                     }
                 }
@@ -743,6 +753,34 @@ mod tests {
         assert_eq!(usage.model, Some("claude-sonnet-4-20250514".to_string()));
     }
 
+    #[test]
+    fn test_openai_chat_stream_parsing_cache_read_in_delta() {
+        // openai_chat 转换后的流式响应：缓存命中数同样只在 message_delta 中
+        let events = vec![
+            json!({
+                "type": "message_start",
+                "message": {
+                    "model": "deepseek-chat",
+                    "usage": {"input_tokens": 0, "output_tokens": 0}
+                }
+            }),
+            json!({
+                "type": "message_delta",
+                "delta": {"stop_reason": "end_turn"},
+                "usage": {
+                    "input_tokens": 40,
+                    "output_tokens": 7,
+                    "cache_read_input_tokens": 60
+                }
+            }),
+        ];
+
+        let usage = TokenUsage::from_claude_stream_events(&events).unwrap();
+        assert_eq!(usage.input_tokens, 40);
+        assert_eq!(usage.cache_read_tokens, 60);
+        assert_eq!(usage.output_tokens, 7);
+    }
+
     #[test]
     fn test_native_claude_stream_parsing() {
         // 测试原生 Claude API 流式响应解析
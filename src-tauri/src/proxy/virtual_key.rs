@@ -0,0 +1,218 @@
+//! 多用户虚拟 Key
+//!
+//! 一个 cc-switch 代理可以分发多个虚拟 Key 给不同使用者：
+//!
+//! - 入站认证中间件按 Key 摘要识别虚拟 Key，校验允许的应用，并通过
+//!   [`VIRTUAL_KEY_HEADER`] 把 Key ID 传给处理器（客户端自带的同名头会被丢弃）
+//! - 创建请求上下文时校验允许的模型与每日/每月消费限额
+//! - 请求日志记录 Key ID，消费按日志中已计算好的费用汇总
+
+use super::{model_mapper::model_matches, types::VirtualKey, ProxyError};
+use crate::database::Database;
+use crate::provider::ModelMatchType;
+use axum::http::HeaderMap;
+use sha2::{Digest, Sha256};
+
+/// 认证中间件写入的虚拟 Key ID（内部头，不会转发给上游）
+pub const VIRTUAL_KEY_HEADER: &str = "x-cc-switch-virtual-key-id";
+
+/// 虚拟 Key 前缀，用于和普通令牌/上游 API Key 区分
+pub const KEY_PREFIX: &str = "sk-ccs-";
+
+/// 界面展示的 Key 前缀长度
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// 生成新的虚拟 Key 明文
+pub fn generate_secret() -> String {
+    format!("{KEY_PREFIX}{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 虚拟 Key 的摘要（数据库只保存摘要）
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.trim().as_bytes()))
+}
+
+/// 界面展示用的 Key 前缀
+pub fn display_prefix(secret: &str) -> String {
+    let prefix: String = secret.chars().take(DISPLAY_PREFIX_LEN).collect();
+    format!("{prefix}…")
+}
+
+/// 客户端令牌是否为虚拟 Key 格式
+pub fn is_virtual_key(token: &str) -> bool {
+    token.starts_with(KEY_PREFIX)
+}
+
+/// 读取认证中间件写入的虚拟 Key ID
+pub fn key_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(VIRTUAL_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// 虚拟 Key 是否允许访问该应用
+pub fn app_allowed(key: &VirtualKey, app_type: &str) -> bool {
+    key.allowed_apps.is_empty()
+        || key
+            .allowed_apps
+            .iter()
+            .any(|app| app.eq_ignore_ascii_case(app_type))
+}
+
+/// 虚拟 Key 是否允许使用该模型（列表中的条目按 glob 匹配）
+pub fn model_allowed(key: &VirtualKey, model: &str) -> bool {
+    key.allowed_models.is_empty()
+        || key
+            .allowed_models
+            .iter()
+            .any(|pattern| model_matches(pattern, ModelMatchType::Glob, model))
+}
+
+/// 校验虚拟 Key 对本次请求的模型权限与消费限额
+///
+/// Key 在认证之后被删除或停用时按认证失败处理
+pub fn authorize(db: &Database, key_id: &str, model: &str) -> Result<(), ProxyError> {
+    let key = db
+        .get_virtual_key(key_id)
+        .map_err(|e| ProxyError::DatabaseError(e.to_string()))?
+        .filter(|key| key.enabled)
+        .ok_or_else(|| ProxyError::AuthError("虚拟 Key 不存在或已停用".to_string()))?;
+
+    if !model_allowed(&key, model) {
+        return Err(ProxyError::Forbidden(format!(
+            "虚拟 Key「{}」不允许使用模型 {model}",
+            key.name
+        )));
+    }
+
+    let (daily, monthly) = db
+        .get_virtual_key_spend(&key.id)
+        .map_err(|e| ProxyError::DatabaseError(e.to_string()))?;
+    if let Some(reason) = budget_exceeded(&key, daily, monthly) {
+        return Err(ProxyError::KeyBudgetExceeded(reason));
+    }
+
+    Ok(())
+}
+
+/// 按今日/本月消费判断是否超出限额，返回超限说明
+fn budget_exceeded(key: &VirtualKey, daily: f64, monthly: f64) -> Option<String> {
+    let limit =
+        |value: &Option<String>| value.as_deref().and_then(|v| v.trim().parse::<f64>().ok());
+
+    if let Some(limit) = limit(&key.limit_daily_usd) {
+        if daily >= limit {
+            return Some(format!(
+                "{} 今日已消费 ${daily:.2}（限额 ${limit:.2}）",
+                key.name
+            ));
+        }
+    }
+    if let Some(limit) = limit(&key.limit_monthly_usd) {
+        if monthly >= limit {
+            return Some(format!(
+                "{} 本月已消费 ${monthly:.2}（限额 ${limit:.2}）",
+                key.name
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn key() -> VirtualKey {
+        VirtualKey {
+            id: "vk-1".to_string(),
+            name: "alice".to_string(),
+            key_prefix: String::new(),
+            allowed_apps: vec!["claude".to_string()],
+            allowed_models: vec!["claude-sonnet-*".to_string()],
+            limit_daily_usd: Some("5".to_string()),
+            limit_monthly_usd: Some("50".to_string()),
+            enabled: true,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn generated_secret_is_recognised_and_hashed() {
+        let secret = generate_secret();
+        assert!(is_virtual_key(&secret));
+        assert!(!is_virtual_key("PROXY_MANAGED"));
+        assert_eq!(hash_secret(&secret), hash_secret(&format!(" {secret} ")));
+        assert_ne!(hash_secret(&secret), hash_secret(&generate_secret()));
+        assert_eq!(
+            display_prefix(&secret).chars().count(),
+            DISPLAY_PREFIX_LEN + 1
+        );
+    }
+
+    #[test]
+    fn restricts_apps_and_models() {
+        let mut key = key();
+        assert!(app_allowed(&key, "claude"));
+        assert!(!app_allowed(&key, "codex"));
+        assert!(model_allowed(&key, "claude-sonnet-4-5"));
+        assert!(!model_allowed(&key, "claude-opus-4-5"));
+
+        key.allowed_apps.clear();
+        key.allowed_models.clear();
+        assert!(app_allowed(&key, "gemini"));
+        assert!(model_allowed(&key, "gemini-2.5-pro"));
+    }
+
+    #[test]
+    fn enforces_daily_then_monthly_budget() {
+        let key = key();
+        assert_eq!(budget_exceeded(&key, 4.99, 20.0), None);
+        assert!(budget_exceeded(&key, 5.0, 20.0).unwrap().contains("今日"));
+        assert!(budget_exceeded(&key, 1.0, 50.0).unwrap().contains("本月"));
+    }
+
+    #[test]
+    fn authorize_sums_logged_costs() -> Result<(), AppError> {
+        let db = Database::memory().unwrap();
+        let key = key();
+        db.insert_virtual_key(&key, &hash_secret("sk-ccs-test"))
+            .unwrap();
+        assert!(authorize(&db, "vk-1", "claude-sonnet-4-5").is_ok());
+        assert!(matches!(
+            authorize(&db, "vk-1", "claude-opus-4-5"),
+            Err(ProxyError::Forbidden(_))
+        ));
+
+        {
+            let conn = crate::database::lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, total_cost_usd,
+                    latency_ms, status_code, virtual_key_id, created_at
+                ) VALUES ('req-1', 'p1', 'claude', 'claude-sonnet-4-5', '5.5', 100, 200, 'vk-1', strftime('%s', 'now'))",
+                [],
+            )
+            .unwrap();
+        }
+        assert!(matches!(
+            authorize(&db, "vk-1", "claude-sonnet-4-5"),
+            Err(ProxyError::KeyBudgetExceeded(_))
+        ));
+
+        let stats = db.get_virtual_key_stats().unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].request_count, 1);
+        assert_eq!(stats[0].daily_usage, "5.500000");
+
+        db.delete_virtual_key("vk-1").unwrap();
+        assert!(matches!(
+            authorize(&db, "vk-1", "claude-sonnet-4-5"),
+            Err(ProxyError::AuthError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::live_watch::{self, LiveConfigChange, LiveFileKind, ManagedFile, RenderScope};
+use crate::prompt_files::prompt_file_path;
+use crate::services::provider::{
+    import_opencode_providers_from_live, read_live_settings, sync_app_providers_to_live,
+};
+use crate::services::{McpService, PromptService};
+use crate::store::AppState;
+
+/// 处理 live 文件的外部修改（见 [`live_watch`]）
+pub struct LiveWatchService;
+
+impl LiveWatchService {
+    /// 尚未处理的外部修改
+    pub fn get_changes() -> Vec<LiveConfigChange> {
+        live_watch::pending_changes()
+    }
+
+    /// 把外部修改吸收到数据库：回填当前供应商配置（OpenCode 回填全部供应商）、
+    /// 已启用的提示词，并导入新增的 MCP 服务器
+    pub fn absorb(state: &AppState, path: &str) -> Result<(), AppError> {
+        let change = live_watch::find_pending(path)?;
+        let app = AppType::from_str(&change.app_type)?;
+        for kind in &change.kinds {
+            match kind {
+                LiveFileKind::Provider => Self::absorb_provider(state, &app)?,
+                LiveFileKind::Prompt => PromptService::backfill_from_live(state, &app)?,
+                LiveFileKind::Mcp => Self::absorb_mcp(state, &app)?,
+            }
+        }
+        log::info!("已吸收 live 配置的外部修改: {path}");
+        live_watch::accept_current(path)
+    }
+
+    /// 按数据库重新写入该应用的 live 配置（与切换供应商使用相同的写入函数），覆盖外部修改
+    pub fn reapply(state: &AppState, path: &str) -> Result<(), AppError> {
+        let change = live_watch::find_pending(path)?;
+        let app = AppType::from_str(&change.app_type)?;
+        for kind in &change.kinds {
+            match kind {
+                LiveFileKind::Provider => {
+                    state.proxy_service.ensure_live_not_taken_over(&app)?;
+                    sync_app_providers_to_live(state, &app)?;
+                }
+                LiveFileKind::Prompt => Self::reapply_prompt(state, &app)?,
+                LiveFileKind::Mcp => McpService::sync_app_enabled(state, &app)?,
+            }
+        }
+        log::info!("已重新应用 live 配置: {path}");
+        live_watch::accept_current(path)
+    }
+
+    /// 按数据库计算 live 文件的期望内容（`files` 均属于 `app`）
+    ///
+    /// 供应商配置与 MCP 配置：把当前文件复制到临时目录，在重定向的路径上执行切换供应商时的
+    /// 写入函数，写入后的内容即期望内容（写入函数不改动的部分保持当前内容）。提示词文件按
+    /// 已启用的提示词渲染。期望内容为 None 表示无法判断（代理接管中的供应商配置）
+    pub fn expected_contents(
+        state: &AppState,
+        app: &AppType,
+        files: &[&ManagedFile],
+    ) -> Result<HashMap<PathBuf, Option<String>>, AppError> {
+        let mut expected = HashMap::new();
+        let has = |kind: LiveFileKind| files.iter().any(|f| f.kinds.contains(&kind));
+
+        if has(LiveFileKind::Prompt) {
+            let path = prompt_file_path(app)?;
+            let live = std::fs::read_to_string(&path).unwrap_or_default();
+            let content = PromptService::expected_prompt_file(state, app, &live)?
+                .map(|content| live_watch::LiveSection::Whole.extract(&content))
+                .unwrap_or_else(|| live_watch::LiveSection::Whole.extract(&live));
+            expected.insert(path, Some(content));
+        }
+
+        let rendered: Vec<&&ManagedFile> = files
+            .iter()
+            .filter(|f| !f.kinds.contains(&LiveFileKind::Prompt))
+            .collect();
+        if rendered.is_empty() {
+            return Ok(expected);
+        }
+
+        // 接管中的 live 配置指向本地代理，与供应商配置不同是预期的
+        let taken_over = has(LiveFileKind::Provider)
+            && state
+                .proxy_service
+                .detect_takeover_in_live_config_for_app(app);
+        let all_files: Vec<ManagedFile> = live_watch::managed_files()
+            .into_iter()
+            .filter(|f| f.app == *app && !f.kinds.contains(&LiveFileKind::Prompt))
+            .collect();
+
+        let root = tempfile::tempdir().map_err(|e| AppError::IoContext {
+            context: "创建 live 配置渲染目录失败".to_string(),
+            source: e,
+        })?;
+        {
+            let _scope = RenderScope::enter(root.path());
+            // 复制该应用的全部 live 文件，写入函数按当前内容合并
+            for file in &all_files {
+                copy_into_render_root(&file.path)?;
+            }
+            if has(LiveFileKind::Provider) && !taken_over {
+                sync_app_providers_to_live(state, app)?;
+            }
+            if has(LiveFileKind::Mcp) {
+                McpService::sync_app_enabled(state, app)?;
+            }
+
+            for file in rendered {
+                if taken_over && file.kinds.contains(&LiveFileKind::Provider) {
+                    expected.insert(file.path.clone(), None);
+                    continue;
+                }
+                let target = live_watch::redirect(file.path.clone());
+                let raw = std::fs::read_to_string(&target).unwrap_or_default();
+                expected.insert(file.path.clone(), Some(file.section.extract(&raw)));
+            }
+        }
+        Ok(expected)
+    }
+
+    /// 按数据库重写提示词文件（不回填文件中的修改）
+    fn reapply_prompt(state: &AppState, app: &AppType) -> Result<(), AppError> {
+        let path = prompt_file_path(app)?;
+        let live = std::fs::read_to_string(&path).unwrap_or_default();
+        match PromptService::expected_prompt_file(state, app, &live)? {
+            Some(content) => crate::config::write_text_file(&path, &content),
+            None => Ok(()),
+        }
+    }
+
+    /// 忽略外部修改（保留文件当前内容，不写回数据库）
+    pub fn dismiss(path: &str) -> Result<(), AppError> {
+        live_watch::find_pending(path)?;
+        live_watch::accept_current(path)
+    }
+
+    fn absorb_provider(state: &AppState, app: &AppType) -> Result<(), AppError> {
+        state.proxy_service.ensure_live_not_taken_over(app)?;
+
+        let mut providers = state.db.get_all_providers(app.as_str())?;
+        if app.is_additive_mode() {
+            // 累加模式：回填已有的供应商，导入新增的供应商
+            for (id, config) in crate::opencode_config::get_typed_providers()? {
+                if let Some(provider) = providers.get_mut(&id) {
+                    provider.settings_config = serde_json::to_value(&config)
+                        .map_err(|e| AppError::JsonSerialize { source: e })?;
+                    state.db.save_provider(app.as_str(), provider)?;
+                }
+            }
+            import_opencode_providers_from_live(state)?;
+            return Ok(());
+        }
+
+        let mut provider = crate::settings::get_effective_current_provider(&state.db, app)?
+            .and_then(|id| providers.shift_remove(&id))
+            .ok_or_else(|| {
+                AppError::localized(
+                    "live_change_no_current_provider",
+                    format!("{} 没有当前供应商，无法吸收修改", app.as_str()),
+                    format!("{} has no current provider to absorb into", app.as_str()),
+                )
+            })?;
+        provider.settings_config = read_live_settings(app.clone())?;
+        state.db.save_provider(app.as_str(), &provider)
+    }
+
+    fn absorb_mcp(state: &AppState, app: &AppType) -> Result<(), AppError> {
+        let imported = match app {
+            AppType::Claude => McpService::import_from_claude(state)?,
+            AppType::Codex => McpService::import_from_codex(state)?,
+            AppType::Gemini => McpService::import_from_gemini(state)?,
+            AppType::OpenCode => McpService::import_from_opencode(state)?,
+        };
+        log::info!(
+            "从 {} 的 live 配置导入 {imported} 个 MCP 服务器",
+            app.as_str()
+        );
+        Ok(())
+    }
+}
+
+/// 把 live 文件复制到当前的渲染目录（文件不存在时跳过）
+fn copy_into_render_root(path: &Path) -> Result<(), AppError> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let target = live_watch::redirect(path.to_path_buf());
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    std::fs::copy(path, &target).map_err(|e| AppError::io(&target, e))?;
+    Ok(())
+}
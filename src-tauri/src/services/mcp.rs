@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use crate::app_config::{AppType, McpServer};
 use crate::error::AppError;
 use crate::mcp;
+use crate::services::WorkspaceService;
 use crate::store::AppState;
 
 /// MCP 相关业务逻辑（v3.7.0 统一结构）
@@ -44,7 +45,8 @@ impl McpService {
         // 同步到各个启用的应用
         Self::sync_server_to_apps(state, &server)?;
 
-        Ok(())
+        // 同步到启用了该服务器的工作区
+        WorkspaceService::sync_mcp_server(state, &server)
     }
 
     /// 删除 MCP 服务器
@@ -54,8 +56,9 @@ impl McpService {
         if let Some(server) = server {
             state.db.delete_mcp_server(id)?;
 
-            // 从所有应用的 live 配置中移除
+            // 从所有应用的 live 配置及工作区中移除
             Self::remove_server_from_all_apps(state, id, &server)?;
+            WorkspaceService::remove_mcp_server(state, id)?;
             Ok(true)
         } else {
             Ok(false)
@@ -163,6 +166,19 @@ impl McpService {
         Ok(())
     }
 
+    /// 同步所有启用的 MCP 服务器到指定应用（不改动其他应用的配置）
+    pub fn sync_app_enabled(state: &AppState, app: &AppType) -> Result<(), AppError> {
+        let servers = Self::get_all_servers(state)?;
+
+        for server in servers.values() {
+            if server.apps.is_enabled_for(app) {
+                Self::sync_server_to_app(state, server, app)?;
+            }
+        }
+
+        Ok(())
+    }
+
     // ========================================================================
     // 兼容层：支持旧的 v3.6.x 命令（已废弃，将在 v4.0 移除）
     // ========================================================================
@@ -200,15 +216,7 @@ impl McpService {
     /// [已废弃] 同步启用的 MCP 到指定应用（兼容旧 API）
     #[deprecated(since = "3.7.0", note = "Use sync_all_enabled instead")]
     pub fn sync_enabled(state: &AppState, app: AppType) -> Result<(), AppError> {
-        let servers = Self::get_all_servers(state)?;
-
-        for server in servers.values() {
-            if server.apps.is_enabled_for(&app) {
-                Self::sync_server_to_app(state, server, &app)?;
-            }
-        }
-
-        Ok(())
+        Self::sync_app_enabled(state, &app)
     }
 
     /// 从 Claude 导入 MCP（v3.7.0 已更新为统一结构）
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod env_checker;
 pub mod env_manager;
+pub mod live_watch;
 pub mod mcp;
 pub mod prompt;
 pub mod provider;
@@ -9,8 +10,10 @@ pub mod skill;
 pub mod speedtest;
 pub mod stream_check;
 pub mod usage_stats;
+pub mod workspace;
 
 pub use config::ConfigService;
+pub use live_watch::LiveWatchService;
 pub use mcp::McpService;
 pub use prompt::PromptService;
 pub use provider::{ProviderService, ProviderSortUpdate};
@@ -23,3 +26,4 @@ pub use usage_stats::{
     DailyStats, LogFilters, ModelStats, PaginatedLogs, ProviderLimitStatus, ProviderStats,
     RequestLogDetail, UsageSummary,
 };
+pub use workspace::WorkspaceService;
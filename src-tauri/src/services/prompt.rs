@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use indexmap::IndexMap;
 
 use crate::app_config::AppType;
@@ -5,7 +7,12 @@ use crate::config::write_text_file;
 use crate::error::AppError;
 use crate::prompt::Prompt;
 use crate::prompt_files::prompt_file_path;
+use crate::prompt_layers;
+use crate::prompt_revision::{self, DiffLine, PromptRevision, PromptRevisionSource};
+use crate::prompt_template::{self, TemplateContext};
+use crate::services::WorkspaceService;
 use crate::store::AppState;
+use crate::workspace::{self, Workspace};
 
 /// 安全地获取当前 Unix 时间戳
 fn get_unix_timestamp() -> Result<i64, AppError> {
@@ -30,33 +37,290 @@ impl PromptService {
         app: AppType,
         _id: &str,
         prompt: Prompt,
+        source: PromptRevisionSource,
     ) -> Result<(), AppError> {
-        // 检查是否为已启用的提示词
-        let is_enabled = prompt.enabled;
+        Self::save_prompt(state, &app, &prompt, source)?;
+        Self::write_prompt_file(state, &app, Some(&prompt.id))?;
+        WorkspaceService::sync_prompts(state, &app)
+    }
 
-        state.db.save_prompt(app.as_str(), &prompt)?;
+    /// 保存提示词，内容有变化时记录一个版本（见 [`prompt_revision`]）
+    fn save_prompt(
+        state: &AppState,
+        app: &AppType,
+        prompt: &Prompt,
+        source: PromptRevisionSource,
+    ) -> Result<(), AppError> {
+        let previous = state.db.get_prompts(app.as_str())?.shift_remove(&prompt.id);
+        state.db.save_prompt(app.as_str(), prompt)?;
+
+        let baseline = previous.as_ref().map(|p| {
+            (
+                p.content.as_str(),
+                p.updated_at.or(p.created_at).unwrap_or_default(),
+            )
+        });
+        state
+            .db
+            .record_prompt_revision(app.as_str(), &prompt.id, &prompt.content, source, baseline)
+    }
 
-        if is_enabled {
-            // 启用提示词：写入内容到文件
-            let target_path = prompt_file_path(&app)?;
-            write_text_file(&target_path, &prompt.content)?;
-        } else {
-            // 禁用提示词：检查是否还有其他已启用的提示词
-            let prompts = state.db.get_prompts(app.as_str())?;
-            let any_enabled = prompts.values().any(|p| p.enabled);
-
-            if !any_enabled {
-                // 所有提示词都已禁用，清空文件
-                let target_path = prompt_file_path(&app)?;
-                if target_path.exists() {
-                    write_text_file(&target_path, "")?;
+    /// 获取提示词的历史版本（最新的在前）
+    pub fn get_revisions(
+        state: &AppState,
+        app: AppType,
+        prompt_id: &str,
+    ) -> Result<Vec<PromptRevision>, AppError> {
+        state.db.get_prompt_revisions(app.as_str(), prompt_id)
+    }
+
+    /// 按行对比两个版本，`to` 为 None 时与提示词当前内容对比
+    pub fn diff_revisions(
+        state: &AppState,
+        app: AppType,
+        prompt_id: &str,
+        from: i64,
+        to: Option<i64>,
+    ) -> Result<Vec<DiffLine>, AppError> {
+        let old = Self::require_revision(state, &app, prompt_id, from)?.content;
+        let new = match to {
+            Some(to) => Self::require_revision(state, &app, prompt_id, to)?.content,
+            None => Self::require_prompt(state, &app, prompt_id)?.content,
+        };
+        Ok(prompt_revision::diff_lines(&old, &new))
+    }
+
+    /// 回滚提示词到指定版本（回滚本身记录为新版本），并重写提示词文件
+    ///
+    /// 提示词已被删除时按该版本恢复（名称取自删除时记录的版本，恢复后为禁用状态）
+    pub fn rollback_prompt(
+        state: &AppState,
+        app: AppType,
+        prompt_id: &str,
+        revision_id: i64,
+    ) -> Result<(), AppError> {
+        let revision = Self::require_revision(state, &app, prompt_id, revision_id)?;
+        let now = get_unix_timestamp()?;
+        let mut prompt = match state.db.get_prompts(app.as_str())?.shift_remove(prompt_id) {
+            Some(prompt) => prompt,
+            None => Self::deleted_prompt(state, &app, prompt_id, now)?,
+        };
+        prompt.content = revision.content;
+        prompt.updated_at = Some(now);
+        Self::upsert_prompt(
+            state,
+            app,
+            prompt_id,
+            prompt,
+            PromptRevisionSource::Rollback,
+        )
+    }
+
+    fn require_prompt(state: &AppState, app: &AppType, id: &str) -> Result<Prompt, AppError> {
+        state
+            .db
+            .get_prompts(app.as_str())?
+            .shift_remove(id)
+            .ok_or_else(|| AppError::InvalidInput(format!("提示词 {id} 不存在")))
+    }
+
+    /// 按删除时记录的版本重建已删除的提示词（内容由调用方填入）
+    fn deleted_prompt(
+        state: &AppState,
+        app: &AppType,
+        prompt_id: &str,
+        now: i64,
+    ) -> Result<Prompt, AppError> {
+        let name = state
+            .db
+            .get_prompt_revisions(app.as_str(), prompt_id)?
+            .into_iter()
+            .find(|r| r.source == PromptRevisionSource::Delete)
+            .and_then(|r| r.prompt_name)
+            .ok_or_else(|| AppError::InvalidInput(format!("提示词 {prompt_id} 不存在")))?;
+        Ok(Prompt {
+            id: prompt_id.to_string(),
+            name,
+            content: String::new(),
+            description: None,
+            enabled: false,
+            created_at: Some(now),
+            updated_at: Some(now),
+            layer: false,
+            layer_order: 0,
+        })
+    }
+
+    fn require_revision(
+        state: &AppState,
+        app: &AppType,
+        prompt_id: &str,
+        revision_id: i64,
+    ) -> Result<PromptRevision, AppError> {
+        state
+            .db
+            .get_prompt_revision(app.as_str(), revision_id)?
+            .filter(|r| r.prompt_id == prompt_id)
+            .ok_or_else(|| AppError::InvalidInput(format!("提示词版本 {revision_id} 不存在")))
+    }
+
+    /// 获取应用的提示词模板变量
+    pub fn get_variables(
+        state: &AppState,
+        app: AppType,
+    ) -> Result<BTreeMap<String, String>, AppError> {
+        state.db.get_prompt_variables(app.as_str())
+    }
+
+    /// 更新应用的提示词模板变量，并按新变量重写提示词文件
+    pub fn set_variables(
+        state: &AppState,
+        app: AppType,
+        variables: BTreeMap<String, String>,
+    ) -> Result<(), AppError> {
+        state.db.set_prompt_variables(app.as_str(), &variables)?;
+        Self::write_prompt_file(state, &app, None)?;
+        WorkspaceService::sync_prompts(state, &app)
+    }
+
+    /// 按模板渲染提示词内容（见 [`prompt_template`]）
+    ///
+    /// 可用变量依次为应用变量、工作区变量（`workspace` 不为 None 时）和内置变量，后者优先
+    pub(crate) fn render_prompts(
+        state: &AppState,
+        app: &AppType,
+        prompts: &IndexMap<String, Prompt>,
+        targets: &[&Prompt],
+        workspace: Option<&Workspace>,
+    ) -> Result<Vec<Prompt>, AppError> {
+        let mut variables = state.db.get_prompt_variables(app.as_str())?;
+        if let Some(ws) = workspace {
+            variables.extend(
+                state
+                    .db
+                    .get_prompt_variables(&workspace::variables_scope(&ws.id))?,
+            );
+            variables.insert(
+                prompt_template::VAR_WORKSPACE_NAME.to_string(),
+                ws.name.clone(),
+            );
+            variables.insert(
+                prompt_template::VAR_WORKSPACE_PATH.to_string(),
+                ws.path.clone(),
+            );
+        }
+        variables.insert(
+            prompt_template::VAR_APP.to_string(),
+            app.as_str().to_string(),
+        );
+
+        let ctx = TemplateContext {
+            app,
+            variables: &variables,
+            prompts,
+        };
+        targets
+            .iter()
+            .map(|prompt| {
+                Ok(Prompt {
+                    content: prompt_template::render(prompt, &ctx)?,
+                    ..(*prompt).clone()
+                })
+            })
+            .collect()
+    }
+
+    /// 按已启用的提示词重写提示词文件（内容按模板渲染，见 [`Self::render_prompt_file`]）
+    ///
+    /// 使用受管区块时，改写前先回填区块中的手动修改（`saved` 为刚保存的提示词，以保存的内容为准）
+    fn write_prompt_file(
+        state: &AppState,
+        app: &AppType,
+        saved: Option<&str>,
+    ) -> Result<(), AppError> {
+        let target_path = prompt_file_path(app)?;
+        let mut prompts = state.db.get_prompts(app.as_str())?;
+        let live = std::fs::read_to_string(&target_path).unwrap_or_default();
+
+        if prompt_layers::uses_managed_block(&prompts, &live) {
+            if let Some(layers) = prompt_layers::parse_block(&live) {
+                let layers = layers
+                    .into_iter()
+                    .filter(|(id, _)| Some(id.as_str()) != saved)
+                    .collect();
+                if Self::backfill_layers(state, app, layers)? {
+                    prompts = state.db.get_prompts(app.as_str())?;
                 }
             }
         }
 
-        Ok(())
+        match Self::render_prompt_file(state, app, &prompts, saved, &live, target_path.exists())? {
+            Some(content) if content != live || !target_path.exists() => {
+                write_text_file(&target_path, &content)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 计算提示词文件的新内容，返回 None 表示不需要改写
+    ///
+    /// 使用受管区块时只改写区块（见 [`prompt_layers`]）；否则沿用整文件写入：
+    /// `saved` 已启用时写入其内容，全部禁用时清空文件。`saved` 为 None（如变量变更）或
+    /// 已启用的是模板提示词（引入的提示词可能已变化）时，重新渲染已启用的提示词
+    fn render_prompt_file(
+        state: &AppState,
+        app: &AppType,
+        prompts: &IndexMap<String, Prompt>,
+        saved: Option<&str>,
+        live: &str,
+        exists: bool,
+    ) -> Result<Option<String>, AppError> {
+        if prompt_layers::uses_managed_block(prompts, live) {
+            let active = prompt_layers::active_prompts(prompts);
+            let rendered = Self::render_prompts(state, app, prompts, &active, None)?;
+            let block = prompt_layers::render_block(&rendered.iter().collect::<Vec<_>>());
+            // 还没有区块时，与某个提示词内容相同的原文件是旧版本整体写入的，可以直接替换
+            let legacy: Vec<&str> = prompts
+                .values()
+                .map(|p| p.content.as_str())
+                .chain(rendered.iter().map(|p| p.content.as_str()))
+                .collect();
+            return Ok(Some(prompt_layers::merge_block(live, &block, &legacy)));
+        }
+
+        let target = saved
+            .and_then(|id| prompts.get(id))
+            .filter(|p| p.enabled)
+            .or_else(|| {
+                prompts.values().find(|p| {
+                    p.enabled && (saved.is_none() || prompt_template::is_template(&p.content))
+                })
+            });
+        if let Some(prompt) = target {
+            let rendered = Self::render_prompts(state, app, prompts, &[prompt], None)?;
+            Ok(rendered.into_iter().next().map(|p| p.content))
+        } else if !prompts.values().any(|p| p.enabled) && exists {
+            // 所有提示词都已禁用，清空文件
+            Ok(Some(String::new()))
+        } else {
+            Ok(None)
+        }
     }
 
+    /// 按数据库中已启用的提示词计算提示词文件应有的内容（不回填文件中的修改）
+    ///
+    /// 用于判断文件是否被外部修改（见 [`crate::live_watch`]）；返回 None 表示
+    /// cc-switch 不会改写该文件
+    pub(crate) fn expected_prompt_file(
+        state: &AppState,
+        app: &AppType,
+        live: &str,
+    ) -> Result<Option<String>, AppError> {
+        let prompts = state.db.get_prompts(app.as_str())?;
+        Self::render_prompt_file(state, app, &prompts, None, live, true)
+    }
+
+    /// 删除提示词（保留版本历史并记录删除时的内容，可通过回滚恢复）
     pub fn delete_prompt(state: &AppState, app: AppType, id: &str) -> Result<(), AppError> {
         let prompts = state.db.get_prompts(app.as_str())?;
 
@@ -64,16 +328,64 @@ impl PromptService {
             if prompt.enabled {
                 return Err(AppError::InvalidInput("无法删除已启用的提示词".to_string()));
             }
+            state
+                .db
+                .record_prompt_deletion(app.as_str(), id, &prompt.name, &prompt.content)?;
         }
 
         state.db.delete_prompt(app.as_str(), id)?;
-        Ok(())
+        WorkspaceService::remove_prompt(state, &app, id)
     }
 
+    /// 已删除的提示词（每个提示词最近一次删除时的版本），回滚到其中的版本即可恢复
+    pub fn get_deleted_prompts(
+        state: &AppState,
+        app: AppType,
+    ) -> Result<Vec<PromptRevision>, AppError> {
+        state.db.get_deleted_prompt_revisions(app.as_str())
+    }
+
+    /// 启用提示词
+    ///
+    /// 普通提示词会停用其他普通提示词；分层提示词只启用自身
     pub fn enable_prompt(state: &AppState, app: AppType, id: &str) -> Result<(), AppError> {
         // 回填当前 live 文件内容到已启用的提示词，或创建备份
-        let target_path = prompt_file_path(&app)?;
-        if target_path.exists() {
+        Self::backfill_from_live(state, &app)?;
+
+        // 启用目标提示词并写入文件
+        let mut prompts = state.db.get_prompts(app.as_str())?;
+        let is_layer = match prompts.get(id) {
+            Some(prompt) => prompt.layer,
+            None => return Err(AppError::InvalidInput(format!("提示词 {id} 不存在"))),
+        };
+
+        for prompt in prompts.values_mut() {
+            let enabled = if prompt.id == id {
+                true
+            } else if is_layer || prompt.layer {
+                // 分层与其他提示词互不影响
+                prompt.enabled
+            } else {
+                false
+            };
+            if prompt.enabled != enabled || prompt.id == id {
+                prompt.enabled = enabled;
+                state.db.save_prompt(app.as_str(), prompt)?;
+            }
+        }
+
+        Self::write_prompt_file(state, &app, Some(id))
+    }
+
+    /// 把 live 提示词文件的内容回填到已启用的提示词（没有已启用的提示词时创建备份）
+    pub(crate) fn backfill_from_live(state: &AppState, app: &AppType) -> Result<(), AppError> {
+        let target_path = prompt_file_path(app)?;
+        let managed_layers = std::fs::read_to_string(&target_path)
+            .ok()
+            .and_then(|live| prompt_layers::parse_block(&live));
+        if let Some(layers) = managed_layers {
+            Self::backfill_layers(state, app, layers)?;
+        } else if target_path.exists() {
             if let Ok(live_content) = std::fs::read_to_string(&target_path) {
                 if !live_content.trim().is_empty() {
                     let mut prompts = state.db.get_prompts(app.as_str())?;
@@ -84,11 +396,21 @@ impl PromptService {
                         .find(|(_, p)| p.enabled)
                         .map(|(id, p)| (id.clone(), p))
                     {
-                        let timestamp = get_unix_timestamp()?;
-                        enabled_prompt.content = live_content.clone();
-                        enabled_prompt.updated_at = Some(timestamp);
-                        log::info!("回填 live 提示词内容到已启用项: {enabled_id}");
-                        state.db.save_prompt(app.as_str(), enabled_prompt)?;
+                        if prompt_template::is_template(&enabled_prompt.content) {
+                            // 文件内容是模板的渲染结果，不回填
+                            log::info!("已启用的提示词为模板，跳过回填: {enabled_id}");
+                        } else {
+                            let timestamp = get_unix_timestamp()?;
+                            enabled_prompt.content = live_content.clone();
+                            enabled_prompt.updated_at = Some(timestamp);
+                            log::info!("回填 live 提示词内容到已启用项: {enabled_id}");
+                            Self::save_prompt(
+                                state,
+                                app,
+                                enabled_prompt,
+                                PromptRevisionSource::Backfill,
+                            )?;
+                        }
                     } else {
                         // 没有已启用的提示词，则创建一次备份（避免重复备份）
                         let content_exists = prompts
@@ -111,36 +433,49 @@ impl PromptService {
                                 enabled: false,
                                 created_at: Some(timestamp),
                                 updated_at: Some(timestamp),
+                                layer: false,
+                                layer_order: 0,
                             };
                             log::info!("回填 live 提示词内容，创建备份: {backup_id}");
-                            state.db.save_prompt(app.as_str(), &backup_prompt)?;
+                            Self::save_prompt(
+                                state,
+                                app,
+                                &backup_prompt,
+                                PromptRevisionSource::Backfill,
+                            )?;
                         }
                     }
                 }
             }
         }
+        Ok(())
+    }
 
-        // 启用目标提示词并写入文件
+    /// 把受管区块中被手动修改的内容回填到对应的已启用提示词（区块外的内容不回填）
+    ///
+    /// 返回是否有提示词被更新
+    fn backfill_layers(
+        state: &AppState,
+        app: &AppType,
+        layers: Vec<(String, String)>,
+    ) -> Result<bool, AppError> {
         let mut prompts = state.db.get_prompts(app.as_str())?;
-
-        for prompt in prompts.values_mut() {
-            prompt.enabled = false;
-        }
-
-        if let Some(prompt) = prompts.get_mut(id) {
-            prompt.enabled = true;
-            write_text_file(&target_path, &prompt.content)?; // 原子写入
-            state.db.save_prompt(app.as_str(), prompt)?;
-        } else {
-            return Err(AppError::InvalidInput(format!("提示词 {id} 不存在")));
-        }
-
-        // Save all prompts to disable others
-        for (_, prompt) in prompts.iter() {
-            state.db.save_prompt(app.as_str(), prompt)?;
+        let mut updated = false;
+        for (id, content) in layers {
+            let Some(prompt) = prompts.get_mut(&id).filter(|p| p.enabled) else {
+                continue;
+            };
+            // 模板提示词写入的是渲染结果，不回填
+            if prompt.content.trim() == content || prompt_template::is_template(&prompt.content) {
+                continue;
+            }
+            prompt.content = content;
+            prompt.updated_at = Some(get_unix_timestamp()?);
+            log::info!("回填受管区块中的提示词内容: {id}");
+            Self::save_prompt(state, app, prompt, PromptRevisionSource::Backfill)?;
+            updated = true;
         }
-
-        Ok(())
+        Ok(updated)
     }
 
     pub fn import_from_file(state: &AppState, app: AppType) -> Result<String, AppError> {
@@ -166,9 +501,11 @@ impl PromptService {
             enabled: false,
             created_at: Some(timestamp),
             updated_at: Some(timestamp),
+            layer: false,
+            layer_order: 0,
         };
 
-        Self::upsert_prompt(state, app, &id, prompt)?;
+        Self::upsert_prompt(state, app, &id, prompt, PromptRevisionSource::Import)?;
         Ok(id)
     }
 
@@ -231,10 +568,12 @@ impl PromptService {
             enabled: true, // 首次导入时自动启用
             created_at: Some(timestamp),
             updated_at: Some(timestamp),
+            layer: false,
+            layer_order: 0,
         };
 
         // 保存到数据库
-        state.db.save_prompt(app.as_str(), &prompt)?;
+        Self::save_prompt(state, &app, &prompt, PromptRevisionSource::Import)?;
 
         log::info!("自动导入完成: {}", app.as_str());
         Ok(1)
@@ -113,8 +113,9 @@ pub(crate) fn write_live_snapshot(app_type: &AppType, provider: &Provider) -> Re
             write_json_file(&path, &settings)?;
 
             // Sync to VS Code Claude Code extension if enabled
+            // 计算 live 配置的期望内容时只写 live 文件
             let app_settings = crate::settings::get_settings();
-            if app_settings.enable_vscode_claude_sync {
+            if app_settings.enable_vscode_claude_sync && !crate::live_watch::is_rendering() {
                 if let Some(env) = provider.settings_config.get("env") {
                     if let Err(e) = crate::vscode_sync::sync_env_to_vscode(env) {
                         log::warn!("同步 VS Code Claude 插件失败: {e}");
@@ -137,7 +138,7 @@ pub(crate) fn write_live_snapshot(app_type: &AppType, provider: &Provider) -> Re
             let auth_path = get_codex_auth_path();
             write_json_file(&auth_path, auth)?;
             let config_path = get_codex_config_path();
-            std::fs::write(&config_path, config_str).map_err(|e| AppError::io(&config_path, e))?;
+            crate::config::write_text_file(&config_path, config_str)?;
         }
         AppType::Gemini => {
             // Delegate to write_gemini_live which handles env file writing correctly
@@ -241,24 +242,7 @@ fn sync_all_providers_to_live(state: &AppState, app_type: &AppType) -> Result<()
 pub fn sync_current_to_live(state: &AppState) -> Result<(), AppError> {
     // Sync providers based on mode
     for app_type in AppType::all() {
-        if app_type.is_additive_mode() {
-            // Additive mode: sync ALL providers
-            sync_all_providers_to_live(state, &app_type)?;
-        } else {
-            // Switch mode: sync only current provider
-            let current_id =
-                match crate::settings::get_effective_current_provider(&state.db, &app_type)? {
-                    Some(id) => id,
-                    None => continue,
-                };
-
-            let providers = state.db.get_all_providers(app_type.as_str())?;
-            if let Some(provider) = providers.get(&current_id) {
-                write_live_snapshot(&app_type, provider)?;
-            }
-            // Note: get_effective_current_provider already validates existence,
-            // so providers.get() should always succeed here
-        }
+        sync_app_providers_to_live(state, &app_type)?;
     }
 
     // MCP sync
@@ -275,6 +259,32 @@ pub fn sync_current_to_live(state: &AppState) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Sync one app's providers to live configuration
+///
+/// Switch mode writes the current provider; additive mode (OpenCode) writes all providers.
+pub(crate) fn sync_app_providers_to_live(
+    state: &AppState,
+    app_type: &AppType,
+) -> Result<(), AppError> {
+    if app_type.is_additive_mode() {
+        // Additive mode: sync ALL providers
+        return sync_all_providers_to_live(state, app_type);
+    }
+
+    // Switch mode: sync only current provider
+    let Some(current_id) = crate::settings::get_effective_current_provider(&state.db, app_type)?
+    else {
+        return Ok(());
+    };
+    let providers = state.db.get_all_providers(app_type.as_str())?;
+    if let Some(provider) = providers.get(&current_id) {
+        write_live_snapshot(app_type, provider)?;
+    }
+    // Note: get_effective_current_provider already validates existence,
+    // so providers.get() should always succeed here
+    Ok(())
+}
+
 /// Read current live settings for an app type
 pub fn read_live_settings(app_type: AppType) -> Result<Value, AppError> {
     match app_type {
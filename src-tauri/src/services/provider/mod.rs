@@ -27,7 +27,7 @@ pub use live::{
 
 // Internal re-exports (pub(crate))
 pub(crate) use live::sanitize_claude_settings_for_live;
-pub(crate) use live::write_live_snapshot;
+pub(crate) use live::{sync_app_providers_to_live, write_live_snapshot};
 
 // Internal re-exports
 use live::{remove_opencode_provider_from_live, write_gemini_live};
@@ -5,6 +5,7 @@
 use crate::app_config::AppType;
 use crate::config::{get_claude_settings_path, read_json_file, write_json_file};
 use crate::database::Database;
+use crate::error::AppError;
 use crate::provider::Provider;
 use crate::proxy::server::ProxyServer;
 use crate::proxy::types::*;
@@ -112,6 +113,7 @@ impl ProxyService {
                 port: status.port,
                 // 无法精确取回首次启动时间，返回当前时间用于 UI 展示即可
                 started_at: chrono::Utc::now().to_rfc3339(),
+                tls: status.tls,
             });
         }
 
@@ -803,7 +805,18 @@ impl ProxyService {
             connect_host
         };
 
-        let proxy_origin = format!("http://{}:{}", connect_host_for_url, config.listen_port);
+        // 代理运行中以实际监听方式为准，否则按 TLS 配置推断下次启动的协议
+        let tls = match self.server.read().await.as_ref() {
+            Some(server) => server.get_status().await.tls,
+            None => self
+                .db
+                .get_proxy_tls_config()
+                .map(|c| c.enabled)
+                .unwrap_or(false),
+        };
+        let scheme = if tls { "https" } else { "http" };
+
+        let proxy_origin = format!("{scheme}://{}:{}", connect_host_for_url, config.listen_port);
         let proxy_url = proxy_origin.clone();
         let proxy_codex_base_url = format!("{}/v1", proxy_origin.trim_end_matches('/'));
 
@@ -1189,6 +1202,31 @@ impl ProxyService {
         }
     }
 
+    /// 代理接管期间拒绝改写该应用的 live 配置
+    ///
+    /// 接管中的 live 配置指向本地代理（有接管备份，或 live 中仍是接管占位符），
+    /// 直接写入供应商配置会破坏接管
+    pub fn ensure_live_not_taken_over(&self, app_type: &AppType) -> Result<(), AppError> {
+        let has_backup = futures::executor::block_on(self.db.get_live_backup(app_type.as_str()))
+            .ok()
+            .flatten()
+            .is_some();
+        if has_backup || self.detect_takeover_in_live_config_for_app(app_type) {
+            return Err(AppError::localized(
+                "proxy.live_taken_over",
+                format!(
+                    "{} 当前处于代理接管模式，live 配置指向本地代理。请在运行代理的 CC Switch 实例中操作，或先关闭接管。",
+                    app_type.as_str()
+                ),
+                format!(
+                    "{} is currently taken over by the proxy and its live config points to the local proxy. Use the CC Switch instance running the proxy, or turn off the takeover first.",
+                    app_type.as_str()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn detect_takeover_in_live_config_for_app(&self, app_type: &AppType) -> bool {
         match app_type {
             AppType::Claude => match self.read_claude_live() {
@@ -1255,10 +1293,12 @@ impl ProxyService {
 
     fn is_local_proxy_url(url: &str) -> bool {
         let url = url.trim();
-        if !url.starts_with("http://") {
+        let Some(rest) = url
+            .strip_prefix("http://")
+            .or_else(|| url.strip_prefix("https://"))
+        else {
             return false;
-        }
-        let rest = &url["http://".len()..];
+        };
         rest.starts_with("127.0.0.1")
             || rest.starts_with("localhost")
             || rest.starts_with("0.0.0.0")
@@ -1855,6 +1895,20 @@ impl ProxyService {
         Ok(())
     }
 
+    /// 保存入站访问控制配置
+    ///
+    /// 如果代理服务器正在运行，立即应用到访问控制中间件与 CORS 校验
+    pub async fn update_auth_config(&self, config: ProxyAuthConfig) -> Result<(), String> {
+        self.db
+            .set_proxy_auth_config(&config)
+            .map_err(|e| format!("保存代理访问控制配置失败: {e}"))?;
+        if let Some(server) = self.server.read().await.as_ref() {
+            server.apply_auth_config(config);
+            log::info!("代理访问控制配置已实时应用");
+        }
+        Ok(())
+    }
+
     /// 重置指定 Provider 的熔断器
     ///
     /// 如果代理服务器正在运行，立即重置内存中的熔断器状态
@@ -1871,6 +1925,19 @@ impl ProxyService {
         }
         Ok(())
     }
+
+    /// 获取指定 Provider 的上游限流状态（代理未运行时为 None）
+    pub async fn get_provider_rate_limit(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+    ) -> Option<RateLimitState> {
+        self.server
+            .read()
+            .await
+            .as_ref()
+            .and_then(|server| server.get_provider_rate_limit(provider_id, app_type))
+    }
 }
 
 #[cfg(test)]
@@ -39,6 +39,9 @@ pub struct StreamCheckConfig {
     /// 检查提示词
     #[serde(default = "default_test_prompt")]
     pub test_prompt: String,
+    /// 代理运行时后台探测故障转移队列的间隔（秒），0 表示关闭
+    #[serde(default)]
+    pub probe_interval_secs: u64,
 }
 
 fn default_test_prompt() -> String {
@@ -55,6 +58,7 @@ impl Default for StreamCheckConfig {
             codex_model: "gpt-5.1-codex@low".to_string(),
             gemini_model: "gemini-3-pro-preview".to_string(),
             test_prompt: default_test_prompt(),
+            probe_interval_secs: 0,
         }
     }
 }
@@ -73,6 +77,22 @@ pub struct StreamCheckResult {
     pub retry_count: u32,
 }
 
+impl StreamCheckResult {
+    /// 检查过程出错（未拿到响应）时的失败结果
+    pub fn failed(message: String) -> Self {
+        Self {
+            status: HealthStatus::Failed,
+            success: false,
+            message,
+            response_time_ms: None,
+            http_status: None,
+            model_used: String::new(),
+            tested_at: chrono::Utc::now().timestamp(),
+            retry_count: 0,
+        }
+    }
+}
+
 /// 流式健康检查服务
 pub struct StreamCheckService;
 
@@ -168,6 +188,7 @@ impl StreamCheckService {
                     .test_prompt
                     .clone()
                     .unwrap_or_else(|| global_config.test_prompt.clone()),
+                probe_interval_secs: global_config.probe_interval_secs,
             },
             None => global_config.clone(),
         }
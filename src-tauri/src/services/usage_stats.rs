@@ -4,6 +4,9 @@
 
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
+use crate::proxy::types::RequestBodyCapture;
+use crate::proxy::usage::calculator::{CostCalculator, ModelPricing};
+use crate::proxy::usage::parser::TokenUsage;
 use chrono::{Local, TimeZone};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
@@ -22,6 +25,10 @@ pub struct UsageSummary {
     pub total_cache_creation_tokens: u64,
     pub total_cache_read_tokens: u64,
     pub success_rate: f32,
+    /// 响应缓存命中次数
+    pub cache_hits: u64,
+    /// 响应缓存节省的费用（按命中时的 token 用量与模型定价估算）
+    pub cache_savings: String,
 }
 
 /// 每日统计
@@ -51,6 +58,25 @@ pub struct ProviderStats {
     pub avg_latency_ms: u64,
 }
 
+/// 虚拟 Key 统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualKeyStats {
+    pub key_id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub enabled: bool,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    pub total_cost: String,
+    pub success_rate: f32,
+    pub daily_usage: String,
+    pub daily_limit: Option<String>,
+    pub monthly_usage: String,
+    pub monthly_limit: Option<String>,
+    pub last_used_at: Option<i64>,
+}
+
 /// 模型统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -72,6 +98,8 @@ pub struct LogFilters {
     pub status_code: Option<u16>,
     pub start_date: Option<i64>,
     pub end_date: Option<i64>,
+    /// 按虚拟 Key 过滤
+    pub virtual_key_id: Option<String>,
 }
 
 /// 分页请求日志响应
@@ -112,7 +140,21 @@ pub struct RequestLogDetail {
     pub duration_ms: Option<u64>,
     pub status_code: u16,
     pub error_message: Option<String>,
+    /// 实际使用的 API Key（脱敏）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub masked_key: Option<String>,
+    /// 是否由响应缓存直接返回
+    pub cache_hit: bool,
+    /// 发起请求的虚拟 Key ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub virtual_key_id: Option<String>,
+    /// 对冲角色（winner / loser）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hedge_role: Option<String>,
     pub created_at: i64,
+    /// 抓取的请求/响应体（仅请求详情中返回，且需开启抓取模式）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_capture: Option<RequestBodyCapture>,
 }
 
 impl Database {
@@ -150,11 +192,14 @@ impl Database {
                 COALESCE(SUM(output_tokens), 0) as total_output_tokens,
                 COALESCE(SUM(cache_creation_tokens), 0) as total_cache_creation_tokens,
                 COALESCE(SUM(cache_read_tokens), 0) as total_cache_read_tokens,
-                COALESCE(SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END), 0) as success_count
+                COALESCE(SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END), 0) as success_count,
+                COALESCE(SUM(cache_hit), 0) as cache_hits
              FROM proxy_request_logs
              {where_clause}"
         );
 
+        let cache_savings = estimate_cache_savings(&conn, &where_clause, &params_vec)?;
+
         let result = conn.query_row(&sql, rusqlite::params_from_iter(params_vec), |row| {
             let total_requests: i64 = row.get(0)?;
             let total_cost: f64 = row.get(1)?;
@@ -163,6 +208,7 @@ impl Database {
             let total_cache_creation_tokens: i64 = row.get(4)?;
             let total_cache_read_tokens: i64 = row.get(5)?;
             let success_count: i64 = row.get(6)?;
+            let cache_hits: i64 = row.get(7)?;
 
             let success_rate = if total_requests > 0 {
                 (success_count as f32 / total_requests as f32) * 100.0
@@ -178,6 +224,8 @@ impl Database {
                 total_cache_creation_tokens: total_cache_creation_tokens as u64,
                 total_cache_read_tokens: total_cache_read_tokens as u64,
                 success_rate,
+                cache_hits: cache_hits as u64,
+                cache_savings: format!("{cache_savings:.6}"),
             })
         })?;
 
@@ -342,6 +390,61 @@ impl Database {
         Ok(stats)
     }
 
+    /// 获取虚拟 Key 统计（含今日/本月消费与限额）
+    pub fn get_virtual_key_stats(&self) -> Result<Vec<VirtualKeyStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let sql = "SELECT
+                k.id, k.name, k.key_prefix, k.enabled, k.limit_daily_usd, k.limit_monthly_usd,
+                COUNT(l.request_id) as request_count,
+                COALESCE(SUM(l.input_tokens + l.output_tokens), 0) as total_tokens,
+                COALESCE(SUM(CAST(l.total_cost_usd AS REAL)), 0) as total_cost,
+                COALESCE(SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 1 ELSE 0 END), 0) as success_count,
+                COALESCE(SUM(CASE WHEN date(datetime(l.created_at, 'unixepoch', 'localtime')) = date('now', 'localtime')
+                    THEN CAST(l.total_cost_usd AS REAL) ELSE 0 END), 0) as daily_usage,
+                COALESCE(SUM(CASE WHEN strftime('%Y-%m', datetime(l.created_at, 'unixepoch', 'localtime')) = strftime('%Y-%m', 'now', 'localtime')
+                    THEN CAST(l.total_cost_usd AS REAL) ELSE 0 END), 0) as monthly_usage,
+                MAX(l.created_at) as last_used_at
+             FROM proxy_virtual_keys k
+             LEFT JOIN proxy_request_logs l ON l.virtual_key_id = k.id
+             GROUP BY k.id
+             ORDER BY total_cost DESC, k.created_at ASC";
+
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| {
+            let request_count: i64 = row.get(6)?;
+            let success_count: i64 = row.get(9)?;
+            let success_rate = if request_count > 0 {
+                (success_count as f32 / request_count as f32) * 100.0
+            } else {
+                0.0
+            };
+
+            Ok(VirtualKeyStats {
+                key_id: row.get(0)?,
+                name: row.get(1)?,
+                key_prefix: row.get(2)?,
+                enabled: row.get(3)?,
+                daily_limit: row.get(4)?,
+                monthly_limit: row.get(5)?,
+                request_count: request_count as u64,
+                total_tokens: row.get::<_, i64>(7)? as u64,
+                total_cost: format!("{:.6}", row.get::<_, f64>(8)?),
+                success_rate,
+                daily_usage: format!("{:.6}", row.get::<_, f64>(10)?),
+                monthly_usage: format!("{:.6}", row.get::<_, f64>(11)?),
+                last_used_at: row.get(12)?,
+            })
+        })?;
+
+        let mut stats = Vec::new();
+        for row in rows {
+            stats.push(row?);
+        }
+
+        Ok(stats)
+    }
+
     /// 获取模型统计
     pub fn get_model_stats(&self) -> Result<Vec<ModelStats>, AppError> {
         let conn = lock_conn!(self.conn);
@@ -418,6 +521,10 @@ impl Database {
             conditions.push("l.created_at <= ?");
             params.push(Box::new(end));
         }
+        if let Some(ref virtual_key_id) = filters.virtual_key_id {
+            conditions.push("l.virtual_key_id = ?");
+            params.push(Box::new(virtual_key_id.clone()));
+        }
 
         let where_clause = if conditions.is_empty() {
             String::new()
@@ -447,7 +554,8 @@ impl Database {
                     l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
                     l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
                     l.is_streaming, l.latency_ms, l.first_token_ms, l.duration_ms,
-                    l.status_code, l.error_message, l.created_at
+                    l.status_code, l.error_message, l.created_at, l.masked_key, l.cache_hit,
+                    l.virtual_key_id, l.hedge_role
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              {where_clause}
@@ -484,6 +592,11 @@ impl Database {
                 status_code: row.get::<_, i64>(20)? as u16,
                 error_message: row.get(21)?,
                 created_at: row.get(22)?,
+                masked_key: row.get(23)?,
+                cache_hit: row.get::<_, i64>(24)? != 0,
+                virtual_key_id: row.get(25)?,
+                hedge_role: row.get(26)?,
+                body_capture: None,
             })
         })?;
 
@@ -523,7 +636,8 @@ impl Database {
                     input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                     input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                     is_streaming, latency_ms, first_token_ms, duration_ms,
-                    status_code, error_message, created_at
+                    status_code, error_message, created_at, l.masked_key, l.cache_hit,
+                    l.virtual_key_id, l.hedge_role
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              WHERE l.request_id = ?",
@@ -553,6 +667,11 @@ impl Database {
                     status_code: row.get::<_, i64>(20)? as u16,
                     error_message: row.get(21)?,
                     created_at: row.get(22)?,
+                    masked_key: row.get(23)?,
+                    cache_hit: row.get::<_, i64>(24)? != 0,
+                    virtual_key_id: row.get(25)?,
+                    hedge_role: row.get(26)?,
+                    body_capture: None,
                 })
             },
         );
@@ -567,6 +686,9 @@ impl Database {
                     &mut provider_cache,
                     &mut pricing_cache,
                 )?;
+                drop(conn);
+                detail.body_capture =
+                    self.get_request_body_capture(request_id, chrono::Utc::now().timestamp())?;
                 Ok(Some(detail))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -648,6 +770,27 @@ impl Database {
             monthly_exceeded,
         })
     }
+
+    /// 虚拟 Key 今日与本月的消费（USD）
+    ///
+    /// 每条请求日志的费用在记录时已由 `CostCalculator` 按模型定价与倍率算出，这里直接汇总
+    pub fn get_virtual_key_spend(&self, key_id: &str) -> Result<(f64, f64), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let spend = conn.query_row(
+            "SELECT
+                COALESCE(SUM(CASE WHEN date(datetime(created_at, 'unixepoch', 'localtime')) = date('now', 'localtime')
+                    THEN CAST(total_cost_usd AS REAL) ELSE 0 END), 0),
+                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
+             FROM proxy_request_logs
+             WHERE virtual_key_id = ?
+               AND strftime('%Y-%m', datetime(created_at, 'unixepoch', 'localtime')) = strftime('%Y-%m', 'now', 'localtime')",
+            [key_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        Ok(spend)
+    }
 }
 
 /// Provider 限额状态
@@ -814,6 +957,71 @@ impl Database {
     }
 }
 
+/// 估算响应缓存节省的费用
+///
+/// 缓存命中的日志成本记为 0，这里按命中时记录的 token 用量、模型定价与成本倍率重新计算；
+/// 优先按响应模型定价，找不到时回退到请求模型
+fn estimate_cache_savings(
+    conn: &Connection,
+    where_clause: &str,
+    params_vec: &[i64],
+) -> Result<rust_decimal::Decimal, AppError> {
+    let hit_clause = if where_clause.is_empty() {
+        "WHERE cache_hit = 1".to_string()
+    } else {
+        format!("{where_clause} AND cache_hit = 1")
+    };
+    let sql = format!(
+        "SELECT model, request_model, cost_multiplier,
+                COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0),
+                COALESCE(SUM(cache_read_tokens), 0), COALESCE(SUM(cache_creation_tokens), 0)
+         FROM proxy_request_logs
+         {hit_clause}
+         GROUP BY model, request_model, cost_multiplier"
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let groups = stmt
+        .query_map(rusqlite::params_from_iter(params_vec), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                TokenUsage {
+                    input_tokens: row.get::<_, i64>(3)?.clamp(0, u32::MAX as i64) as u32,
+                    output_tokens: row.get::<_, i64>(4)?.clamp(0, u32::MAX as i64) as u32,
+                    cache_read_tokens: row.get::<_, i64>(5)?.clamp(0, u32::MAX as i64) as u32,
+                    cache_creation_tokens: row.get::<_, i64>(6)?.clamp(0, u32::MAX as i64) as u32,
+                    model: None,
+                },
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut savings = rust_decimal::Decimal::ZERO;
+    for (model, request_model, multiplier, usage) in groups {
+        let row = match find_model_pricing_row(conn, &model)? {
+            Some(row) => Some(row),
+            None => match request_model.as_deref() {
+                Some(request_model) => find_model_pricing_row(conn, request_model)?,
+                None => None,
+            },
+        };
+        let Some((input, output, cache_read, cache_creation)) = row else {
+            continue;
+        };
+        let Ok(pricing) = ModelPricing::from_strings(&input, &output, &cache_read, &cache_creation)
+        else {
+            continue;
+        };
+        let multiplier =
+            rust_decimal::Decimal::from_str(&multiplier).unwrap_or(rust_decimal::Decimal::ONE);
+        savings += CostCalculator::calculate(&usage, &pricing, multiplier).total_cost;
+    }
+
+    Ok(savings)
+}
+
 pub(crate) fn find_model_pricing_row(
     conn: &Connection,
     model_id: &str,
@@ -892,6 +1100,75 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_usage_summary_reports_cache_savings() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO model_pricing (model_id, display_name, input_cost_per_million, output_cost_per_million)
+                 VALUES ('cache-test-model', 'Cache Test', '3.0', '15.0')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model,
+                    input_tokens, output_tokens, total_cost_usd,
+                    latency_ms, status_code, created_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    "req1",
+                    "p1",
+                    "claude",
+                    "cache-test-model",
+                    1000,
+                    500,
+                    "0.0105",
+                    100,
+                    200,
+                    1000
+                ],
+            )?;
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model,
+                    input_tokens, output_tokens, total_cost_usd, cost_multiplier,
+                    latency_ms, status_code, cache_hit, created_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    "req2",
+                    "p1",
+                    "claude",
+                    "cache-test-model",
+                    1000,
+                    500,
+                    "0",
+                    "2",
+                    1,
+                    200,
+                    1,
+                    2000
+                ],
+            )?;
+        }
+
+        let summary = db.get_usage_summary(None, None)?;
+        assert_eq!(summary.total_requests, 2);
+        assert_eq!(summary.cache_hits, 1);
+        assert_eq!(summary.total_cost, "0.010500");
+        // 命中的请求按定价 × 倍率估算：(1000 × 3 + 500 × 15) / 1M × 2
+        assert_eq!(summary.cache_savings, "0.021000");
+
+        let summary = db.get_usage_summary(Some(1500), None)?;
+        assert_eq!(summary.cache_hits, 1);
+        let summary = db.get_usage_summary(None, Some(1500))?;
+        assert_eq!(summary.cache_hits, 0);
+        assert_eq!(summary.cache_savings, "0.000000");
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_model_stats() -> Result<(), AppError> {
         let db = Database::memory()?;
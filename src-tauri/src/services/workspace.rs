@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::app_config::{AppType, McpServer};
+use crate::error::AppError;
+use crate::prompt::Prompt;
+use crate::services::PromptService;
+use crate::store::AppState;
+use crate::workspace::{self, Workspace, WorkspaceAssignment, WorkspaceItemKind};
+
+/// 工作区（项目级提示词与 MCP）相关业务逻辑，见 [`crate::workspace`]
+pub struct WorkspaceService;
+
+impl WorkspaceService {
+    pub fn get_workspaces(state: &AppState) -> Result<Vec<Workspace>, AppError> {
+        state.db.get_workspaces()
+    }
+
+    /// 登记项目目录（必须是已存在的目录，同一目录只能登记一次）
+    pub fn add_workspace(state: &AppState, name: &str, path: &str) -> Result<Workspace, AppError> {
+        let dir = Path::new(path.trim());
+        if !dir.is_dir() {
+            return Err(AppError::InvalidInput(format!(
+                "目录不存在: {}",
+                dir.display()
+            )));
+        }
+        let dir = dir.canonicalize().map_err(|e| AppError::io(dir, e))?;
+        let path = dir.to_string_lossy().to_string();
+
+        if state.db.get_workspaces()?.iter().any(|w| w.path == path) {
+            return Err(AppError::InvalidInput(format!("工作区已存在: {path}")));
+        }
+
+        let name = match name.trim() {
+            "" => dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone()),
+            name => name.to_string(),
+        };
+        let workspace = Workspace {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            path,
+            created_at: chrono::Utc::now().timestamp(),
+            assignments: Vec::new(),
+        };
+        state.db.insert_workspace(&workspace)?;
+        Ok(workspace)
+    }
+
+    /// 移除工作区：先从项目文件中移除 cc-switch 写入的内容，再删除登记
+    ///
+    /// 项目目录已不存在或清理失败时只记录日志，登记照常删除
+    pub fn remove_workspace(state: &AppState, id: &str) -> Result<bool, AppError> {
+        let Some(workspace) = Self::find(state, id)? else {
+            return Ok(false);
+        };
+
+        if Path::new(&workspace.path).is_dir() {
+            for assignment in &workspace.assignments {
+                if let Err(e) = Self::clear_item(&workspace, assignment) {
+                    log::warn!(
+                        "清理工作区 {} 中的 {} {} 失败: {e}",
+                        workspace.path,
+                        assignment.kind.as_str(),
+                        assignment.item_id
+                    );
+                }
+            }
+        }
+
+        state.db.delete_workspace(id)?;
+        state
+            .db
+            .set_prompt_variables(&workspace::variables_scope(id), &BTreeMap::new())?;
+        Ok(true)
+    }
+
+    /// 在工作区中启用/停用提示词
+    ///
+    /// 与全局开关一致：普通提示词会停用该工作区中其他普通提示词，分层提示词互不影响
+    pub fn set_prompt_enabled(
+        state: &AppState,
+        workspace_id: &str,
+        app: AppType,
+        prompt_id: &str,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let workspace = Self::require(state, workspace_id)?;
+        let dir = Path::new(&workspace.path);
+        workspace::project_prompt_path(dir, &app)?;
+
+        let prompts = state.db.get_prompts(app.as_str())?;
+        let Some(target) = prompts.get(prompt_id) else {
+            return Err(AppError::InvalidInput(format!("提示词 {prompt_id} 不存在")));
+        };
+
+        if enabled && !target.layer {
+            for other in workspace.enabled_items(WorkspaceItemKind::Prompt, &app) {
+                let exclusive = prompts.get(other).is_some_and(|p| !p.layer);
+                if other != prompt_id && exclusive {
+                    state.db.set_workspace_assignment(
+                        workspace_id,
+                        &Self::assignment(WorkspaceItemKind::Prompt, &app, other),
+                        false,
+                    )?;
+                }
+            }
+        }
+        state.db.set_workspace_assignment(
+            workspace_id,
+            &Self::assignment(WorkspaceItemKind::Prompt, &app, prompt_id),
+            enabled,
+        )?;
+
+        let workspace = Self::require(state, workspace_id)?;
+        Self::write_prompts(state, &workspace, &app)
+    }
+
+    /// 在工作区中启用/停用 MCP 服务器
+    pub fn set_mcp_enabled(
+        state: &AppState,
+        workspace_id: &str,
+        server_id: &str,
+        app: AppType,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let workspace = Self::require(state, workspace_id)?;
+        let path = workspace::project_mcp_path(Path::new(&workspace.path), &app)?;
+
+        let servers = state.db.get_all_mcp_servers()?;
+        let Some(server) = servers.get(server_id) else {
+            return Err(AppError::InvalidInput(format!(
+                "MCP 服务器 {server_id} 不存在"
+            )));
+        };
+
+        state.db.set_workspace_assignment(
+            workspace_id,
+            &Self::assignment(WorkspaceItemKind::Mcp, &app, server_id),
+            enabled,
+        )?;
+        workspace::set_project_mcp_server(&path, &app, server_id, enabled.then_some(&server.server))
+    }
+
+    /// 获取工作区的提示词模板变量
+    pub fn get_variables(
+        state: &AppState,
+        workspace_id: &str,
+    ) -> Result<BTreeMap<String, String>, AppError> {
+        state
+            .db
+            .get_prompt_variables(&workspace::variables_scope(workspace_id))
+    }
+
+    /// 更新工作区的提示词模板变量，并按新变量重写该工作区的提示词文件
+    pub fn set_variables(
+        state: &AppState,
+        workspace_id: &str,
+        variables: BTreeMap<String, String>,
+    ) -> Result<(), AppError> {
+        let workspace = Self::require(state, workspace_id)?;
+        state
+            .db
+            .set_prompt_variables(&workspace::variables_scope(workspace_id), &variables)?;
+
+        for app in AppType::all() {
+            if !workspace
+                .enabled_items(WorkspaceItemKind::Prompt, &app)
+                .is_empty()
+            {
+                Self::write_prompts(state, &workspace, &app)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 提示词保存后，重写启用了该应用提示词的工作区
+    ///
+    /// 模板提示词可能引入了刚保存的提示词，因此不只重写启用了它的工作区
+    pub fn sync_prompts(state: &AppState, app: &AppType) -> Result<(), AppError> {
+        for workspace in state.db.get_workspaces()? {
+            if !workspace
+                .enabled_items(WorkspaceItemKind::Prompt, app)
+                .is_empty()
+            {
+                Self::write_prompts(state, &workspace, app)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 提示词删除后，从启用了它的工作区中移除
+    pub fn remove_prompt(state: &AppState, app: &AppType, prompt_id: &str) -> Result<(), AppError> {
+        let assignment = Self::assignment(WorkspaceItemKind::Prompt, app, prompt_id);
+        for workspace in state.db.get_workspaces()? {
+            if workspace.assignments.contains(&assignment) {
+                state
+                    .db
+                    .set_workspace_assignment(&workspace.id, &assignment, false)?;
+                let workspace = Self::require(state, &workspace.id)?;
+                Self::write_prompts(state, &workspace, app)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// MCP 服务器保存后，更新启用了它的工作区
+    pub fn sync_mcp_server(state: &AppState, server: &McpServer) -> Result<(), AppError> {
+        for workspace in state.db.get_workspaces()? {
+            for assignment in workspace
+                .assignments
+                .iter()
+                .filter(|a| a.kind == WorkspaceItemKind::Mcp && a.item_id == server.id)
+            {
+                let app: AppType = assignment.app_type.parse()?;
+                let path = workspace::project_mcp_path(Path::new(&workspace.path), &app)?;
+                workspace::set_project_mcp_server(&path, &app, &server.id, Some(&server.server))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// MCP 服务器删除后，从启用了它的工作区中移除
+    pub fn remove_mcp_server(state: &AppState, server_id: &str) -> Result<(), AppError> {
+        for workspace in state.db.get_workspaces()? {
+            for assignment in workspace
+                .assignments
+                .iter()
+                .filter(|a| a.kind == WorkspaceItemKind::Mcp && a.item_id == server_id)
+            {
+                state
+                    .db
+                    .set_workspace_assignment(&workspace.id, assignment, false)?;
+                Self::clear_item(&workspace, assignment)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 按工作区中启用的提示词重写项目提示词文件
+    fn write_prompts(
+        state: &AppState,
+        workspace: &Workspace,
+        app: &AppType,
+    ) -> Result<(), AppError> {
+        let path = workspace::project_prompt_path(Path::new(&workspace.path), app)?;
+        let prompts = state.db.get_prompts(app.as_str())?;
+        let enabled: Vec<&Prompt> = workspace
+            .enabled_items(WorkspaceItemKind::Prompt, app)
+            .into_iter()
+            .filter_map(|id| prompts.get(id))
+            .collect();
+        let rendered =
+            PromptService::render_prompts(state, app, &prompts, &enabled, Some(workspace))?;
+        workspace::write_project_prompts(&path, &rendered.iter().collect::<Vec<_>>())
+    }
+
+    /// 从项目文件中移除单个条目写入的内容
+    fn clear_item(workspace: &Workspace, assignment: &WorkspaceAssignment) -> Result<(), AppError> {
+        let app: AppType = assignment.app_type.parse()?;
+        let dir = Path::new(&workspace.path);
+        match assignment.kind {
+            WorkspaceItemKind::Prompt => {
+                workspace::write_project_prompts(&workspace::project_prompt_path(dir, &app)?, &[])
+            }
+            WorkspaceItemKind::Mcp => workspace::set_project_mcp_server(
+                &workspace::project_mcp_path(dir, &app)?,
+                &app,
+                &assignment.item_id,
+                None,
+            ),
+        }
+    }
+
+    fn find(state: &AppState, id: &str) -> Result<Option<Workspace>, AppError> {
+        Ok(state.db.get_workspaces()?.into_iter().find(|w| w.id == id))
+    }
+
+    fn require(state: &AppState, id: &str) -> Result<Workspace, AppError> {
+        Self::find(state, id)?.ok_or_else(|| AppError::InvalidInput(format!("工作区 {id} 不存在")))
+    }
+
+    fn assignment(kind: WorkspaceItemKind, app: &AppType, item_id: &str) -> WorkspaceAssignment {
+        WorkspaceAssignment {
+            kind,
+            app_type: app.as_str().to_string(),
+            item_id: item_id.to_string(),
+        }
+    }
+}
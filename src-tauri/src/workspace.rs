@@ -0,0 +1,268 @@
+//! 工作区（项目级提示词与 MCP）
+//!
+//! 工作区是登记过的项目目录。提示词与 MCP 服务器可以按工作区单独启用，写入项目目录中
+//! 对应应用的项目级文件：
+//!
+//! - 提示词：`CLAUDE.md` / `AGENTS.md` / `GEMINI.md`，只改写其中的受管区块
+//!   （见 [`prompt_layers`]），项目中手写的内容保持不变
+//! - MCP：Claude 的 `.mcp.json`、Gemini 的 `.gemini/settings.json`，只改写 `mcpServers`
+//!   中由 cc-switch 启用的条目
+//!
+//! 项目文件通常随仓库提交、在多个平台间共享，因此不会对命令做 Windows `cmd /c` 包装。
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::app_config::AppType;
+use crate::config::{read_json_file, write_json_file, write_text_file};
+use crate::error::AppError;
+use crate::prompt::Prompt;
+use crate::prompt_layers;
+
+/// 工作区中可启用的条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceItemKind {
+    Prompt,
+    Mcp,
+}
+
+impl WorkspaceItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceItemKind::Prompt => "prompt",
+            WorkspaceItemKind::Mcp => "mcp",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "prompt" => Some(WorkspaceItemKind::Prompt),
+            "mcp" => Some(WorkspaceItemKind::Mcp),
+            _ => None,
+        }
+    }
+}
+
+/// 在工作区中启用的一个条目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceAssignment {
+    pub kind: WorkspaceItemKind,
+    pub app_type: String,
+    pub item_id: String,
+}
+
+/// 登记的项目目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub assignments: Vec<WorkspaceAssignment>,
+}
+
+impl Workspace {
+    /// 指定应用在该工作区中启用的条目 ID
+    pub fn enabled_items(&self, kind: WorkspaceItemKind, app: &AppType) -> Vec<&str> {
+        self.assignments
+            .iter()
+            .filter(|a| a.kind == kind && a.app_type == app.as_str())
+            .map(|a| a.item_id.as_str())
+            .collect()
+    }
+}
+
+/// 工作区提示词模板变量的存储范围
+pub fn variables_scope(workspace_id: &str) -> String {
+    format!("workspace:{workspace_id}")
+}
+
+/// 项目级提示词文件路径
+pub fn project_prompt_path(dir: &Path, app: &AppType) -> Result<PathBuf, AppError> {
+    let filename = match app {
+        AppType::Claude => "CLAUDE.md",
+        AppType::Codex => "AGENTS.md",
+        AppType::Gemini => "GEMINI.md",
+        // OpenCode 与 Codex 共用项目中的 AGENTS.md，统一由 Codex 管理
+        AppType::OpenCode => {
+            return Err(AppError::InvalidInput(
+                "OpenCode 与 Codex 共用项目 AGENTS.md，请在 Codex 中启用".to_string(),
+            ))
+        }
+    };
+    Ok(dir.join(filename))
+}
+
+/// 项目级 MCP 配置文件路径
+pub fn project_mcp_path(dir: &Path, app: &AppType) -> Result<PathBuf, AppError> {
+    match app {
+        AppType::Claude => Ok(dir.join(".mcp.json")),
+        AppType::Gemini => Ok(dir.join(".gemini").join("settings.json")),
+        other => Err(AppError::InvalidInput(format!(
+            "{} 不支持项目级 MCP 配置",
+            other.as_str()
+        ))),
+    }
+}
+
+/// 把启用的提示词写入项目提示词文件的受管区块；没有启用的提示词时移除受管区块
+///
+/// 文件只剩受管区块（即由 cc-switch 创建）时，移除区块后一并删除文件
+pub fn write_project_prompts(path: &Path, prompts: &[&Prompt]) -> Result<(), AppError> {
+    let live = if path.exists() {
+        std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?
+    } else {
+        String::new()
+    };
+
+    let updated = if prompts.is_empty() {
+        prompt_layers::remove_block(&live)
+    } else {
+        let mut ordered = prompts.to_vec();
+        prompt_layers::sort_for_writing(&mut ordered);
+        prompt_layers::append_block(&live, &prompt_layers::render_block(&ordered))
+    };
+
+    if updated == live {
+        return Ok(());
+    }
+    if updated.trim().is_empty() {
+        return std::fs::remove_file(path).map_err(|e| AppError::io(path, e));
+    }
+    write_text_file(path, &updated)
+}
+
+/// 在项目 MCP 配置中写入（`spec` 为 Some）或移除（None）单个服务器，其他内容保持不变
+///
+/// 移除后 `mcpServers` 为空时删除该字段，文件为空对象时删除文件
+pub fn set_project_mcp_server(
+    path: &Path,
+    app: &AppType,
+    id: &str,
+    spec: Option<&Value>,
+) -> Result<(), AppError> {
+    let mut root: Value = if path.exists() {
+        read_json_file(path)?
+    } else if spec.is_none() {
+        return Ok(());
+    } else {
+        Value::Object(Map::new())
+    };
+
+    let display = path.display().to_string();
+    let obj = root
+        .as_object_mut()
+        .ok_or_else(|| AppError::Config(format!("{display} 根必须是对象")))?;
+    let servers = obj
+        .entry("mcpServers")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| AppError::Config(format!("{display} 中的 mcpServers 必须是对象")))?;
+
+    match spec {
+        Some(spec) => {
+            let live = match app {
+                AppType::Gemini => crate::gemini_mcp::to_live_server(id, spec)?,
+                _ => crate::claude_mcp::to_live_server(id, spec, false)?,
+            };
+            servers.insert(id.to_string(), live);
+        }
+        None => {
+            if servers.remove(id).is_none() {
+                return Ok(());
+            }
+            if servers.is_empty() {
+                obj.remove("mcpServers");
+            }
+        }
+    }
+
+    if obj.is_empty() {
+        return std::fs::remove_file(path).map_err(|e| AppError::io(path, e));
+    }
+    write_json_file(path, &root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prompt(id: &str, content: &str, layer: bool) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            name: id.to_string(),
+            content: content.to_string(),
+            description: None,
+            enabled: false,
+            created_at: None,
+            updated_at: None,
+            layer,
+            layer_order: 0,
+        }
+    }
+
+    #[test]
+    fn project_prompts_keep_handwritten_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = project_prompt_path(dir.path(), &AppType::Claude).unwrap();
+        std::fs::write(&path, "# Repo notes\n").unwrap();
+
+        let style = prompt("style", "be brief", true);
+        let base = prompt("base", "team rules", false);
+        write_project_prompts(&path, &[&style, &base]).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("# Repo notes\n\n"));
+        assert!(written.find("team rules").unwrap() < written.find("be brief").unwrap());
+
+        write_project_prompts(&path, &[]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Repo notes\n");
+
+        // 由 cc-switch 创建的文件在停用全部提示词后删除
+        let created = project_prompt_path(dir.path(), &AppType::Gemini).unwrap();
+        write_project_prompts(&created, &[&base]).unwrap();
+        assert!(created.exists());
+        write_project_prompts(&created, &[]).unwrap();
+        assert!(!created.exists());
+    }
+
+    #[test]
+    fn project_mcp_only_touches_managed_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = project_mcp_path(dir.path(), &AppType::Claude).unwrap();
+        std::fs::write(
+            &path,
+            r#"{"mcpServers": {"local": {"command": "./run.sh"}}}"#,
+        )
+        .unwrap();
+
+        let spec =
+            json!({"type": "stdio", "command": "npx", "args": ["-y", "ctx"], "enabled": true});
+        set_project_mcp_server(&path, &AppType::Claude, "ctx", Some(&spec)).unwrap();
+        let root: Value = read_json_file(&path).unwrap();
+        assert_eq!(root["mcpServers"]["local"]["command"], "./run.sh");
+        assert_eq!(root["mcpServers"]["ctx"]["command"], "npx");
+        assert!(root["mcpServers"]["ctx"].get("enabled").is_none());
+
+        set_project_mcp_server(&path, &AppType::Claude, "ctx", None).unwrap();
+        let root: Value = read_json_file(&path).unwrap();
+        assert_eq!(
+            root,
+            json!({"mcpServers": {"local": {"command": "./run.sh"}}})
+        );
+
+        let gemini = project_mcp_path(dir.path(), &AppType::Gemini).unwrap();
+        set_project_mcp_server(&gemini, &AppType::Gemini, "ctx", Some(&spec)).unwrap();
+        assert!(gemini.exists());
+        set_project_mcp_server(&gemini, &AppType::Gemini, "ctx", None).unwrap();
+        assert!(!gemini.exists());
+
+        assert!(project_mcp_path(dir.path(), &AppType::Codex).is_err());
+    }
+}
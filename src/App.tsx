@@ -20,6 +20,7 @@ import {
 } from "lucide-react";
 import type { Provider, VisibleApps } from "@/types";
 import type { EnvConflict } from "@/types/env";
+import type { BudgetAlert } from "@/types/proxy";
 import { useProvidersQuery, useSettingsQuery } from "@/lib/query";
 import {
   providersApi,
@@ -51,6 +52,7 @@ import PromptPanel from "@/components/prompts/PromptPanel";
 import { SkillsPage } from "@/components/skills/SkillsPage";
 import UnifiedSkillsPanel from "@/components/skills/UnifiedSkillsPanel";
 import { DeepLinkImportDialog } from "@/components/DeepLinkImportDialog";
+import { LiveConfigChangeDialog } from "@/components/LiveConfigChangeDialog";
 import { AgentsPanel } from "@/components/agents/AgentsPanel";
 import { UniversalProviderPanel } from "@/components/universal";
 import { McpIcon } from "@/components/BrandIcons";
@@ -253,6 +255,44 @@ function App() {
     };
   }, [queryClient]);
 
+  // 监听代理消费限额告警（达到预警阈值或超额）
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined;
+
+    const setupListener = async () => {
+      try {
+        const { listen } = await import("@tauri-apps/api/event");
+        unsubscribe = await listen<BudgetAlert>(
+          "provider-budget-alert",
+          (event) => {
+            const alert = event.payload;
+            const params = {
+              provider: alert.providerName,
+              period: t(`failover.budgetPeriod.${alert.period}`),
+              usage: Number(alert.usageUsd).toFixed(2),
+              limit: alert.limitUsd,
+            };
+            if (alert.exceeded) {
+              toast.error(t("failover.budgetExceeded", params));
+            } else {
+              toast.warning(t("failover.budgetWarning", params));
+            }
+          },
+        );
+      } catch (error) {
+        console.error(
+          "[App] Failed to subscribe provider-budget-alert event",
+          error,
+        );
+      }
+    };
+
+    setupListener();
+    return () => {
+      unsubscribe?.();
+    };
+  }, [t]);
+
   // 应用启动时检测所有应用的环境变量冲突
   useEffect(() => {
     const checkEnvOnStartup = async () => {
@@ -1086,6 +1126,7 @@ function App() {
       />
 
       <DeepLinkImportDialog />
+      <LiveConfigChangeDialog />
     </div>
   );
 }
@@ -0,0 +1,174 @@
+import { useCallback, useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { useQueryClient } from "@tanstack/react-query";
+import { toast } from "sonner";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import { liveConfigApi, type LiveConfigChange } from "@/lib/api";
+import { extractErrorMessage } from "@/utils/errorUtils";
+
+type Action = "absorb" | "reapply" | "dismiss";
+
+// live 配置被其他程序修改时，逐个询问吸收到 cc-switch、重新应用还是忽略
+export function LiveConfigChangeDialog() {
+  const { t } = useTranslation();
+  const queryClient = useQueryClient();
+  const [changes, setChanges] = useState<LiveConfigChange[]>([]);
+  const [pendingAction, setPendingAction] = useState<Action | null>(null);
+
+  // 同一文件只保留最新的修改
+  const upsertChange = useCallback((change: LiveConfigChange) => {
+    setChanges((prev) => [
+      ...prev.filter((item) => item.path !== change.path),
+      change,
+    ]);
+  }, []);
+
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined;
+
+    const setup = async () => {
+      try {
+        unsubscribe = await liveConfigApi.onChanged(upsertChange);
+        // 界面打开前已检测到的修改
+        const existing = await liveConfigApi.getChanges();
+        existing.forEach(upsertChange);
+      } catch (error) {
+        console.error("[LiveConfigChangeDialog] Failed to subscribe", error);
+      }
+    };
+
+    void setup();
+    return () => {
+      unsubscribe?.();
+    };
+  }, [upsertChange]);
+
+  const current = changes[0];
+
+  const handleAction = async (action: Action) => {
+    if (!current) return;
+    setPendingAction(action);
+    try {
+      if (action === "absorb") {
+        await liveConfigApi.absorb(current.path);
+        await Promise.all([
+          queryClient.invalidateQueries({
+            queryKey: ["providers", current.appType],
+          }),
+          queryClient.invalidateQueries({ queryKey: ["mcp", "all"] }),
+        ]);
+        toast.success(t("liveConfig.absorbed"));
+      } else if (action === "reapply") {
+        await liveConfigApi.reapply(current.path);
+        toast.success(t("liveConfig.reapplied"));
+      } else {
+        await liveConfigApi.dismiss(current.path);
+      }
+      setChanges((prev) =>
+        prev.filter((item) => item.path !== current.path),
+      );
+    } catch (error) {
+      toast.error(
+        t("liveConfig.actionFailed", { error: extractErrorMessage(error) }),
+      );
+    } finally {
+      setPendingAction(null);
+    }
+  };
+
+  if (!current) return null;
+
+  return (
+    <Dialog
+      open
+      onOpenChange={(open) => {
+        if (!open && !pendingAction) {
+          void handleAction("dismiss");
+        }
+      }}
+    >
+      <DialogContent className="max-w-2xl" zIndex="alert">
+        <DialogHeader className="text-left sm:text-left">
+          <DialogTitle className="flex items-center gap-2">
+            {t("liveConfig.title", { app: t(`apps.${current.appType}`) })}
+            {current.conflict && (
+              <Badge variant="destructive">{t("liveConfig.conflict")}</Badge>
+            )}
+          </DialogTitle>
+          <DialogDescription>
+            {current.conflict
+              ? t("liveConfig.conflictDescription")
+              : t("liveConfig.description")}
+          </DialogDescription>
+        </DialogHeader>
+
+        <div className="space-y-2 px-8 py-4">
+          <div className="flex items-center justify-between gap-2 text-xs text-muted-foreground">
+            <span className="truncate font-mono">{current.path}</span>
+            {changes.length > 1 && (
+              <span className="shrink-0">
+                {t("liveConfig.remaining", { count: changes.length - 1 })}
+              </span>
+            )}
+          </div>
+          <pre className="max-h-80 overflow-auto rounded-md border border-border bg-muted/40 p-3 text-xs leading-5">
+            {current.diff.map((line, index) => (
+              <div
+                key={index}
+                className={
+                  line.op === "insert"
+                    ? "bg-green-500/10 text-green-700 dark:text-green-400"
+                    : line.op === "delete"
+                      ? "bg-red-500/10 text-red-700 dark:text-red-400"
+                      : "text-muted-foreground"
+                }
+              >
+                {line.op === "insert"
+                  ? "+ "
+                  : line.op === "delete"
+                    ? "- "
+                    : "  "}
+                {line.text}
+              </div>
+            ))}
+          </pre>
+          <p className="text-xs text-muted-foreground">
+            {t("liveConfig.diffHint")}
+          </p>
+        </div>
+
+        <DialogFooter>
+          <Button
+            variant="outline"
+            disabled={pendingAction !== null}
+            onClick={() => void handleAction("dismiss")}
+          >
+            {t("liveConfig.dismiss")}
+          </Button>
+          <Button
+            variant="outline"
+            disabled={pendingAction !== null}
+            onClick={() => void handleAction("reapply")}
+          >
+            {t("liveConfig.reapply")}
+          </Button>
+          <Button
+            disabled={pendingAction !== null}
+            onClick={() => void handleAction("absorb")}
+          >
+            {t("liveConfig.absorb")}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -3,6 +3,7 @@ import { useTranslation } from "react-i18next";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
+import { Switch } from "@/components/ui/switch";
 import MarkdownEditor from "@/components/MarkdownEditor";
 import { FullScreenPanel } from "@/components/common/FullScreenPanel";
 import type { Prompt, AppId } from "@/lib/api";
@@ -34,6 +35,8 @@ const PromptFormPanel: React.FC<PromptFormPanelProps> = ({
   const [name, setName] = useState("");
   const [description, setDescription] = useState("");
   const [content, setContent] = useState("");
+  const [layer, setLayer] = useState(false);
+  const [layerOrder, setLayerOrder] = useState(0);
   const [saving, setSaving] = useState(false);
   const [isDarkMode, setIsDarkMode] = useState(false);
 
@@ -57,6 +60,8 @@ const PromptFormPanel: React.FC<PromptFormPanelProps> = ({
       setName(initialData.name);
       setDescription(initialData.description || "");
       setContent(initialData.content);
+      setLayer(initialData.layer ?? false);
+      setLayerOrder(initialData.layerOrder ?? 0);
     }
   }, [initialData]);
 
@@ -77,6 +82,8 @@ const PromptFormPanel: React.FC<PromptFormPanelProps> = ({
         enabled: initialData?.enabled || false,
         createdAt: initialData?.createdAt || timestamp,
         updatedAt: timestamp,
+        layer,
+        layerOrder,
       };
       await onSave(id, prompt);
       onClose();
@@ -134,6 +141,35 @@ const PromptFormPanel: React.FC<PromptFormPanelProps> = ({
           />
         </div>
 
+        <div className="flex items-start justify-between gap-4">
+          <div className="space-y-0.5">
+            <Label htmlFor="layer" className="text-foreground">
+              {t("prompts.layer")}
+            </Label>
+            <p className="text-xs text-muted-foreground">
+              {t("prompts.layerHint", { filename })}
+            </p>
+          </div>
+          <Switch id="layer" checked={layer} onCheckedChange={setLayer} />
+        </div>
+
+        {layer && (
+          <div>
+            <Label htmlFor="layerOrder" className="text-foreground">
+              {t("prompts.layerOrder")}
+            </Label>
+            <Input
+              id="layerOrder"
+              type="number"
+              value={layerOrder}
+              onChange={(e) =>
+                setLayerOrder(parseInt(e.target.value, 10) || 0)
+              }
+              className="mt-2 w-32"
+            />
+          </div>
+        )}
+
         <div>
           <Label htmlFor="content" className="block mb-2 text-foreground">
             {t("prompts.content")}
@@ -145,6 +181,13 @@ const PromptFormPanel: React.FC<PromptFormPanelProps> = ({
             darkMode={isDarkMode}
             minHeight="167px"
           />
+          <p className="mt-2 text-xs text-muted-foreground">
+            {t("prompts.templateHint", {
+              variable: "{{name}}",
+              include: "{{> prompt-id}}",
+              condition: "{{#app claude}}…{{else}}…{{/app}}",
+            })}
+          </p>
         </div>
       </div>
     </FullScreenPanel>
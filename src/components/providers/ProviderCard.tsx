@@ -268,6 +268,7 @@ export function ProviderCard({
               {isProxyRunning && isInFailoverQueue && health && (
                 <ProviderHealthBadge
                   consecutiveFailures={health.consecutive_failures}
+                  rateLimitedSecs={health.rate_limit?.remaining_secs}
                 />
               )}
 
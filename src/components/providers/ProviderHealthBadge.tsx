@@ -4,22 +4,35 @@ import { useTranslation } from "react-i18next";
 
 interface ProviderHealthBadgeProps {
   consecutiveFailures: number;
+  /** 限流冷却剩余秒数（未限流时不传） */
+  rateLimitedSecs?: number;
   className?: string;
 }
 
 /**
  * 供应商健康状态徽章
- * 根据连续失败次数显示不同颜色的状态指示器
+ * 根据连续失败次数显示不同颜色的状态指示器，上游限流冷却中时优先显示限流状态
  */
 export function ProviderHealthBadge({
   consecutiveFailures,
+  rateLimitedSecs,
   className,
 }: ProviderHealthBadgeProps) {
   const { t } = useTranslation();
+  const isRateLimited = rateLimitedSecs !== undefined && rateLimitedSecs > 0;
 
   // 根据失败次数计算状态
   const getStatus = () => {
-    if (consecutiveFailures === 0) {
+    if (isRateLimited) {
+      return {
+        labelKey: "health.rateLimited",
+        labelFallback: "限流中",
+        status: ProviderHealthStatus.Degraded,
+        color: "bg-orange-500",
+        bgColor: "bg-orange-500/10",
+        textColor: "text-orange-600 dark:text-orange-400",
+      };
+    } else if (consecutiveFailures === 0) {
       return {
         labelKey: "health.operational",
         labelFallback: "正常",
@@ -63,10 +76,17 @@ export function ProviderHealthBadge({
         statusConfig.textColor,
         className,
       )}
-      title={t("health.consecutiveFailures", {
-        count: consecutiveFailures,
-        defaultValue: `连续失败 ${consecutiveFailures} 次`,
-      })}
+      title={
+        isRateLimited
+          ? t("health.rateLimitedTitle", {
+              seconds: rateLimitedSecs,
+              defaultValue: `上游限流，${rateLimitedSecs} 秒后恢复`,
+            })
+          : t("health.consecutiveFailures", {
+              count: consecutiveFailures,
+              defaultValue: `连续失败 ${consecutiveFailures} 次`,
+            })
+      }
     >
       <div className={cn("w-2 h-2 rounded-full", statusConfig.color)} />
       <span>{label}</span>
@@ -209,6 +209,11 @@ export function ClaudeFormFields({
                   defaultValue: "OpenAI Chat Completions (需转换)",
                 })}
               </SelectItem>
+              <SelectItem value="gemini">
+                {t("providerForm.apiFormatGemini", {
+                  defaultValue: "Gemini generateContent (需转换)",
+                })}
+              </SelectItem>
             </SelectContent>
           </Select>
           <p className="text-xs text-muted-foreground">
@@ -1,7 +1,15 @@
 import { useTranslation } from "react-i18next";
+import { FormLabel } from "@/components/ui/form";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
 import EndpointSpeedTest from "./EndpointSpeedTest";
 import { ApiKeySection, EndpointField } from "./shared";
-import type { ProviderCategory } from "@/types";
+import type { ProviderCategory, CodexApiFormat } from "@/types";
 
 interface EndpointCandidate {
   url: string;
@@ -35,6 +43,10 @@ interface CodexFormFieldsProps {
 
   // Speed Test Endpoints
   speedTestEndpoints: EndpointCandidate[];
+
+  // API Format
+  apiFormat?: CodexApiFormat;
+  onApiFormatChange?: (format: CodexApiFormat) => void;
 }
 
 export function CodexFormFields({
@@ -58,6 +70,8 @@ export function CodexFormFields({
   modelName = "",
   onModelNameChange,
   speedTestEndpoints,
+  apiFormat = "responses",
+  onApiFormatChange,
 }: CodexFormFieldsProps) {
   const { t } = useTranslation();
 
@@ -97,6 +111,37 @@ export function CodexFormFields({
         />
       )}
 
+      {/* API 格式选择（仅非官方供应商显示） */}
+      {shouldShowModelField && onApiFormatChange && (
+        <div className="space-y-2">
+          <FormLabel htmlFor="codexApiFormat">
+            {t("providerForm.apiFormat", { defaultValue: "API 格式" })}
+          </FormLabel>
+          <Select value={apiFormat} onValueChange={onApiFormatChange}>
+            <SelectTrigger id="codexApiFormat" className="w-full">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="responses">
+                {t("providerForm.apiFormatResponses", {
+                  defaultValue: "OpenAI Responses (原生)",
+                })}
+              </SelectItem>
+              <SelectItem value="openai_chat">
+                {t("providerForm.apiFormatOpenAIChat", {
+                  defaultValue: "OpenAI Chat Completions (需转换)",
+                })}
+              </SelectItem>
+            </SelectContent>
+          </Select>
+          <p className="text-xs text-muted-foreground">
+            {t("providerForm.apiFormatHint", {
+              defaultValue: "选择供应商 API 的输入格式",
+            })}
+          </p>
+        </div>
+      )}
+
       {/* Codex Model Name 输入框 */}
       {shouldShowModelField && onModelNameChange && (
         <div className="space-y-2">
@@ -14,6 +14,7 @@ import type {
   ProviderTestConfig,
   ProviderProxyConfig,
   ClaudeApiFormat,
+  CodexApiFormat,
 } from "@/types";
 import {
   providerPresets,
@@ -294,6 +295,19 @@ export function ProviderForm({
     setLocalApiFormat(format);
   }, []);
 
+  // Codex API Format state - only "openai_chat" is persisted to meta.apiFormat
+  const [localCodexApiFormat, setLocalCodexApiFormat] =
+    useState<CodexApiFormat>(() => {
+      if (appId !== "codex") return "responses";
+      return initialData?.meta?.apiFormat === "openai_chat"
+        ? "openai_chat"
+        : "responses";
+    });
+
+  const handleCodexApiFormatChange = useCallback((format: CodexApiFormat) => {
+    setLocalCodexApiFormat(format);
+  }, []);
+
   // 使用 Codex 配置 hook (仅 Codex 模式)
   const {
     codexAuth,
@@ -947,7 +961,11 @@ export function ProviderForm({
       apiFormat:
         appId === "claude" && category !== "official"
           ? localApiFormat
-          : undefined,
+          : appId === "codex" &&
+              category !== "official" &&
+              localCodexApiFormat === "openai_chat"
+            ? "openai_chat"
+            : undefined,
     };
 
     onSubmit(payload);
@@ -1319,6 +1337,8 @@ export function ProviderForm({
             modelName={codexModelName}
             onModelNameChange={handleCodexModelNameChange}
             speedTestEndpoints={speedTestEndpoints}
+            apiFormat={localCodexApiFormat}
+            onApiFormatChange={handleCodexApiFormatChange}
           />
         )}
 
@@ -645,6 +645,7 @@ function ProviderQueueItem({
       {/* 健康徽章 */}
       <ProviderHealthBadge
         consecutiveFailures={health?.consecutive_failures ?? 0}
+        rateLimitedSecs={health?.rate_limit?.remaining_secs}
       />
     </div>
   );
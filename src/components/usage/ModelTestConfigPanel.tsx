@@ -27,6 +27,7 @@ export function ModelTestConfigPanel() {
     codexModel: "gpt-5.1-codex@low",
     geminiModel: "gemini-3-pro-preview",
     testPrompt: "Who are you?",
+    probeIntervalSecs: "0",
   });
 
   useEffect(() => {
@@ -46,6 +47,7 @@ export function ModelTestConfigPanel() {
         codexModel: data.codexModel,
         geminiModel: data.geminiModel,
         testPrompt: data.testPrompt || "Who are you?",
+        probeIntervalSecs: String(data.probeIntervalSecs ?? 0),
       });
     } catch (e) {
       setError(String(e));
@@ -70,6 +72,7 @@ export function ModelTestConfigPanel() {
         codexModel: config.codexModel,
         geminiModel: config.geminiModel,
         testPrompt: config.testPrompt || "Who are you?",
+        probeIntervalSecs: parseNum(config.probeIntervalSecs, 0),
       };
       await saveStreamCheckConfig(parsed);
       toast.success(t("streamCheck.configSaved"), {
@@ -192,6 +195,25 @@ export function ModelTestConfigPanel() {
               }
             />
           </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="probeIntervalSecs">
+              {t("streamCheck.probeInterval")}
+            </Label>
+            <Input
+              id="probeIntervalSecs"
+              type="number"
+              min={0}
+              step={30}
+              value={config.probeIntervalSecs}
+              onChange={(e) =>
+                setConfig({ ...config, probeIntervalSecs: e.target.value })
+              }
+            />
+            <p className="text-xs text-muted-foreground">
+              {t("streamCheck.probeIntervalHint")}
+            </p>
+          </div>
         </div>
 
         {/* 检查提示词配置 */}
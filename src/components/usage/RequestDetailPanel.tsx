@@ -121,6 +121,22 @@ export function RequestDetailPanel({
                   </span>
                 </dd>
               </div>
+              {request.maskedKey && (
+                <div>
+                  <dt className="text-muted-foreground">
+                    {t("usage.apiKey", "API Key")}
+                  </dt>
+                  <dd className="font-mono">{request.maskedKey}</dd>
+                </div>
+              )}
+              {request.cacheHit && (
+                <div>
+                  <dt className="text-muted-foreground">
+                    {t("usage.responseCache", "Response Cache")}
+                  </dt>
+                  <dd>{t("usage.responseCacheHit", "Hit (no cost)")}</dd>
+                </div>
+              )}
             </dl>
           </div>
 
@@ -269,6 +285,43 @@ export function RequestDetailPanel({
             </dl>
           </div>
 
+          {/* 抓取的请求/响应体 */}
+          {request.bodyCapture && (
+            <div className="rounded-lg border p-4">
+              <h3 className="mb-3 font-semibold">
+                {t("usage.bodyCapture", "请求/响应体")}
+              </h3>
+              <div className="space-y-3 text-sm">
+                <div>
+                  <div className="mb-1 text-muted-foreground">
+                    {t("usage.capturedRequest", "请求")}{" "}
+                    <span className="font-mono">
+                      {request.bodyCapture.endpoint}
+                    </span>
+                    {request.bodyCapture.requestTruncated &&
+                      ` (${t("usage.captureTruncated", "已截断")})`}
+                  </div>
+                  <pre className="max-h-60 overflow-auto whitespace-pre-wrap break-all rounded bg-muted p-2 font-mono text-xs">
+                    {request.bodyCapture.requestBody}
+                  </pre>
+                </div>
+                <div>
+                  <div className="mb-1 text-muted-foreground">
+                    {t("usage.capturedResponse", "响应")}{" "}
+                    <span className="font-mono">
+                      {request.bodyCapture.responseStatus}
+                    </span>
+                    {request.bodyCapture.responseTruncated &&
+                      ` (${t("usage.captureTruncated", "已截断")})`}
+                  </div>
+                  <pre className="max-h-60 overflow-auto whitespace-pre-wrap break-all rounded bg-muted p-2 font-mono text-xs">
+                    {request.bodyCapture.responseBody}
+                  </pre>
+                </div>
+              </div>
+            </div>
+          )}
+
           {/* 错误信息 */}
           {request.errorMessage && (
             <div className="rounded-lg border border-red-200 bg-red-50 p-4">
@@ -17,6 +17,8 @@ export function UsageSummaryCards({ days }: UsageSummaryCardsProps) {
   const stats = useMemo(() => {
     const totalRequests = summary?.totalRequests ?? 0;
     const totalCost = parseFloat(summary?.totalCost || "0");
+    const cacheHits = summary?.cacheHits ?? 0;
+    const cacheSavings = parseFloat(summary?.cacheSavings || "0");
 
     const inputTokens = summary?.totalInputTokens ?? 0;
     const outputTokens = summary?.totalOutputTokens ?? 0;
@@ -41,7 +43,23 @@ export function UsageSummaryCards({ days }: UsageSummaryCardsProps) {
         icon: DollarSign,
         color: "text-green-500",
         bg: "bg-green-500/10",
-        subValue: null,
+        subValue:
+          cacheHits > 0 ? (
+            <div className="flex flex-col gap-1 text-xs text-muted-foreground mt-3 pt-3 border-t border-border/50">
+              <div className="flex justify-between items-center">
+                <span>{t("usage.responseCacheHits")}</span>
+                <span className="text-foreground/80">
+                  {cacheHits.toLocaleString()}
+                </span>
+              </div>
+              <div className="flex justify-between items-center">
+                <span>{t("usage.responseCacheSavings")}</span>
+                <span className="text-foreground/80">
+                  ${cacheSavings.toFixed(4)}
+                </span>
+              </div>
+            </div>
+          ) : null,
       },
       {
         title: t("usage.totalTokens"),
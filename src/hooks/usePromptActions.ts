@@ -78,13 +78,15 @@ export function usePromptActions(appId: AppId) {
       // Optimistic update
       const previousPrompts = prompts;
 
-      // 如果要启用当前提示词，先禁用其他所有提示词
+      // 启用普通提示词时禁用其他普通提示词；分层提示词互不影响
       if (enabled) {
+        const isLayer = prompts[id]?.layer === true;
         const updatedPrompts = Object.keys(prompts).reduce(
           (acc, key) => {
+            const keep = isLayer || prompts[key].layer === true;
             acc[key] = {
               ...prompts[key],
-              enabled: key === id,
+              enabled: key === id || (keep && prompts[key].enabled),
             };
             return acc;
           },
@@ -4,6 +4,9 @@ import type {
   CircuitBreakerConfig,
   CircuitBreakerStats,
   FailoverQueueItem,
+  HedgeConfig,
+  LoadBalanceStrategy,
+  ModelRoute,
 } from "@/types/proxy";
 
 export interface Provider {
@@ -96,4 +99,44 @@ export const failoverApi = {
   ): Promise<void> {
     return invoke("set_auto_failover_enabled", { appType, enabled });
   },
+
+  // 获取指定应用的负载均衡策略
+  async getLoadBalanceStrategy(appType: string): Promise<LoadBalanceStrategy> {
+    return invoke("get_load_balance_strategy", { appType });
+  },
+
+  // 设置指定应用的负载均衡策略
+  async setLoadBalanceStrategy(
+    appType: string,
+    strategy: LoadBalanceStrategy,
+  ): Promise<void> {
+    return invoke("set_load_balance_strategy", { appType, strategy });
+  },
+
+  // 获取指定应用的对冲请求配置
+  async getHedgeConfig(appType: string): Promise<HedgeConfig> {
+    return invoke("get_hedge_config", { appType });
+  },
+
+  // 设置指定应用的对冲请求配置
+  async setHedgeConfig(appType: string, config: HedgeConfig): Promise<void> {
+    return invoke("set_hedge_config", { appType, config });
+  },
+
+  // ========== 模型路由 API ==========
+
+  // 获取指定应用的模型路由
+  async getModelRoutes(appType: string): Promise<ModelRoute[]> {
+    return invoke("get_model_routes", { appType });
+  },
+
+  // 新增或更新模型路由（id 为空时由后端生成）
+  async saveModelRoute(route: ModelRoute): Promise<ModelRoute> {
+    return invoke("save_model_route", { route });
+  },
+
+  // 删除模型路由
+  async deleteModelRoute(appType: string, id: string): Promise<void> {
+    return invoke("delete_model_route", { appType, id });
+  },
 };
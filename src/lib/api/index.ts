@@ -8,6 +8,10 @@ export { usageApi } from "./usage";
 export { vscodeApi } from "./vscode";
 export { proxyApi } from "./proxy";
 export { sessionsApi } from "./sessions";
+export { workspacesApi } from "./workspaces";
+export { liveConfigApi } from "./liveConfig";
 export * as configApi from "./config";
 export type { ProviderSwitchEvent } from "./providers";
-export type { Prompt } from "./prompts";
+export type { Prompt, PromptRevision, PromptDiffLine } from "./prompts";
+export type { Workspace, WorkspaceAssignment } from "./workspaces";
+export type { LiveConfigChange, LiveFileKind } from "./liveConfig";
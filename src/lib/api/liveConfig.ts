@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { PromptDiffLine } from "./prompts";
+import type { AppId } from "./types";
+
+// live 文件中由 cc-switch 管理的内容，决定吸收修改时写回哪里
+export type LiveFileKind = "provider" | "prompt" | "mcp";
+
+// live 配置文件被其他程序修改（与 cc-switch 最近一次写入的内容不一致）
+export interface LiveConfigChange {
+  appType: AppId;
+  kinds: LiveFileKind[];
+  path: string;
+  // 受管内容 → 当前文件内容
+  diff: PromptDiffLine[];
+  // cc-switch 写入后不久即被覆盖，有其他工具在争用该文件
+  conflict: boolean;
+  detectedAt: number;
+}
+
+export const liveConfigApi = {
+  async getChanges(): Promise<LiveConfigChange[]> {
+    return await invoke("get_live_config_changes");
+  },
+
+  // 把修改吸收到数据库（当前供应商、已启用的提示词、MCP 服务器）
+  async absorb(path: string): Promise<void> {
+    return await invoke("absorb_live_config_change", { path });
+  },
+
+  // 用 cc-switch 管理的内容覆盖修改
+  async reapply(path: string): Promise<void> {
+    return await invoke("reapply_live_config", { path });
+  },
+
+  async dismiss(path: string): Promise<void> {
+    return await invoke("dismiss_live_config_change", { path });
+  },
+
+  async onChanged(
+    handler: (change: LiveConfigChange) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("live-config-changed", (event) => {
+      handler(event.payload as LiveConfigChange);
+    });
+  },
+};
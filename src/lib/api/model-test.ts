@@ -13,6 +13,8 @@ export interface StreamCheckConfig {
   codexModel: string;
   geminiModel: string;
   testPrompt: string;
+  // 代理运行时后台探测故障转移队列的间隔（秒），0 表示关闭
+  probeIntervalSecs: number;
 }
 
 export interface StreamCheckResult {
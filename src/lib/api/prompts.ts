@@ -9,6 +9,35 @@ export interface Prompt {
   enabled: boolean;
   createdAt?: number;
   updatedAt?: number;
+  // 分层提示词：可与其他提示词同时启用，按 layerOrder 拼接写入受管区块
+  layer?: boolean;
+  layerOrder?: number;
+}
+
+export type PromptRevisionSource =
+  | "ui"
+  | "backfill"
+  | "deeplink"
+  | "import"
+  | "sync"
+  | "rollback"
+  | "delete";
+
+// 提示词内容的一个历史版本
+export interface PromptRevision {
+  id: number;
+  promptId: string;
+  content: string;
+  source: PromptRevisionSource;
+  createdAt: number;
+  // 只有删除时记录的版本（source 为 delete）带有提示词名称
+  promptName?: string;
+}
+
+// 按行对比的一行结果
+export interface PromptDiffLine {
+  op: "equal" | "insert" | "delete";
+  text: string;
 }
 
 export const promptsApi = {
@@ -35,4 +64,41 @@ export const promptsApi = {
   async getCurrentFileContent(app: AppId): Promise<string | null> {
     return await invoke("get_current_prompt_file_content", { app });
   },
+
+  // 提示词模板变量（按应用）
+  async getVariables(app: AppId): Promise<Record<string, string>> {
+    return await invoke("get_prompt_variables", { app });
+  },
+
+  async setVariables(
+    app: AppId,
+    variables: Record<string, string>,
+  ): Promise<void> {
+    return await invoke("set_prompt_variables", { app, variables });
+  },
+
+  // 历史版本（最新的在前）
+  async getRevisions(app: AppId, id: string): Promise<PromptRevision[]> {
+    return await invoke("get_prompt_revisions", { app, id });
+  },
+
+  // 未指定 to 时与当前内容对比
+  async diffRevisions(
+    app: AppId,
+    id: string,
+    from: number,
+    to?: number,
+  ): Promise<PromptDiffLine[]> {
+    return await invoke("diff_prompt_revisions", { app, id, from, to });
+  },
+
+  // 提示词已删除时按该版本恢复
+  async rollback(app: AppId, id: string, revisionId: number): Promise<void> {
+    return await invoke("rollback_prompt", { app, id, revisionId });
+  },
+
+  // 已删除的提示词（每个提示词最近一次删除时的版本）
+  async getDeletedPrompts(app: AppId): Promise<PromptRevision[]> {
+    return await invoke("get_deleted_prompts", { app });
+  },
 };
@@ -6,6 +6,12 @@ import type {
   ProxyTakeoverStatus,
   GlobalProxyConfig,
   AppProxyConfig,
+  ResponseCacheConfig,
+  ProxyAuthConfig,
+  ProxyTlsConfig,
+  BodyCaptureConfig,
+  VirtualKey,
+  CreatedVirtualKey,
 } from "@/types/proxy";
 
 export const proxyApi = {
@@ -117,4 +123,90 @@ export const proxyApi = {
   async setPricingModelSource(appType: string, value: string): Promise<void> {
     return invoke("set_pricing_model_source", { appType, value });
   },
+
+  // ========== 响应缓存 API ==========
+
+  // 获取响应缓存配置
+  async getResponseCacheConfig(appType: string): Promise<ResponseCacheConfig> {
+    return invoke("get_response_cache_config", { appType });
+  },
+
+  // 设置响应缓存配置（关闭时清空该应用的缓存）
+  async setResponseCacheConfig(
+    appType: string,
+    config: ResponseCacheConfig,
+  ): Promise<void> {
+    return invoke("set_response_cache_config", { appType, config });
+  },
+
+  // 清空响应缓存（不传 appType 时清空全部），返回删除的条目数
+  async clearResponseCache(appType?: string): Promise<number> {
+    return invoke("clear_response_cache", { appType });
+  },
+
+  // ========== 请求/响应体抓取 API ==========
+
+  // 获取请求/响应体抓取配置
+  async getBodyCaptureConfig(appType: string): Promise<BodyCaptureConfig> {
+    return invoke("get_body_capture_config", { appType });
+  },
+
+  // 设置请求/响应体抓取配置
+  async setBodyCaptureConfig(
+    appType: string,
+    config: BodyCaptureConfig,
+  ): Promise<void> {
+    return invoke("set_body_capture_config", { appType, config });
+  },
+
+  // 清空抓取的请求/响应体（不传 appType 时清空全部），返回删除的条数
+  async clearRequestBodyCaptures(appType?: string): Promise<number> {
+    return invoke("clear_request_body_captures", { appType });
+  },
+
+  // ========== 入站访问控制 API ==========
+
+  // 获取代理访问控制配置
+  async getProxyAuthConfig(): Promise<ProxyAuthConfig> {
+    return invoke("get_proxy_auth_config");
+  },
+
+  // 设置代理访问控制配置（代理运行时立即生效）
+  async setProxyAuthConfig(config: ProxyAuthConfig): Promise<void> {
+    return invoke("set_proxy_auth_config", { config });
+  },
+
+  // 获取代理 TLS 配置
+  async getProxyTlsConfig(): Promise<ProxyTlsConfig> {
+    return invoke("get_proxy_tls_config");
+  },
+
+  // 设置代理 TLS 配置（下次启动代理时生效）
+  async setProxyTlsConfig(config: ProxyTlsConfig): Promise<void> {
+    return invoke("set_proxy_tls_config", { config });
+  },
+
+  // 获取代理实际使用的证书路径
+  async getProxyTlsCertPath(): Promise<string> {
+    return invoke("get_proxy_tls_cert_path");
+  },
+
+  // ========== 虚拟 Key API ==========
+
+  async getVirtualKeys(): Promise<VirtualKey[]> {
+    return invoke("get_virtual_keys");
+  },
+
+  // 新建虚拟 Key（返回的 secret 只会出现这一次）
+  async createVirtualKey(key: VirtualKey): Promise<CreatedVirtualKey> {
+    return invoke("create_virtual_key", { key });
+  },
+
+  async updateVirtualKey(key: VirtualKey): Promise<VirtualKey> {
+    return invoke("update_virtual_key", { key });
+  },
+
+  async deleteVirtualKey(id: string): Promise<void> {
+    return invoke("delete_virtual_key", { id });
+  },
 };
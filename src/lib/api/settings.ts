@@ -147,6 +147,14 @@ export const settingsApi = {
     return await invoke("set_rectifier_config", { config });
   },
 
+  async getTelemetryConfig(): Promise<TelemetryConfig> {
+    return await invoke("get_telemetry_config");
+  },
+
+  async setTelemetryConfig(config: TelemetryConfig): Promise<boolean> {
+    return await invoke("set_telemetry_config", { config });
+  },
+
   async getLogConfig(): Promise<LogConfig> {
     return await invoke("get_log_config");
   },
@@ -165,3 +173,9 @@ export interface LogConfig {
   enabled: boolean;
   level: "error" | "warn" | "info" | "debug" | "trace";
 }
+
+export interface TelemetryConfig {
+  otlpEnabled: boolean;
+  otlpEndpoint: string;
+  serviceName: string;
+}
@@ -3,12 +3,14 @@ import type {
   UsageSummary,
   DailyStats,
   ProviderStats,
+  VirtualKeyStats,
   ModelStats,
   RequestLog,
   LogFilters,
   ModelPricing,
   ProviderLimitStatus,
   PaginatedLogs,
+  ReplayResult,
 } from "@/types/usage";
 import type { UsageResult } from "@/types";
 import type { AppId } from "./types";
@@ -62,6 +64,10 @@ export const usageApi = {
     return invoke("get_provider_stats");
   },
 
+  getVirtualKeyStats: async (): Promise<VirtualKeyStats[]> => {
+    return invoke("get_virtual_key_stats");
+  },
+
   getModelStats: async (): Promise<ModelStats[]> => {
     return invoke("get_model_stats");
   },
@@ -82,6 +88,13 @@ export const usageApi = {
     return invoke("get_request_detail", { requestId });
   },
 
+  replayCapturedRequest: async (
+    requestId: string,
+    providerId: string,
+  ): Promise<ReplayResult> => {
+    return invoke("replay_captured_request", { requestId, providerId });
+  },
+
   getModelPricing: async (): Promise<ModelPricing[]> => {
     return invoke("get_model_pricing");
   },
@@ -0,0 +1,75 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { AppId } from "./types";
+
+export type WorkspaceItemKind = "prompt" | "mcp";
+
+// 在工作区中启用的提示词或 MCP 服务器
+export interface WorkspaceAssignment {
+  kind: WorkspaceItemKind;
+  appType: AppId;
+  itemId: string;
+}
+
+// 登记的项目目录：启用的提示词写入项目提示词文件的受管区块，
+// MCP 写入项目级 MCP 配置（Claude 的 .mcp.json、Gemini 的 .gemini/settings.json）
+export interface Workspace {
+  id: string;
+  name: string;
+  path: string;
+  createdAt: number;
+  assignments: WorkspaceAssignment[];
+}
+
+export const workspacesApi = {
+  async getWorkspaces(): Promise<Workspace[]> {
+    return await invoke("get_workspaces");
+  },
+
+  async addWorkspace(name: string, path: string): Promise<Workspace> {
+    return await invoke("add_workspace", { name, path });
+  },
+
+  async removeWorkspace(id: string): Promise<boolean> {
+    return await invoke("remove_workspace", { id });
+  },
+
+  async setPromptEnabled(
+    workspaceId: string,
+    app: AppId,
+    promptId: string,
+    enabled: boolean,
+  ): Promise<void> {
+    return await invoke("set_workspace_prompt_enabled", {
+      workspaceId,
+      app,
+      promptId,
+      enabled,
+    });
+  },
+
+  async setMcpEnabled(
+    workspaceId: string,
+    serverId: string,
+    app: AppId,
+    enabled: boolean,
+  ): Promise<void> {
+    return await invoke("set_workspace_mcp_enabled", {
+      workspaceId,
+      serverId,
+      app,
+      enabled,
+    });
+  },
+
+  // 工作区提示词模板变量（覆盖同名的应用变量）
+  async getVariables(workspaceId: string): Promise<Record<string, string>> {
+    return await invoke("get_workspace_variables", { workspaceId });
+  },
+
+  async setVariables(
+    workspaceId: string,
+    variables: Record<string, string>,
+  ): Promise<void> {
+    return await invoke("set_workspace_variables", { workspaceId, variables });
+  },
+};
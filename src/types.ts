@@ -142,7 +142,32 @@ export interface ProviderMeta {
   // Claude API 格式（仅 Claude 供应商使用）
   // - "anthropic": 原生 Anthropic Messages API 格式，直接透传
   // - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
-  apiFormat?: "anthropic" | "openai_chat";
+  // - "gemini": Gemini generateContent 格式，需要格式转换
+  apiFormat?: "anthropic" | "openai_chat" | "gemini";
+  // 负载均衡权重（weighted 策略，默认 1，0 表示仅作为备用）
+  loadBalanceWeight?: number;
+  // 模型路由规则（代理模式下按顺序匹配，优先于 env 中的模型映射）
+  modelRoutes?: ModelRouteRule[];
+  // 额外的 API Key（代理模式下与配置中的 Key 组成 Key 池轮换使用）
+  apiKeys?: string[];
+  // Key 池轮换方式（默认轮询）
+  keyRotation?: "round_robin" | "least_recently_rate_limited";
+}
+
+// 模型路由规则：按模式匹配请求模型，满足附加条件时改用 targetModel
+export interface ModelRouteRule {
+  // 匹配模式：glob（* / ?，不区分大小写）或正则
+  pattern: string;
+  matchType?: "glob" | "regex";
+  // 仅在 thinking / reasoning 开启（true）或关闭（false）时生效
+  thinking?: boolean;
+  // 工具数量范围（含边界）
+  minTools?: number;
+  maxTools?: number;
+  // 估算 prompt tokens 范围（含边界）
+  minPromptTokens?: number;
+  maxPromptTokens?: number;
+  targetModel: string;
 }
 
 // Skill 同步方式
@@ -151,7 +176,11 @@ export type SkillSyncMethod = "auto" | "symlink" | "copy";
 // Claude API 格式类型
 // - "anthropic": 原生 Anthropic Messages API 格式，直接透传
 // - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
-export type ClaudeApiFormat = "anthropic" | "openai_chat";
+// - "gemini": Gemini generateContent 格式，需要格式转换
+export type ClaudeApiFormat = "anthropic" | "openai_chat" | "gemini";
+
+// Codex 上游 API 格式：responses = 原生 Responses API，openai_chat = 仅支持 Chat Completions（代理转换）
+export type CodexApiFormat = "responses" | "openai_chat";
 
 // 主页面显示的应用配置
 export interface VisibleApps {
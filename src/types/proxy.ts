@@ -15,6 +15,7 @@ export interface ProxyStatus {
   running: boolean;
   address: string;
   port: number;
+  tls: boolean;
   active_connections: number;
   total_requests: number;
   success_requests: number;
@@ -39,6 +40,7 @@ export interface ProxyServerInfo {
   address: string;
   port: number;
   started_at: string;
+  tls: boolean;
 }
 
 export interface ProxyTakeoverStatus {
@@ -57,6 +59,71 @@ export interface ProviderHealth {
   last_failure_at: string | null;
   last_error: string | null;
   updated_at: string;
+  rate_limit?: RateLimitState | null;
+}
+
+// 代理入站访问控制（全局，修改后立即生效）
+export interface ProxyAuthConfig {
+  enabled: boolean;
+  accessToken: string;
+  // 信任本机回环地址的请求（接管模式写入的是占位 Token）
+  allowLoopback: boolean;
+  // 客户端 IP 或 CIDR，为空时不限制
+  ipAllowlist: string[];
+  // CORS 来源，为空时允许任意来源
+  corsOrigins: string[];
+}
+
+// 代理 TLS 配置（证书与私钥路径均为空时使用自签名证书）
+export interface ProxyTlsConfig {
+  enabled: boolean;
+  certPath: string;
+  keyPath: string;
+}
+
+// 代理虚拟 Key（每位使用者一个，明文 Key 只在创建时返回）
+export interface VirtualKey {
+  id: string;
+  name: string;
+  keyPrefix: string;
+  // 允许的应用，为空时不限制
+  allowedApps: string[];
+  // 允许的模型（glob），为空时不限制
+  allowedModels: string[];
+  limitDailyUsd?: string;
+  limitMonthlyUsd?: string;
+  enabled: boolean;
+  createdAt: number;
+}
+
+export interface CreatedVirtualKey {
+  key: VirtualKey;
+  secret: string;
+}
+
+// 响应缓存配置（每应用独立，默认关闭）
+export interface ResponseCacheConfig {
+  enabled: boolean;
+  ttlSeconds: number;
+  maxEntries: number;
+}
+
+// 请求/响应体抓取配置（每应用独立，默认关闭）
+export interface BodyCaptureConfig {
+  enabled: boolean;
+  maxBodyBytes: number;
+  retentionHours: number;
+}
+
+// 供应商限流冷却状态（代理运行时由内存状态提供）
+export interface RateLimitState {
+  status: number;
+  cooldown_until: string;
+  remaining_secs: number;
+  hits: number;
+  last_limited_at: string;
+  remaining_requests: number | null;
+  remaining_tokens: number | null;
 }
 
 // 熔断器相关类型
@@ -111,6 +178,44 @@ export interface FailoverQueueItem {
   sortIndex?: number;
 }
 
+// 供应商消费限额告警（provider-budget-alert 事件）
+export interface BudgetAlert {
+  appType: string;
+  providerId: string;
+  providerName: string;
+  period: "daily" | "monthly";
+  usageUsd: string;
+  limitUsd: string;
+  exceeded: boolean;
+}
+
+// 对冲请求配置（每应用独立）：流式请求首选供应商在 delayMs 内未返回首个数据块时，
+// 同时向下一个供应商发起相同请求，先返回的一方胜出
+export interface HedgeConfig {
+  enabled: boolean;
+  delayMs: number;
+}
+
+// 故障转移队列的负载均衡策略（每应用独立）
+export type LoadBalanceStrategy =
+  | "priority"
+  | "round_robin"
+  | "weighted"
+  | "least_latency"
+  | "lowest_cost";
+
+// 跨供应商模型路由：命中后仅在 providerIds 中按顺序选择（即该路由的故障转移顺序）
+export interface ModelRoute {
+  id: string;
+  appType: string;
+  name: string;
+  pattern: string;
+  matchType?: "glob" | "regex";
+  providerIds: string[];
+  enabled: boolean;
+  sortIndex: number;
+}
+
 // 全局代理配置（统一字段，三行镜像）
 export interface GlobalProxyConfig {
   proxyEnabled: boolean;
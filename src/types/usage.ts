@@ -30,7 +30,44 @@ export interface RequestLog {
   durationMs?: number;
   statusCode: number;
   errorMessage?: string;
+  // 实际使用的 API Key（脱敏）
+  maskedKey?: string;
+  // 是否由响应缓存直接返回
+  cacheHit: boolean;
+  // 发起请求的虚拟 Key ID
+  virtualKeyId?: string;
+  // 对冲角色：winner 为胜出的请求，loser 为被取消或失败的对冲请求
+  hedgeRole?: "winner" | "loser";
   createdAt: number;
+  // 抓取的请求/响应体（仅请求详情返回，需开启抓取模式）
+  bodyCapture?: RequestBodyCapture;
+}
+
+// 抓取的请求与响应（API Key 与认证头已脱敏）
+export interface RequestBodyCapture {
+  requestId: string;
+  appType: string;
+  endpoint: string;
+  // 请求/响应头（JSON 对象文本）
+  requestHeaders: string;
+  requestBody: string;
+  requestTruncated: boolean;
+  responseStatus: number;
+  responseHeaders: string;
+  // 流式响应为拼接后的完整 SSE 文本
+  responseBody: string;
+  responseTruncated: boolean;
+  createdAt: number;
+}
+
+// 重放抓取请求的结果
+export interface ReplayResult {
+  providerId: string;
+  providerName: string;
+  statusCode: number;
+  latencyMs: number;
+  responseBody: string;
+  truncated: boolean;
 }
 
 export interface PaginatedLogs {
@@ -57,6 +94,9 @@ export interface UsageSummary {
   totalCacheCreationTokens: number;
   totalCacheReadTokens: number;
   successRate: number;
+  // 响应缓存命中次数与节省的费用
+  cacheHits: number;
+  cacheSavings: string;
 }
 
 export interface DailyStats {
@@ -80,6 +120,22 @@ export interface ProviderStats {
   avgLatencyMs: number;
 }
 
+export interface VirtualKeyStats {
+  keyId: string;
+  name: string;
+  keyPrefix: string;
+  enabled: boolean;
+  requestCount: number;
+  totalTokens: number;
+  totalCost: string;
+  successRate: number;
+  dailyUsage: string;
+  dailyLimit?: string;
+  monthlyUsage: string;
+  monthlyLimit?: string;
+  lastUsedAt?: number;
+}
+
 export interface ModelStats {
   model: string;
   requestCount: number;
@@ -95,6 +151,7 @@ export interface LogFilters {
   statusCode?: number;
   startDate?: number;
   endDate?: number;
+  virtualKeyId?: string;
 }
 
 export interface ProviderLimitStatus {